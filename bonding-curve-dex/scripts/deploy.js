@@ -30,6 +30,8 @@ async function main() {
   const uploadReceipt = await client.upload(firstAccount.address, wasmCode, "auto");
   console.log("Upload successful, code ID:", uploadReceipt.codeId);
 
+  // Instantiate from the factory owner's account so the DEX can register itself
+  // as an allowed caller of the factory
   const initMsg = {
     token_factory: "neutron1f3224jexv9qwnvaa3xm772nfnqqrwkualph8cc05h0559yfjmfjqcsgxfd",
     fee_collector: firstAccount.address.toString(),
@@ -39,7 +41,11 @@ async function main() {
     maker_fee: JSON.stringify(0.01),
     taker_fee: JSON.stringify(0.02),
     secondary_amm_address: "osmosis-escrow",
-    base_token_denom: "untrn"
+    base_token_denom: "untrn",
+    min_order_notional: "1000000",
+    order_deposit: "0",
+    base_token_decimals: 6,
+    token_creation_fee: "1000000"
   };
 
   const instantiateReceipt = await client.instantiate(
@@ -1,26 +1,45 @@
+use std::collections::BTreeMap;
+
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{from_json, Reply};
 use cosmwasm_std::{
-    to_json_binary, Addr, Binary, Decimal, Deps, DepsMut, Env, MessageInfo, Response, StdError,
-    StdResult, Uint128,
+    to_json_binary, Addr, Binary, Coin, Decimal, Deps, DepsMut, Env, MessageInfo, Response,
+    StdError, StdResult, SubMsg, Uint128, WasmMsg,
 };
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
+use cw20::Denom;
 use execute::{
-    execute_cancel_order, execute_create_token, execute_graduate, execute_place_limit_order,
-    execute_swap, execute_update_config,
+    execute_accept_ownership, execute_bonding_curve_swap, execute_buyback, execute_cancel_order,
+    execute_claim_locked_lp, execute_claim_vested, execute_create_token, execute_distribute_fees,
+    execute_graduate, execute_place_limit_order, execute_register_pair, execute_set_buyback_policy,
+    execute_set_paused, execute_set_trading_enabled, execute_swap, execute_swap_operations,
+    execute_transfer_ownership, execute_update_base_currencies, execute_update_config,
+    execute_update_fee_distribution, execute_update_fees, execute_update_pair,
+    execute_update_pair_trading_rules, execute_update_pool, execute_update_roles,
 };
-use token_factory::state::TokenCreationResponse;
+use semver::Version;
+use token_factory::state::{TokenCreationResponse, TokenKind};
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use crate::migrations::migrate_from_v0_1;
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use crate::price::Price;
 use crate::state::{
-    Config, OrderBook, Pool, TokenInfo, TokenPair, BASE_PRICE, CONFIG, NEXT_ORDER_ID,
-    NEXT_TRADE_ID, POOLS, TOKEN_INFO, TOKEN_PAIRS,
+    denom_id, BaseCurrency, Config, FeeDistribution, LaunchSettings, LpPolicy, OrderBook,
+    OrderType, PauseFlags, Pool, Roles, TokenInfo, TokenPair, BASE_CURRENCIES, BASE_PRICE, CONFIG,
+    FEE_DISTRIBUTION, GLOBAL_PAUSE, MIN_LP_LOCK_DURATION, NEXT_ORDER_ID, NEXT_TRADE_ID,
+    ORDER_BOOKS, PENDING_BASE_CURRENCY, PENDING_INITIAL_BUY, PENDING_LAUNCH, PENDING_VESTING,
+    POOLS, ROLES, TOKEN_INFO, TOKEN_PAIRS, VESTING,
+};
+use token_factory::msg::{
+    ExecuteMsg as TokenFactoryExecuteMsg, GetTokenInfoResponse, QueryMsg as TokenFactoryQueryMsg,
 };
-use token_factory::msg::ExecuteMsg as TokenFactoryExecuteMsg;
 
 const REPLY_TOKEN_CREATION_ID: u64 = 1;
+const REPLY_BUYBACK_ID: u64 = 2;
+const REPLY_GRADUATION_ID: u64 = 3;
+const REPLY_REGISTER_DEX_ID: u64 = 4;
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:bonding-curve-dex";
@@ -54,13 +73,18 @@ pub fn instantiate(
             "Invalid input: zero or negative values are not allowed.",
         ));
     }
+    validate_supplies(
+        msg.quote_token_total_supply.u128(),
+        msg.bonding_curve_supply.u128(),
+        msg.lp_supply.u128(),
+    )?;
 
-    // Validate that the trading fee rate is within acceptable range
-    if msg.maker_fee > Decimal::one() || msg.taker_fee > Decimal::one() {
-        return Err(StdError::generic_err(
-            "Trading fee rate must be between 0 and 1.",
-        ));
-    }
+    // Validate that the fee rates are within acceptable range
+    validate_trading_fee(msg.maker_fee)?;
+    validate_trading_fee(msg.taker_fee)?;
+    validate_curve_fee(msg.curve_fee)?;
+
+    validate_lp_policy(&msg.lp_policy)?;
 
     // Validate that the base token denomination is not empty
     if msg.base_token_denom.is_empty() {
@@ -68,6 +92,11 @@ pub fn instantiate(
             "Base token denomination must not be empty.",
         ));
     }
+    if msg.base_token_symbol.is_empty() {
+        return Err(StdError::generic_err(
+            "Base token symbol must not be empty.",
+        ));
+    }
 
     let config = Config {
         owner: info.sender.clone(),
@@ -79,15 +108,47 @@ pub fn instantiate(
         lp_supply: msg.lp_supply.clone().into(),
         maker_fee: msg.maker_fee.clone(),
         taker_fee: msg.taker_fee.clone(),
+        curve_fee: msg.curve_fee,
         secondary_amm_address: msg.secondary_amm_address.clone(),
         base_token_denom: msg.base_token_denom.clone(),
+        base_token_symbol: msg.base_token_symbol.clone(),
+        base_token_decimals: msg.base_token_decimals,
+        token_creation_fee: msg.token_creation_fee,
+        lp_policy: msg.lp_policy.clone(),
     };
 
     CONFIG.save(deps.storage, &config)?;
+    // The configured base token is the default currency tokens launch against
+    BASE_CURRENCIES.save(
+        deps.storage,
+        config.base_token_symbol.clone(),
+        &BaseCurrency {
+            symbol: config.base_token_symbol.clone(),
+            denom: config.base_token_denom.clone(),
+            decimals: config.base_token_decimals,
+            base_price: Uint128::from(BASE_PRICE),
+            graduation_reserve: Uint128::zero(),
+            min_order_notional: msg.min_order_notional,
+            order_deposit: msg.order_deposit,
+        },
+    )?;
+    ROLES.save(deps.storage, &Roles::default())?;
+    GLOBAL_PAUSE.save(deps.storage, &PauseFlags::default())?;
+    FEE_DISTRIBUTION.save(deps.storage, &FeeDistribution::default())?;
     NEXT_ORDER_ID.save(deps.storage, &0u64)?;
     NEXT_TRADE_ID.save(deps.storage, &0u64)?;
 
+    // Add this DEX to the factory's allowed callers, which works when the
+    // factory owner instantiates it. Otherwise the factory owner allows it with
+    // UpdateAllowedCallers and the deployment goes through regardless.
+    let register_dex = WasmMsg::Execute {
+        contract_addr: msg.token_factory.to_string(),
+        msg: to_json_binary(&TokenFactoryExecuteMsg::RegisterDex {})?,
+        funds: vec![],
+    };
+
     Ok(Response::new()
+        .add_submessage(SubMsg::reply_on_error(register_dex, REPLY_REGISTER_DEX_ID))
         .add_attribute("action", "instantiate")
         .add_attribute("owner", info.sender)
         .add_attribute("token_factory", msg.token_factory.to_string())
@@ -104,7 +165,13 @@ pub fn instantiate(
             "secondary_amm_address",
             msg.secondary_amm_address.to_string(),
         )
-        .add_attribute("base_token_denom", msg.base_token_denom))
+        .add_attribute("base_token_denom", denom_id(&msg.base_token_denom))
+        .add_attribute("base_token_symbol", msg.base_token_symbol)
+        .add_attribute("base_token_decimals", msg.base_token_decimals.to_string())
+        .add_attribute("min_order_notional", msg.min_order_notional.to_string())
+        .add_attribute("order_deposit", msg.order_deposit.to_string())
+        .add_attribute("token_creation_fee", msg.token_creation_fee.to_string())
+        .add_attribute("curve_fee", msg.curve_fee.to_string()))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -122,6 +189,12 @@ pub fn execute(
             uri,
             max_price_impact,
             curve_slope,
+            initial_buy,
+            launch,
+            metadata,
+            kind,
+            base_currency,
+            vesting,
         } => Ok(execute_create_token(
             deps,
             env,
@@ -132,6 +205,12 @@ pub fn execute(
             uri,
             max_price_impact,
             curve_slope,
+            initial_buy,
+            launch,
+            metadata,
+            kind,
+            base_currency,
+            vesting,
         )?),
         ExecuteMsg::PlaceLimitOrder {
             token_address,
@@ -166,6 +245,18 @@ pub fn execute(
             min_return,
             order_type,
         )?),
+        ExecuteMsg::ExecuteSwapOperations {
+            operations,
+            offer_amount,
+            minimum_receive,
+        } => Ok(execute_swap_operations(
+            deps,
+            env,
+            info,
+            operations,
+            offer_amount,
+            minimum_receive,
+        )?),
         ExecuteMsg::UpdateConfig {
             token_factory,
             fee_collector,
@@ -175,6 +266,8 @@ pub fn execute(
             bonding_curve_supply,
             lp_supply,
             enabled,
+            token_creation_fee,
+            lp_policy,
         } => Ok(execute_update_config(
             deps,
             env,
@@ -187,47 +280,202 @@ pub fn execute(
             bonding_curve_supply,
             lp_supply,
             enabled,
+            token_creation_fee,
+            lp_policy,
         )?),
         ExecuteMsg::Graduate { token_address } => {
             Ok(execute_graduate(deps, env, info, token_address)?)
         }
+        ExecuteMsg::ClaimLockedLp { token_address } => {
+            Ok(execute_claim_locked_lp(deps, env, info, token_address)?)
+        }
+        ExecuteMsg::ClaimVested { token_address } => {
+            Ok(execute_claim_vested(deps, env, info, token_address)?)
+        }
+        ExecuteMsg::UpdatePairTradingRules {
+            pair_id,
+            tick_size,
+            lot_size,
+            min_notional,
+            order_deposit,
+        } => Ok(execute_update_pair_trading_rules(
+            deps,
+            info,
+            pair_id,
+            tick_size,
+            lot_size,
+            min_notional,
+            order_deposit,
+        )?),
+        ExecuteMsg::UpdatePair { pair_id, enabled } => {
+            Ok(execute_update_pair(deps, info, pair_id, enabled)?)
+        }
+        ExecuteMsg::UpdatePool {
+            token_address,
+            enabled,
+            curve_slope,
+        } => Ok(execute_update_pool(
+            deps,
+            info,
+            token_address,
+            enabled,
+            curve_slope,
+        )?),
+        ExecuteMsg::RegisterPair {
+            pair_id,
+            base_token,
+            quote_token,
+            base_decimals,
+            quote_decimals,
+        } => Ok(execute_register_pair(
+            deps,
+            info,
+            pair_id,
+            base_token,
+            quote_token,
+            base_decimals,
+            quote_decimals,
+        )?),
+        ExecuteMsg::UpdateBaseCurrencies { add, remove } => {
+            Ok(execute_update_base_currencies(deps, info, add, remove)?)
+        }
+        ExecuteMsg::TransferOwnership { new_owner, expiry } => Ok(execute_transfer_ownership(
+            deps, env, info, new_owner, expiry,
+        )?),
+        ExecuteMsg::AcceptOwnership {} => Ok(execute_accept_ownership(deps, env, info)?),
+        ExecuteMsg::UpdateRoles {
+            fee_manager,
+            pauser,
+            graduator,
+            guardian,
+        } => Ok(execute_update_roles(
+            deps,
+            info,
+            fee_manager,
+            pauser,
+            graduator,
+            guardian,
+        )?),
+        ExecuteMsg::UpdateFees {
+            maker_fee,
+            taker_fee,
+            curve_fee,
+        } => Ok(execute_update_fees(
+            deps, info, maker_fee, taker_fee, curve_fee,
+        )?),
+        ExecuteMsg::UpdateFeeDistribution {
+            fee_collector_share,
+            buyback_share,
+            staker_share,
+            buyback_address,
+            staking_address,
+        } => Ok(execute_update_fee_distribution(
+            deps,
+            info,
+            fee_collector_share,
+            buyback_share,
+            staker_share,
+            buyback_address,
+            staking_address,
+        )?),
+        ExecuteMsg::DistributeFees {} => Ok(execute_distribute_fees(deps)?),
+        ExecuteMsg::SetBuybackPolicy {
+            token_address,
+            share,
+        } => Ok(execute_set_buyback_policy(
+            deps,
+            info,
+            token_address,
+            share,
+        )?),
+        ExecuteMsg::ExecuteBuyback {
+            token_address,
+            min_tokens_out,
+        } => Ok(execute_buyback(
+            deps,
+            env,
+            info,
+            token_address,
+            min_tokens_out,
+        )?),
+        ExecuteMsg::SetTradingEnabled { enabled } => {
+            Ok(execute_set_trading_enabled(deps, info, enabled)?)
+        }
+        ExecuteMsg::Pause { pair_id, actions } => {
+            Ok(execute_set_paused(deps, info, pair_id, actions, true)?)
+        }
+        ExecuteMsg::Unpause { pair_id, actions } => {
+            Ok(execute_set_paused(deps, info, pair_id, actions, false)?)
+        }
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> StdResult<Response> {
+pub fn reply(mut deps: DepsMut, env: Env, msg: Reply) -> StdResult<Response> {
+    if msg.id == REPLY_BUYBACK_ID {
+        return execute::reply_buyback(deps, env);
+    }
+    if msg.id == REPLY_REGISTER_DEX_ID {
+        let error = msg.result.into_result().err().unwrap_or_default();
+        return Ok(Response::new()
+            .add_attribute("action", "register_dex_failed")
+            .add_attribute("error", error));
+    }
+    if msg.id == REPLY_GRADUATION_ID {
+        let data = execute_response_data(msg, "secondary AMM")?
+            .ok_or_else(|| StdError::generic_err("Missing provide liquidity data"))?;
+        return execute::reply_graduation(deps, env, from_json(data)?);
+    }
     if msg.id != REPLY_TOKEN_CREATION_ID {
-        return Err(StdError::generic_err(format!("Unknown reply ID: {}", msg.id)));
+        return Err(StdError::generic_err(format!(
+            "Unknown reply ID: {}",
+            msg.id
+        )));
     }
 
-    let res = cw_utils::parse_execute_response_data(&msg.payload).unwrap();
-    let token_data: TokenCreationResponse = from_json(res.data.unwrap()).unwrap();
+    let token_data: TokenCreationResponse = from_json(
+        execute_response_data(msg, "token factory")?
+            .ok_or_else(|| StdError::generic_err("Missing token creation data"))?,
+    )?;
 
     let config = CONFIG.load(deps.storage)?;
-    let total_supply = config
-        .quote_token_total_supply
-        .checked_mul(10u128.pow(token_data.decimals as u32))
-        .unwrap();
-
+    let currency = PENDING_BASE_CURRENCY.load(deps.storage)?;
+    PENDING_BASE_CURRENCY.remove(deps.storage);
     let token_info = TokenInfo {
         name: token_data.name.clone(),
         symbol: token_data.symbol.clone(),
         decimals: token_data.decimals,
-        total_supply: total_supply.into(),
-        initial_price: Uint128::from(BASE_PRICE),
+        total_supply: scale_supply(config.quote_token_total_supply, token_data.decimals)?,
+        bonding_curve_supply: scale_supply(config.bonding_curve_supply, token_data.decimals)?,
+        lp_supply: scale_supply(config.lp_supply, token_data.decimals)?,
+        graduation_reserve: currency.graduation_reserve,
+        lp_policy: config.lp_policy.clone(),
+        initial_price: Price::new(currency.base_price, token_data.decimals),
         max_price_impact: token_data.max_price_impact,
         graduated: false,
     };
 
+    let quote_token = match token_data.kind {
+        TokenKind::Cw20 => Denom::Cw20(Addr::unchecked(&token_data.token_address)),
+        TokenKind::Native => Denom::Native(token_data.token_address.clone()),
+    };
     let token_pair = TokenPair {
-        base_token: config.base_token_denom.clone(),
-        quote_token: token_data.token_address.clone(),
-        base_decimals: 6,
+        base_token: currency.denom.clone(),
+        quote_token,
+        base_decimals: currency.decimals,
         quote_decimals: token_data.decimals,
         enabled: true,
+        tick_size: Uint128::one(),
+        lot_size: Uint128::one(),
+        min_notional: currency.min_order_notional,
+        order_deposit: currency.order_deposit,
     };
 
-    let pair_id = format!("{}/{}", token_data.symbol, &config.base_token_denom[1..]);
+    // Symbols are not unique in the factory, a launch must not take over a live pair
+    let pair_id = format!("{}/{}", token_data.symbol, currency.symbol);
+    if TOKEN_PAIRS.has(deps.storage, pair_id.clone()) {
+        return Err(StdError::generic_err("Trading pair already exists"));
+    }
 
     let pool = Pool {
         token_address: Addr::unchecked(token_data.token_address.clone()),
@@ -238,33 +486,212 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> StdResult<Response> {
         curve_slope: token_data.curve_slope,
         pair_id: pair_id.clone(),
         total_trades: Uint128::zero(),
-        last_price: Uint128::from(BASE_PRICE),
+        last_price: token_info.initial_price,
         enabled: true,
+        launch: LaunchSettings::default(),
+        launch_height: env.block.height,
+        base_currency: currency.symbol.clone(),
+    };
+
+    let order_book = OrderBook {
+        pair_id: pair_id.clone(),
+        buy_orders: BTreeMap::new(),
+        sell_orders: BTreeMap::new(),
     };
 
     TOKEN_INFO.save(deps.storage, token_data.token_address.clone(), &token_info)?;
-    TOKEN_PAIRS.save(deps.storage, pair_id, &token_pair)?;
+    TOKEN_PAIRS.save(deps.storage, pair_id.clone(), &token_pair)?;
+    ORDER_BOOKS.save(deps.storage, pair_id.clone(), &order_book)?;
     POOLS.save(deps.storage, token_data.token_address.clone(), &pool)?;
 
-    Ok(Response::new()
+    // The creator's allocation stays with the contract and vests from launch
+    if let Some(mut schedule) = PENDING_VESTING.may_load(deps.storage)? {
+        PENDING_VESTING.remove(deps.storage);
+        schedule.pair_id = pair_id.clone();
+        VESTING.save(deps.storage, token_data.token_address.clone(), &schedule)?;
+    }
+
+    let mut response = Response::new()
         .add_attribute("action", "create_token_completed")
-        .add_attribute("token_address", token_data.token_address))
+        .add_attribute("token_address", token_data.token_address.clone());
+
+    // Run the creator's buy against the fresh pool. Native funds were already paid
+    // with CreateToken, a CW20 buy is pulled from the creator's allowance here.
+    // A slippage failure reverts the whole launch.
+    if let Some(buy) = PENDING_INITIAL_BUY.may_load(deps.storage)? {
+        PENDING_INITIAL_BUY.remove(deps.storage);
+        let funds = match currency.denom {
+            Denom::Native(denom) => vec![Coin {
+                denom,
+                amount: buy.amount,
+            }],
+            Denom::Cw20(_) => vec![],
+        };
+        let buy_info = MessageInfo {
+            sender: buy.creator,
+            funds,
+        };
+        let buy_response = execute_bonding_curve_swap(
+            deps.branch(),
+            env,
+            buy_info,
+            pair_id,
+            token_data.token_address.clone(),
+            buy.amount,
+            buy.min_tokens_out,
+            OrderType::Buy,
+        )?;
+        response = response
+            .add_submessages(buy_response.messages)
+            .add_attributes(buy_response.attributes)
+            .add_events(buy_response.events);
+    }
+
+    // Launch protections only switch on after the creator's buy, which is exempt
+    let launch = PENDING_LAUNCH.may_load(deps.storage)?.unwrap_or_default();
+    PENDING_LAUNCH.remove(deps.storage);
+    let mut pool = POOLS.load(deps.storage, token_data.token_address.clone())?;
+    pool.launch = launch;
+    POOLS.save(deps.storage, token_data.token_address, &pool)?;
+
+    Ok(response)
+}
+
+// Data returned by the contract a submessage executed
+fn execute_response_data(msg: Reply, contract: &str) -> StdResult<Option<Binary>> {
+    let response = msg.result.into_result().map_err(StdError::generic_err)?;
+    // Chains on CosmWasm 2 fill msg_responses, older ones only the data field
+    #[allow(deprecated)]
+    let data = match response.msg_responses.into_iter().next() {
+        Some(msg_response) => msg_response.value,
+        None => response
+            .data
+            .ok_or_else(|| StdError::generic_err(format!("Missing {} response data", contract)))?,
+    };
+    let res = cw_utils::parse_execute_response_data(&data)
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+    Ok(res.data)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> StdResult<Response> {
+    let stored = get_contract_version(deps.storage)?;
+    if stored.contract != CONTRACT_NAME {
+        return Err(StdError::generic_err(format!(
+            "Cannot migrate from a different contract: {}",
+            stored.contract
+        )));
+    }
+
+    let stored_version = parse_version(&stored.version)?;
+    if stored_version > parse_version(CONTRACT_VERSION)? {
+        return Err(StdError::generic_err(format!(
+            "Cannot migrate from newer version {} to {}",
+            stored.version, CONTRACT_VERSION
+        )));
+    }
+
+    // Upgrade steps run in order, starting from the stored version
+    if stored_version < Version::new(0, 2, 0) {
+        let upgrade = msg.v0_2.ok_or_else(|| {
+            StdError::generic_err("Migrating from 0.1 requires the v0_2 upgrade settings")
+        })?;
+        migrate_from_v0_1(deps.storage, &upgrade)?;
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", stored.version)
+        .add_attribute("to_version", CONTRACT_VERSION))
+}
+
+fn parse_version(version: &str) -> StdResult<Version> {
+    version
+        .parse()
+        .map_err(|_| StdError::generic_err(format!("Invalid contract version: {}", version)))
+}
+
+// The config supplies are whole tokens, and the curve and LP must fit in the total
+fn validate_supplies(
+    total_supply: u128,
+    bonding_curve_supply: u128,
+    lp_supply: u128,
+) -> StdResult<()> {
+    if total_supply == 0 || bonding_curve_supply == 0 || lp_supply == 0 {
+        return Err(StdError::generic_err("Supplies must be greater than 0."));
+    }
+    match bonding_curve_supply.checked_add(lp_supply) {
+        Some(allocated) if allocated <= total_supply => Ok(()),
+        _ => Err(StdError::generic_err(
+            "Bonding curve and LP supplies must fit within the total supply",
+        )),
+    }
+}
+
+// Whole tokens from the config to atomics of a token with the given decimals
+fn scale_supply(supply: u128, decimals: u8) -> StdResult<Uint128> {
+    let unit = Uint128::new(10).checked_pow(decimals as u32)?;
+    Ok(Uint128::new(supply).checked_mul(unit)?)
+}
+
+// Maker and taker fees must charge something and stay below 100%
+fn validate_trading_fee(fee: Decimal) -> StdResult<()> {
+    if fee.is_zero() || fee >= Decimal::one() {
+        return Err(StdError::generic_err(
+            "Trading fee rate must be between 0 and 1.",
+        ));
+    }
+    Ok(())
+}
+
+// Curves may trade for free
+fn validate_curve_fee(fee: Decimal) -> StdResult<()> {
+    if fee >= Decimal::one() {
+        return Err(StdError::generic_err(
+            "Curve fee rate must be between 0 and 1.",
+        ));
+    }
+    Ok(())
+}
+
+fn validate_lp_policy(lp_policy: &LpPolicy) -> StdResult<()> {
+    if let LpPolicy::Lock { duration } = lp_policy {
+        if *duration < MIN_LP_LOCK_DURATION {
+            return Err(StdError::generic_err(format!(
+                "LP lock duration must be at least {} seconds",
+                MIN_LP_LOCK_DURATION
+            )));
+        }
+    }
+    Ok(())
 }
 
 pub mod execute {
     use std::str::FromStr;
 
     use cosmwasm_std::{
-        attr, Addr, BankMsg, Coin, CosmosMsg, Decimal, Deps, StdError,
-        Storage, SubMsg, Uint128, WasmMsg,
+        attr, Addr, BankMsg, Coin, CosmosMsg, Decimal, Deps, StdError, Storage, SubMsg, Uint128,
+        WasmMsg,
     };
-    use cw20::Cw20ExecuteMsg;
-    use token_factory::state::Cw20Coin;
+    use cw20::{Cw20ExecuteMsg, UncheckedDenom};
+    use token_factory::state::{Cw20Coin, TokenMetadata};
 
+    use cw_utils::Expiration;
+
+    use crate::msg::{
+        BaseCurrencyMsg, InitialBuy, ProvideLiquidityResponse, SecondaryAmmMsg, SwapOperation,
+        VestingAllocation,
+    };
     use crate::state::{
-        Order, OrderStatus, OrderType, TokenPair, Trade, BASE_PRICE,
-        MAX_TRADES_PER_USER, ORDERS, ORDER_BOOKS, POOLS, TOKEN_INFO, TOKEN_PAIRS, TRADES,
-        USER_ORDERS, USER_TRADES, USER_TRADE_COUNT,
+        Buyback, GraduationRecord, LpOutcome, Order, OrderStatus, OrderType, PauseAction,
+        PauseFlags, PendingBuyback, PendingInitialBuy, PendingOwner, Roles, TokenPair, Trade,
+        TreasuryBalance, VestingSchedule, BUYBACKS, GLOBAL_PAUSE, GRADUATIONS, LAUNCH_BUYS,
+        MAX_ACTIVE_ORDERS_PER_USER, MAX_SWAP_OPERATIONS, MAX_TRADES_PER_USER, ORDERS, ORDER_BOOKS,
+        PAIR_PAUSE, PENDING_BUYBACK, PENDING_GRADUATION, PENDING_INITIAL_BUY, PENDING_OWNER, POOLS,
+        ROLES, TOKEN_INFO, TOKEN_PAIRS, TRADES, TREASURY, USER_ACTIVE_ORDERS, USER_ORDERS,
+        USER_TRADES, USER_TRADE_COUNT,
     };
 
     use super::*;
@@ -272,15 +699,22 @@ pub mod execute {
     pub fn execute_create_token(
         deps: DepsMut,
         env: Env,
-        _info: MessageInfo,
+        info: MessageInfo,
         name: String,
         symbol: String,
         decimals: u8,
         uri: String,
         max_price_impact: Uint128,
         curve_slope: Uint128,
+        initial_buy: Option<InitialBuy>,
+        launch: Option<LaunchSettings>,
+        metadata: Option<TokenMetadata>,
+        kind: Option<TokenKind>,
+        base_currency: Option<String>,
+        vesting: Option<VestingAllocation>,
     ) -> StdResult<Response> {
         let config = CONFIG.load(deps.storage)?;
+        assert_not_paused(deps.storage, None, PauseAction::TokenCreation)?;
 
         // Validate input values
         if name.is_empty() || symbol.is_empty() {
@@ -300,10 +734,81 @@ pub mod execute {
             return Err(StdError::generic_err("Curve slope must be greater than 0."));
         }
 
-        let total_supply = config
-            .quote_token_total_supply
-            .checked_mul(10u128.pow(decimals as u32))
-            .unwrap();
+        // Tokens launch against the default currency unless another whitelisted one is picked
+        let currency = BASE_CURRENCIES
+            .may_load(
+                deps.storage,
+                base_currency.unwrap_or_else(|| config.base_token_symbol.clone()),
+            )?
+            .ok_or_else(|| StdError::generic_err("Base currency is not whitelisted"))?;
+
+        // The creation fee keeps token spam from being free and is always paid in the
+        // default currency. A native dev buy is paid along with it and held until the
+        // reply spends it on the curve, a CW20 dev buy is pulled by the reply itself.
+        let initial_buy_amount = initial_buy
+            .as_ref()
+            .map(|buy| buy.amount)
+            .unwrap_or_default();
+        if initial_buy.is_some() && initial_buy_amount.is_zero() {
+            return Err(StdError::generic_err(
+                "Initial buy amount must be greater than 0.",
+            ));
+        }
+        let held_buy = match currency.denom {
+            Denom::Native(_) => initial_buy_amount,
+            Denom::Cw20(_) => Uint128::zero(),
+        };
+        let mut messages: Vec<CosmosMsg> = vec![];
+        if currency.denom == config.base_token_denom {
+            messages.extend(receive_payment(
+                deps.as_ref(),
+                &env,
+                &info,
+                &config.base_token_denom,
+                config.token_creation_fee.checked_add(held_buy)?,
+            )?);
+        } else {
+            messages.extend(receive_payment(
+                deps.as_ref(),
+                &env,
+                &info,
+                &config.base_token_denom,
+                config.token_creation_fee,
+            )?);
+            if let Denom::Native(denom) = &currency.denom {
+                validate_native_token_payment(&info, denom, held_buy)?;
+            }
+        }
+        PENDING_BASE_CURRENCY.save(deps.storage, &currency)?;
+        let launch = launch.unwrap_or_default();
+        validate_launch_settings(deps.as_ref(), &launch)?;
+        PENDING_LAUNCH.save(deps.storage, &launch)?;
+        let vesting_amount = match vesting {
+            Some(allocation) => {
+                let schedule =
+                    vesting_schedule(deps.as_ref(), &env, &info, &config, decimals, allocation)?;
+                PENDING_VESTING.save(deps.storage, &schedule)?;
+                schedule.total
+            }
+            None => Uint128::zero(),
+        };
+        if let Some(buy) = initial_buy {
+            PENDING_INITIAL_BUY.save(
+                deps.storage,
+                &PendingInitialBuy {
+                    creator: info.sender.clone(),
+                    amount: buy.amount,
+                    min_tokens_out: buy.min_tokens_out,
+                },
+            )?;
+        }
+        accrue_fee(
+            deps.storage,
+            &config.base_token_denom,
+            config.token_creation_fee,
+        )?;
+
+        let total_supply = scale_supply(config.quote_token_total_supply, decimals)?;
 
         // Call token factory contract with additional parameters
         let msg = WasmMsg::Execute {
@@ -317,20 +822,112 @@ pub mod execute {
                 curve_slope,
                 initial_balances: vec![Cw20Coin {
                     address: env.contract.address.to_string(),
-                    amount: total_supply.into(),
+                    amount: total_supply,
                 }],
+                // Record the user as creator so they can manage the token's metadata
+                creator: Some(info.sender.to_string()),
+                metadata,
+                kind,
             })?,
             funds: vec![],
         };
 
         Ok(Response::new()
+            .add_messages(messages)
             .add_submessage(SubMsg::reply_on_success(msg, REPLY_TOKEN_CREATION_ID))
             .add_attribute("action", "create_token_pending")
             .add_attribute("name", name)
             .add_attribute("symbol", symbol)
             .add_attribute("decimals", decimals.to_string())
             .add_attribute("max_price_impact", max_price_impact.to_string())
-            .add_attribute("curve_slope", curve_slope.to_string()))
+            .add_attribute("curve_slope", curve_slope.to_string())
+            .add_attribute("creation_fee", config.token_creation_fee.to_string())
+            .add_attribute("initial_buy", initial_buy_amount.to_string())
+            .add_attribute("base_currency", currency.symbol)
+            .add_attribute("vesting", vesting_amount))
+    }
+
+    // Validates a creator allocation against the supply the curve and LP leave over.
+    // The reply fills in the pair once the token exists.
+    fn vesting_schedule(
+        deps: Deps,
+        env: &Env,
+        info: &MessageInfo,
+        config: &Config,
+        decimals: u8,
+        allocation: VestingAllocation,
+    ) -> StdResult<VestingSchedule> {
+        if allocation.amount.is_zero() {
+            return Err(StdError::generic_err(
+                "Vesting amount must be greater than 0.",
+            ));
+        }
+        if allocation.duration == 0 || allocation.cliff > allocation.duration {
+            return Err(StdError::generic_err(
+                "Vesting duration must be greater than 0 and at least the cliff.",
+            ));
+        }
+        let unallocated = config
+            .quote_token_total_supply
+            .checked_sub(config.bonding_curve_supply)
+            .and_then(|rest| rest.checked_sub(config.lp_supply))
+            .unwrap_or_default();
+        let available = scale_supply(unallocated, decimals)?;
+        if allocation.amount > available {
+            return Err(StdError::generic_err(format!(
+                "Vesting amount exceeds the {} tokens left outside the curve and LP supplies",
+                available
+            )));
+        }
+        let beneficiary = match allocation.beneficiary {
+            Some(beneficiary) => deps.api.addr_validate(&beneficiary)?,
+            None => info.sender.clone(),
+        };
+
+        Ok(VestingSchedule {
+            pair_id: String::new(),
+            beneficiary,
+            total: allocation.amount,
+            claimed: Uint128::zero(),
+            start: env.block.time,
+            cliff: allocation.cliff,
+            duration: allocation.duration,
+        })
+    }
+
+    pub fn execute_claim_vested(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_address: String,
+    ) -> StdResult<Response> {
+        let mut schedule = VESTING
+            .may_load(deps.storage, token_address.clone())?
+            .ok_or_else(|| StdError::generic_err("No vesting schedule for this token"))?;
+        if info.sender != schedule.beneficiary {
+            return Err(StdError::generic_err("Unauthorized"));
+        }
+
+        let claimable = schedule
+            .vested(env.block.time)
+            .checked_sub(schedule.claimed)?;
+        if claimable.is_zero() {
+            return Err(StdError::generic_err("No vested tokens to claim"));
+        }
+        schedule.claimed += claimable;
+        VESTING.save(deps.storage, token_address.clone(), &schedule)?;
+
+        // The pair outlives graduation, unlike the pool
+        let token_pair = TOKEN_PAIRS.load(deps.storage, schedule.pair_id.clone())?;
+        let transfer = execute_transfer(&token_pair.quote_token, &schedule.beneficiary, claimable)?;
+
+        Ok(Response::new()
+            .add_message(transfer)
+            .add_attribute("action", "claim_vested")
+            .add_attribute("token", token_address)
+            .add_attribute("beneficiary", schedule.beneficiary)
+            .add_attribute("amount", claimable)
+            .add_attribute("claimed", schedule.claimed))
     }
 
     pub fn execute_place_limit_order(
@@ -339,7 +936,7 @@ pub mod execute {
         info: MessageInfo,
         pair_id: String,
         amount: Uint128,
-        price: Uint128,
+        price: Price,
         is_buy: bool,
     ) -> StdResult<Response> {
         let config = CONFIG.load(deps.storage)?;
@@ -352,9 +949,56 @@ pub mod execute {
         if !token_pair.enabled {
             return Err(StdError::generic_err("Trading pair is disabled"));
         }
+        assert_not_paused(deps.storage, Some(&pair_id), PauseAction::BookTrading)?;
+
+        // Price levels are keyed in atomics at the quote token's decimals
+        let price = price.to_precision(token_pair.quote_decimals)?;
+
+        // Keep the book on the pair's price and quantity grid
+        if price.is_zero() || !(price.atomics % token_pair.tick_size).is_zero() {
+            return Err(StdError::generic_err(format!(
+                "Price must be a non-zero multiple of tick size {}",
+                token_pair.tick_size
+            )));
+        }
+        if amount.is_zero() || !(amount % token_pair.lot_size).is_zero() {
+            return Err(StdError::generic_err(format!(
+                "Amount must be a non-zero multiple of lot size {}",
+                token_pair.lot_size
+            )));
+        }
+
+        // Reject dust orders so the book can't be griefed into unusable gas costs
+        let notional = price.checked_quote_to_base_ceil(amount)?;
+        if notional < token_pair.min_notional {
+            return Err(StdError::generic_err(format!(
+                "Order notional below minimum. Required: {}, Got: {}",
+                token_pair.min_notional, notional
+            )));
+        }
+
+        // Enforce the per-user cap on resting orders
+        let active_orders = USER_ACTIVE_ORDERS
+            .may_load(deps.storage, info.sender.clone())?
+            .unwrap_or_default();
+        if active_orders >= MAX_ACTIVE_ORDERS_PER_USER as u64 {
+            return Err(StdError::generic_err(format!(
+                "Too many active orders. Maximum: {}",
+                MAX_ACTIVE_ORDERS_PER_USER
+            )));
+        }
 
-        // Check if tokens were sent and handle token transfers
-        validate_and_handle_tokens(&deps, &env, &info, &token_pair, amount, price, is_buy)?;
+        // Check if tokens were sent and collect the transfers that escrow them
+        let payment = validate_and_handle_tokens(
+            &deps,
+            &env,
+            &info,
+            &token_pair,
+            amount,
+            price,
+            is_buy,
+            token_pair.order_deposit,
+        )?;
 
         let mut order_book = ORDER_BOOKS.load(deps.storage, pair_id.clone())?;
         let next_id = NEXT_ORDER_ID.load(deps.storage)?;
@@ -376,19 +1020,20 @@ pub mod execute {
                 OrderType::Sell
             },
             created_at: env.block.height,
+            deposit: token_pair.order_deposit,
         };
 
         // Add to order book
         if is_buy {
             order_book
                 .buy_orders
-                .entry(price.u128())
+                .entry(price.atomics.u128())
                 .or_insert_with(Vec::new)
                 .push(order.clone());
         } else {
             order_book
                 .sell_orders
-                .entry(price.u128())
+                .entry(price.atomics.u128())
                 .or_insert_with(Vec::new)
                 .push(order.clone());
         }
@@ -398,11 +1043,14 @@ pub mod execute {
         NEXT_ORDER_ID.save(deps.storage, &(next_id + 1))?;
         USER_ORDERS.save(deps.storage, (info.sender.clone(), next_id), &order)?;
         ORDERS.save(deps.storage, next_id, &order)?;
+        USER_ACTIVE_ORDERS.save(deps.storage, info.sender.clone(), &(active_orders + 1))?;
 
         // Try to match orders
-        match_orders(deps, &env, pair_id.clone(), is_buy)?;
+        let match_response = match_orders(deps, &env, pair_id.clone(), is_buy)?;
 
         Ok(Response::new()
+            .add_messages(payment)
+            .add_submessages(match_response.messages)
             .add_attribute("action", "place_limit_order")
             .add_attribute("order_id", next_id.to_string())
             .add_attribute("pair_id", pair_id)
@@ -418,7 +1066,10 @@ pub mod execute {
         order_id: u64,
         pair_id: String,
     ) -> StdResult<Response> {
+        // Deliberately unaffected by pauses and the trading switch, so makers can
+        // always pull their orders and funds out of the book
         let mut order_book = ORDER_BOOKS.load(deps.storage, pair_id.clone())?;
+        let token_pair = TOKEN_PAIRS.load(deps.storage, pair_id.clone())?;
 
         // Check if the order exists
         let order = USER_ORDERS.may_load(deps.storage, (info.sender.clone(), order_id))?;
@@ -433,27 +1084,32 @@ pub mod execute {
         }
 
         // Find and remove order from order book
-        let removed_order = if order_book.buy_orders.contains_key(&order.price.u128()) {
+        let price_key = order
+            .price
+            .to_precision(token_pair.quote_decimals)?
+            .atomics
+            .u128();
+        let removed_order = if order_book.buy_orders.contains_key(&price_key) {
             order_book
                 .buy_orders
-                .get_mut(&order.price.u128())
+                .get_mut(&price_key)
                 .and_then(|orders| orders.iter().position(|o| o.id == order_id))
                 .map(|index| {
                     order_book
                         .buy_orders
-                        .get_mut(&order.price.u128())
+                        .get_mut(&price_key)
                         .unwrap()
                         .remove(index)
                 })
-        } else if order_book.sell_orders.contains_key(&order.price.u128()) {
+        } else if order_book.sell_orders.contains_key(&price_key) {
             order_book
                 .sell_orders
-                .get_mut(&order.price.u128())
+                .get_mut(&price_key)
                 .and_then(|orders| orders.iter().position(|o| o.id == order_id))
                 .map(|index| {
                     order_book
                         .sell_orders
-                        .get_mut(&order.price.u128())
+                        .get_mut(&price_key)
                         .unwrap()
                         .remove(index)
                 })
@@ -476,12 +1132,36 @@ pub mod execute {
         ORDERS.save(deps.storage, order_id, &updated_order)?;
         ORDER_BOOKS.save(deps.storage, pair_id, &order_book)?;
 
+        let mut refund: Vec<CosmosMsg> =
+            release_order(deps.storage, &updated_order, &token_pair.base_token)?
+                .into_iter()
+                .collect();
+        // Hand back what the order still holds in escrow: base for the unfilled
+        // part of a buy, quote for the unfilled part of a sell
+        let remaining = updated_order.remaining_amount;
+        if !remaining.is_zero() {
+            refund.push(match updated_order.order_type {
+                OrderType::Buy => execute_transfer(
+                    &token_pair.base_token,
+                    &updated_order.owner,
+                    updated_order.price.checked_quote_to_base_ceil(remaining)?,
+                )?,
+                OrderType::Sell => {
+                    execute_transfer(&token_pair.quote_token, &updated_order.owner, remaining)?
+                }
+            });
+        }
+
         Ok(Response::new()
+            .add_messages(refund)
             .add_attribute("action", "cancel_order")
             .add_attribute("order_id", order_id.to_string()))
     }
 
     // Function to execute limit orders before using bonding curve
+    /// Market swap on one pair. Resting orders are crossed best price first and the
+    /// rest fills on the bonding curve. Buys spend `amount` base tokens, sells spend
+    /// `amount` quote tokens.
     pub fn execute_swap(
         deps: DepsMut,
         env: Env,
@@ -492,1190 +1172,2728 @@ pub mod execute {
         min_return: Uint128,
         order_type: OrderType,
     ) -> StdResult<Response> {
-        let config = CONFIG.load(deps.storage)?;
-        if !config.enabled {
-            return Err(StdError::generic_err("Trading is currently disabled"));
+        let token_pair = TOKEN_PAIRS.load(deps.storage, pair_id.clone())?;
+        if denom_id(&token_pair.quote_token) != token_address {
+            return Err(StdError::generic_err("Token does not belong to the pair"));
         }
 
-        let mut response = Response::new();
+        let operations = [SwapOperation {
+            pair_id,
+            order_type,
+        }];
+        let response = swap_through(deps, &env, &info, &operations, amount, min_return)?;
+        Ok(response.add_attribute("action", "swap"))
+    }
 
-        // Try to match with limit orders first
-        let (matched_amount, remaining_amount, match_response) = match_limit_orders(
-            deps.storage,
-            &info,
+    /// Swaps through a chain of pairs in one transaction. Each hop trades the output
+    /// of the one before it, intermediate amounts never leave the contract, and only
+    /// the final output is checked against `minimum_receive`.
+    pub fn execute_swap_operations(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        operations: Vec<SwapOperation>,
+        offer_amount: Uint128,
+        minimum_receive: Uint128,
+    ) -> StdResult<Response> {
+        let response = swap_through(
+            deps,
             &env,
-            pair_id.clone(),
-            amount,
-            &order_type,
-            min_return,
+            &info,
+            &operations,
+            offer_amount,
+            minimum_receive,
         )?;
-        response = response.add_attributes(match_response.attributes);
-
-        // If there's remaining amount, use bonding curve
-        if !remaining_amount.is_zero() {
-            let curve_response = execute_bonding_curve_swap(
-                deps,
-                env,
-                info,
-                pair_id,
-                token_address,
-                remaining_amount,
-                min_return - remaining_amount,
-                order_type,
-            )?;
-            response = response.add_attributes(curve_response.attributes);
-        }
-
-        Ok(response
-            .add_attribute("matched_amount", matched_amount)
-            .add_attribute("remaining_amount", remaining_amount))
+        Ok(response.add_attribute("action", "execute_swap_operations"))
     }
 
-    pub fn execute_graduate(
+    fn swap_through(
         deps: DepsMut,
-        _env: Env,
-        info: MessageInfo,
-        token_address: String,
+        env: &Env,
+        info: &MessageInfo,
+        operations: &[SwapOperation],
+        offer_amount: Uint128,
+        minimum_receive: Uint128,
     ) -> StdResult<Response> {
-        // Load config and token info
-        let config = CONFIG.load(deps.storage)?;
-        let mut token_info = TOKEN_INFO.load(deps.storage, token_address.clone())?;
-        let pool = POOLS.load(deps.storage, token_address.clone())?;
+        let (offer_denom, ask_denom) = validate_swap_operations(deps.storage, operations)?;
+        if offer_amount.is_zero() {
+            return Err(StdError::generic_err("Offer amount must be greater than 0"));
+        }
 
-        // Verify caller is contract admin
-        if info.sender != config.owner {
-            return Err(StdError::generic_err("Unauthorized"));
+        let mut response = Response::new().add_messages(receive_payment(
+            deps.as_ref(),
+            env,
+            info,
+            &offer_denom,
+            offer_amount,
+        )?);
+
+        let mut amount = offer_amount;
+        for operation in operations {
+            let plan = plan_swap_operation(deps.storage, operation, amount)?;
+            let hop_response = apply_swap_operation(deps.storage, env, &info.sender, &plan)?;
+            response = response
+                .add_submessages(hop_response.messages)
+                .add_attributes(hop_response.attributes);
+            amount = plan.return_amount;
         }
 
-        // Check if token is eligible for graduation
-        if token_info.graduated {
-            return Err(StdError::generic_err("Token already graduated"));
+        if amount < minimum_receive {
+            return Err(StdError::generic_err(format!(
+                "Slippage tolerance exceeded. Expected: {}, Minimum: {}",
+                amount, minimum_receive
+            )));
         }
-
-        if pool.token_sold != Uint128::from(config.bonding_curve_supply) {
-            return Err(StdError::generic_err("Some token have not been sold"));
+        if !amount.is_zero() {
+            response = response.add_message(execute_transfer(&ask_denom, &info.sender, amount)?);
         }
 
-        // Prepare messages for secondary AMM interaction
-        let mut messages: Vec<CosmosMsg> = vec![];
+        Ok(response.add_attributes(vec![
+            attr("offer_denom", denom_id(&offer_denom)),
+            attr("ask_denom", denom_id(&ask_denom)),
+            attr("offer_amount", offer_amount),
+            attr("return_amount", amount),
+        ]))
+    }
 
-        // Approve secondary AMM to spend tokens
-        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: token_address.clone(),
-            msg: to_json_binary(&Cw20ExecuteMsg::IncreaseAllowance {
-                spender: config.secondary_amm_address.to_string(),
-                amount: config.lp_supply.into(),
-                expires: None,
-            })?,
-            funds: vec![],
-        }));
-
-        // // Add liquidity to secondary AMM
-        // messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-        //     contract_addr: config.secondary_amm_address.to_string(),
-        //     msg: to_json_binary(&SecondaryAmmMsg::AddLiquidity {
-        //         token_address: token_address.clone(),
-        //         token_amount: config.lp_supply,
-        //         min_liquidity: Uint128::zero(),
-        //         max_tokens: pool.total_reserve_token,
-        //         expiration: None,
-        //     })?,
-        //     funds: vec![Coin {
-        //         denom: "uhuahua",
-        //         amount: pool.total_reserve_token,
-        //     }],
-        // }));
+    /// Output of a routed swap, worked out without touching state
+    pub fn simulate_swap_operations(
+        storage: &dyn Storage,
+        offer_amount: Uint128,
+        operations: &[SwapOperation],
+    ) -> StdResult<Uint128> {
+        validate_swap_operations(storage, operations)?;
+        operations
+            .iter()
+            .try_fold(offer_amount, |amount, operation| {
+                Ok(plan_swap_operation(storage, operation, amount)?.return_amount)
+            })
+    }
 
-        // Disable trading in bonding curve
-        token_info.graduated = true;
-        TOKEN_INFO.save(deps.storage, token_address.clone(), &token_info)?;
+    // Checks that each hop spends what the previous one returned and gives back the
+    // denoms the route takes in and pays out
+    fn validate_swap_operations(
+        storage: &dyn Storage,
+        operations: &[SwapOperation],
+    ) -> StdResult<(Denom, Denom)> {
+        let config = CONFIG.load(storage)?;
+        if !config.enabled {
+            return Err(StdError::generic_err("Trading is currently disabled"));
+        }
+        if operations.is_empty() {
+            return Err(StdError::generic_err("No swap operations given"));
+        }
+        if operations.len() > MAX_SWAP_OPERATIONS {
+            return Err(StdError::generic_err(format!(
+                "A route takes at most {} swap operations",
+                MAX_SWAP_OPERATIONS
+            )));
+        }
 
-        // Remove pool from bonding curve
-        POOLS.remove(deps.storage, token_address.clone());
+        let mut route: Option<(Denom, Denom)> = None;
+        for (i, operation) in operations.iter().enumerate() {
+            // Hops are planned against the state before the swap, so a pair may
+            // only be crossed once
+            if operations[..i]
+                .iter()
+                .any(|previous| previous.pair_id == operation.pair_id)
+            {
+                return Err(StdError::generic_err(format!(
+                    "Pair {} appears more than once in the route",
+                    operation.pair_id
+                )));
+            }
 
-        // 5. Emit graduation event
-        Ok(Response::new()
-            .add_messages(messages)
-            .add_attribute("action", "graduate")
-            .add_attribute("token", token_address)
-            .add_attribute("secondary_amm", config.secondary_amm_address))
-    }
+            let token_pair = TOKEN_PAIRS.load(storage, operation.pair_id.clone())?;
+            let (offer, ask) = match operation.order_type {
+                OrderType::Buy => (token_pair.base_token, token_pair.quote_token),
+                OrderType::Sell => (token_pair.quote_token, token_pair.base_token),
+            };
+            route = match route {
+                None => Some((offer, ask)),
+                Some((first, previous)) => {
+                    if denom_id(&previous) != denom_id(&offer) {
+                        return Err(StdError::generic_err(format!(
+                            "Swap operation on {} does not take the previous output {}",
+                            operation.pair_id,
+                            denom_id(&previous)
+                        )));
+                    }
+                    Some((first, ask))
+                }
+            };
+        }
 
-    // Helper function to check if token has graduated
-    pub fn is_token_graduated(storage: &dyn Storage, token_address: &str) -> StdResult<bool> {
-        let token_info = TOKEN_INFO.load(storage, token_address.to_string())?;
-        Ok(token_info.graduated)
+        Ok(route.unwrap())
     }
 
-    pub fn execute_update_config(
-        deps: DepsMut,
-        _env: Env,
-        info: MessageInfo,
-        token_factory: Option<Addr>,
-        fee_collector: Option<Addr>,
-        maker_fee: Option<Decimal>,
-        taker_fee: Option<Decimal>,
-        quote_token_total_supply: Option<Uint128>,
-        bonding_curve_supply: Option<Uint128>,
-        lp_supply: Option<Uint128>,
-        enabled: Option<bool>,
-    ) -> StdResult<Response> {
-        let mut config = CONFIG.load(deps.storage)?;
-
-        // Verify authority
-        if info.sender != config.owner {
-            return Err(StdError::generic_err("Unauthorized"));
-        }
+    // A resting order crossed by a swap. The taker pays the taker rate and the
+    // resting order the maker rate, each out of what it receives.
+    struct BookFill {
+        price_level: u128,
+        order_id: u64,
+        quote_amount: Uint128,
+        base_amount: Uint128,
+        buyer_fee: Uint128,
+        seller_fee: Uint128,
+    }
 
-        // Update fee configuration
-        if token_factory.is_some() {
-            config.token_factory = token_factory.unwrap();
-        }
+    // A priced curve trade. The fee is in the base currency and already out of
+    // return_amount on a sell, out of what reaches the reserve on a buy.
+    struct CurveFill {
+        token_address: String,
+        offer_amount: Uint128,
+        return_amount: Uint128,
+        fee: Uint128,
+        price: Price,
+    }
 
-        if fee_collector.is_some() {
-            config.fee_collector = fee_collector.unwrap();
-        }
+    struct SwapPlan {
+        token_pair: TokenPair,
+        pair_id: String,
+        order_type: OrderType,
+        offer_amount: Uint128,
+        fills: Vec<BookFill>,
+        curve: Option<CurveFill>,
+        return_amount: Uint128,
+    }
 
-        if maker_fee.is_some() {
-            assert!(!maker_fee.unwrap().is_zero());
-            assert!(maker_fee.unwrap() < Decimal::one());
-            config.maker_fee = maker_fee.unwrap();
+    // Works out one hop: resting orders are crossed best price first and whatever is
+    // left goes to the curve. Buys spend base tokens, sells spend quote tokens.
+    fn plan_swap_operation(
+        storage: &dyn Storage,
+        operation: &SwapOperation,
+        offer_amount: Uint128,
+    ) -> StdResult<SwapPlan> {
+        let pair_id = operation.pair_id.clone();
+        let token_pair = TOKEN_PAIRS.load(storage, pair_id.clone())?;
+        if !token_pair.enabled {
+            return Err(StdError::generic_err("Trading pair is disabled"));
         }
+        let is_buy = matches!(operation.order_type, OrderType::Buy);
+        let config = CONFIG.load(storage)?;
 
-        if taker_fee.is_some() {
-            assert!(!taker_fee.unwrap().is_zero());
-            assert!(taker_fee.unwrap() < Decimal::one());
-            config.taker_fee = taker_fee.unwrap();
-        }
+        let mut remaining = offer_amount;
+        let mut return_amount = Uint128::zero();
+        let mut fills = vec![];
 
-        if quote_token_total_supply.is_some() {
-            config.quote_token_total_supply = quote_token_total_supply.unwrap().into();
-        }
+        let order_book = if is_paused(storage, Some(&pair_id), PauseAction::BookTrading)? {
+            None
+        } else {
+            ORDER_BOOKS.may_load(storage, pair_id.clone())?
+        };
+        if let Some(order_book) = &order_book {
+            let levels: Vec<(&u128, &Vec<Order>)> = if is_buy {
+                order_book.sell_orders.iter().collect()
+            } else {
+                order_book.buy_orders.iter().rev().collect()
+            };
+            'levels: for (price_level, orders) in levels {
+                for order in orders
+                    .iter()
+                    .filter(|order| !order.remaining_amount.is_zero())
+                {
+                    let price = order.price;
+                    let (quote_amount, base_amount) = if is_buy {
+                        let quote_amount = std::cmp::min(
+                            order.remaining_amount,
+                            price.checked_base_to_quote(remaining)?,
+                        );
+                        let cost = price.checked_quote_to_base_ceil(quote_amount)?;
+                        (quote_amount, cost)
+                    } else {
+                        let quote_amount = std::cmp::min(order.remaining_amount, remaining);
+                        (quote_amount, price.checked_quote_to_base(quote_amount)?)
+                    };
+                    if quote_amount.is_zero() || base_amount.is_zero() {
+                        break 'levels;
+                    }
 
-        if bonding_curve_supply.is_some() {
-            config.bonding_curve_supply = bonding_curve_supply.unwrap().into();
+                    let (buyer_fee, seller_fee) = if is_buy {
+                        let buyer_fee = fee_amount(quote_amount, config.taker_fee)?;
+                        remaining -= base_amount;
+                        return_amount += quote_amount.checked_sub(buyer_fee)?;
+                        (buyer_fee, fee_amount(base_amount, config.maker_fee)?)
+                    } else {
+                        let seller_fee = fee_amount(base_amount, config.taker_fee)?;
+                        remaining -= quote_amount;
+                        return_amount += base_amount.checked_sub(seller_fee)?;
+                        (fee_amount(quote_amount, config.maker_fee)?, seller_fee)
+                    };
+                    fills.push(BookFill {
+                        price_level: *price_level,
+                        order_id: order.id,
+                        quote_amount,
+                        base_amount,
+                        buyer_fee,
+                        seller_fee,
+                    });
+                    if remaining.is_zero() {
+                        break 'levels;
+                    }
+                }
+            }
         }
 
-        if lp_supply.is_some() {
-            config.lp_supply = lp_supply.unwrap().into();
-        }
+        let mut curve = None;
+        if !remaining.is_zero() {
+            assert_not_paused(storage, Some(&pair_id), PauseAction::CurveTrading)?;
+            let token_address = denom_id(&token_pair.quote_token).to_string();
+            let pool = POOLS
+                .may_load(storage, token_address.clone())?
+                .ok_or_else(|| StdError::generic_err("Insufficient liquidity in pool"))?;
+            if !pool.enabled {
+                return Err(StdError::generic_err("Pool is disabled"));
+            }
 
-        if enabled.is_some() {
-            config.enabled = enabled.unwrap().into();
+            let fill = quote_curve(
+                storage,
+                &pool,
+                &token_address,
+                remaining,
+                is_buy,
+                config.curve_fee,
+            )?;
+            return_amount += fill.return_amount;
+            curve = Some(fill);
         }
 
-        CONFIG.save(deps.storage, &config)?;
-
-        Ok(Response::new().add_attribute("action", "update_config"))
+        Ok(SwapPlan {
+            token_pair,
+            pair_id,
+            order_type: operation.order_type.clone(),
+            offer_amount,
+            fills,
+            curve,
+            return_amount,
+        })
     }
 
-    fn match_orders(
-        deps: DepsMut,
+    // Settles a planned hop. The taker's side stays in the contract for the next hop,
+    // makers are paid in the token the taker spends.
+    fn apply_swap_operation(
+        storage: &mut dyn Storage,
         env: &Env,
-        pair_id: String,
-        is_buy: bool,
+        trader: &Addr,
+        plan: &SwapPlan,
     ) -> StdResult<Response> {
-        let mut order_book = ORDER_BOOKS.load(deps.storage, pair_id.clone())?;
-        let config = CONFIG.load(deps.storage)?;
-        let token_pair = TOKEN_PAIRS.load(deps.storage, pair_id.clone())?;
-        let mut next_trade_id = NEXT_TRADE_ID.load(deps.storage)?;
-
+        let token_pair = &plan.token_pair;
+        let is_buy = matches!(plan.order_type, OrderType::Buy);
         let mut response = Response::new();
-        let mut messages: Vec<SubMsg> = vec![];
 
-        if is_buy {
-            for (buy_price, buy_orders) in order_book.buy_orders.iter_mut().rev() {
-                for buy_order in buy_orders.iter_mut() {
-                    if buy_order.remaining_amount.is_zero() {
-                        continue;
-                    }
-                    for (sell_price, sell_orders) in order_book.sell_orders.iter_mut() {
-                        if sell_price > buy_price {
-                            break;
-                        }
-                        for sell_order in sell_orders.iter_mut() {
-                            if sell_order.remaining_amount.is_zero() {
-                                continue;
-                            }
-                            let trade_response = create_order(
-                                deps.storage,
-                                env,
-                                buy_order,
-                                sell_order,
-                                next_trade_id,
-                                &token_pair,
-                                &config,
-                            )?;
-                            messages.extend(trade_response.messages);
-                            response = response.add_attributes(trade_response.attributes);
-                            next_trade_id += 1;
-                        }
-                    }
+        if !plan.fills.is_empty() {
+            let mut order_book = ORDER_BOOKS.load(storage, plan.pair_id.clone())?;
+            for fill in &plan.fills {
+                let side = if is_buy {
+                    &mut order_book.sell_orders
+                } else {
+                    &mut order_book.buy_orders
+                };
+                let order = side
+                    .get_mut(&fill.price_level)
+                    .and_then(|orders| orders.iter_mut().find(|order| order.id == fill.order_id))
+                    .ok_or_else(|| StdError::generic_err("Order not found"))?;
+
+                order.remaining_amount -= fill.quote_amount;
+                order.filled_amount += fill.quote_amount;
+                if order.remaining_amount.is_zero() {
+                    order.status = OrderStatus::Filled;
                 }
-            }
-        } else {
-            for (sell_price, sell_orders) in order_book.sell_orders.iter_mut() {
-                for sell_order in sell_orders.iter_mut() {
-                    if sell_order.remaining_amount.is_zero() {
-                        continue;
-                    }
-                    for (buy_price, buy_orders) in order_book.buy_orders.iter_mut().rev() {
-                        if buy_price < sell_price {
-                            break;
-                        }
-                        for buy_order in buy_orders.iter_mut() {
-                            if buy_order.remaining_amount.is_zero() {
-                                continue;
-                            }
-                            let trade_response = create_order(
-                                deps.storage,
-                                env,
-                                buy_order,
-                                sell_order,
-                                next_trade_id,
-                                &token_pair,
-                                &config,
-                            )?;
-                            messages.extend(trade_response.messages);
-                            response = response.add_attributes(trade_response.attributes);
-                            next_trade_id += 1;
-                        }
-                    }
+                USER_ORDERS.save(storage, (order.owner.clone(), order.id), order)?;
+                ORDERS.save(storage, order.id, order)?;
+
+                // The market side of the trade has no order id
+                let (buyer, seller, buy_order_id, sell_order_id, maker_side, maker_proceeds) =
+                    if is_buy {
+                        let proceeds = fill.base_amount.checked_sub(fill.seller_fee)?;
+                        (
+                            trader,
+                            &order.owner,
+                            0,
+                            order.id,
+                            OrderType::Sell,
+                            execute_transfer(&token_pair.base_token, &order.owner, proceeds)?,
+                        )
+                    } else {
+                        let proceeds = fill.quote_amount.checked_sub(fill.buyer_fee)?;
+                        (
+                            &order.owner,
+                            trader,
+                            order.id,
+                            0,
+                            OrderType::Buy,
+                            execute_transfer(&token_pair.quote_token, &order.owner, proceeds)?,
+                        )
+                    };
+                let trade_id = execute_trade(
+                    storage,
+                    env,
+                    buyer,
+                    seller,
+                    plan.pair_id.clone(),
+                    buy_order_id,
+                    sell_order_id,
+                    fill.quote_amount,
+                    order.price,
+                    maker_side.clone(),
+                    fill.buyer_fee,
+                    fill.seller_fee,
+                )?;
+                accrue_fee(storage, &token_pair.quote_token, fill.buyer_fee)?;
+                accrue_fee(storage, &token_pair.base_token, fill.seller_fee)?;
+
+                response = response.add_message(maker_proceeds).add_attributes(vec![
+                    attr("event_type", "trade"),
+                    attr("trade_id", trade_id.to_string()),
+                    attr("pair_id", plan.pair_id.clone()),
+                    attr("price", order.price.to_string()),
+                    attr("amount", fill.quote_amount),
+                    attr("total", fill.base_amount),
+                    attr("maker_side", format!("{:?}", maker_side)),
+                    attr("buyer_fee", fill.buyer_fee),
+                    attr("seller_fee", fill.seller_fee),
+                ]);
+
+                if order.remaining_amount.is_zero() {
+                    response = response.add_messages(release_order(
+                        storage,
+                        order,
+                        &token_pair.base_token,
+                    )?);
                 }
             }
+            clean_up_order_book(&mut order_book);
+            ORDER_BOOKS.save(storage, plan.pair_id.clone(), &order_book)?;
         }
 
-        clean_up_order_book(&mut order_book);
-        ORDER_BOOKS.save(deps.storage, pair_id, &order_book)?;
-        NEXT_TRADE_ID.save(deps.storage, &next_trade_id)?;
+        let mut curve_amount = Uint128::zero();
+        if let Some(curve) = &plan.curve {
+            settle_curve(
+                storage,
+                env,
+                Some(trader),
+                token_pair,
+                &plan.order_type,
+                curve,
+            )?;
+            curve_amount = curve.offer_amount;
+        }
 
-        Ok(response.add_submessages(messages))
+        Ok(response.add_attributes(vec![
+            attr("hop_pair_id", plan.pair_id.clone()),
+            attr("hop_order_type", format!("{:?}", plan.order_type)),
+            attr("hop_offer_amount", plan.offer_amount),
+            attr("hop_book_amount", plan.offer_amount - curve_amount),
+            attr("hop_curve_amount", curve_amount),
+            attr("hop_return_amount", plan.return_amount),
+        ]))
     }
 
-    fn validate_and_handle_tokens(
-        deps: &DepsMut,
-        env: &Env,
-        info: &MessageInfo,
-        token_pair: &TokenPair,
+    // Prices a curve trade against the pool. The fee comes out of the base currency
+    // side: off what a buy spends, off what a sell receives.
+    fn quote_curve(
+        storage: &dyn Storage,
+        pool: &Pool,
+        token_address: &str,
         amount: Uint128,
-        price: Uint128,
         is_buy: bool,
-    ) -> StdResult<()> {
-        if is_buy {
-            let total_price = price * amount;
-            let denom = token_pair.base_token.clone();
-            validate_native_token_payment(info, &denom, total_price)?;
+        fee_rate: Decimal,
+    ) -> StdResult<CurveFill> {
+        let token_info = TOKEN_INFO.load(storage, token_address.to_string())?;
+        if !is_buy && amount > pool.token_sold {
+            return Err(StdError::generic_err("Insufficient liquidity in pool"));
+        }
+
+        let (price, return_amount, fee) = if is_buy {
+            let fee = fee_amount(amount, fee_rate)?;
+            let spent = amount.checked_sub(fee)?;
+            // The curve runs over quote atomics: the spot price turns the spend into
+            // the supply it reaches, whose average price then fixes the fill
+            let spot_price = calculate_exponential_price(
+                storage,
+                token_address.to_string(),
+                pool.token_sold,
+                Uint128::one(),
+                true,
+            )?;
+            let estimate = spot_price.checked_base_to_quote(spent)?;
+            if estimate.is_zero() {
+                return Err(StdError::generic_err(
+                    "Buy amount is too small to receive any tokens",
+                ));
+            }
+            let price = calculate_exponential_price(
+                storage,
+                token_address.to_string(),
+                pool.token_sold,
+                estimate,
+                true,
+            )?;
+            let tokens_to_receive = price.checked_base_to_quote(spent)?;
+            if tokens_to_receive > token_info.bonding_curve_supply - pool.token_sold {
+                return Err(StdError::generic_err("Insufficient liquidity in pool"));
+            }
+            (price, tokens_to_receive, fee)
         } else {
-            // Handle native token cases for sell orders
-            validate_cw20_token_payment(
-                &deps.as_ref(),
-                env,
-                info,
-                &token_pair.quote_token,
+            let price = calculate_exponential_price(
+                storage,
+                token_address.to_string(),
+                pool.token_sold,
                 amount,
+                false,
             )?;
-        }
-        Ok(())
+            let base_to_receive = price.checked_quote_to_base(amount)?;
+            if base_to_receive > pool.total_reserve_token {
+                return Err(StdError::generic_err("Insufficient liquidity in pool"));
+            }
+            let fee = fee_amount(base_to_receive, fee_rate)?;
+            (price, base_to_receive.checked_sub(fee)?, fee)
+        };
+
+        Ok(CurveFill {
+            token_address: token_address.to_string(),
+            offer_amount: amount,
+            return_amount,
+            fee,
+            price,
+        })
     }
 
-    fn validate_native_token_payment(
-        info: &MessageInfo,
-        denom: &str,
-        required_amount: Uint128,
+    // Books a priced curve trade on its pool and collects its fee. Protocol trades
+    // pass no trader and skip the launch rules.
+    fn settle_curve(
+        storage: &mut dyn Storage,
+        env: &Env,
+        trader: Option<&Addr>,
+        token_pair: &TokenPair,
+        order_type: &OrderType,
+        fill: &CurveFill,
     ) -> StdResult<()> {
-        // Find the coin with matching denom in the sent funds
-        let sent_amount = info
-            .funds
-            .iter()
-            .find(|coin| coin.denom == denom)
-            .map(|coin| coin.amount)
-            .unwrap_or_default();
+        let mut pool = POOLS.load(storage, fill.token_address.clone())?;
+        let tokens = match order_type {
+            OrderType::Buy => {
+                pool.total_reserve_token += fill.offer_amount.checked_sub(fill.fee)?;
+                pool.token_sold += fill.return_amount;
+                fill.return_amount
+            }
+            OrderType::Sell => {
+                pool.token_sold -= fill.offer_amount;
+                pool.total_reserve_token -= fill.return_amount + fill.fee;
+                fill.offer_amount
+            }
+        };
+        if let Some(trader) = trader {
+            assert_launch_rules(storage, env, &pool, trader, order_type, tokens)?;
+        }
 
-        // Check if sent amount matches required amount
-        if sent_amount < required_amount {
-            return Err(StdError::generic_err(format!(
-                "Insufficient native token sent. Required: {}, Sent: {}",
-                required_amount, sent_amount
-            )));
-        }
-
-        // Check if excess amount was sent
-        if sent_amount > required_amount {
-            return Err(StdError::generic_err(format!(
-                "Excess native token sent. Required: {}, Sent: {}",
-                required_amount, sent_amount
-            )));
-        }
+        pool.last_price = fill.price;
+        pool.total_trades += Uint128::new(1);
+        pool.total_fees_collected += fill.fee;
+        POOLS.save(storage, fill.token_address.clone(), &pool)?;
 
-        Ok(())
+        collect_curve_fee(
+            storage,
+            &fill.token_address,
+            &token_pair.base_token,
+            fill.fee,
+        )
     }
 
-    fn validate_cw20_token_payment(
-        deps: &Deps,
-        env: &Env,
-        info: &MessageInfo,
+    // Sets aside the token's buyback share of a curve fee, the rest goes to the treasury
+    fn collect_curve_fee(
+        storage: &mut dyn Storage,
         token_address: &str,
-        required_amount: Uint128,
+        denom: &Denom,
+        fee: Uint128,
     ) -> StdResult<()> {
-        // Query token balance
-        let balance: cw20::BalanceResponse = deps.querier.query_wasm_smart(
-            token_address,
-            &cw20::Cw20QueryMsg::Balance {
-                address: info.sender.to_string(),
-            },
-        )?;
-
-        // Check if user has sufficient balance
-        if balance.balance < required_amount {
-            return Err(StdError::generic_err(format!(
-                "Insufficient CW20 token balance. Required: {}, Balance: {}",
-                required_amount, balance.balance
-            )));
-        }
-
-        // Query allowance
-        let allowance: cw20::AllowanceResponse = deps.querier.query_wasm_smart(
-            token_address,
-            &cw20::Cw20QueryMsg::Allowance {
-                owner: info.sender.to_string(),
-                spender: env.contract.address.to_string(),
-            },
-        )?;
-
-        // Check if contract has sufficient allowance
-        if allowance.allowance < required_amount {
-            return Err(StdError::generic_err(format!(
-                "Insufficient CW20 token allowance. Required: {}, Allowance: {}",
-                required_amount, allowance.allowance
-            )));
+        let mut treasury_fee = fee;
+        if let Some(mut buyback) = BUYBACKS.may_load(storage, token_address.to_string())? {
+            let set_aside = fee.mul_floor(buyback.share);
+            buyback.pending += set_aside;
+            treasury_fee -= set_aside;
+            BUYBACKS.save(storage, token_address.to_string(), &buyback)?;
         }
-
-        Ok(())
+        accrue_fee(storage, denom, treasury_fee)
     }
 
-    /// Creates a trade between a buy order and a sell order, executing token transfers for both parties.
-    ///
-    /// This function handles the matching of a buy order (wanting quote tokens) with a sell order (wanting base tokens),
-    /// ensuring that tokens are exchanged correctly: the buyer receives quote tokens (CW20), and the seller receives
-    /// base tokens (native, e.g., "uhuahua"), with fees deducted and sent to the fee collector.
-    ///
-    /// # Arguments
-    /// * `storage` - Mutable storage reference for updating order states.
-    /// * `env` - Environment info, including block time and contract address.
-    /// * `buy_order` - The buy order being matched (buyer wants quote tokens).
-    /// * `sell_order` - The sell order being matched (seller wants base tokens).
-    /// * `trade_id` - Unique identifier for this trade.
-    /// * `token_pair` - Pair info defining base (native) and quote (CW20) tokens.
-    /// * `config` - Contract configuration with fee rates and collector address.
-    ///
-    /// # Returns
-    /// A `StdResult<Response>` containing transfer messages and trade attributes.
-    fn create_order(
-        storage: &mut dyn Storage,
-        env: &Env,
-        buy_order: &mut Order,
-        sell_order: &mut Order,
-        trade_id: u64,
-        token_pair: &TokenPair,
-        config: &Config,
+    pub fn execute_set_buyback_policy(
+        deps: DepsMut,
+        info: MessageInfo,
+        token_address: String,
+        share: Decimal,
     ) -> StdResult<Response> {
-        // Determine the trade amount as the lesser of the remaining amounts from both orders
-        let trade_amount = std::cmp::min(buy_order.remaining_amount, sell_order.remaining_amount);
-
-        // Use the sell order's price as the trade price (price-time priority)
-        let trade_price = sell_order.price;
-
-        // Calculate total cost in base tokens (price * amount)
-        let total_price = trade_amount
-            .checked_mul(trade_price)
-            .map_err(|e| StdError::overflow(e))?;
+        let config = CONFIG.load(deps.storage)?;
+        let roles = ROLES.may_load(deps.storage)?.unwrap_or_default();
+        assert_owner_or_role(&config, &roles.fee_manager, &info.sender)?;
 
-        // Calculate maker and taker fees based on order timestamps
-        // - Maker gets lower fee (older order), taker gets higher fee (newer order)
-        let (maker_fee_amount, taker_fee_amount) = if buy_order.timestamp < sell_order.timestamp {
-            (
-                (Decimal::new(total_price) * config.maker_fee).to_uint_ceil(), // Buy order is maker
-                (Decimal::new(total_price) * config.taker_fee).to_uint_ceil(), // Sell order is taker
-            )
-        } else {
-            (
-                (Decimal::new(total_price) * config.taker_fee).to_uint_ceil(), // Buy order is taker
-                (Decimal::new(total_price) * config.maker_fee).to_uint_ceil(), // Sell order is maker
-            )
+        if share > Decimal::one() {
+            return Err(StdError::generic_err(
+                "Buyback share must be between 0 and 1.",
+            ));
+        }
+        // Curve fees only accrue while the token trades on its curve
+        let pool = POOLS
+            .may_load(deps.storage, token_address.clone())?
+            .ok_or_else(|| StdError::generic_err("Token has no bonding curve pool"))?;
+
+        let buyback = match BUYBACKS.may_load(deps.storage, token_address.clone())? {
+            Some(buyback) => Buyback { share, ..buyback },
+            None => Buyback {
+                pair_id: pool.pair_id,
+                share,
+                pending: Uint128::zero(),
+                spent: Uint128::zero(),
+                burned: Uint128::zero(),
+            },
         };
+        BUYBACKS.save(deps.storage, token_address.clone(), &buyback)?;
 
-        // Calculate amounts to transfer after fees
-        let buyer_receives = trade_amount; // Buyer gets full quote token amount they bought
-        let seller_receives = total_price
-            .checked_sub(maker_fee_amount)? // Subtract maker fee
-            .checked_sub(taker_fee_amount)?; // Subtract taker fee, seller gets net base tokens
+        Ok(Response::new()
+            .add_attribute("action", "set_buyback_policy")
+            .add_attribute("token", token_address)
+            .add_attribute("share", share.to_string()))
+    }
 
-        // Update buy order state: reduce remaining, increase filled
-        buy_order.remaining_amount = buy_order.remaining_amount.checked_sub(trade_amount)?;
-        buy_order.filled_amount = buy_order.filled_amount.checked_add(trade_amount)?;
-        if buy_order.remaining_amount.is_zero() {
-            buy_order.status = OrderStatus::Filled; // Mark as filled if fully executed
+    pub fn execute_buyback(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_address: String,
+        min_tokens_out: Uint128,
+    ) -> StdResult<Response> {
+        let config = CONFIG.load(deps.storage)?;
+        let roles = ROLES.may_load(deps.storage)?.unwrap_or_default();
+        assert_owner_or_role(&config, &roles.fee_manager, &info.sender)?;
+
+        let mut buyback = BUYBACKS
+            .may_load(deps.storage, token_address.clone())?
+            .ok_or_else(|| StdError::generic_err("Token has no buyback policy"))?;
+        if buyback.pending.is_zero() {
+            return Err(StdError::generic_err("No fees set aside for a buyback"));
         }
+        let amount = buyback.pending;
+        buyback.pending = Uint128::zero();
+        buyback.spent += amount;
+
+        let token_pair = TOKEN_PAIRS.load(deps.storage, buyback.pair_id.clone())?;
+        let token_info = TOKEN_INFO.load(deps.storage, token_address.clone())?;
+        let response = Response::new()
+            .add_attribute("action", "buyback")
+            .add_attribute("token", token_address.clone())
+            .add_attribute("spent", amount);
+
+        if !token_info.graduated {
+            assert_not_paused(
+                deps.storage,
+                Some(&buyback.pair_id),
+                PauseAction::CurveTrading,
+            )?;
+            let pool = POOLS.load(deps.storage, token_address.clone())?;
+            if !pool.enabled {
+                return Err(StdError::generic_err("Pool is disabled"));
+            }
+            // The buyback pays no curve fee, it would only come back to it
+            let fill = quote_curve(
+                deps.storage,
+                &pool,
+                &token_address,
+                amount,
+                true,
+                Decimal::zero(),
+            )?;
+            if fill.return_amount < min_tokens_out {
+                return Err(StdError::generic_err(format!(
+                    "Slippage tolerance exceeded. Expected: {}, Minimum: {}",
+                    fill.return_amount, min_tokens_out
+                )));
+            }
+            settle_curve(
+                deps.storage,
+                &env,
+                None,
+                &token_pair,
+                &OrderType::Buy,
+                &fill,
+            )?;
 
-        // Update sell order state: reduce remaining, increase filled
-        sell_order.remaining_amount = sell_order.remaining_amount.checked_sub(trade_amount)?;
-        sell_order.filled_amount = sell_order.filled_amount.checked_add(trade_amount)?;
-        if sell_order.remaining_amount.is_zero() {
-            sell_order.status = OrderStatus::Filled; // Mark as filled if fully executed
+            buyback.burned += fill.return_amount;
+            BUYBACKS.save(deps.storage, token_address, &buyback)?;
+            return Ok(response
+                .add_message(burn_tokens(&token_pair.quote_token, fill.return_amount)?)
+                .add_attribute("burned", fill.return_amount));
         }
 
-        // Persist updated order states to storage
-        USER_ORDERS.save(storage, (buy_order.owner.clone(), buy_order.id), buy_order)?;
-        USER_ORDERS.save(
-            storage,
-            (sell_order.owner.clone(), sell_order.id),
-            sell_order,
+        // Graduated tokens are bought on the secondary AMM, the reply burns what
+        // the swap sent back
+        let balance_before = balance_of(deps.as_ref(), &env, &token_pair.quote_token)?;
+        PENDING_BUYBACK.save(
+            deps.storage,
+            &PendingBuyback {
+                token_address: token_address.clone(),
+                balance_before,
+            },
         )?;
-        ORDERS.save(storage, buy_order.id, buy_order)?;
-        ORDERS.save(storage, sell_order.id, sell_order)?;
-
-        // Prepare messages for token transfers
-        let mut messages: Vec<CosmosMsg> = vec![];
-
-        // Transfer quote tokens (CW20) to the buyer from the contract
-        // - Buyer placed a buy order, paid base tokens, now gets quote tokens
-        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: token_pair.quote_token.clone(),
-            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
-                recipient: buy_order.owner.to_string(),
-                amount: buyer_receives,
-            })?,
-            funds: vec![],
-        }));
+        BUYBACKS.save(deps.storage, token_address, &buyback)?;
 
-        // Transfer base tokens (native) to the seller from the contract
-        // - Seller placed a sell order, provided quote tokens, now gets base tokens minus fees
-        messages.push(CosmosMsg::Bank(BankMsg::Send {
-            to_address: sell_order.owner.to_string(),
-            amount: vec![Coin {
-                denom: token_pair.base_token.clone(),
-                amount: seller_receives,
+        let funds = match &token_pair.base_token {
+            Denom::Native(denom) => vec![Coin {
+                denom: denom.clone(),
+                amount,
             }],
-        }));
-
-        // Transfer combined fees to the fee collector (in base tokens)
-        let total_fees = maker_fee_amount.checked_add(taker_fee_amount)?;
-        if !total_fees.is_zero() {
-            messages.push(CosmosMsg::Bank(BankMsg::Send {
-                to_address: config.fee_collector.to_string(),
-                amount: vec![Coin {
-                    denom: token_pair.base_token.clone(),
-                    amount: total_fees,
-                }],
+            Denom::Cw20(_) => vec![],
+        };
+        let mut messages: Vec<CosmosMsg> = vec![];
+        if let Denom::Cw20(token) = &token_pair.base_token {
+            messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: token.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::IncreaseAllowance {
+                    spender: config.secondary_amm_address.to_string(),
+                    amount,
+                    expires: None,
+                })?,
+                funds: vec![],
             }));
         }
+        let swap = WasmMsg::Execute {
+            contract_addr: config.secondary_amm_address.to_string(),
+            msg: to_json_binary(&SecondaryAmmMsg::Swap {
+                offer_denom: token_pair.base_token.clone(),
+                offer_amount: amount,
+                ask_denom: token_pair.quote_token.clone(),
+                min_output: min_tokens_out,
+            })?,
+            funds,
+        };
 
-        // Construct response with transfer messages and trade details
-        Ok(Response::new()
+        Ok(response
             .add_messages(messages)
-            .add_attribute("event_type", "trade")
-            .add_attribute("trade_id", trade_id.to_string())
-            .add_attribute("pair_id", buy_order.pair_id.clone())
-            .add_attribute("buy_order_id", buy_order.id.to_string())
-            .add_attribute("sell_order_id", sell_order.id.to_string())
-            .add_attribute("price", trade_price.to_string())
-            .add_attribute("amount", trade_amount.to_string())
-            .add_attribute("total", total_price.to_string())
-            .add_attribute("maker_fee", maker_fee_amount.to_string())
-            .add_attribute("taker_fee", taker_fee_amount.to_string())
-            .add_attribute("buyer_receives", buyer_receives.to_string())
-            .add_attribute("seller_receives", seller_receives.to_string())
-            .add_attribute("base_token", token_pair.base_token.clone())
-            .add_attribute("quote_token", token_pair.quote_token.clone())
-            .add_attribute("timestamp", env.block.time.seconds().to_string()))
+            .add_submessage(SubMsg::reply_on_success(swap, REPLY_BUYBACK_ID)))
     }
 
-    fn execute_cw20_transfer(
-        token_address: &str,
-        from: &Addr,
-        to: &Addr,
-        amount: Uint128,
-    ) -> StdResult<CosmosMsg> {
-        Ok(WasmMsg::Execute {
-            contract_addr: token_address.to_string(),
-            msg: to_json_binary(&cw20::Cw20ExecuteMsg::TransferFrom {
-                owner: from.to_string(),
-                recipient: to.to_string(),
-                amount,
-            })?,
-            funds: vec![],
+    // Burns whatever the secondary AMM swap of a buyback sent back
+    pub fn reply_buyback(deps: DepsMut, env: Env) -> StdResult<Response> {
+        let pending = PENDING_BUYBACK.load(deps.storage)?;
+        PENDING_BUYBACK.remove(deps.storage);
+        let mut buyback = BUYBACKS.load(deps.storage, pending.token_address.clone())?;
+        let token_pair = TOKEN_PAIRS.load(deps.storage, buyback.pair_id.clone())?;
+
+        let bought = balance_of(deps.as_ref(), &env, &token_pair.quote_token)?
+            .checked_sub(pending.balance_before)?;
+        buyback.burned += bought;
+        BUYBACKS.save(deps.storage, pending.token_address.clone(), &buyback)?;
+
+        let mut response = Response::new()
+            .add_attribute("action", "buyback_burn")
+            .add_attribute("token", pending.token_address)
+            .add_attribute("burned", bought);
+        if !bought.is_zero() {
+            response = response.add_message(burn_tokens(&token_pair.quote_token, bought)?);
         }
-        .into())
+        Ok(response)
     }
 
-    // Helper function to handle native token transfers
-    fn execute_native_transfer(denom: &str, to: &Addr, amount: Uint128) -> StdResult<CosmosMsg> {
-        Ok(BankMsg::Send {
-            to_address: to.to_string(),
-            amount: vec![Coin {
-                denom: denom.to_string(),
-                amount,
-            }],
-        }
-        .into())
+    fn burn_tokens(denom: &Denom, amount: Uint128) -> StdResult<CosmosMsg> {
+        Ok(match denom {
+            Denom::Native(denom) => BankMsg::Burn {
+                amount: vec![Coin {
+                    denom: denom.clone(),
+                    amount,
+                }],
+            }
+            .into(),
+            Denom::Cw20(token) => WasmMsg::Execute {
+                contract_addr: token.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Burn { amount })?,
+                funds: vec![],
+            }
+            .into(),
+        })
     }
 
-    fn clean_up_order_book(order_book: &mut OrderBook) {
-        // Remove filled buy orders
-        order_book.buy_orders.retain(|_, orders| {
-            orders.retain(|order| !order.remaining_amount.is_zero());
-            !orders.is_empty()
-        });
-
-        // Remove filled sell orders
-        order_book.sell_orders.retain(|_, orders| {
-            orders.retain(|order| !order.remaining_amount.is_zero());
-            !orders.is_empty()
-        });
+    // The contract's own holding of a token
+    fn balance_of(deps: Deps, env: &Env, denom: &Denom) -> StdResult<Uint128> {
+        match denom {
+            Denom::Native(denom) => Ok(deps
+                .querier
+                .query_balance(&env.contract.address, denom)?
+                .amount),
+            Denom::Cw20(token) => {
+                let balance: cw20::BalanceResponse = deps.querier.query_wasm_smart(
+                    token,
+                    &cw20::Cw20QueryMsg::Balance {
+                        address: env.contract.address.to_string(),
+                    },
+                )?;
+                Ok(balance.balance)
+            }
+        }
     }
 
-    fn match_limit_orders(
-        storage: &mut dyn Storage,
-        info: &MessageInfo,
-        env: &Env,
-        pair_id: String,
-        amount: Uint128,
-        order_type: &OrderType,
-        min_return: Uint128,
-    ) -> StdResult<(Uint128, Uint128, Response)> {
-        let mut response = Response::new();
-        let mut order_book = ORDER_BOOKS.load(storage, pair_id.clone())?;
-        let config = CONFIG.load(storage)?;
-        let token_pair = TOKEN_PAIRS.load(storage, pair_id.clone())?;
-        let mut next_trade_id = NEXT_TRADE_ID.load(storage)?;
+    pub fn execute_graduate(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_address: String,
+    ) -> StdResult<Response> {
+        // Load config and token info
+        let config = CONFIG.load(deps.storage)?;
+        let mut token_info = TOKEN_INFO.load(deps.storage, token_address.clone())?;
+        let pool = POOLS.load(deps.storage, token_address.clone())?;
 
-        let mut matched_amount = Uint128::zero();
-        let mut remaining_amount = amount;
-        let mut total_return_amount = Uint128::zero();
+        // Verify caller is contract admin or the graduation operator
+        let roles = ROLES.may_load(deps.storage)?.unwrap_or_default();
+        assert_owner_or_role(&config, &roles.graduator, &info.sender)?;
+        assert_not_paused(deps.storage, Some(&pool.pair_id), PauseAction::Graduation)?;
 
-        let orders_to_match = match order_type {
-            OrderType::Buy => &mut order_book.sell_orders,
-            OrderType::Sell => &mut order_book.buy_orders,
-        };
+        // Check if token is eligible for graduation
+        if token_info.graduated {
+            return Err(StdError::generic_err("Token already graduated"));
+        }
 
-        // Sort orders by price (best price first)
-        let mut price_levels: Vec<_> = orders_to_match.keys().cloned().collect();
-        match order_type {
-            OrderType::Buy => price_levels.sort(), // Ascending for buys (lowest price first)
-            OrderType::Sell => price_levels.sort_by(|a, b| b.cmp(a)), // Descending for sells (highest price first)
+        // A pool graduates once it sells out, or earlier once it raised the
+        // graduation reserve its currency had at launch
+        let graduation_reserve = token_info.graduation_reserve;
+        let sold_out = pool.token_sold == token_info.bonding_curve_supply;
+        let raised =
+            !graduation_reserve.is_zero() && pool.total_reserve_token >= graduation_reserve;
+        if !sold_out && !raised {
+            return Err(StdError::generic_err("Some token have not been sold"));
         }
 
-        for price_level in price_levels {
-            if remaining_amount.is_zero() {
-                break;
-            }
+        // Locked LP tokens go back to the token creator, never to the owner
+        let creator = deps
+            .querier
+            .query_wasm_smart::<GetTokenInfoResponse>(
+                &config.token_factory,
+                &TokenFactoryQueryMsg::GetTokenInfo {
+                    address: token_address.clone(),
+                },
+            )?
+            .token_info
+            .creator;
 
-            let orders = orders_to_match.get_mut(&price_level).unwrap();
-            let mut i = 0;
-            while i < orders.len() && !remaining_amount.is_zero() {
-                let order = &mut orders[i];
+        // Prepare messages for secondary AMM interaction
+        let mut messages: Vec<CosmosMsg> = vec![];
 
-                // Skip already filled orders or orders with different price
-                if order.remaining_amount.is_zero() || order.price != Uint128::from(price_level) {
-                    i += 1;
-                    continue;
-                }
+        // Approve secondary AMM to spend tokens. Native tokens need no allowance,
+        // they are sent along as funds.
+        let token_pair = TOKEN_PAIRS.load(deps.storage, pool.pair_id.clone())?;
+        let token_amount = token_info.lp_supply;
+        let base_amount = pool.total_reserve_token;
+        let mut funds = vec![];
+        for (denom, amount) in [
+            (&token_pair.quote_token, token_amount),
+            (&token_pair.base_token, base_amount),
+        ] {
+            match denom {
+                Denom::Cw20(token) => messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: token.to_string(),
+                    msg: to_json_binary(&Cw20ExecuteMsg::IncreaseAllowance {
+                        spender: config.secondary_amm_address.to_string(),
+                        amount,
+                        expires: None,
+                    })?,
+                    funds: vec![],
+                })),
+                Denom::Native(denom) => funds.push(Coin {
+                    denom: denom.clone(),
+                    amount,
+                }),
+            }
+        }
+        funds.sort_by(|a, b| a.denom.cmp(&b.denom));
+
+        // Graduating on the reserve leaves part of the curve supply unsold. It
+        // would sit in the contract for good, so it is burned.
+        let unsold_burned = token_info
+            .bonding_curve_supply
+            .checked_sub(pool.token_sold)?;
+        if !unsold_burned.is_zero() {
+            messages.push(burn_tokens(&token_pair.quote_token, unsold_burned)?);
+        }
 
-                let match_amount = std::cmp::min(remaining_amount, order.remaining_amount);
-                let trade_price = Uint128::from(price_level);
-                let trade_return_amount = match_amount * trade_price;
+        // Add liquidity to secondary AMM, the reply deals with the LP tokens
+        let provide_liquidity = WasmMsg::Execute {
+            contract_addr: config.secondary_amm_address.to_string(),
+            msg: to_json_binary(&SecondaryAmmMsg::ProvideLiquidity {
+                token_denom: token_pair.quote_token.clone(),
+                token_amount,
+                base_denom: token_pair.base_token.clone(),
+                base_amount,
+            })?,
+            funds,
+        };
+        GRADUATIONS.save(
+            deps.storage,
+            token_address.clone(),
+            &GraduationRecord {
+                pair_id: pool.pair_id.clone(),
+                graduated_at: env.block.time,
+                token_amount,
+                base_amount,
+                unsold_burned,
+                lp_recipient: creator,
+                lp_token: None,
+                lp_amount: Uint128::zero(),
+                lp_outcome: LpOutcome::Pending,
+            },
+        )?;
+        PENDING_GRADUATION.save(deps.storage, &token_address)?;
 
-                // Calculate fees
-                let total_price = match_amount * trade_price;
-                let maker_fee = (Decimal::new(total_price) * config.maker_fee)
-                    .checked_div(Decimal::percent(100))
-                    .unwrap();
-                let taker_fee = (Decimal::new(total_price) * config.taker_fee)
-                    .checked_div(Decimal::percent(100))
-                    .unwrap();
+        // Disable trading in bonding curve
+        token_info.graduated = true;
+        TOKEN_INFO.save(deps.storage, token_address.clone(), &token_info)?;
 
-                if !match_amount.is_zero() {
-                    // Determine buyer and seller based on order type
-                    let owner_clone = order.owner.clone();
-                    let (buyer, seller, buy_order_id, sell_order_id) = match order_type {
-                        OrderType::Buy => {
-                            (order.owner.clone(), &info.sender, order.id, next_trade_id)
-                        }
-                        OrderType::Sell => {
-                            // Bind the clone to a variable
-                            (info.sender.clone(), &owner_clone, next_trade_id, order.id)
-                        }
-                    };
-
-                    // Execute the trade
-                    execute_trade(
-                        storage,
-                        env,
-                        &buyer,
-                        &seller,
-                        pair_id.clone(),
-                        buy_order_id,
-                        sell_order_id,
-                        match_amount,
-                        trade_price,
-                        maker_fee.to_uint_ceil(),
-                        taker_fee.to_uint_ceil(),
-                    )?;
-
-                    // Create trade event attributes
-                    let trade_attrs = vec![
-                        ("event_type", "trade".to_string()),
-                        ("trade_id", next_trade_id.to_string()),
-                        ("pair_id", pair_id.clone()),
-                        (
-                            "buy_order_id",
-                            if order_type == &OrderType::Buy {
-                                "market_order".to_string()
-                            } else {
-                                order.id.to_string()
-                            },
-                        ),
-                        (
-                            "sell_order_id",
-                            if order_type == &OrderType::Sell {
-                                "market_order".to_string()
-                            } else {
-                                order.id.to_string()
-                            },
-                        ),
-                        ("price", trade_price.to_string()),
-                        ("amount", match_amount.to_string()),
-                        ("total", total_price.to_string()),
-                        ("maker_fee", maker_fee.to_string()),
-                        ("taker_fee", taker_fee.to_string()),
-                        ("base_token", token_pair.base_token.clone()),
-                        ("quote_token", token_pair.quote_token.clone()),
-                        ("timestamp", env.block.time.seconds().to_string()),
-                    ]
-                    .into_iter()
-                    .map(|(k, v)| attr(k, v))
-                    .collect::<Vec<_>>();
-
-                    response = response.add_attributes(trade_attrs);
-
-                    // Update amounts
-                    matched_amount += match_amount;
-                    remaining_amount -= match_amount;
-                    order.remaining_amount -= match_amount;
-                    order.filled_amount += match_amount;
-                    total_return_amount += trade_return_amount;
-
-                    next_trade_id += 1;
-                }
+        // Remove pool from bonding curve
+        POOLS.remove(deps.storage, token_address.clone());
 
-                // Check if the total return amount meets the minimum return requirement
-                if total_return_amount >= min_return {
-                    break;
-                }
+        // 5. Emit graduation event
+        Ok(Response::new()
+            .add_messages(messages)
+            .add_submessage(SubMsg::reply_on_success(
+                provide_liquidity,
+                REPLY_GRADUATION_ID,
+            ))
+            .add_attribute("action", "graduate")
+            .add_attribute("token", token_address)
+            .add_attribute("secondary_amm", config.secondary_amm_address))
+    }
 
-                i += 1;
+    // Burns the LP tokens the secondary AMM minted for a graduation, or locks them
+    pub fn reply_graduation(
+        deps: DepsMut,
+        env: Env,
+        liquidity: ProvideLiquidityResponse,
+    ) -> StdResult<Response> {
+        let token_address = PENDING_GRADUATION.load(deps.storage)?;
+        PENDING_GRADUATION.remove(deps.storage);
+        // The policy snapshotted at creation, not the live config one
+        let token_info = TOKEN_INFO.load(deps.storage, token_address.clone())?;
+        let mut record = GRADUATIONS.load(deps.storage, token_address.clone())?;
+        record.lp_token = Some(liquidity.lp_token.clone());
+        record.lp_amount = liquidity.lp_amount;
+
+        let mut response = Response::new()
+            .add_attribute("action", "graduation_lp")
+            .add_attribute("token", token_address.clone())
+            .add_attribute("lp_token", denom_id(&liquidity.lp_token))
+            .add_attribute("lp_amount", liquidity.lp_amount);
+        match token_info.lp_policy {
+            LpPolicy::Burn => {
+                record.lp_outcome = LpOutcome::Burned;
+                response = response
+                    .add_message(burn_tokens(&liquidity.lp_token, liquidity.lp_amount)?)
+                    .add_attribute("lp_outcome", "burned");
+            }
+            LpPolicy::Lock { duration } => {
+                let unlocks_at = env.block.time.plus_seconds(duration);
+                record.lp_outcome = LpOutcome::Locked { unlocks_at };
+                response = response
+                    .add_attribute("lp_outcome", "locked")
+                    .add_attribute("unlocks_at", unlocks_at.to_string());
             }
-
-            // Remove filled orders
-            orders.retain(|order| !order.remaining_amount.is_zero());
         }
+        GRADUATIONS.save(deps.storage, token_address, &record)?;
 
-        // Clean up empty price levels and save updated state
-        clean_up_order_book(&mut order_book);
-
-        ORDER_BOOKS.save(storage, pair_id, &order_book)?;
-        NEXT_TRADE_ID.save(storage, &next_trade_id)?;
-
-        // Add final amounts to response
-        response = response.add_attributes(vec![
-            attr("matched_amount", matched_amount),
-            attr("remaining_amount", remaining_amount),
-        ]);
-
-        Ok((matched_amount, remaining_amount, response))
+        Ok(response)
     }
 
-    // Function to execute bonding curve swap
-    fn execute_bonding_curve_swap(
+    pub fn execute_claim_locked_lp(
         deps: DepsMut,
         env: Env,
         info: MessageInfo,
-        pair_id: String,
         token_address: String,
-        amount: Uint128,
-        min_return: Uint128,
-        order_type: OrderType,
     ) -> StdResult<Response> {
         let config = CONFIG.load(deps.storage)?;
-        if !config.enabled {
-            return Err(StdError::generic_err("Trading is currently disabled"));
+        let mut record = GRADUATIONS.load(deps.storage, token_address.clone())?;
+        if info.sender != config.owner && info.sender != record.lp_recipient {
+            return Err(StdError::generic_err("Unauthorized"));
+        }
+        let (LpOutcome::Locked { unlocks_at }, Some(lp_token)) =
+            (&record.lp_outcome, &record.lp_token)
+        else {
+            return Err(StdError::generic_err("No locked LP tokens for this token"));
+        };
+        if env.block.time < *unlocks_at {
+            return Err(StdError::generic_err(format!(
+                "LP tokens are locked until {}",
+                unlocks_at
+            )));
         }
 
-        let mut pool = POOLS.load(deps.storage, token_address.clone())?;
-        let token_pair = TOKEN_PAIRS.load(deps.storage, pair_id.clone())?;
-        if !token_pair.enabled {
-            return Err(StdError::generic_err("Trading pair is disabled"));
+        let transfer = execute_transfer(lp_token, &record.lp_recipient, record.lp_amount)?;
+        record.lp_outcome = LpOutcome::Released { at: env.block.time };
+        GRADUATIONS.save(deps.storage, token_address.clone(), &record)?;
+
+        Ok(Response::new()
+            .add_message(transfer)
+            .add_attribute("action", "claim_locked_lp")
+            .add_attribute("token", token_address)
+            .add_attribute("recipient", record.lp_recipient.to_string())
+            .add_attribute("lp_amount", record.lp_amount))
+    }
+
+    // Helper function to check if token has graduated
+    pub fn is_token_graduated(storage: &dyn Storage, token_address: &str) -> StdResult<bool> {
+        let token_info = TOKEN_INFO.load(storage, token_address.to_string())?;
+        Ok(token_info.graduated)
+    }
+
+    pub fn execute_update_config(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        token_factory: Option<Addr>,
+        fee_collector: Option<Addr>,
+        maker_fee: Option<Decimal>,
+        taker_fee: Option<Decimal>,
+        quote_token_total_supply: Option<Uint128>,
+        bonding_curve_supply: Option<Uint128>,
+        lp_supply: Option<Uint128>,
+        enabled: Option<bool>,
+        token_creation_fee: Option<Uint128>,
+        lp_policy: Option<LpPolicy>,
+    ) -> StdResult<Response> {
+        let mut config = CONFIG.load(deps.storage)?;
+
+        // Verify authority
+        if info.sender != config.owner {
+            return Err(StdError::generic_err("Unauthorized"));
         }
 
-        if !pool.enabled {
-            return Err(StdError::generic_err("Pool is disabled"));
+        // Update fee configuration
+        if token_factory.is_some() {
+            config.token_factory = token_factory.unwrap();
         }
 
-        let price = calculate_exponential_price(
-            deps.storage,
-            token_address.clone(),
-            pool.token_sold,
-            amount,
-            matches!(order_type, OrderType::Buy),
-        )?;
+        if fee_collector.is_some() {
+            config.fee_collector = fee_collector.unwrap();
+        }
 
-        // Calculate the swap amounts based on the price
-        let (base_amount, quote_amount, messages) = match order_type {
-            OrderType::Buy => {
-                // When buying quote tokens with base tokens
-                validate_native_or_cw20_payment(
-                    deps.as_ref(),
-                    &info,
-                    &env,
-                    &token_pair.base_token,
-                    amount,
-                    true,
-                )?;
+        if let Some(maker_fee) = maker_fee {
+            validate_trading_fee(maker_fee)?;
+            config.maker_fee = maker_fee;
+        }
 
-                let tokens_to_receive = amount * price / Uint128::new(1_000_000); // Using 6 decimals for price
+        if let Some(taker_fee) = taker_fee {
+            validate_trading_fee(taker_fee)?;
+            config.taker_fee = taker_fee;
+        }
 
-                // Check if pool has enough quote tokens
-                if tokens_to_receive > Uint128::from(config.bonding_curve_supply) - pool.token_sold
-                {
-                    return Err(StdError::generic_err("Insufficient liquidity in pool"));
-                }
+        if quote_token_total_supply.is_some() {
+            config.quote_token_total_supply = quote_token_total_supply.unwrap().into();
+        }
 
-                if tokens_to_receive < min_return {
-                    return Err(StdError::generic_err(format!(
-                        "Slippage tolerance exceeded. Expected: {}, Minimum: {}",
-                        tokens_to_receive, min_return
-                    )));
-                }
+        if bonding_curve_supply.is_some() {
+            config.bonding_curve_supply = bonding_curve_supply.unwrap().into();
+        }
 
-                // Update pool reserves
-                pool.total_reserve_token += amount;
-                pool.token_sold += tokens_to_receive;
+        if lp_supply.is_some() {
+            config.lp_supply = lp_supply.unwrap().into();
+        }
 
-                // Prepare transfer messages
-                let mut msgs = vec![];
+        if enabled.is_some() {
+            config.enabled = enabled.unwrap().into();
+        }
 
-                // Transfer quote tokens from pool to sender
-                msgs.push(execute_cw20_transfer(
-                    &token_pair.quote_token,
-                    &env.contract.address,
-                    &info.sender,
-                    tokens_to_receive,
-                )?);
+        if let Some(token_creation_fee) = token_creation_fee {
+            config.token_creation_fee = token_creation_fee;
+        }
 
-                (amount, tokens_to_receive, msgs)
+        if let Some(lp_policy) = lp_policy {
+            validate_lp_policy(&lp_policy)?;
+            if !config.lp_policy.allows_change_to(&lp_policy) {
+                return Err(StdError::generic_err("LP policy can only be made stricter"));
             }
-            OrderType::Sell => {
-                // When selling quote tokens for base tokens
-                validate_native_or_cw20_payment(
-                    deps.as_ref(),
-                    &info,
-                    &env,
-                    &token_pair.quote_token,
-                    amount,
-                    false,
-                )?;
-
-                let base_to_receive = amount * price / Uint128::new(1_000_000);
+            config.lp_policy = lp_policy;
+        }
 
-                // Check if pool has enough base tokens
-                if base_to_receive > pool.token_sold {
-                    return Err(StdError::generic_err("Insufficient liquidity in pool"));
-                }
+        // Live tokens keep the supplies snapshotted at their creation
+        validate_supplies(
+            config.quote_token_total_supply,
+            config.bonding_curve_supply,
+            config.lp_supply,
+        )?;
 
-                if base_to_receive < min_return {
-                    return Err(StdError::generic_err(format!(
-                        "Slippage tolerance exceeded. Expected: {}, Minimum: {}",
-                        base_to_receive, min_return
-                    )));
-                }
+        CONFIG.save(deps.storage, &config)?;
 
-                // Update pool reserves
-                pool.token_sold -= amount;
-                pool.total_reserve_token -= base_to_receive;
+        Ok(Response::new().add_attribute("action", "update_config"))
+    }
 
-                // Prepare transfer messages
-                let mut msgs = vec![];
+    pub fn execute_update_pair_trading_rules(
+        deps: DepsMut,
+        info: MessageInfo,
+        pair_id: String,
+        tick_size: Option<Uint128>,
+        lot_size: Option<Uint128>,
+        min_notional: Option<Uint128>,
+        order_deposit: Option<Uint128>,
+    ) -> StdResult<Response> {
+        let config = CONFIG.load(deps.storage)?;
 
-                // Transfer CW20 tokens from sender to pool
-                msgs.push(execute_cw20_transfer(
-                    &token_pair.quote_token,
-                    &info.sender,
-                    &env.contract.address,
-                    amount,
-                )?);
+        // Verify authority
+        if info.sender != config.owner {
+            return Err(StdError::generic_err("Unauthorized"));
+        }
 
-                // Transfer base tokens from pool to sender
-                msgs.push(execute_native_transfer(
-                    &token_pair.base_token,
-                    &info.sender,
-                    base_to_receive,
-                )?);
+        let mut token_pair = TOKEN_PAIRS.load(deps.storage, pair_id.clone())?;
 
-                (base_to_receive, amount, msgs)
+        if let Some(tick_size) = tick_size {
+            if tick_size.is_zero() {
+                return Err(StdError::generic_err("Tick size must be greater than 0."));
             }
-        };
+            token_pair.tick_size = tick_size;
+        }
 
-        // Update pool state
-        pool.last_price = price;
-        pool.total_trades += Uint128::new(1);
-        POOLS.save(deps.storage, token_address.clone(), &pool)?;
+        if let Some(lot_size) = lot_size {
+            if lot_size.is_zero() {
+                return Err(StdError::generic_err("Lot size must be greater than 0."));
+            }
+            token_pair.lot_size = lot_size;
+        }
 
-        Ok(Response::new().add_messages(messages).add_attributes(vec![
-            attr("action", "bonding_curve_swap"),
-            attr("pair_id", pair_id),
-            attr("order_type", format!("{:?}", order_type)),
-            attr("base_amount", base_amount),
-            attr("quote_amount", quote_amount),
-            attr("price", price.to_string()),
-        ]))
-    }
+        if let Some(min_notional) = min_notional {
+            token_pair.min_notional = min_notional;
+        }
 
-    fn validate_native_or_cw20_payment(
-        deps: Deps,
-        info: &MessageInfo,
-        env: &Env,
-        token: &str,
-        required_amount: Uint128,
-        is_native: bool,
-    ) -> StdResult<()> {
-        if is_native {
-            validate_native_token_payment(info, token, required_amount)
-        } else {
-            validate_cw20_token_payment(&deps, env, info, token, required_amount)
+        // Resting orders keep the deposit they were placed with
+        if let Some(order_deposit) = order_deposit {
+            token_pair.order_deposit = order_deposit;
         }
+
+        TOKEN_PAIRS.save(deps.storage, pair_id.clone(), &token_pair)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "update_pair_trading_rules")
+            .add_attribute("pair_id", pair_id)
+            .add_attribute("tick_size", token_pair.tick_size.to_string())
+            .add_attribute("lot_size", token_pair.lot_size.to_string())
+            .add_attribute("min_notional", token_pair.min_notional.to_string())
+            .add_attribute("order_deposit", token_pair.order_deposit.to_string()))
     }
 
-    // Helper function to execute trade and update user history
-    fn execute_trade(
-        storage: &mut dyn Storage,
-        env: &Env,
-        buyer: &Addr,
-        seller: &Addr,
+    pub fn execute_update_pair(
+        deps: DepsMut,
+        info: MessageInfo,
         pair_id: String,
-        buy_order_id: u64,
-        sell_order_id: u64,
-        amount: Uint128,
-        price: Uint128,
-        maker_fee: Uint128,
-        taker_fee: Uint128,
-    ) -> StdResult<()> {
-        // Create trade record
-        let trade = Trade {
-            id: NEXT_TRADE_ID.load(storage)?,
-            pair_id,
-            buy_order_id,
-            sell_order_id,
-            buyer: buyer.clone(),
-            seller: seller.clone(),
-            token_amount: amount,
-            price,
-            timestamp: env.block.time.seconds() as u64,
-            total_price: amount * price,
-            maker_fee_amount: maker_fee,
-            taker_fee_amount: taker_fee,
-        };
+        enabled: Option<bool>,
+    ) -> StdResult<Response> {
+        let config = CONFIG.load(deps.storage)?;
 
-        // Update user trade counts and histories
-        for user in [buyer, seller] {
-            let count = USER_TRADE_COUNT.load(storage, user.clone()).unwrap_or(0);
-            if count >= MAX_TRADES_PER_USER as u64 {
-                // Remove oldest trade
-                USER_TRADES.remove(storage, (user.clone(), count - MAX_TRADES_PER_USER as u64));
-            }
+        // Halting a single pair is also open to the pauser
+        let roles = ROLES.may_load(deps.storage)?.unwrap_or_default();
+        assert_owner_or_role(&config, &roles.pauser, &info.sender)?;
 
-            // remove oldest orders
-            let order_count = USER_ORDERS
-                .keys(storage, None, None, cosmwasm_std::Order::Ascending)
-                .count();
-            if order_count >= MAX_TRADES_PER_USER {
-                USER_ORDERS.remove(
-                    storage,
-                    (user.clone(), (order_count - MAX_TRADES_PER_USER) as u64),
-                );
-            }
+        let mut token_pair = TOKEN_PAIRS.load(deps.storage, pair_id.clone())?;
 
-            USER_TRADES.save(storage, (user.clone(), count), &trade)?;
-            USER_TRADE_COUNT.save(storage, user.clone(), &(count + 1))?;
+        if let Some(enabled) = enabled {
+            token_pair.enabled = enabled;
         }
 
-        TRADES.save(storage, trade.id, &trade)?;
-        NEXT_TRADE_ID.save(storage, &(trade.id + 1u64))?;
+        TOKEN_PAIRS.save(deps.storage, pair_id.clone(), &token_pair)?;
 
-        Ok(())
+        Ok(Response::new()
+            .add_attribute("action", "update_pair")
+            .add_attribute("pair_id", pair_id)
+            .add_attribute("enabled", token_pair.enabled.to_string()))
     }
 
-    fn calculate_exponential_price(
-        storage: &dyn Storage,
+    pub fn execute_update_pool(
+        deps: DepsMut,
+        info: MessageInfo,
         token_address: String,
-        current_supply: Uint128,
-        amount: Uint128,
-        is_buy: bool,
-    ) -> StdResult<Uint128> {
-        let config = CONFIG.load(storage)?;
-        let pool = POOLS.load(storage, token_address.clone())?;
-        let token_info = TOKEN_INFO.load(storage, token_address)?;
-
-        let base_price = Decimal::from_ratio(BASE_PRICE, Uint128::new(1_000_000));
-        let slope = Decimal::from_ratio(pool.curve_slope, Uint128::new(1_000_000));
+        enabled: Option<bool>,
+        curve_slope: Option<Uint128>,
+    ) -> StdResult<Response> {
+        let config = CONFIG.load(deps.storage)?;
 
-        if pool.token_sold + amount >= Uint128::from(config.bonding_curve_supply) {
-            return Err(StdError::generic_err(
-                "Supply exceeds maximum limit for pricing",
-            ));
+        // Verify authority
+        if info.sender != config.owner {
+            return Err(StdError::generic_err("Unauthorized"));
         }
 
-        let (lower_bound, upper_bound) = if is_buy {
-            (current_supply, current_supply + amount)
-        } else {
-            (current_supply - amount, current_supply)
-        };
-
-        let lower_dec = Decimal::from_ratio(lower_bound, Uint128::new(1));
-        let upper_dec = Decimal::from_ratio(upper_bound, Uint128::new(1));
-
-        let alpha = Decimal::from_str("0.1").unwrap();
+        let mut pool = POOLS.load(deps.storage, token_address.clone())?;
 
-        let exp_upper = calculate_ema_exp(slope * upper_dec, alpha)?;
-        let exp_lower = calculate_ema_exp(slope * lower_dec, alpha)?;
+        if let Some(enabled) = enabled {
+            pool.enabled = enabled;
+        }
 
-        let avg_price = base_price * (exp_upper - exp_lower)
-            / (slope * Decimal::from_ratio(amount, Uint128::new(1)));
+        if let Some(curve_slope) = curve_slope {
+            if curve_slope.is_zero() {
+                return Err(StdError::generic_err("Curve slope must be greater than 0."));
+            }
+            pool.curve_slope = curve_slope;
+        }
 
-        let price_uint = Uint128::new(
-            (avg_price * Decimal::from_ratio(10_u128.pow(token_info.decimals as u32), 1u128))
-                .to_uint_ceil()
-                .try_into()
-                .map_err(|_| StdError::generic_err("Price overflow"))?,
-        );
+        POOLS.save(deps.storage, token_address.clone(), &pool)?;
 
-        Ok(price_uint)
+        Ok(Response::new()
+            .add_attribute("action", "update_pool")
+            .add_attribute("token_address", token_address)
+            .add_attribute("pair_id", pool.pair_id)
+            .add_attribute("enabled", pool.enabled.to_string())
+            .add_attribute("curve_slope", pool.curve_slope.to_string()))
     }
 
-    fn calculate_ema_exp(x: Decimal, alpha: Decimal) -> StdResult<Decimal> {
-        let mut ema = Decimal::one();
-        let steps = 100;
-        for _ in 0..steps {
-            ema = alpha * x + (Decimal::one() - alpha) * ema;
+    /// Lists a pair that did not come through the launchpad. It only trades on the
+    /// order book since there is no bonding curve pool behind it.
+    pub fn execute_register_pair(
+        deps: DepsMut,
+        info: MessageInfo,
+        pair_id: String,
+        base_token: UncheckedDenom,
+        quote_token: UncheckedDenom,
+        base_decimals: u8,
+        quote_decimals: u8,
+    ) -> StdResult<Response> {
+        let config = CONFIG.load(deps.storage)?;
+
+        // Verify authority
+        if info.sender != config.owner {
+            return Err(StdError::generic_err("Unauthorized"));
         }
-        Ok(ema)
-    }
 
-    #[cfg(test)]
-    mod tests {
-        use std::collections::BTreeMap;
-        use std::str::FromStr;
+        if pair_id.is_empty() {
+            return Err(StdError::generic_err("Pair ID must not be empty."));
+        }
+        if TOKEN_PAIRS.has(deps.storage, pair_id.clone()) {
+            return Err(StdError::generic_err("Trading pair already exists"));
+        }
+        let base_token = validate_denom(deps.as_ref(), base_token)?;
+        let quote_token = validate_denom(deps.as_ref(), quote_token)?;
+        if base_token == quote_token {
+            return Err(StdError::generic_err(
+                "Base and quote tokens must be different.",
+            ));
+        }
 
-        use super::*;
-        use cosmwasm_std::testing::{message_info, mock_dependencies, mock_env};
-        use cosmwasm_std::{
-            from_json, Addr, Api, Coin, CosmosMsg, Decimal, SystemError, Uint128, WasmMsg,
+        // A pair quoted in a whitelisted currency starts with its order book rules
+        let currency = BASE_CURRENCIES
+            .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?
+            .into_iter()
+            .map(|(_, currency)| currency)
+            .find(|currency| currency.denom == base_token);
+        let token_pair = TokenPair {
+            base_token: base_token.clone(),
+            quote_token: quote_token.clone(),
+            base_decimals,
+            quote_decimals,
+            enabled: true,
+            tick_size: Uint128::one(),
+            lot_size: Uint128::one(),
+            min_notional: currency
+                .as_ref()
+                .map(|currency| currency.min_order_notional)
+                .unwrap_or_default(),
+            order_deposit: currency
+                .map(|currency| currency.order_deposit)
+                .unwrap_or_default(),
         };
-        use cosmwasm_std::{ContractResult, StdError, SystemResult, WasmQuery};
-        use cosmwasm_std::{HexBinary, SubMsg};
-        use cw20::{AllowanceResponse, BalanceResponse, Cw20ExecuteMsg, Expiration};
-        use cw_multi_test::App;
-        use token_factory::state::{Cw20Coin, State};
 
-        #[test]
-        fn test_proper_instantiate() {
-            let mut deps = mock_dependencies();
+        let order_book = OrderBook {
+            pair_id: pair_id.clone(),
+            buy_orders: BTreeMap::new(),
+            sell_orders: BTreeMap::new(),
+        };
 
-            let msg = InstantiateMsg {
-                token_factory: Addr::unchecked("token_factory_addr"),
-                fee_collector: Addr::unchecked("fee_collector_addr"),
-                quote_token_total_supply: Uint128::from(100_000_000_000u128),
-                bonding_curve_supply: Uint128::from(80_000_000_000u128),
-                lp_supply: Uint128::from(20_000_000_000u128),
-                maker_fee: Decimal::percent(1),
-                taker_fee: Decimal::percent(1),
-                secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
-                base_token_denom: "uhuahua".to_string(),
-            };
+        TOKEN_PAIRS.save(deps.storage, pair_id.clone(), &token_pair)?;
+        ORDER_BOOKS.save(deps.storage, pair_id.clone(), &order_book)?;
 
-            let info = message_info(&Addr::unchecked("creator"), &[]);
+        Ok(Response::new()
+            .add_attribute("action", "register_pair")
+            .add_attribute("pair_id", pair_id)
+            .add_attribute("base_token", denom_id(&base_token))
+            .add_attribute("quote_token", denom_id(&quote_token)))
+    }
 
-            // Instantiate the contract
-            let res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+    pub fn execute_update_base_currencies(
+        deps: DepsMut,
+        info: MessageInfo,
+        add: Vec<BaseCurrencyMsg>,
+        remove: Vec<String>,
+    ) -> StdResult<Response> {
+        let config = CONFIG.load(deps.storage)?;
 
-            // Check the response
-            assert_eq!(res.attributes.len(), 11);
-            assert_eq!(res.attributes[0].key, "action");
-            assert_eq!(res.attributes[0].value, "instantiate");
-            assert_eq!(res.attributes[1].key, "owner");
-            assert_eq!(res.attributes[1].value, info.sender.to_string());
-            assert_eq!(res.attributes[2].key, "token_factory");
-            assert_eq!(res.attributes[2].value, msg.token_factory.to_string());
-            assert_eq!(res.attributes[3].key, "fee_collector");
-            assert_eq!(res.attributes[3].value, msg.fee_collector.to_string());
-            assert_eq!(res.attributes[4].key, "quote_token_total_supply");
-            assert_eq!(
-                res.attributes[4].value,
-                msg.quote_token_total_supply.to_string()
-            );
-            assert_eq!(res.attributes[5].key, "bonding_curve_supply");
-            assert_eq!(
-                res.attributes[5].value,
-                msg.bonding_curve_supply.to_string()
-            );
-            assert_eq!(res.attributes[6].key, "lp_supply");
-            assert_eq!(res.attributes[6].value, msg.lp_supply.to_string());
-            assert_eq!(res.attributes[7].key, "maker_fee");
-            assert_eq!(res.attributes[7].value, msg.maker_fee.to_string());
-            assert_eq!(res.attributes[8].key, "taker_fee");
-            assert_eq!(res.attributes[8].value, msg.taker_fee.to_string());
-            assert_eq!(res.attributes[9].key, "secondary_amm_address");
-            assert_eq!(
-                res.attributes[9].value,
-                msg.secondary_amm_address.to_string()
-            );
-            assert_eq!(res.attributes[10].key, "base_token_denom");
-            assert_eq!(res.attributes[10].value, msg.base_token_denom.to_string());
+        // Verify authority
+        if info.sender != config.owner {
+            return Err(StdError::generic_err("Unauthorized"));
+        }
 
-            // Verify state was set correctly
-            let config = CONFIG.load(&deps.storage).unwrap();
-            assert_eq!(config.owner, info.sender);
-            assert_eq!(config.token_factory, msg.token_factory);
-            assert_eq!(config.fee_collector, msg.fee_collector);
-            assert_eq!(config.enabled, true);
-            assert_eq!(
-                config.quote_token_total_supply,
-                msg.quote_token_total_supply.into()
-            );
-            assert_eq!(config.bonding_curve_supply, msg.bonding_curve_supply.into());
-            assert_eq!(config.lp_supply, msg.lp_supply.into());
-            assert_eq!(config.maker_fee, msg.maker_fee,);
-            assert_eq!(config.taker_fee, msg.taker_fee,);
-            assert_eq!(config.secondary_amm_address, msg.secondary_amm_address);
-            assert_eq!(config.base_token_denom, msg.base_token_denom);
+        for currency in &add {
+            if currency.symbol.is_empty() {
+                return Err(StdError::generic_err("Currency symbol must not be empty."));
+            }
+            if currency.base_price.is_zero() {
+                return Err(StdError::generic_err("Base price must be greater than 0."));
+            }
+            let currency = BaseCurrency {
+                symbol: currency.symbol.clone(),
+                denom: validate_denom(deps.as_ref(), currency.denom.clone())?,
+                decimals: currency.decimals,
+                base_price: currency.base_price,
+                graduation_reserve: currency.graduation_reserve,
+                min_order_notional: currency.min_order_notional,
+                order_deposit: currency.order_deposit,
+            };
+            BASE_CURRENCIES.save(deps.storage, currency.symbol.clone(), &currency)?;
+        }
+        // Pools already launched keep trading, only new launches lose the currency
+        for symbol in &remove {
+            if *symbol == config.base_token_symbol {
+                return Err(StdError::generic_err(
+                    "The default base currency cannot be removed",
+                ));
+            }
+            BASE_CURRENCIES.remove(deps.storage, symbol.clone());
+        }
 
-            let next_order_id = NEXT_ORDER_ID.load(&deps.storage).unwrap();
-            assert_eq!(next_order_id, 0u64);
+        Ok(Response::new()
+            .add_attribute("action", "update_base_currencies")
+            .add_attribute(
+                "added",
+                add.iter()
+                    .map(|currency| currency.symbol.as_str())
+                    .collect::<Vec<_>>()
+                    .join(","),
+            )
+            .add_attribute("removed", remove.join(",")))
+    }
 
-            let next_trade_id = NEXT_TRADE_ID.load(&deps.storage).unwrap();
-            assert_eq!(next_trade_id, 0u64);
+    // Checks the shape of a denom without querying it, CW20 addresses only need to be valid
+    fn validate_denom(deps: Deps, denom: UncheckedDenom) -> StdResult<Denom> {
+        match denom {
+            UncheckedDenom::Native(denom) if denom.is_empty() => {
+                Err(StdError::generic_err("Native denom must not be empty."))
+            }
+            UncheckedDenom::Native(denom) => Ok(Denom::Native(denom)),
+            UncheckedDenom::Cw20(address) => Ok(Denom::Cw20(deps.api.addr_validate(&address)?)),
         }
+    }
 
-        #[test]
-        fn test_execute_create_token_happy_case() {
-            let mut deps = mock_dependencies();
-            let env = mock_env();
+    pub fn execute_transfer_ownership(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        new_owner: String,
+        expiry: Option<Expiration>,
+    ) -> StdResult<Response> {
+        let config = CONFIG.load(deps.storage)?;
 
-            // Setup initial configuration
-            let config = Config {
-                owner: Addr::unchecked("creator"),
-                token_factory: Addr::unchecked("token_factory_addr"),
-                fee_collector: Addr::unchecked("fee_collector_addr"),
-                enabled: true,
-                quote_token_total_supply: 100_000_000_000u128,
-                bonding_curve_supply: 80_000_000_000u128,
-                lp_supply: 20_000_000_000u128,
-                maker_fee: Decimal::percent(1),
-                taker_fee: Decimal::percent(1),
-                secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
-                base_token_denom: "base_token_denom".to_string(),
-            };
+        // Verify authority
+        if info.sender != config.owner {
+            return Err(StdError::generic_err("Unauthorized"));
+        }
 
-            CONFIG.save(deps.as_mut().storage, &config).unwrap();
+        let new_owner = deps.api.addr_validate(&new_owner)?;
+        if let Some(expiry) = expiry {
+            if expiry.is_expired(&env.block) {
+                return Err(StdError::generic_err("Expiry has already passed"));
+            }
+        }
 
-            let app = App::default();
-            let creator = app.api().addr_make("creator");
-            let info = message_info(&creator, &[]);
+        // Proposing again replaces any earlier pending transfer
+        PENDING_OWNER.save(
+            deps.storage,
+            &PendingOwner {
+                address: new_owner.clone(),
+                expiry,
+            },
+        )?;
 
-            // Define parameters for creating the token
-            let name = "Test Token".to_string();
-            let symbol = "TST".to_string();
-            let decimals = 8;
-            let uri = "URL".to_string();
-            let max_price_impact = Uint128::from(100u128);
-            let curve_slope = Uint128::from(1u128);
+        Ok(Response::new()
+            .add_attribute("action", "transfer_ownership")
+            .add_attribute("owner", config.owner)
+            .add_attribute("pending_owner", new_owner)
+            .add_attribute(
+                "pending_expiry",
+                expiry.map_or("none".to_string(), |e| e.to_string()),
+            ))
+    }
 
-            // Mock the token factory query response
-            deps.querier.update_wasm(move |_| {
-                SystemResult::Ok(ContractResult::Ok(
-                    to_json_binary(&token_factory::msg::GetConfigResponse {
-                        config: State {
-                            owner: app.api().addr_make("creator"),
-                            token_count: 0,
-                            token_code_id: 11013,
-                            token_code_hash: HexBinary::from_hex(
-                                &"528E5F16D05CDE640CDEF6D779A458CBF566AA4820E40ACFCF5066978D388CAD",
-                            )
-                            .unwrap(),
-                            token_creation_reply_id: 1,
-                        },
-                    })
-                    .unwrap(),
-                ))
-            });
+    pub fn execute_accept_ownership(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+    ) -> StdResult<Response> {
+        let mut config = CONFIG.load(deps.storage)?;
 
-            // Execute the create_token function
-            let res = execute_create_token(
-                deps.as_mut(),
-                env.clone(),
-                info.clone(),
-                name.clone(),
-                symbol.clone(),
-                decimals,
-                uri.clone(),
-                max_price_impact,
-                curve_slope,
-            )
-            .unwrap();
+        let pending = match PENDING_OWNER.may_load(deps.storage)? {
+            Some(pending) => pending,
+            None => return Err(StdError::generic_err("No pending ownership transfer")),
+        };
+        if info.sender != pending.address {
+            return Err(StdError::generic_err("Unauthorized: not the pending owner"));
+        }
+        if let Some(expiry) = pending.expiry {
+            if expiry.is_expired(&env.block) {
+                return Err(StdError::generic_err("Ownership transfer has expired"));
+            }
+        }
 
-            // Check the response
-            assert_eq!(res.attributes.len(), 6);
-            assert_eq!(res.attributes[0].key, "action");
-            assert_eq!(res.attributes[0].value, "create_token_pending");
-            assert_eq!(res.attributes[1].key, "name");
-            assert_eq!(res.attributes[1].value, name);
-            assert_eq!(res.attributes[2].key, "symbol");
-            assert_eq!(res.attributes[2].value, symbol);
-            assert_eq!(res.attributes[3].key, "decimals");
-            assert_eq!(res.attributes[3].value, decimals.to_string());
-            assert_eq!(res.attributes[4].key, "max_price_impact");
-            assert_eq!(res.attributes[4].value, max_price_impact.to_string());
-            assert_eq!(res.attributes[5].key, "curve_slope");
-            assert_eq!(res.attributes[5].value, curve_slope.to_string());
+        let previous_owner = config.owner;
+        config.owner = pending.address;
+        CONFIG.save(deps.storage, &config)?;
+        PENDING_OWNER.remove(deps.storage);
 
-            // Check that the response includes the correct message and attributes
-            let msg = res.messages.get(0).expect("no message");
-            assert_eq!(
-                msg.msg,
-                CosmosMsg::Wasm(WasmMsg::Execute {
-                    contract_addr: "token_factory_addr".to_string(),
-                    msg: to_json_binary(&token_factory::msg::ExecuteMsg::CreateToken {
-                        name: name.clone(),
-                        symbol: symbol.clone(),
-                        decimals,
-                        uri,
-                        max_price_impact,
-                        curve_slope,
-                        initial_balances: vec![Cw20Coin {
-                            address: env.contract.address.to_string(),
-                            amount: Uint128::from(
-                                100_000_000_000u128 * 10u128.pow(decimals as u32)
-                            ),
-                        }],
-                    })
-                    .unwrap(),
-                    funds: vec![],
-                })
-            );
+        Ok(Response::new()
+            .add_attribute("action", "accept_ownership")
+            .add_attribute("previous_owner", previous_owner)
+            .add_attribute("owner", config.owner))
+    }
 
-            // // Verify that the token information was saved correctly
-            // let token_info = TOKEN_INFO
-            //     .load(&deps.storage, token_address.to_string())
-            //     .unwrap();
+    pub fn execute_update_fee_distribution(
+        deps: DepsMut,
+        info: MessageInfo,
+        fee_collector_share: Decimal,
+        buyback_share: Decimal,
+        staker_share: Decimal,
+        buyback_address: Option<String>,
+        staking_address: Option<String>,
+    ) -> StdResult<Response> {
+        let config = CONFIG.load(deps.storage)?;
+        let roles = ROLES.may_load(deps.storage)?.unwrap_or_default();
+        assert_owner_or_role(&config, &roles.fee_manager, &info.sender)?;
+
+        if fee_collector_share + buyback_share + staker_share != Decimal::one() {
+            return Err(StdError::generic_err("Fee shares must add up to 1"));
+        }
+        let buyback_address = buyback_address
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()?;
+        let staking_address = staking_address
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()?;
+        if !buyback_share.is_zero() && buyback_address.is_none() {
+            return Err(StdError::generic_err(
+                "A buyback share needs a buyback address",
+            ));
+        }
+        if !staker_share.is_zero() && staking_address.is_none() {
+            return Err(StdError::generic_err(
+                "A staker share needs a staking address",
+            ));
+        }
+
+        FEE_DISTRIBUTION.save(
+            deps.storage,
+            &FeeDistribution {
+                fee_collector_share,
+                buyback_share,
+                staker_share,
+                buyback_address,
+                staking_address,
+            },
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("action", "update_fee_distribution")
+            .add_attribute("fee_collector_share", fee_collector_share.to_string())
+            .add_attribute("buyback_share", buyback_share.to_string())
+            .add_attribute("staker_share", staker_share.to_string()))
+    }
+
+    /// Pays every fee collected since the last distribution out to the fee collector,
+    /// the buyback address and stakers, by their configured shares.
+    pub fn execute_distribute_fees(deps: DepsMut) -> StdResult<Response> {
+        let config = CONFIG.load(deps.storage)?;
+        let distribution = FEE_DISTRIBUTION.may_load(deps.storage)?.unwrap_or_default();
+        let balances = TREASURY
+            .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let mut response = Response::new().add_attribute("action", "distribute_fees");
+        for (key, mut balance) in balances {
+            let pending = balance.accumulated.checked_sub(balance.distributed)?;
+            if pending.is_zero() {
+                continue;
+            }
+            let buyback = pending.mul_floor(distribution.buyback_share);
+            let stakers = pending.mul_floor(distribution.staker_share);
+            let fee_collector = pending.checked_sub(buyback)?.checked_sub(stakers)?;
+
+            for (recipient, amount) in [
+                (Some(&config.fee_collector), fee_collector),
+                (distribution.buyback_address.as_ref(), buyback),
+                (distribution.staking_address.as_ref(), stakers),
+            ] {
+                if let Some(recipient) = recipient.filter(|_| !amount.is_zero()) {
+                    response =
+                        response.add_message(execute_transfer(&balance.denom, recipient, amount)?);
+                }
+            }
+
+            balance.distributed += pending;
+            TREASURY.save(deps.storage, key.clone(), &balance)?;
+            response = response.add_attributes(vec![
+                attr("denom", key),
+                attr("fee_collector_amount", fee_collector),
+                attr("buyback_amount", buyback),
+                attr("staker_amount", stakers),
+            ]);
+        }
+
+        Ok(response)
+    }
+
+    pub fn execute_update_roles(
+        deps: DepsMut,
+        info: MessageInfo,
+        fee_manager: Option<String>,
+        pauser: Option<String>,
+        graduator: Option<String>,
+        guardian: Option<String>,
+    ) -> StdResult<Response> {
+        let config = CONFIG.load(deps.storage)?;
+
+        // Verify authority
+        if info.sender != config.owner {
+            return Err(StdError::generic_err("Unauthorized"));
+        }
+
+        let roles = Roles {
+            fee_manager: fee_manager
+                .map(|addr| deps.api.addr_validate(&addr))
+                .transpose()?,
+            pauser: pauser
+                .map(|addr| deps.api.addr_validate(&addr))
+                .transpose()?,
+            graduator: graduator
+                .map(|addr| deps.api.addr_validate(&addr))
+                .transpose()?,
+            guardian: guardian
+                .map(|addr| deps.api.addr_validate(&addr))
+                .transpose()?,
+        };
+        ROLES.save(deps.storage, &roles)?;
+
+        let role_attr =
+            |role: &Option<Addr>| role.as_ref().map_or("none", |a| a.as_str()).to_string();
+        Ok(Response::new()
+            .add_attribute("action", "update_roles")
+            .add_attribute("fee_manager", role_attr(&roles.fee_manager))
+            .add_attribute("pauser", role_attr(&roles.pauser))
+            .add_attribute("graduator", role_attr(&roles.graduator))
+            .add_attribute("guardian", role_attr(&roles.guardian)))
+    }
+
+    pub fn execute_update_fees(
+        deps: DepsMut,
+        info: MessageInfo,
+        maker_fee: Option<Decimal>,
+        taker_fee: Option<Decimal>,
+        curve_fee: Option<Decimal>,
+    ) -> StdResult<Response> {
+        let mut config = CONFIG.load(deps.storage)?;
+        let roles = ROLES.may_load(deps.storage)?.unwrap_or_default();
+        assert_owner_or_role(&config, &roles.fee_manager, &info.sender)?;
+
+        for fee in [maker_fee, taker_fee].into_iter().flatten() {
+            validate_trading_fee(fee)?;
+        }
+        if let Some(fee) = curve_fee {
+            validate_curve_fee(fee)?;
+        }
+
+        if let Some(maker_fee) = maker_fee {
+            config.maker_fee = maker_fee;
+        }
+        if let Some(taker_fee) = taker_fee {
+            config.taker_fee = taker_fee;
+        }
+        if let Some(curve_fee) = curve_fee {
+            config.curve_fee = curve_fee;
+        }
+
+        CONFIG.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "update_fees")
+            .add_attribute("maker_fee", config.maker_fee.to_string())
+            .add_attribute("taker_fee", config.taker_fee.to_string())
+            .add_attribute("curve_fee", config.curve_fee.to_string()))
+    }
+
+    pub fn execute_set_trading_enabled(
+        deps: DepsMut,
+        info: MessageInfo,
+        enabled: bool,
+    ) -> StdResult<Response> {
+        let mut config = CONFIG.load(deps.storage)?;
+        let roles = ROLES.may_load(deps.storage)?.unwrap_or_default();
+        assert_owner_or_role(&config, &roles.pauser, &info.sender)?;
+
+        config.enabled = enabled;
+        CONFIG.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "set_trading_enabled")
+            .add_attribute("enabled", enabled.to_string()))
+    }
+
+    pub fn execute_set_paused(
+        deps: DepsMut,
+        info: MessageInfo,
+        pair_id: Option<String>,
+        actions: Vec<PauseAction>,
+        paused: bool,
+    ) -> StdResult<Response> {
+        let config = CONFIG.load(deps.storage)?;
+        let roles = ROLES.may_load(deps.storage)?.unwrap_or_default();
+
+        // The guardian can only ever tighten, lifting a pause takes the owner or pauser
+        if !(paused && roles.guardian.as_ref() == Some(&info.sender)) {
+            assert_owner_or_role(&config, &roles.pauser, &info.sender)?;
+        }
+
+        if actions.is_empty() {
+            return Err(StdError::generic_err("No actions to pause or unpause"));
+        }
+
+        let mut flags = match &pair_id {
+            Some(pair_id) => {
+                if actions.contains(&PauseAction::TokenCreation) {
+                    return Err(StdError::generic_err(
+                        "Token creation can only be paused globally",
+                    ));
+                }
+                if !TOKEN_PAIRS.has(deps.storage, pair_id.clone()) {
+                    return Err(StdError::generic_err("Trading pair not found"));
+                }
+                PAIR_PAUSE
+                    .may_load(deps.storage, pair_id.clone())?
+                    .unwrap_or_default()
+            }
+            None => GLOBAL_PAUSE.may_load(deps.storage)?.unwrap_or_default(),
+        };
+
+        for action in &actions {
+            match action {
+                PauseAction::TokenCreation => flags.token_creation = paused,
+                PauseAction::CurveTrading => flags.curve_trading = paused,
+                PauseAction::BookTrading => flags.book_trading = paused,
+                PauseAction::Graduation => flags.graduation = paused,
+            }
+        }
+
+        match &pair_id {
+            Some(pair_id) => PAIR_PAUSE.save(deps.storage, pair_id.clone(), &flags)?,
+            None => GLOBAL_PAUSE.save(deps.storage, &flags)?,
+        }
+
+        let action_names: Vec<&str> = actions.iter().map(pause_action_name).collect();
+        Ok(Response::new()
+            .add_attribute("action", if paused { "pause" } else { "unpause" })
+            .add_attribute("scope", pair_id.unwrap_or_else(|| "global".to_string()))
+            .add_attribute("actions", action_names.join(",")))
+    }
+
+    /// Fails when `action` is paused globally or, given a pair, for that pair
+    fn assert_not_paused(
+        storage: &dyn Storage,
+        pair_id: Option<&str>,
+        action: PauseAction,
+    ) -> StdResult<()> {
+        if is_paused(storage, pair_id, action)? {
+            // "token_creation" reads as "Token creation"
+            let label = pause_action_name(&action).replace('_', " ");
+            return Err(StdError::generic_err(format!(
+                "{}{} is paused",
+                label[..1].to_uppercase(),
+                &label[1..]
+            )));
+        }
+        Ok(())
+    }
+
+    fn is_paused(
+        storage: &dyn Storage,
+        pair_id: Option<&str>,
+        action: PauseAction,
+    ) -> StdResult<bool> {
+        let flag = |flags: PauseFlags| match action {
+            PauseAction::TokenCreation => flags.token_creation,
+            PauseAction::CurveTrading => flags.curve_trading,
+            PauseAction::BookTrading => flags.book_trading,
+            PauseAction::Graduation => flags.graduation,
+        };
+
+        if flag(GLOBAL_PAUSE.may_load(storage)?.unwrap_or_default()) {
+            return Ok(true);
+        }
+        match pair_id {
+            Some(pair_id) => Ok(PAIR_PAUSE
+                .may_load(storage, pair_id.to_string())?
+                .is_some_and(flag)),
+            None => Ok(false),
+        }
+    }
+
+    fn pause_action_name(action: &PauseAction) -> &'static str {
+        match action {
+            PauseAction::TokenCreation => "token_creation",
+            PauseAction::CurveTrading => "curve_trading",
+            PauseAction::BookTrading => "book_trading",
+            PauseAction::Graduation => "graduation",
+        }
+    }
+
+    /// Passes for the owner and for whoever currently holds `role`
+    fn assert_owner_or_role(config: &Config, role: &Option<Addr>, sender: &Addr) -> StdResult<()> {
+        if *sender == config.owner || role.as_ref() == Some(sender) {
+            return Ok(());
+        }
+        Err(StdError::generic_err("Unauthorized"))
+    }
+
+    fn match_orders(
+        deps: DepsMut,
+        env: &Env,
+        pair_id: String,
+        is_buy: bool,
+    ) -> StdResult<Response> {
+        let mut order_book = ORDER_BOOKS.load(deps.storage, pair_id.clone())?;
+        let config = CONFIG.load(deps.storage)?;
+        let token_pair = TOKEN_PAIRS.load(deps.storage, pair_id.clone())?;
+
+        let mut response = Response::new();
+        let mut messages: Vec<SubMsg> = vec![];
+
+        if is_buy {
+            for (buy_price, buy_orders) in order_book.buy_orders.iter_mut().rev() {
+                for buy_order in buy_orders.iter_mut() {
+                    if buy_order.remaining_amount.is_zero() {
+                        continue;
+                    }
+                    for (sell_price, sell_orders) in order_book.sell_orders.iter_mut() {
+                        if sell_price > buy_price {
+                            break;
+                        }
+                        for sell_order in sell_orders.iter_mut() {
+                            if sell_order.remaining_amount.is_zero() {
+                                continue;
+                            }
+                            let trade_response = create_order(
+                                deps.storage,
+                                env,
+                                buy_order,
+                                sell_order,
+                                &token_pair,
+                                &config,
+                            )?;
+                            messages.extend(trade_response.messages);
+                            response = response.add_attributes(trade_response.attributes);
+                        }
+                    }
+                }
+            }
+        } else {
+            for (sell_price, sell_orders) in order_book.sell_orders.iter_mut() {
+                for sell_order in sell_orders.iter_mut() {
+                    if sell_order.remaining_amount.is_zero() {
+                        continue;
+                    }
+                    for (buy_price, buy_orders) in order_book.buy_orders.iter_mut().rev() {
+                        if buy_price < sell_price {
+                            break;
+                        }
+                        for buy_order in buy_orders.iter_mut() {
+                            if buy_order.remaining_amount.is_zero() {
+                                continue;
+                            }
+                            let trade_response = create_order(
+                                deps.storage,
+                                env,
+                                buy_order,
+                                sell_order,
+                                &token_pair,
+                                &config,
+                            )?;
+                            messages.extend(trade_response.messages);
+                            response = response.add_attributes(trade_response.attributes);
+                        }
+                    }
+                }
+            }
+        }
+
+        clean_up_order_book(&mut order_book);
+        ORDER_BOOKS.save(deps.storage, pair_id, &order_book)?;
+
+        Ok(response.add_submessages(messages))
+    }
+
+    fn validate_and_handle_tokens(
+        deps: &DepsMut,
+        env: &Env,
+        info: &MessageInfo,
+        token_pair: &TokenPair,
+        amount: Uint128,
+        price: Price,
+        is_buy: bool,
+        deposit: Uint128,
+    ) -> StdResult<Vec<CosmosMsg>> {
+        let mut messages = vec![];
+        if is_buy {
+            // The placement deposit is paid in the same denom as the order itself
+            let total_price = price.checked_quote_to_base_ceil(amount)? + deposit;
+            messages.extend(receive_payment(
+                deps.as_ref(),
+                env,
+                info,
+                &token_pair.base_token,
+                total_price,
+            )?);
+        } else {
+            if !deposit.is_zero() {
+                messages.extend(receive_payment(
+                    deps.as_ref(),
+                    env,
+                    info,
+                    &token_pair.base_token,
+                    deposit,
+                )?);
+            }
+
+            // The quote tokens are escrowed with the order itself
+            messages.extend(receive_payment(
+                deps.as_ref(),
+                env,
+                info,
+                &token_pair.quote_token,
+                amount,
+            )?);
+        }
+        Ok(messages)
+    }
+
+    fn validate_native_token_payment(
+        info: &MessageInfo,
+        denom: &str,
+        required_amount: Uint128,
+    ) -> StdResult<()> {
+        // Find the coin with matching denom in the sent funds
+        let sent_amount = info
+            .funds
+            .iter()
+            .find(|coin| coin.denom == denom)
+            .map(|coin| coin.amount)
+            .unwrap_or_default();
+
+        // Check if sent amount matches required amount
+        if sent_amount < required_amount {
+            return Err(StdError::generic_err(format!(
+                "Insufficient native token sent. Required: {}, Sent: {}",
+                required_amount, sent_amount
+            )));
+        }
+
+        // Check if excess amount was sent
+        if sent_amount > required_amount {
+            return Err(StdError::generic_err(format!(
+                "Excess native token sent. Required: {}, Sent: {}",
+                required_amount, sent_amount
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn validate_cw20_token_payment(
+        deps: &Deps,
+        env: &Env,
+        info: &MessageInfo,
+        token_address: &str,
+        required_amount: Uint128,
+    ) -> StdResult<()> {
+        // Query token balance
+        let balance: cw20::BalanceResponse = deps.querier.query_wasm_smart(
+            token_address,
+            &cw20::Cw20QueryMsg::Balance {
+                address: info.sender.to_string(),
+            },
+        )?;
+
+        // Check if user has sufficient balance
+        if balance.balance < required_amount {
+            return Err(StdError::generic_err(format!(
+                "Insufficient CW20 token balance. Required: {}, Balance: {}",
+                required_amount, balance.balance
+            )));
+        }
+
+        // Query allowance
+        let allowance: cw20::AllowanceResponse = deps.querier.query_wasm_smart(
+            token_address,
+            &cw20::Cw20QueryMsg::Allowance {
+                owner: info.sender.to_string(),
+                spender: env.contract.address.to_string(),
+            },
+        )?;
+
+        // Check if contract has sufficient allowance
+        if allowance.allowance < required_amount {
+            return Err(StdError::generic_err(format!(
+                "Insufficient CW20 token allowance. Required: {}, Allowance: {}",
+                required_amount, allowance.allowance
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Creates a trade between a buy order and a sell order, executing token transfers for both parties.
+    ///
+    /// This function handles the matching of a buy order (wanting quote tokens) with a sell order (wanting base tokens),
+    /// ensuring that tokens are exchanged correctly: the buyer receives quote tokens and the seller receives base
+    /// tokens. Each side pays its own fee out of what it receives, at the maker rate if its order was resting on
+    /// the book and at the taker rate otherwise.
+    ///
+    /// # Arguments
+    /// * `storage` - Mutable storage reference for updating order states.
+    /// * `env` - Environment info, including block time and contract address.
+    /// * `buy_order` - The buy order being matched (buyer wants quote tokens).
+    /// * `sell_order` - The sell order being matched (seller wants base tokens).
+    /// * `token_pair` - Pair info defining the base and quote tokens.
+    /// * `config` - Contract configuration with fee rates and collector address.
+    ///
+    /// # Returns
+    /// A `StdResult<Response>` containing transfer messages and trade attributes.
+    fn create_order(
+        storage: &mut dyn Storage,
+        env: &Env,
+        buy_order: &mut Order,
+        sell_order: &mut Order,
+        token_pair: &TokenPair,
+        config: &Config,
+    ) -> StdResult<Response> {
+        // Determine the trade amount as the lesser of the remaining amounts from both orders
+        let trade_amount = std::cmp::min(buy_order.remaining_amount, sell_order.remaining_amount);
+
+        // Use the sell order's price as the trade price (price-time priority)
+        let trade_price = sell_order.price;
+
+        // Calculate total cost in base tokens (price * amount)
+        let total_price = trade_price.checked_quote_to_base(trade_amount)?;
+
+        // The older order was resting on the book and pays the maker rate.
+        // Order ids only grow, unlike timestamps which tie within a block.
+        let (maker_side, buyer_rate, seller_rate) = if buy_order.id < sell_order.id {
+            (OrderType::Buy, config.maker_fee, config.taker_fee)
+        } else {
+            (OrderType::Sell, config.taker_fee, config.maker_fee)
+        };
+        let buyer_fee = fee_amount(trade_amount, buyer_rate)?;
+        let seller_fee = fee_amount(total_price, seller_rate)?;
+
+        // Calculate amounts to transfer after fees
+        let buyer_receives = trade_amount.checked_sub(buyer_fee)?; // Quote tokens net of the buyer's fee
+        let seller_receives = total_price.checked_sub(seller_fee)?; // Base tokens net of the seller's fee
+
+        // Update buy order state: reduce remaining, increase filled
+        let escrow_before = buy_order
+            .price
+            .checked_quote_to_base_ceil(buy_order.remaining_amount)?;
+        buy_order.remaining_amount = buy_order.remaining_amount.checked_sub(trade_amount)?;
+        buy_order.filled_amount = buy_order.filled_amount.checked_add(trade_amount)?;
+        if buy_order.remaining_amount.is_zero() {
+            buy_order.status = OrderStatus::Filled; // Mark as filled if fully executed
+        }
+
+        // The buyer escrowed at their own price, the trade runs at the sell price.
+        // What the fill frees beyond the trade total goes back to the buyer.
+        let escrow_released = escrow_before.checked_sub(
+            buy_order
+                .price
+                .checked_quote_to_base_ceil(buy_order.remaining_amount)?,
+        )?;
+        let buyer_refund = escrow_released.checked_sub(total_price)?;
+
+        // Update sell order state: reduce remaining, increase filled
+        sell_order.remaining_amount = sell_order.remaining_amount.checked_sub(trade_amount)?;
+        sell_order.filled_amount = sell_order.filled_amount.checked_add(trade_amount)?;
+        if sell_order.remaining_amount.is_zero() {
+            sell_order.status = OrderStatus::Filled; // Mark as filled if fully executed
+        }
+
+        // Persist updated order states to storage
+        USER_ORDERS.save(storage, (buy_order.owner.clone(), buy_order.id), buy_order)?;
+        USER_ORDERS.save(
+            storage,
+            (sell_order.owner.clone(), sell_order.id),
+            sell_order,
+        )?;
+        ORDERS.save(storage, buy_order.id, buy_order)?;
+        ORDERS.save(storage, sell_order.id, sell_order)?;
+
+        let trade_id = execute_trade(
+            storage,
+            env,
+            &buy_order.owner,
+            &sell_order.owner,
+            buy_order.pair_id.clone(),
+            buy_order.id,
+            sell_order.id,
+            trade_amount,
+            trade_price,
+            maker_side.clone(),
+            buyer_fee,
+            seller_fee,
+        )?;
+
+        // Prepare messages for token transfers
+        let mut messages: Vec<CosmosMsg> = vec![];
+
+        // Transfer quote tokens to the buyer from the contract
+        // - Buyer placed a buy order, paid base tokens, now gets quote tokens minus fees
+        messages.push(execute_transfer(
+            &token_pair.quote_token,
+            &buy_order.owner,
+            buyer_receives,
+        )?);
+
+        // Transfer base tokens to the seller from the contract
+        // - Seller placed a sell order, provided quote tokens, now gets base tokens minus fees
+        messages.push(execute_transfer(
+            &token_pair.base_token,
+            &sell_order.owner,
+            seller_receives,
+        )?);
+        if !buyer_refund.is_zero() {
+            messages.push(execute_transfer(
+                &token_pair.base_token,
+                &buy_order.owner,
+                buyer_refund,
+            )?);
+        }
+
+        // Each fee stays in the treasury in the token it was withheld from
+        accrue_fee(storage, &token_pair.quote_token, buyer_fee)?;
+        accrue_fee(storage, &token_pair.base_token, seller_fee)?;
+
+        // Free the active order slot and refund the deposit of fully filled orders
+        for order in [&*buy_order, &*sell_order] {
+            if order.status == OrderStatus::Filled {
+                messages.extend(release_order(storage, order, &token_pair.base_token)?);
+            }
+        }
+
+        // Construct response with transfer messages and trade details
+        Ok(Response::new()
+            .add_messages(messages)
+            .add_attribute("event_type", "trade")
+            .add_attribute("trade_id", trade_id.to_string())
+            .add_attribute("pair_id", buy_order.pair_id.clone())
+            .add_attribute("buy_order_id", buy_order.id.to_string())
+            .add_attribute("sell_order_id", sell_order.id.to_string())
+            .add_attribute("price", trade_price.to_string())
+            .add_attribute("amount", trade_amount.to_string())
+            .add_attribute("total", total_price.to_string())
+            .add_attribute("maker_side", format!("{:?}", maker_side))
+            .add_attribute("buyer_fee", buyer_fee.to_string())
+            .add_attribute("seller_fee", seller_fee.to_string())
+            .add_attribute("buyer_receives", buyer_receives.to_string())
+            .add_attribute("seller_receives", seller_receives.to_string())
+            .add_attribute("buyer_refund", buyer_refund.to_string())
+            .add_attribute("base_token", denom_id(&token_pair.base_token))
+            .add_attribute("quote_token", denom_id(&token_pair.quote_token))
+            .add_attribute("timestamp", env.block.time.seconds().to_string()))
+    }
+
+    // Trading fee on `amount`, rounded in the protocol's favour
+    fn fee_amount(amount: Uint128, rate: Decimal) -> StdResult<Uint128> {
+        amount
+            .checked_mul_ceil(rate)
+            .map_err(|e| StdError::generic_err(e.to_string()))
+    }
+
+    // Books a protocol fee the contract now holds into the treasury ledger
+    fn accrue_fee(storage: &mut dyn Storage, denom: &Denom, amount: Uint128) -> StdResult<()> {
+        if amount.is_zero() {
+            return Ok(());
+        }
+        let key = denom_id(denom).to_string();
+        let mut balance = TREASURY
+            .may_load(storage, key.clone())?
+            .unwrap_or(TreasuryBalance {
+                denom: denom.clone(),
+                accumulated: Uint128::zero(),
+                distributed: Uint128::zero(),
+            });
+        balance.accumulated = balance.accumulated.checked_add(amount)?;
+        TREASURY.save(storage, key, &balance)
+    }
+
+    // Pays out of the contract's own balance, by bank send or CW20 Transfer
+    fn execute_transfer(denom: &Denom, to: &Addr, amount: Uint128) -> StdResult<CosmosMsg> {
+        Ok(match denom {
+            Denom::Native(denom) => BankMsg::Send {
+                to_address: to.to_string(),
+                amount: vec![Coin {
+                    denom: denom.clone(),
+                    amount,
+                }],
+            }
+            .into(),
+            Denom::Cw20(token) => WasmMsg::Execute {
+                contract_addr: token.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: to.to_string(),
+                    amount,
+                })?,
+                funds: vec![],
+            }
+            .into(),
+        })
+    }
+
+    /// Takes `amount` of `denom` from the sender into the contract. Native coins must
+    /// be attached to the message, CW20 tokens are pulled with TransferFrom against
+    /// the allowance granted to this contract.
+    fn receive_payment(
+        deps: Deps,
+        env: &Env,
+        info: &MessageInfo,
+        denom: &Denom,
+        amount: Uint128,
+    ) -> StdResult<Option<CosmosMsg>> {
+        match denom {
+            Denom::Native(denom) => {
+                validate_native_token_payment(info, denom, amount)?;
+                Ok(None)
+            }
+            Denom::Cw20(_) if amount.is_zero() => Ok(None),
+            Denom::Cw20(token) => {
+                validate_cw20_token_payment(&deps, env, info, token.as_str(), amount)?;
+                Ok(Some(
+                    WasmMsg::Execute {
+                        contract_addr: token.to_string(),
+                        msg: to_json_binary(&Cw20ExecuteMsg::TransferFrom {
+                            owner: info.sender.to_string(),
+                            recipient: env.contract.address.to_string(),
+                            amount,
+                        })?,
+                        funds: vec![],
+                    }
+                    .into(),
+                ))
+            }
+        }
+    }
+
+    /// Releases the owner's active order slot once an order leaves the book, either
+    /// because it was filled or cancelled, and refunds its placement deposit.
+    fn release_order(
+        storage: &mut dyn Storage,
+        order: &Order,
+        deposit_denom: &Denom,
+    ) -> StdResult<Option<CosmosMsg>> {
+        let active_orders = USER_ACTIVE_ORDERS
+            .may_load(storage, order.owner.clone())?
+            .unwrap_or_default();
+        USER_ACTIVE_ORDERS.save(
+            storage,
+            order.owner.clone(),
+            &active_orders.saturating_sub(1),
+        )?;
+
+        if order.deposit.is_zero() {
+            return Ok(None);
+        }
+
+        Ok(Some(execute_transfer(
+            deposit_denom,
+            &order.owner,
+            order.deposit,
+        )?))
+    }
+
+    fn clean_up_order_book(order_book: &mut OrderBook) {
+        // Remove filled buy orders
+        order_book.buy_orders.retain(|_, orders| {
+            orders.retain(|order| !order.remaining_amount.is_zero());
+            !orders.is_empty()
+        });
+
+        // Remove filled sell orders
+        order_book.sell_orders.retain(|_, orders| {
+            orders.retain(|order| !order.remaining_amount.is_zero());
+            !orders.is_empty()
+        });
+    }
+
+    // Function to execute bonding curve swap
+    pub fn execute_bonding_curve_swap(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        pair_id: String,
+        token_address: String,
+        amount: Uint128,
+        min_return: Uint128,
+        order_type: OrderType,
+    ) -> StdResult<Response> {
+        let config = CONFIG.load(deps.storage)?;
+        if !config.enabled {
+            return Err(StdError::generic_err("Trading is currently disabled"));
+        }
+
+        let pool = POOLS.load(deps.storage, token_address.clone())?;
+        let token_pair = TOKEN_PAIRS.load(deps.storage, pair_id.clone())?;
+        if !token_pair.enabled {
+            return Err(StdError::generic_err("Trading pair is disabled"));
+        }
+
+        if !pool.enabled {
+            return Err(StdError::generic_err("Pool is disabled"));
+        }
+
+        let is_buy = matches!(order_type, OrderType::Buy);
+        let fill = quote_curve(
+            deps.storage,
+            &pool,
+            &token_address,
+            amount,
+            is_buy,
+            config.curve_fee,
+        )?;
+
+        // Buys spend base tokens for quote tokens, sells the other way around
+        let (offer_denom, ask_denom, base_amount, quote_amount) = if is_buy {
+            (
+                &token_pair.base_token,
+                &token_pair.quote_token,
+                amount,
+                fill.return_amount,
+            )
+        } else {
+            (
+                &token_pair.quote_token,
+                &token_pair.base_token,
+                fill.return_amount,
+                amount,
+            )
+        };
+        let payment = receive_payment(deps.as_ref(), &env, &info, offer_denom, amount)?;
+
+        if fill.return_amount < min_return {
+            return Err(StdError::generic_err(format!(
+                "Slippage tolerance exceeded. Expected: {}, Minimum: {}",
+                fill.return_amount, min_return
+            )));
+        }
+
+        settle_curve(
+            deps.storage,
+            &env,
+            Some(&info.sender),
+            &token_pair,
+            &order_type,
+            &fill,
+        )?;
+
+        Ok(Response::new()
+            .add_messages(payment)
+            .add_message(execute_transfer(
+                ask_denom,
+                &info.sender,
+                fill.return_amount,
+            )?)
+            .add_attributes(vec![
+                attr("action", "bonding_curve_swap"),
+                attr("pair_id", pair_id),
+                attr("order_type", format!("{:?}", order_type)),
+                attr("base_amount", base_amount),
+                attr("quote_amount", quote_amount),
+                attr("price", fill.price.to_string()),
+                attr("curve_fee", fill.fee),
+            ]))
+    }
+
+    fn validate_launch_settings(deps: Deps, launch: &LaunchSettings) -> StdResult<()> {
+        for addr in &launch.allow_list {
+            deps.api.addr_validate(addr.as_str())?;
+        }
+        if launch.allow_list.is_empty() != launch.allow_list_end.is_none() {
+            return Err(StdError::generic_err(
+                "Launch allow list and its end must be set together",
+            ));
+        }
+        if let Some(max_buy) = launch.max_buy_per_address {
+            if max_buy.is_zero() {
+                return Err(StdError::generic_err(
+                    "Max buy per address must be greater than 0.",
+                ));
+            }
+            if launch.max_buy_blocks == 0 {
+                return Err(StdError::generic_err(
+                    "Max buy window must be at least one block.",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // Enforces the pool's launch settings on a curve trade of `tokens` quote tokens
+    fn assert_launch_rules(
+        storage: &mut dyn Storage,
+        env: &Env,
+        pool: &Pool,
+        trader: &Addr,
+        order_type: &OrderType,
+        tokens: Uint128,
+    ) -> StdResult<()> {
+        let launch = &pool.launch;
+        if let Some(start) = &launch.trading_start {
+            if !start.is_expired(&env.block) {
+                return Err(StdError::generic_err("Trading has not started yet"));
+            }
+        }
+
+        // The remaining rules only limit buying
+        if matches!(order_type, OrderType::Sell) {
+            return Ok(());
+        }
+
+        if let Some(end) = &launch.allow_list_end {
+            if !end.is_expired(&env.block) && !launch.allow_list.contains(trader) {
+                return Err(StdError::generic_err(
+                    "Address is not on the launch allow list",
+                ));
+            }
+        }
+
+        if let Some(max_buy) = launch.max_buy_per_address {
+            if env.block.height < pool.launch_height.saturating_add(launch.max_buy_blocks) {
+                let key = (pool.token_address.to_string(), trader.clone());
+                let bought = LAUNCH_BUYS
+                    .may_load(storage, key.clone())?
+                    .unwrap_or_default()
+                    .checked_add(tokens)?;
+                if bought > max_buy {
+                    return Err(StdError::generic_err(format!(
+                        "Launch buy limit exceeded. Limit: {}, Bought: {}",
+                        max_buy, bought
+                    )));
+                }
+                LAUNCH_BUYS.save(storage, key, &bought)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Helper function to execute trade and update user history
+    fn execute_trade(
+        storage: &mut dyn Storage,
+        env: &Env,
+        buyer: &Addr,
+        seller: &Addr,
+        pair_id: String,
+        buy_order_id: u64,
+        sell_order_id: u64,
+        amount: Uint128,
+        price: Price,
+        maker_side: OrderType,
+        buyer_fee: Uint128,
+        seller_fee: Uint128,
+    ) -> StdResult<u64> {
+        // Create trade record
+        let trade = Trade {
+            id: NEXT_TRADE_ID.load(storage)?,
+            pair_id,
+            buy_order_id,
+            sell_order_id,
+            buyer: buyer.clone(),
+            seller: seller.clone(),
+            token_amount: amount,
+            price,
+            timestamp: env.block.time.seconds() as u64,
+            total_price: price.checked_quote_to_base(amount)?,
+            maker_side,
+            buyer_fee,
+            seller_fee,
+        };
+
+        // Update user trade counts and histories
+        for user in [buyer, seller] {
+            let count = USER_TRADE_COUNT.load(storage, user.clone()).unwrap_or(0);
+            if count >= MAX_TRADES_PER_USER as u64 {
+                // Remove oldest trade
+                USER_TRADES.remove(storage, (user.clone(), count - MAX_TRADES_PER_USER as u64));
+            }
+
+            // remove oldest orders
+            let order_count = USER_ORDERS
+                .keys(storage, None, None, cosmwasm_std::Order::Ascending)
+                .count();
+            if order_count >= MAX_TRADES_PER_USER {
+                USER_ORDERS.remove(
+                    storage,
+                    (user.clone(), (order_count - MAX_TRADES_PER_USER) as u64),
+                );
+            }
+
+            USER_TRADES.save(storage, (user.clone(), count), &trade)?;
+            USER_TRADE_COUNT.save(storage, user.clone(), &(count + 1))?;
+        }
+
+        TRADES.save(storage, trade.id, &trade)?;
+        NEXT_TRADE_ID.save(storage, &(trade.id + 1u64))?;
+
+        Ok(trade.id)
+    }
+
+    fn calculate_exponential_price(
+        storage: &dyn Storage,
+        token_address: String,
+        current_supply: Uint128,
+        amount: Uint128,
+        is_buy: bool,
+    ) -> StdResult<Price> {
+        let pool = POOLS.load(storage, token_address.clone())?;
+        let token_info = TOKEN_INFO.load(storage, token_address)?;
+
+        // Base atomics per quote atomic at the start of the curve
+        let base_price = token_info.initial_price.to_decimal()?;
+        let slope = Decimal::from_ratio(pool.curve_slope, Uint128::new(1_000_000));
+
+        if pool.token_sold + amount >= token_info.bonding_curve_supply {
+            return Err(StdError::generic_err(
+                "Supply exceeds maximum limit for pricing",
+            ));
+        }
+
+        let (lower_bound, upper_bound) = if is_buy {
+            (current_supply, current_supply + amount)
+        } else {
+            (current_supply - amount, current_supply)
+        };
+
+        let lower_dec = Decimal::from_ratio(lower_bound, Uint128::new(1));
+        let upper_dec = Decimal::from_ratio(upper_bound, Uint128::new(1));
+
+        let alpha = Decimal::from_str("0.1").unwrap();
+
+        let exp_upper = calculate_ema_exp(slope * upper_dec, alpha)?;
+        let exp_lower = calculate_ema_exp(slope * lower_dec, alpha)?;
+
+        let avg_price = base_price * (exp_upper - exp_lower)
+            / (slope * Decimal::from_ratio(amount, Uint128::new(1)));
+
+        Price::from_decimal(avg_price, token_info.decimals)
+    }
+
+    fn calculate_ema_exp(x: Decimal, alpha: Decimal) -> StdResult<Decimal> {
+        let mut ema = Decimal::one();
+        let steps = 100;
+        for _ in 0..steps {
+            ema = alpha * x + (Decimal::one() - alpha) * ema;
+        }
+        Ok(ema)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::collections::BTreeMap;
+        use std::str::FromStr;
+
+        use super::*;
+        use cosmwasm_std::testing::{message_info, mock_dependencies, mock_env};
+        use cosmwasm_std::{
+            coins, from_json, Addr, Api, Coin, CosmosMsg, Decimal, SystemError, Uint128, WasmMsg,
+        };
+        use cosmwasm_std::{ContractResult, StdError, SystemResult, WasmQuery};
+        use cosmwasm_std::{HexBinary, SubMsg};
+        use cw20::{AllowanceResponse, BalanceResponse, Cw20ExecuteMsg, Expiration};
+        use cw_multi_test::App;
+        use token_factory::state::{Cw20Coin, State};
+
+        #[test]
+        fn test_proper_instantiate() {
+            let mut deps = mock_dependencies();
+
+            let msg = InstantiateMsg {
+                token_factory: Addr::unchecked("token_factory_addr"),
+                fee_collector: Addr::unchecked("fee_collector_addr"),
+                quote_token_total_supply: Uint128::from(100_000_000_000u128),
+                bonding_curve_supply: Uint128::from(80_000_000_000u128),
+                lp_supply: Uint128::from(20_000_000_000u128),
+                maker_fee: Decimal::percent(1),
+                taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
+                secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
+                base_token_denom: Denom::Native("uhuahua".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                min_order_notional: Uint128::from(1_000u128),
+                order_deposit: Uint128::from(100u128),
+                token_creation_fee: Uint128::from(1_000_000u128),
+                lp_policy: LpPolicy::Burn,
+            };
+
+            let info = message_info(&Addr::unchecked("creator"), &[]);
+
+            // Instantiate the contract
+            let res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+
+            // Check the response
+            assert_eq!(res.attributes.len(), 17);
+            assert_eq!(res.attributes[0].key, "action");
+            assert_eq!(res.attributes[0].value, "instantiate");
+            assert_eq!(res.attributes[1].key, "owner");
+            assert_eq!(res.attributes[1].value, info.sender.to_string());
+            assert_eq!(res.attributes[2].key, "token_factory");
+            assert_eq!(res.attributes[2].value, msg.token_factory.to_string());
+            assert_eq!(res.attributes[3].key, "fee_collector");
+            assert_eq!(res.attributes[3].value, msg.fee_collector.to_string());
+            assert_eq!(res.attributes[4].key, "quote_token_total_supply");
+            assert_eq!(
+                res.attributes[4].value,
+                msg.quote_token_total_supply.to_string()
+            );
+            assert_eq!(res.attributes[5].key, "bonding_curve_supply");
+            assert_eq!(
+                res.attributes[5].value,
+                msg.bonding_curve_supply.to_string()
+            );
+            assert_eq!(res.attributes[6].key, "lp_supply");
+            assert_eq!(res.attributes[6].value, msg.lp_supply.to_string());
+            assert_eq!(res.attributes[7].key, "maker_fee");
+            assert_eq!(res.attributes[7].value, msg.maker_fee.to_string());
+            assert_eq!(res.attributes[8].key, "taker_fee");
+            assert_eq!(res.attributes[8].value, msg.taker_fee.to_string());
+            assert_eq!(res.attributes[9].key, "secondary_amm_address");
+            assert_eq!(
+                res.attributes[9].value,
+                msg.secondary_amm_address.to_string()
+            );
+            assert_eq!(res.attributes[10].key, "base_token_denom");
+            assert_eq!(res.attributes[10].value, "uhuahua");
+            assert_eq!(res.attributes[11].key, "base_token_symbol");
+            assert_eq!(res.attributes[11].value, "huahua");
+            assert_eq!(res.attributes[12].key, "base_token_decimals");
+            assert_eq!(res.attributes[12].value, "6");
+            assert_eq!(res.attributes[13].key, "min_order_notional");
+            assert_eq!(res.attributes[13].value, "1000");
+            assert_eq!(res.attributes[14].key, "order_deposit");
+            assert_eq!(res.attributes[14].value, "100");
+            assert_eq!(res.attributes[15].key, "token_creation_fee");
+            assert_eq!(res.attributes[15].value, "1000000");
+            assert_eq!(res.attributes[16].key, "curve_fee");
+            assert_eq!(res.attributes[16].value, "0");
+
+            // The DEX registers itself with the factory, a refusal is only reported
+            assert_eq!(
+                res.messages,
+                vec![SubMsg::reply_on_error(
+                    WasmMsg::Execute {
+                        contract_addr: msg.token_factory.to_string(),
+                        msg: to_json_binary(&TokenFactoryExecuteMsg::RegisterDex {}).unwrap(),
+                        funds: vec![],
+                    },
+                    REPLY_REGISTER_DEX_ID,
+                )]
+            );
+
+            // Verify state was set correctly
+            let config = CONFIG.load(&deps.storage).unwrap();
+            assert_eq!(config.owner, info.sender);
+            assert_eq!(config.token_factory, msg.token_factory);
+            assert_eq!(config.fee_collector, msg.fee_collector);
+            assert_eq!(config.enabled, true);
+            assert_eq!(
+                config.quote_token_total_supply,
+                msg.quote_token_total_supply.into()
+            );
+            assert_eq!(config.bonding_curve_supply, msg.bonding_curve_supply.into());
+            assert_eq!(config.lp_supply, msg.lp_supply.into());
+            assert_eq!(config.maker_fee, msg.maker_fee,);
+            assert_eq!(config.taker_fee, msg.taker_fee,);
+            assert_eq!(config.secondary_amm_address, msg.secondary_amm_address);
+            assert_eq!(config.base_token_denom, msg.base_token_denom);
+
+            let next_order_id = NEXT_ORDER_ID.load(&deps.storage).unwrap();
+            assert_eq!(next_order_id, 0u64);
+
+            let next_trade_id = NEXT_TRADE_ID.load(&deps.storage).unwrap();
+            assert_eq!(next_trade_id, 0u64);
+
+            // A fee taking the whole trade is rejected like in UpdateFees
+            let res = instantiate(
+                deps.as_mut(),
+                mock_env(),
+                info.clone(),
+                InstantiateMsg {
+                    taker_fee: Decimal::one(),
+                    ..msg.clone()
+                },
+            );
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Trading fee rate must be between 0 and 1.")
+            );
+            let res = instantiate(
+                deps.as_mut(),
+                mock_env(),
+                info,
+                InstantiateMsg {
+                    curve_fee: Decimal::one(),
+                    ..msg
+                },
+            );
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Curve fee rate must be between 0 and 1.")
+            );
+        }
+
+        // Whitelists the configured base token the way instantiate does
+        fn save_default_currency(storage: &mut dyn Storage, config: &Config) {
+            let currency = BaseCurrency {
+                symbol: config.base_token_symbol.clone(),
+                denom: config.base_token_denom.clone(),
+                decimals: config.base_token_decimals,
+                base_price: Uint128::from(BASE_PRICE),
+                graduation_reserve: Uint128::zero(),
+                min_order_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
+            };
+            BASE_CURRENCIES
+                .save(storage, currency.symbol.clone(), &currency)
+                .unwrap();
+        }
+
+        #[test]
+        fn test_execute_create_token_happy_case() {
+            let mut deps = mock_dependencies();
+            let env = mock_env();
+
+            // Setup initial configuration
+            let config = Config {
+                owner: Addr::unchecked("creator"),
+                token_factory: Addr::unchecked("token_factory_addr"),
+                fee_collector: Addr::unchecked("fee_collector_addr"),
+                enabled: true,
+                quote_token_total_supply: 100_000_000_000u128,
+                bonding_curve_supply: 80_000_000_000u128,
+                lp_supply: 20_000_000_000u128,
+                maker_fee: Decimal::percent(1),
+                taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
+                secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
+                base_token_denom: Denom::Native("base_token_denom".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+            };
+
+            CONFIG.save(deps.as_mut().storage, &config).unwrap();
+            save_default_currency(deps.as_mut().storage, &config);
+
+            let app = App::default();
+            let creator = app.api().addr_make("creator");
+            let info = message_info(&creator, &[]);
+
+            // Define parameters for creating the token
+            let name = "Test Token".to_string();
+            let symbol = "TST".to_string();
+            let decimals = 8;
+            let uri = "URL".to_string();
+            let max_price_impact = Uint128::from(100u128);
+            let curve_slope = Uint128::from(1u128);
+
+            // Mock the token factory query response
+            deps.querier.update_wasm(move |_| {
+                SystemResult::Ok(ContractResult::Ok(
+                    to_json_binary(&token_factory::msg::GetConfigResponse {
+                        config: State {
+                            owner: app.api().addr_make("creator"),
+                            token_count: 0,
+                            token_code_id: 11013,
+                            token_code_hash: HexBinary::from_hex(
+                                &"528E5F16D05CDE640CDEF6D779A458CBF566AA4820E40ACFCF5066978D388CAD",
+                            )
+                            .unwrap(),
+                            token_creation_reply_id: 1,
+                        },
+                    })
+                    .unwrap(),
+                ))
+            });
+
+            // Execute the create_token function
+            let res = execute_create_token(
+                deps.as_mut(),
+                env.clone(),
+                info.clone(),
+                name.clone(),
+                symbol.clone(),
+                decimals,
+                uri.clone(),
+                max_price_impact,
+                curve_slope,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+
+            // Check the response
+            assert_eq!(res.attributes.len(), 10);
+            assert_eq!(res.attributes[0].key, "action");
+            assert_eq!(res.attributes[0].value, "create_token_pending");
+            assert_eq!(res.attributes[1].key, "name");
+            assert_eq!(res.attributes[1].value, name);
+            assert_eq!(res.attributes[2].key, "symbol");
+            assert_eq!(res.attributes[2].value, symbol);
+            assert_eq!(res.attributes[3].key, "decimals");
+            assert_eq!(res.attributes[3].value, decimals.to_string());
+            assert_eq!(res.attributes[4].key, "max_price_impact");
+            assert_eq!(res.attributes[4].value, max_price_impact.to_string());
+            assert_eq!(res.attributes[5].key, "curve_slope");
+            assert_eq!(res.attributes[5].value, curve_slope.to_string());
+            assert_eq!(res.attributes[6], attr("creation_fee", "0"));
+            assert_eq!(res.attributes[7], attr("initial_buy", "0"));
+            assert_eq!(res.attributes[8], attr("base_currency", "huahua"));
+            assert_eq!(res.attributes[9], attr("vesting", "0"));
+
+            // Check that the response includes the correct message and attributes
+            let msg = res.messages.get(0).expect("no message");
+            assert_eq!(
+                msg.msg,
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: "token_factory_addr".to_string(),
+                    msg: to_json_binary(&token_factory::msg::ExecuteMsg::CreateToken {
+                        name: name.clone(),
+                        symbol: symbol.clone(),
+                        decimals,
+                        uri,
+                        max_price_impact,
+                        curve_slope,
+                        initial_balances: vec![Cw20Coin {
+                            address: env.contract.address.to_string(),
+                            amount: Uint128::from(
+                                100_000_000_000u128 * 10u128.pow(decimals as u32)
+                            ),
+                        }],
+                        creator: Some(info.sender.to_string()),
+                        metadata: None,
+                        kind: None,
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                })
+            );
+
+            // // Verify that the token information was saved correctly
+            // let token_info = TOKEN_INFO
+            //     .load(&deps.storage, token_address.to_string())
+            //     .unwrap();
 
             // assert_eq!(token_info.name, name);
             // assert_eq!(token_info.symbol, symbol);
@@ -1688,73 +3906,2242 @@ pub mod execute {
             // assert_eq!(token_info.max_price_impact, max_price_impact);
             // assert_eq!(token_info.graduated, false);
 
-            // // Verify that the token pair information was saved correctly
-            // let pair_id = format!("{}/{}", symbol, &config.base_token_denom[1..]);
-            // let token_pair = TOKEN_PAIRS.load(&deps.storage, pair_id.clone()).unwrap();
-            // assert_eq!(token_pair.base_token, config.base_token_denom);
-            // assert_eq!(token_pair.quote_token, token_address.to_string());
-            // assert_eq!(token_pair.base_decimals, 6);
-            // assert_eq!(token_pair.quote_decimals, decimals);
-            // assert_eq!(token_pair.enabled, true);
+            // // Verify that the token pair information was saved correctly
+            // let pair_id = format!("{}/{}", symbol, &config.base_token_denom[1..]);
+            // let token_pair = TOKEN_PAIRS.load(&deps.storage, pair_id.clone()).unwrap();
+            // assert_eq!(token_pair.base_token, config.base_token_denom);
+            // assert_eq!(token_pair.quote_token, token_address.to_string());
+            // assert_eq!(token_pair.base_decimals, 6);
+            // assert_eq!(token_pair.quote_decimals, decimals);
+            // assert_eq!(token_pair.enabled, true);
+
+            // // Verify that the pool information was saved correctly
+            // let pool = POOLS
+            //     .load(&deps.storage, token_address.to_string())
+            //     .unwrap();
+            // assert_eq!(pool.token_address, token_address);
+            // assert_eq!(pool.total_reserve_token, Uint128::zero());
+            // assert_eq!(pool.token_sold, Uint128::zero());
+            // assert_eq!(pool.total_volume, Uint128::zero());
+            // assert_eq!(pool.total_fees_collected, Uint128::zero());
+            // assert_eq!(pool.curve_slope, curve_slope);
+            // assert_eq!(pool.pair_id, pair_id);
+            // assert_eq!(pool.total_trades, Uint128::zero());
+            // assert_eq!(pool.last_price, Uint128::from(BASE_PRICE));
+            // assert_eq!(pool.enabled, true);
+        }
+
+        #[test]
+        fn test_execute_create_token_creation_fee() {
+            let mut deps = mock_dependencies();
+            let env = mock_env();
+
+            let config = Config {
+                owner: Addr::unchecked("creator"),
+                token_factory: Addr::unchecked("token_factory_addr"),
+                fee_collector: Addr::unchecked("fee_collector_addr"),
+                enabled: true,
+                quote_token_total_supply: 100_000_000_000u128,
+                bonding_curve_supply: 80_000_000_000u128,
+                lp_supply: 20_000_000_000u128,
+                maker_fee: Decimal::percent(1),
+                taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
+                secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
+                base_token_denom: Denom::Native("uhuahua".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::from(1_000u128),
+                lp_policy: LpPolicy::Burn,
+            };
+            CONFIG.save(deps.as_mut().storage, &config).unwrap();
+            save_default_currency(deps.as_mut().storage, &config);
+
+            let create = |deps: DepsMut, funds: &[Coin]| {
+                execute_create_token(
+                    deps,
+                    env.clone(),
+                    message_info(&Addr::unchecked("creator"), funds),
+                    "Test Token".to_string(),
+                    "TST".to_string(),
+                    6,
+                    "URL".to_string(),
+                    Uint128::from(100u128),
+                    Uint128::from(1u128),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                )
+            };
+
+            // The fee must be paid in full, and no more
+            let res = create(deps.as_mut(), &[]);
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Insufficient native token sent. Required: 1000, Sent: 0")
+            );
+            let res = create(deps.as_mut(), &coins(1_500, "uhuahua"));
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Excess native token sent. Required: 1000, Sent: 1500")
+            );
+
+            // The fee stays in the treasury, only the factory is called
+            let res = create(deps.as_mut(), &coins(1_000, "uhuahua")).unwrap();
+            assert_eq!(res.messages.len(), 1);
+            assert_eq!(
+                TREASURY
+                    .load(deps.as_ref().storage, "uhuahua".to_string())
+                    .unwrap(),
+                TreasuryBalance {
+                    denom: Denom::Native("uhuahua".to_string()),
+                    accumulated: Uint128::new(1_000),
+                    distributed: Uint128::zero(),
+                }
+            );
+            assert_eq!(res.attributes[6], attr("creation_fee", "1000"));
+        }
+
+        #[test]
+        fn test_distribute_fees_by_shares() {
+            let mut deps = mock_dependencies();
+            let config = Config {
+                owner: Addr::unchecked("creator"),
+                token_factory: Addr::unchecked("token_factory_addr"),
+                fee_collector: Addr::unchecked("fee_collector_addr"),
+                enabled: true,
+                quote_token_total_supply: 100_000_000_000u128,
+                bonding_curve_supply: 80_000_000_000u128,
+                lp_supply: 20_000_000_000u128,
+                maker_fee: Decimal::percent(1),
+                taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
+                secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
+                base_token_denom: Denom::Native("uhuahua".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+            };
+            CONFIG.save(deps.as_mut().storage, &config).unwrap();
+            let native = Denom::Native("uhuahua".to_string());
+            let cw20 = Denom::Cw20(Addr::unchecked("quote_token"));
+            accrue_fee(deps.as_mut().storage, &native, Uint128::new(1_000)).unwrap();
+            accrue_fee(deps.as_mut().storage, &cw20, Uint128::new(11)).unwrap();
+            let buyback_addr = deps.api.addr_make("buyback");
+            let staking_addr = deps.api.addr_make("staking");
+
+            let update = |deps: DepsMut, sender: &str, shares: [u64; 3], buyback: bool| {
+                execute_update_fee_distribution(
+                    deps,
+                    message_info(&Addr::unchecked(sender), &[]),
+                    Decimal::percent(shares[0]),
+                    Decimal::percent(shares[1]),
+                    Decimal::percent(shares[2]),
+                    buyback.then(|| buyback_addr.to_string()),
+                    Some(staking_addr.to_string()),
+                )
+            };
+
+            // Only the owner sets the split, which must be complete and addressed
+            let res = update(deps.as_mut(), "random", [50, 30, 20], true);
+            assert_eq!(res.unwrap_err(), StdError::generic_err("Unauthorized"));
+            let res = update(deps.as_mut(), "creator", [50, 30, 30], true);
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Fee shares must add up to 1")
+            );
+            let res = update(deps.as_mut(), "creator", [50, 30, 20], false);
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("A buyback share needs a buyback address")
+            );
+            update(deps.as_mut(), "creator", [50, 30, 20], true).unwrap();
+
+            // Buyback and stakers round down, the fee collector keeps the dust
+            let res = execute_distribute_fees(deps.as_mut()).unwrap();
+            let cw20_transfer = |recipient: &Addr, amount: u128| {
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: "quote_token".to_string(),
+                    msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                        recipient: recipient.to_string(),
+                        amount: Uint128::new(amount),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                })
+            };
+            let bank_send = |recipient: &Addr, amount: u128| {
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: recipient.to_string(),
+                    amount: coins(amount, "uhuahua"),
+                })
+            };
+            assert_eq!(
+                res.messages.into_iter().map(|m| m.msg).collect::<Vec<_>>(),
+                vec![
+                    cw20_transfer(&config.fee_collector, 6),
+                    cw20_transfer(&buyback_addr, 3),
+                    cw20_transfer(&staking_addr, 2),
+                    bank_send(&config.fee_collector, 500),
+                    bank_send(&buyback_addr, 300),
+                    bank_send(&staking_addr, 200),
+                ]
+            );
+
+            // Everything accrued is now distributed, so a second call pays nothing
+            let treasury = query::query_treasury(deps.as_ref()).unwrap();
+            assert_eq!(treasury.distribution.buyback_share, Decimal::percent(30));
+            for balance in treasury.balances {
+                assert_eq!(balance.accumulated, balance.distributed);
+            }
+            let res = execute_distribute_fees(deps.as_mut()).unwrap();
+            assert!(res.messages.is_empty());
+        }
+
+        #[test]
+        fn test_execute_create_token_initial_buy() {
+            let mut deps = mock_dependencies();
+            let env = mock_env();
+
+            let config = Config {
+                owner: Addr::unchecked("creator"),
+                token_factory: Addr::unchecked("token_factory_addr"),
+                fee_collector: Addr::unchecked("fee_collector_addr"),
+                enabled: true,
+                quote_token_total_supply: 100_000_000_000u128,
+                bonding_curve_supply: 80_000_000_000u128,
+                lp_supply: 20_000_000_000u128,
+                maker_fee: Decimal::percent(1),
+                taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
+                secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
+                base_token_denom: Denom::Native("uhuahua".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::from(1_000u128),
+                lp_policy: LpPolicy::Burn,
+            };
+            CONFIG.save(deps.as_mut().storage, &config).unwrap();
+            save_default_currency(deps.as_mut().storage, &config);
+
+            let create = |deps: DepsMut, funds: &[Coin], amount: u128| {
+                execute_create_token(
+                    deps,
+                    env.clone(),
+                    message_info(&Addr::unchecked("creator"), funds),
+                    "Test Token".to_string(),
+                    "TST".to_string(),
+                    6,
+                    "URL".to_string(),
+                    Uint128::from(100u128),
+                    Uint128::from(1u128),
+                    Some(InitialBuy {
+                        amount: Uint128::from(amount),
+                        min_tokens_out: Uint128::from(10u128),
+                    }),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                )
+            };
+
+            let res = create(deps.as_mut(), &coins(1_000, "uhuahua"), 0);
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Initial buy amount must be greater than 0.")
+            );
+
+            // The buy is funded on top of the creation fee
+            let res = create(deps.as_mut(), &coins(1_000, "uhuahua"), 5_000);
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Insufficient native token sent. Required: 6000, Sent: 1000")
+            );
+            assert!(PENDING_INITIAL_BUY
+                .may_load(deps.as_ref().storage)
+                .unwrap()
+                .is_none());
+
+            // Only the fee is booked to the treasury, the rest waits for the reply
+            let res = create(deps.as_mut(), &coins(6_000, "uhuahua"), 5_000).unwrap();
+            assert_eq!(res.messages.len(), 1);
+            assert_eq!(
+                TREASURY
+                    .load(deps.as_ref().storage, "uhuahua".to_string())
+                    .unwrap()
+                    .accumulated,
+                Uint128::new(1_000)
+            );
+            assert_eq!(res.attributes[7], attr("initial_buy", "5000"));
+            assert_eq!(
+                PENDING_INITIAL_BUY.load(deps.as_ref().storage).unwrap(),
+                PendingInitialBuy {
+                    creator: Addr::unchecked("creator"),
+                    amount: Uint128::from(5_000u128),
+                    min_tokens_out: Uint128::from(10u128),
+                }
+            );
+        }
+
+        #[test]
+        fn test_execute_update_base_currencies() {
+            let mut deps = mock_dependencies();
+            let env = mock_env();
+
+            let config = Config {
+                owner: Addr::unchecked("owner"),
+                token_factory: Addr::unchecked("token_factory_addr"),
+                fee_collector: Addr::unchecked("fee_collector_addr"),
+                enabled: true,
+                quote_token_total_supply: 100_000_000_000u128,
+                bonding_curve_supply: 80_000_000_000u128,
+                lp_supply: 20_000_000_000u128,
+                maker_fee: Decimal::percent(1),
+                taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
+                secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
+                base_token_denom: Denom::Native("uhuahua".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::from(1_000u128),
+                lp_policy: LpPolicy::Burn,
+            };
+            CONFIG.save(deps.as_mut().storage, &config).unwrap();
+            save_default_currency(deps.as_mut().storage, &config);
+
+            let atom = BaseCurrencyMsg {
+                symbol: "atom".to_string(),
+                denom: UncheckedDenom::Native("uatom".to_string()),
+                decimals: 6,
+                base_price: Uint128::from(5u128),
+                graduation_reserve: Uint128::from(1_000_000u128),
+                min_order_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
+            };
+            let owner_info = message_info(&Addr::unchecked("owner"), &[]);
+
+            let res = execute_update_base_currencies(
+                deps.as_mut(),
+                message_info(&Addr::unchecked("creator"), &[]),
+                vec![atom.clone()],
+                vec![],
+            );
+            assert_eq!(res.unwrap_err(), StdError::generic_err("Unauthorized"));
+
+            execute_update_base_currencies(deps.as_mut(), owner_info.clone(), vec![atom], vec![])
+                .unwrap();
+            assert_eq!(
+                BASE_CURRENCIES
+                    .load(deps.as_ref().storage, "atom".to_string())
+                    .unwrap()
+                    .denom,
+                Denom::Native("uatom".to_string())
+            );
+
+            // The fee stays in the default currency, the dev buy is paid in the picked one
+            let create = |deps: DepsMut, funds: &[Coin], base_currency: &str| {
+                execute_create_token(
+                    deps,
+                    env.clone(),
+                    message_info(&Addr::unchecked("creator"), funds),
+                    "Test Token".to_string(),
+                    "TST".to_string(),
+                    6,
+                    "URL".to_string(),
+                    Uint128::from(100u128),
+                    Uint128::from(1u128),
+                    Some(InitialBuy {
+                        amount: Uint128::from(5_000u128),
+                        min_tokens_out: Uint128::from(10u128),
+                    }),
+                    None,
+                    None,
+                    None,
+                    Some(base_currency.to_string()),
+                    None,
+                )
+            };
+            let res = create(deps.as_mut(), &coins(1_000, "uhuahua"), "usdc");
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Base currency is not whitelisted")
+            );
+            let res = create(deps.as_mut(), &coins(6_000, "uhuahua"), "atom");
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Excess native token sent. Required: 1000, Sent: 6000")
+            );
+            let res = create(
+                deps.as_mut(),
+                &[
+                    Coin::new(1_000u128, "uhuahua"),
+                    Coin::new(5_000u128, "uatom"),
+                ],
+                "atom",
+            )
+            .unwrap();
+            assert_eq!(res.attributes[8], attr("base_currency", "atom"));
+            assert_eq!(
+                PENDING_BASE_CURRENCY
+                    .load(deps.as_ref().storage)
+                    .unwrap()
+                    .base_price,
+                Uint128::from(5u128)
+            );
+
+            // The default currency stays, others can be dropped for new launches
+            let res = execute_update_base_currencies(
+                deps.as_mut(),
+                owner_info.clone(),
+                vec![],
+                vec!["huahua".to_string()],
+            );
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("The default base currency cannot be removed")
+            );
+            execute_update_base_currencies(
+                deps.as_mut(),
+                owner_info,
+                vec![],
+                vec!["atom".to_string()],
+            )
+            .unwrap();
+            let currencies = query::query_base_currencies(deps.as_ref())
+                .unwrap()
+                .base_currencies;
+            assert_eq!(currencies.len(), 1);
+            assert_eq!(currencies[0].symbol, "huahua");
+        }
+
+        #[test]
+        fn test_create_order_happy_case() {
+            let mut deps = mock_dependencies();
+            let env = mock_env();
+
+            // Create initial buy and sell orders
+            let mut buy_order = Order {
+                id: 1,
+                owner: Addr::unchecked("buyer"),
+                pair_id: "pair_id".to_string(),
+                token_amount: Uint128::from(100u128),
+                price: Price::new(Uint128::from(10u128), 0),
+                timestamp: env.block.time.seconds() as u64,
+                status: OrderStatus::Active,
+                filled_amount: Uint128::zero(),
+                remaining_amount: Uint128::from(100u128),
+                order_type: OrderType::Buy,
+                created_at: env.block.height,
+                deposit: Uint128::zero(),
+            };
+
+            let mut sell_order = Order {
+                id: 2,
+                owner: Addr::unchecked("seller"),
+                pair_id: "pair_id".to_string(),
+                token_amount: Uint128::from(100u128),
+                price: Price::new(Uint128::from(10u128), 0),
+                timestamp: env.block.time.seconds() as u64 + 1, // Sell order is newer for fee testing
+                status: OrderStatus::Active,
+                filled_amount: Uint128::zero(),
+                remaining_amount: Uint128::from(100u128),
+                order_type: OrderType::Sell,
+                created_at: env.block.height,
+                deposit: Uint128::zero(),
+            };
+
+            // Define token pair and config
+            let token_pair = TokenPair {
+                base_token: Denom::Native("base_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
+                base_decimals: 6,
+                quote_decimals: 8,
+                enabled: true,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
+            };
+
+            let config = Config {
+                owner: Addr::unchecked("creator"),
+                token_factory: Addr::unchecked("token_factory_addr"),
+                fee_collector: Addr::unchecked("fee_collector_addr"),
+                enabled: true,
+                quote_token_total_supply: 100_000_000_000u128,
+                bonding_curve_supply: 80_000_000_000u128,
+                lp_supply: 20_000_000_000u128,
+                maker_fee: Decimal::percent(1), // 1% maker fee
+                taker_fee: Decimal::percent(2), // 2% taker fee
+                curve_fee: Decimal::zero(),
+                secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
+                base_token_denom: Denom::Native("base_token_denom".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+            };
+
+            // Execute the create_order function
+            NEXT_TRADE_ID.save(&mut deps.storage, &1).unwrap();
+            let res = create_order(
+                &mut deps.storage,
+                &env,
+                &mut buy_order,
+                &mut sell_order,
+                &token_pair,
+                &config,
+            )
+            .unwrap();
+
+            // The buy order is older, so the buyer pays the maker rate in quote tokens
+            // and the seller the taker rate in base tokens
+            let total_price = Uint128::new(100u128) * Uint128::new(10u128); // 1000 base tokens
+            let buyer_fee = Uint128::new(1); // 1% of 100 quote tokens
+            let seller_fee = Uint128::new(20); // 2% of 1000 base tokens
+            let seller_receives = total_price - seller_fee; // 980 base tokens
+
+            // Check response messages (token transfers)
+            assert_eq!(res.messages.len(), 2); // buyer quote, seller base
+
+            // Message 1: Quote tokens to buyer (99 quote tokens after fees)
+            match &res.messages[0].msg {
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr,
+                    msg,
+                    funds,
+                }) => {
+                    assert_eq!(contract_addr, "quote_token");
+                    let cw20_msg: Cw20ExecuteMsg = from_json(msg).unwrap();
+                    assert_eq!(
+                        cw20_msg,
+                        Cw20ExecuteMsg::Transfer {
+                            recipient: "buyer".to_string(),
+                            amount: Uint128::new(99u128),
+                        }
+                    );
+                    assert!(funds.is_empty());
+                }
+                _ => panic!("Unexpected message type for quote token transfer"),
+            }
+
+            // Message 2: Base tokens to seller (980 base tokens after fees)
+            match &res.messages[1].msg {
+                CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => {
+                    assert_eq!(to_address, "seller");
+                    assert_eq!(amount.len(), 1);
+                    assert_eq!(
+                        amount[0],
+                        Coin {
+                            denom: "base_token".to_string(),
+                            amount: seller_receives
+                        }
+                    );
+                }
+                _ => panic!("Unexpected message type for base token transfer"),
+            }
+
+            // Both fees stay in the treasury in the token they were withheld from
+            let treasury = |denom: &str| {
+                TREASURY
+                    .load(&deps.storage, denom.to_string())
+                    .unwrap()
+                    .accumulated
+            };
+            assert_eq!(treasury("quote_token"), buyer_fee);
+            assert_eq!(treasury("base_token"), seller_fee);
+
+            // Check response attributes
+            assert_eq!(res.attributes.len(), 17);
+            assert_eq!(res.attributes[0], attr("event_type", "trade"));
+            assert_eq!(res.attributes[1], attr("trade_id", "1"));
+            assert_eq!(res.attributes[2], attr("pair_id", "pair_id"));
+            assert_eq!(res.attributes[3], attr("buy_order_id", "1"));
+            assert_eq!(res.attributes[4], attr("sell_order_id", "2"));
+            assert_eq!(res.attributes[5], attr("price", "10"));
+            assert_eq!(res.attributes[6], attr("amount", "100"));
+            assert_eq!(res.attributes[7], attr("total", total_price.to_string()));
+            assert_eq!(res.attributes[8], attr("maker_side", "Buy"));
+            assert_eq!(res.attributes[9], attr("buyer_fee", buyer_fee.to_string()));
+            assert_eq!(
+                res.attributes[10],
+                attr("seller_fee", seller_fee.to_string())
+            );
+            assert_eq!(res.attributes[11], attr("buyer_receives", "99"));
+            assert_eq!(
+                res.attributes[12],
+                attr("seller_receives", seller_receives.to_string())
+            );
+            assert_eq!(res.attributes[13], attr("buyer_refund", "0"));
+            assert_eq!(res.attributes[14], attr("base_token", "base_token"));
+            assert_eq!(res.attributes[15], attr("quote_token", "quote_token"));
+            assert_eq!(
+                res.attributes[16],
+                attr("timestamp", env.block.time.seconds().to_string())
+            );
+
+            // The trade records who paid which fee
+            let trade = TRADES.load(&deps.storage, 1).unwrap();
+            assert_eq!(trade.buyer, Addr::unchecked("buyer"));
+            assert_eq!(trade.seller, Addr::unchecked("seller"));
+            assert_eq!(trade.maker_side, OrderType::Buy);
+            assert_eq!(trade.buyer_fee, buyer_fee);
+            assert_eq!(trade.seller_fee, seller_fee);
+            assert_eq!(NEXT_TRADE_ID.load(&deps.storage).unwrap(), 2);
+
+            // Check updated order amounts
+            assert_eq!(buy_order.filled_amount, Uint128::new(100u128));
+            assert_eq!(buy_order.remaining_amount, Uint128::zero());
+            assert_eq!(buy_order.status, OrderStatus::Filled);
+            assert_eq!(sell_order.filled_amount, Uint128::new(100u128));
+            assert_eq!(sell_order.remaining_amount, Uint128::zero());
+            assert_eq!(sell_order.status, OrderStatus::Filled);
+
+            // Verify orders were saved correctly in storage
+            let saved_buy_order = USER_ORDERS
+                .load(&deps.storage, (Addr::unchecked("buyer"), 1))
+                .unwrap();
+            assert_eq!(saved_buy_order, buy_order);
+            let saved_sell_order = USER_ORDERS
+                .load(&deps.storage, (Addr::unchecked("seller"), 2))
+                .unwrap();
+            assert_eq!(saved_sell_order, sell_order);
+        }
+
+        #[test]
+        fn test_create_order_refunds_price_improvement() {
+            let mut deps = mock_dependencies();
+            let env = mock_env();
+
+            // The buyer escrowed 100 at 12, a cheaper sell fills 60 of it at 10
+            let mut buy_order = Order {
+                id: 1,
+                owner: Addr::unchecked("buyer"),
+                pair_id: "pair_id".to_string(),
+                token_amount: Uint128::new(100),
+                price: Price::new(Uint128::new(12), 0),
+                timestamp: env.block.time.seconds(),
+                status: OrderStatus::Active,
+                filled_amount: Uint128::zero(),
+                remaining_amount: Uint128::new(100),
+                order_type: OrderType::Buy,
+                created_at: env.block.height,
+                deposit: Uint128::zero(),
+            };
+            let mut sell_order = Order {
+                id: 2,
+                owner: Addr::unchecked("seller"),
+                pair_id: "pair_id".to_string(),
+                token_amount: Uint128::new(60),
+                price: Price::new(Uint128::new(10), 0),
+                timestamp: env.block.time.seconds(),
+                status: OrderStatus::Active,
+                filled_amount: Uint128::zero(),
+                remaining_amount: Uint128::new(60),
+                order_type: OrderType::Sell,
+                created_at: env.block.height,
+                deposit: Uint128::zero(),
+            };
+            let token_pair = TokenPair {
+                base_token: Denom::Native("base_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
+                base_decimals: 6,
+                quote_decimals: 6,
+                enabled: true,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
+            };
+            let config = Config {
+                owner: Addr::unchecked("creator"),
+                token_factory: Addr::unchecked("token_factory_addr"),
+                fee_collector: Addr::unchecked("fee_collector_addr"),
+                enabled: true,
+                quote_token_total_supply: 100_000_000_000u128,
+                bonding_curve_supply: 80_000_000_000u128,
+                lp_supply: 20_000_000_000u128,
+                maker_fee: Decimal::zero(),
+                taker_fee: Decimal::zero(),
+                curve_fee: Decimal::zero(),
+                secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
+                base_token_denom: Denom::Native("base_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+            };
+
+            NEXT_TRADE_ID.save(&mut deps.storage, &1).unwrap();
+            let res = create_order(
+                &mut deps.storage,
+                &env,
+                &mut buy_order,
+                &mut sell_order,
+                &token_pair,
+                &config,
+            )
+            .unwrap();
+
+            // The seller gets 600, the buyer the 120 escrowed above the trade price
+            assert_eq!(res.messages.len(), 3);
+            assert_eq!(
+                res.messages[2].msg,
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "buyer".to_string(),
+                    amount: coins(120, "base_token"),
+                })
+            );
+            assert!(res.attributes.contains(&attr("buyer_refund", "120")));
+
+            // The rest of the escrow still covers the 40 left at the buyer's price
+            assert_eq!(buy_order.remaining_amount, Uint128::new(40));
+            assert_eq!(
+                buy_order
+                    .price
+                    .checked_quote_to_base_ceil(buy_order.remaining_amount)
+                    .unwrap(),
+                Uint128::new(1200 - 600 - 120)
+            );
+        }
+
+        #[test]
+        fn test_execute_place_limit_order_buy() {
+            let mut deps = mock_dependencies();
+            let env = mock_env();
+            let info = message_info(
+                &Addr::unchecked("buyer"),
+                &[Coin {
+                    denom: "base_token".to_string(),
+                    amount: Uint128::from(1000u128),
+                }],
+            );
+
+            let config = Config {
+                owner: Addr::unchecked("creator"),
+                token_factory: Addr::unchecked("token_factory_addr"),
+                fee_collector: Addr::unchecked("fee_collector_addr"),
+                enabled: true,
+                quote_token_total_supply: 100_000_000_000u128,
+                bonding_curve_supply: 80_000_000_000u128,
+                lp_supply: 20_000_000_000u128,
+                maker_fee: Decimal::percent(1),
+                taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
+                secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
+                base_token_denom: Denom::Native("ubase_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+            };
+
+            let token_pair = TokenPair {
+                base_token: Denom::Native("base_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
+                base_decimals: 6,
+                quote_decimals: 8,
+                enabled: true,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
+            };
+
+            CONFIG.save(deps.as_mut().storage, &config).unwrap();
+            TOKEN_PAIRS
+                .save(deps.as_mut().storage, "pair_id".to_string(), &token_pair)
+                .unwrap();
+
+            ORDER_BOOKS
+                .save(
+                    deps.as_mut().storage,
+                    "pair_id".to_string(),
+                    &OrderBook {
+                        pair_id: "pair_id".to_string(),
+                        buy_orders: BTreeMap::new(),
+                        sell_orders: BTreeMap::new(),
+                    },
+                )
+                .unwrap();
+
+            NEXT_ORDER_ID.save(deps.as_mut().storage, &0u64).unwrap();
+            NEXT_TRADE_ID.save(deps.as_mut().storage, &0u64).unwrap(); // Initialize NEXT_TRADE_ID
+
+            let amount = Uint128::from(100u128);
+            let price = Price::new(Uint128::from(1_000_000_000u128), 8);
+            let is_buy = true;
+
+            // Execute the place limit order function
+            let res = execute_place_limit_order(
+                deps.as_mut(),
+                env,
+                info.clone(),
+                "pair_id".to_string(),
+                amount,
+                price,
+                is_buy,
+            )
+            .unwrap();
+
+            // Verify response attributes
+            assert_eq!(res.attributes.len(), 6);
+            assert_eq!(res.attributes[0].key, "action");
+            assert_eq!(res.attributes[0].value, "place_limit_order");
+            assert_eq!(res.attributes[1].key, "order_id");
+            assert_eq!(res.attributes[1].value, "0");
+            assert_eq!(res.attributes[2].key, "pair_id");
+            assert_eq!(res.attributes[2].value, "pair_id");
+            assert_eq!(res.attributes[3].key, "is_buy");
+            assert_eq!(res.attributes[3].value, "true");
+            assert_eq!(res.attributes[4].key, "amount");
+            assert_eq!(res.attributes[4].value, amount.to_string());
+            assert_eq!(res.attributes[5].key, "price");
+            assert_eq!(res.attributes[5].value, price.to_string());
+
+            // Verify the order was added to the order book
+            let order_book = ORDER_BOOKS
+                .load(&deps.storage, "pair_id".to_string())
+                .unwrap();
+            assert_eq!(order_book.buy_orders.len(), 1);
+            assert_eq!(
+                order_book
+                    .buy_orders
+                    .get(&price.atomics.u128())
+                    .unwrap()
+                    .len(),
+                1
+            );
+
+            // Verify the order was saved in user orders
+            let order = USER_ORDERS
+                .load(&deps.storage, (info.sender.clone(), 0))
+                .unwrap();
+            assert_eq!(order.owner, Addr::unchecked("buyer"));
+            assert_eq!(order.pair_id, "pair_id");
+            assert_eq!(order.token_amount, amount);
+            assert_eq!(order.price, price);
+            assert_eq!(order.remaining_amount, amount);
+            assert_eq!(order.order_type, OrderType::Buy);
+        }
+
+        #[test]
+        fn test_execute_place_limit_order_sell() {
+            let mut deps = mock_dependencies();
+            let env = mock_env();
+            let info = message_info(&Addr::unchecked("seller"), &[]);
+
+            let config = Config {
+                owner: Addr::unchecked("creator"),
+                token_factory: Addr::unchecked("token_factory_addr"),
+                fee_collector: Addr::unchecked("fee_collector_addr"),
+                enabled: true,
+                quote_token_total_supply: 100_000_000_000u128,
+                bonding_curve_supply: 80_000_000_000u128,
+                lp_supply: 20_000_000_000u128,
+                maker_fee: Decimal::percent(1),
+                taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
+                secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
+                base_token_denom: Denom::Native("ubase_token_denom".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+            };
+
+            let token_pair = TokenPair {
+                base_token: Denom::Native("base_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
+                base_decimals: 6,
+                quote_decimals: 8,
+                enabled: true,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
+            };
+
+            CONFIG.save(deps.as_mut().storage, &config).unwrap();
+            TOKEN_PAIRS
+                .save(deps.as_mut().storage, "pair_id".to_string(), &token_pair)
+                .unwrap();
+
+            ORDER_BOOKS
+                .save(
+                    deps.as_mut().storage,
+                    "pair_id".to_string(),
+                    &OrderBook {
+                        pair_id: "pair_id".to_string(),
+                        buy_orders: BTreeMap::new(),
+                        sell_orders: BTreeMap::new(),
+                    },
+                )
+                .unwrap();
+
+            NEXT_TRADE_ID.save(deps.as_mut().storage, &0u64).unwrap();
+            NEXT_ORDER_ID.save(deps.as_mut().storage, &0u64).unwrap();
+
+            let amount = Uint128::from(100u128);
+            let price = Price::new(Uint128::from(1_000_000_000u128), 8);
+            let is_buy = false;
+
+            // Mock balance and allowance queries for CW20 tokens
+            let sell_info = message_info(&Addr::unchecked("seller"), &[]);
+            let cw20_token_address = String::from("quote_token");
+
+            // Clone necessary variables to avoid moving them into the closure
+            let cw20_token_address_clone = cw20_token_address.clone();
+            let sell_info_sender_clone = sell_info.sender.clone();
+
+            // Mock the balance and allowance queries
+            deps.querier.update_wasm(move |query| {
+                let cw20_token_address = cw20_token_address_clone.clone();
+                let sell_info_sender = sell_info_sender_clone.clone();
+                let env = mock_env();
+
+                match query {
+                    WasmQuery::Smart { contract_addr, msg } => {
+                        if contract_addr == &cw20_token_address.clone() {
+                            if let Ok(cw20::Cw20QueryMsg::Balance { address }) = from_json(&msg) {
+                                if address == sell_info_sender.into_string() {
+                                    return SystemResult::Ok(ContractResult::Ok(
+                                        to_json_binary(&BalanceResponse {
+                                            balance: Uint128::from(1000u128),
+                                        })
+                                        .unwrap(),
+                                    ));
+                                }
+                            } else if let Ok(cw20::Cw20QueryMsg::Allowance { owner, spender }) =
+                                from_json(&msg)
+                            {
+                                if owner == sell_info_sender.into_string()
+                                    && spender == env.contract.address.to_string()
+                                {
+                                    return SystemResult::Ok(ContractResult::Ok(
+                                        to_json_binary(&AllowanceResponse {
+                                            allowance: Uint128::from(1000u128),
+                                            expires: Expiration::Never {},
+                                        })
+                                        .unwrap(),
+                                    ));
+                                }
+                            }
+                        }
+                        SystemResult::Err(SystemError::UnsupportedRequest {
+                            kind: "".to_string(),
+                        })
+                    }
+                    _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                        kind: "".to_string(),
+                    }),
+                }
+            });
+
+            // Execute the place limit order function
+            let res = execute_place_limit_order(
+                deps.as_mut(),
+                env,
+                info.clone(),
+                "pair_id".to_string(),
+                amount,
+                price,
+                is_buy,
+            )
+            .unwrap();
+
+            // Verify response attributes
+            assert_eq!(res.attributes.len(), 6);
+            assert_eq!(res.attributes[0].key, "action");
+            assert_eq!(res.attributes[0].value, "place_limit_order");
+            assert_eq!(res.attributes[1].key, "order_id");
+            assert_eq!(res.attributes[1].value, "0");
+            assert_eq!(res.attributes[2].key, "pair_id");
+            assert_eq!(res.attributes[2].value, "pair_id");
+            assert_eq!(res.attributes[3].key, "is_buy");
+            assert_eq!(res.attributes[3].value, "false");
+            assert_eq!(res.attributes[4].key, "amount");
+            assert_eq!(res.attributes[4].value, amount.to_string());
+            assert_eq!(res.attributes[5].key, "price");
+            assert_eq!(res.attributes[5].value, price.to_string());
+
+            // Verify the order was added to the order book
+            let order_book = ORDER_BOOKS
+                .load(&deps.storage, "pair_id".to_string())
+                .unwrap();
+            assert_eq!(order_book.sell_orders.len(), 1);
+            assert_eq!(
+                order_book
+                    .sell_orders
+                    .get(&price.atomics.u128())
+                    .unwrap()
+                    .len(),
+                1
+            );
+
+            // Verify the order was saved in user orders
+            let order = USER_ORDERS
+                .load(&deps.storage, (info.sender.clone(), 0))
+                .unwrap();
+            assert_eq!(order.owner, Addr::unchecked("seller"));
+            assert_eq!(order.pair_id, "pair_id");
+            assert_eq!(order.token_amount, amount);
+            assert_eq!(order.price, price);
+            assert_eq!(order.remaining_amount, amount);
+            assert_eq!(order.order_type, OrderType::Sell);
+        }
+
+        #[test]
+        fn test_execute_place_limit_order_matching_orders() {
+            let mut deps = mock_dependencies();
+            let env = mock_env();
+            let buyer_info = message_info(
+                &Addr::unchecked("buyer"),
+                &[Coin {
+                    denom: "base_token".to_string(),
+                    amount: Uint128::from(1000u128),
+                }],
+            );
+            let seller_info = message_info(&Addr::unchecked("seller"), &[]);
+
+            let config = Config {
+                owner: Addr::unchecked("creator"),
+                token_factory: Addr::unchecked("token_factory_addr"),
+                fee_collector: Addr::unchecked("fee_collector_addr"),
+                enabled: true,
+                quote_token_total_supply: 100_000_000_000u128,
+                bonding_curve_supply: 80_000_000_000u128,
+                lp_supply: 20_000_000_000u128,
+                maker_fee: Decimal::percent(1),
+                taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
+                secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
+                base_token_denom: Denom::Native("ubase_token_denom".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+            };
+
+            let token_pair = TokenPair {
+                base_token: Denom::Native("base_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
+                base_decimals: 6,
+                quote_decimals: 8,
+                enabled: true,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
+            };
+
+            CONFIG.save(deps.as_mut().storage, &config).unwrap();
+            TOKEN_PAIRS
+                .save(deps.as_mut().storage, "pair_id".to_string(), &token_pair)
+                .unwrap();
+
+            ORDER_BOOKS
+                .save(
+                    deps.as_mut().storage,
+                    "pair_id".to_string(),
+                    &OrderBook {
+                        pair_id: "pair_id".to_string(),
+                        buy_orders: BTreeMap::new(),
+                        sell_orders: BTreeMap::new(),
+                    },
+                )
+                .unwrap();
+
+            NEXT_ORDER_ID.save(deps.as_mut().storage, &0u64).unwrap();
+            NEXT_TRADE_ID.save(deps.as_mut().storage, &0u64).unwrap();
+
+            let amount = Uint128::from(100u128);
+            let price = Price::new(Uint128::from(10u128), 0);
+
+            // Mock balance and allowance queries for CW20 tokens
+            let sell_info = message_info(&Addr::unchecked("seller"), &[]);
+            let cw20_token_address = String::from("quote_token");
+
+            // Clone necessary variables to avoid moving them into the closure
+            let cw20_token_address_clone = cw20_token_address.clone();
+            let sell_info_sender_clone = sell_info.sender.clone();
+
+            // Mock the balance and allowance queries
+            deps.querier.update_wasm(move |query| {
+                let cw20_token_address = cw20_token_address_clone.clone();
+                let sell_info_sender = sell_info_sender_clone.clone();
+                let env = mock_env();
+
+                match query {
+                    WasmQuery::Smart { contract_addr, msg } => {
+                        if contract_addr == &cw20_token_address.clone() {
+                            if let Ok(cw20::Cw20QueryMsg::Balance { address }) = from_json(&msg) {
+                                if address == sell_info_sender.into_string() {
+                                    return SystemResult::Ok(ContractResult::Ok(
+                                        to_json_binary(&BalanceResponse {
+                                            balance: Uint128::from(1000u128),
+                                        })
+                                        .unwrap(),
+                                    ));
+                                }
+                            } else if let Ok(cw20::Cw20QueryMsg::Allowance { owner, spender }) =
+                                from_json(&msg)
+                            {
+                                if owner == sell_info_sender.into_string()
+                                    && spender == env.contract.address.to_string()
+                                {
+                                    return SystemResult::Ok(ContractResult::Ok(
+                                        to_json_binary(&AllowanceResponse {
+                                            allowance: Uint128::from(1000u128),
+                                            expires: Expiration::Never {},
+                                        })
+                                        .unwrap(),
+                                    ));
+                                }
+                            }
+                        }
+                        SystemResult::Err(SystemError::UnsupportedRequest {
+                            kind: "".to_string(),
+                        })
+                    }
+                    _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                        kind: "".to_string(),
+                    }),
+                }
+            });
+
+            // Place buy order
+            let buy_res = execute_place_limit_order(
+                deps.as_mut(),
+                env.clone(),
+                buyer_info.clone(),
+                "pair_id".to_string(),
+                amount,
+                price,
+                true,
+            )
+            .unwrap();
+
+            // Place sell order
+            let sell_res = execute_place_limit_order(
+                deps.as_mut(),
+                env.clone(),
+                seller_info.clone(),
+                "pair_id".to_string(),
+                amount,
+                price,
+                false,
+            )
+            .unwrap();
+
+            // Verify response attributes for buy order
+            assert_eq!(buy_res.attributes.len(), 6);
+            assert_eq!(buy_res.attributes[0].key, "action");
+            assert_eq!(buy_res.attributes[0].value, "place_limit_order");
+            assert_eq!(buy_res.attributes[1].key, "order_id");
+            assert_eq!(buy_res.attributes[1].value, "0");
+            assert_eq!(buy_res.attributes[2].key, "pair_id");
+            assert_eq!(buy_res.attributes[2].value, "pair_id");
+            assert_eq!(buy_res.attributes[3].key, "is_buy");
+            assert_eq!(buy_res.attributes[3].value, "true");
+            assert_eq!(buy_res.attributes[4].key, "amount");
+            assert_eq!(buy_res.attributes[4].value, amount.to_string());
+            assert_eq!(buy_res.attributes[5].key, "price");
+            assert_eq!(buy_res.attributes[5].value, price.to_string());
+
+            // Verify response attributes for sell order
+            assert_eq!(sell_res.attributes.len(), 6);
+            assert_eq!(sell_res.attributes[0].key, "action");
+            assert_eq!(sell_res.attributes[0].value, "place_limit_order");
+            assert_eq!(sell_res.attributes[1].key, "order_id");
+            assert_eq!(sell_res.attributes[1].value, "1");
+            assert_eq!(sell_res.attributes[2].key, "pair_id");
+            assert_eq!(sell_res.attributes[2].value, "pair_id");
+            assert_eq!(sell_res.attributes[3].key, "is_buy");
+            assert_eq!(sell_res.attributes[3].value, "false");
+            assert_eq!(sell_res.attributes[4].key, "amount");
+            assert_eq!(sell_res.attributes[4].value, amount.to_string());
+            assert_eq!(sell_res.attributes[5].key, "price");
+            assert_eq!(sell_res.attributes[5].value, price.to_string());
+
+            // Verify that orders have been matched and updated in storage
+            let saved_buy_order = USER_ORDERS
+                .load(&deps.storage, (Addr::unchecked("buyer"), 0))
+                .unwrap();
+            assert_eq!(saved_buy_order.filled_amount, amount);
+            assert_eq!(saved_buy_order.remaining_amount, Uint128::zero());
+            assert_eq!(saved_buy_order.status, OrderStatus::Filled);
+
+            let saved_sell_order = USER_ORDERS
+                .load(&deps.storage, (Addr::unchecked("seller"), 1))
+                .unwrap();
+            assert_eq!(saved_sell_order.filled_amount, amount);
+            assert_eq!(saved_sell_order.remaining_amount, Uint128::zero());
+            assert_eq!(saved_sell_order.status, OrderStatus::Filled);
+
+            // Verify the order book is empty after matching orders
+            let order_book = ORDER_BOOKS
+                .load(&deps.storage, "pair_id".to_string())
+                .unwrap();
+            assert!(order_book.buy_orders.is_empty());
+            assert!(order_book.sell_orders.is_empty());
+        }
+
+        #[test]
+        fn test_execute_place_limit_order_trading_disabled() {
+            let mut deps = mock_dependencies();
+            let env = mock_env();
+            let info = message_info(
+                &Addr::unchecked("buyer"),
+                &[Coin {
+                    denom: "base_token".to_string(),
+                    amount: Uint128::from(1000u128),
+                }],
+            );
+
+            let config = Config {
+                owner: Addr::unchecked("creator"),
+                token_factory: Addr::unchecked("token_factory_addr"),
+                fee_collector: Addr::unchecked("fee_collector_addr"),
+                enabled: false, // Trading disabled
+                quote_token_total_supply: 100_000_000_000u128,
+                bonding_curve_supply: 80_000_000_000u128,
+                lp_supply: 20_000_000_000u128,
+                maker_fee: Decimal::percent(1),
+                taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
+                secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
+                base_token_denom: Denom::Native("ubase_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+            };
+
+            CONFIG.save(deps.as_mut().storage, &config).unwrap();
+
+            let amount = Uint128::from(100u128);
+            let price = Price::new(Uint128::from(10u128), 0);
+            let is_buy = true;
+
+            // Execute the place limit order function
+            let res = execute_place_limit_order(
+                deps.as_mut(),
+                env,
+                info.clone(),
+                "pair_id".to_string(),
+                amount,
+                price,
+                is_buy,
+            );
+
+            // Verify that the function returned an error
+            assert!(res.is_err());
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Trading is currently disabled")
+            );
+        }
+
+        #[test]
+        fn test_execute_place_limit_order_trading_pair_disabled() {
+            let mut deps = mock_dependencies();
+            let env = mock_env();
+            let info = message_info(
+                &Addr::unchecked("buyer"),
+                &[Coin {
+                    denom: "base_token".to_string(),
+                    amount: Uint128::from(1000u128),
+                }],
+            );
+
+            let config = Config {
+                owner: Addr::unchecked("creator"),
+                token_factory: Addr::unchecked("token_factory_addr"),
+                fee_collector: Addr::unchecked("fee_collector_addr"),
+                enabled: true,
+                quote_token_total_supply: 100_000_000_000u128,
+                bonding_curve_supply: 80_000_000_000u128,
+                lp_supply: 20_000_000_000u128,
+                maker_fee: Decimal::percent(1),
+                taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
+                secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
+                base_token_denom: Denom::Native("ubase_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+            };
+
+            let token_pair = TokenPair {
+                base_token: Denom::Native("base_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
+                base_decimals: 6,
+                quote_decimals: 8,
+                enabled: false, // Trading pair disabled
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
+            };
+
+            CONFIG.save(deps.as_mut().storage, &config).unwrap();
+            TOKEN_PAIRS
+                .save(deps.as_mut().storage, "pair_id".to_string(), &token_pair)
+                .unwrap();
+
+            let amount = Uint128::from(100u128);
+            let price = Price::new(Uint128::from(10u128), 0);
+            let is_buy = true;
+
+            // Execute the place limit order function
+            let res = execute_place_limit_order(
+                deps.as_mut(),
+                env,
+                info.clone(),
+                "pair_id".to_string(),
+                amount,
+                price,
+                is_buy,
+            );
+
+            // Verify that the function returned an error
+            assert!(res.is_err());
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Trading pair is disabled")
+            );
+        }
+
+        #[test]
+        fn test_match_orders_with_matching_orders() {
+            let mut deps = mock_dependencies();
+            let env = mock_env();
+
+            // Initialize the token pair and config
+            let token_pair = TokenPair {
+                base_token: Denom::Native("base_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
+                base_decimals: 6,
+                quote_decimals: 8,
+                enabled: true,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
+            };
+
+            let config = Config {
+                owner: Addr::unchecked("creator"),
+                token_factory: Addr::unchecked("token_factory_addr"),
+                fee_collector: Addr::unchecked("fee_collector_addr"),
+                enabled: true,
+                quote_token_total_supply: 100_000_000_000u128,
+                bonding_curve_supply: 80_000_000_000u128,
+                lp_supply: 20_000_000_000u128,
+                maker_fee: Decimal::percent(1), // 1% maker fee
+                taker_fee: Decimal::percent(2), // 2% taker fee
+                curve_fee: Decimal::zero(),
+                secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
+                base_token_denom: Denom::Native("ubase_token_denom".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+            };
+
+            CONFIG.save(deps.as_mut().storage, &config).unwrap();
+            TOKEN_PAIRS
+                .save(deps.as_mut().storage, "pair_id".to_string(), &token_pair)
+                .unwrap();
+
+            // Create a buy order and a sell order
+            let buy_order = Order {
+                id: 1,
+                owner: Addr::unchecked("buyer"),
+                pair_id: "pair_id".to_string(),
+                token_amount: Uint128::from(100u128),
+                price: Price::new(Uint128::from(10u128), 0),
+                timestamp: env.block.time.seconds() as u64,
+                status: OrderStatus::Active,
+                filled_amount: Uint128::zero(),
+                remaining_amount: Uint128::from(100u128),
+                order_type: OrderType::Buy,
+                created_at: env.block.height,
+                deposit: Uint128::zero(),
+            };
+
+            let sell_order = Order {
+                id: 2,
+                owner: Addr::unchecked("seller"),
+                pair_id: "pair_id".to_string(),
+                token_amount: Uint128::from(100u128),
+                price: Price::new(Uint128::from(10u128), 0),
+                timestamp: env.block.time.seconds() as u64 + 1, // Sell order is newer
+                status: OrderStatus::Active,
+                filled_amount: Uint128::zero(),
+                remaining_amount: Uint128::from(100u128),
+                order_type: OrderType::Sell,
+                created_at: env.block.height,
+                deposit: Uint128::zero(),
+            };
+
+            // Initialize the order book
+            let mut order_book = OrderBook {
+                pair_id: "pair_id".to_string(),
+                buy_orders: BTreeMap::new(),
+                sell_orders: BTreeMap::new(),
+            };
+
+            order_book
+                .buy_orders
+                .entry(10)
+                .or_insert_with(Vec::new)
+                .push(buy_order.clone());
+            order_book
+                .sell_orders
+                .entry(10)
+                .or_insert_with(Vec::new)
+                .push(sell_order.clone());
+
+            ORDER_BOOKS
+                .save(deps.as_mut().storage, "pair_id".to_string(), &order_book)
+                .unwrap();
+            NEXT_TRADE_ID.save(deps.as_mut().storage, &0u64).unwrap();
+
+            // Execute the match_orders function (testing buy-initiated match)
+            let res = match_orders(deps.as_mut(), &env, "pair_id".to_string(), true).unwrap();
+
+            // The buy order is older: the buyer pays the 1% maker fee in quote tokens,
+            // the seller the 2% taker fee in base tokens
+            let total_price = Uint128::new(100u128) * Uint128::new(10u128); // 1000 base tokens
+            let buyer_fee = Uint128::new(1); // 1 quote token
+            let seller_fee = Uint128::new(20); // 20 base tokens
+            let seller_receives = total_price - seller_fee; // 980 base tokens
+
+            // Verify response messages (2 messages expected from create_order)
+            assert_eq!(res.messages.len(), 2);
+
+            // Message 1: Quote tokens to buyer
+            match &res.messages[0].msg {
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr,
+                    msg,
+                    funds,
+                }) => {
+                    assert_eq!(contract_addr, "quote_token");
+                    let cw20_msg: Cw20ExecuteMsg = from_json(msg).unwrap();
+                    assert_eq!(
+                        cw20_msg,
+                        Cw20ExecuteMsg::Transfer {
+                            recipient: "buyer".to_string(),
+                            amount: Uint128::new(100u128) - buyer_fee,
+                        }
+                    );
+                    assert!(funds.is_empty());
+                }
+                _ => panic!("Unexpected message type for quote token transfer"),
+            }
+
+            // Message 2: Base tokens to seller
+            match &res.messages[1].msg {
+                CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => {
+                    assert_eq!(to_address, "seller");
+                    assert_eq!(amount.len(), 1);
+                    assert_eq!(
+                        amount[0],
+                        Coin {
+                            denom: "base_token".to_string(),
+                            amount: seller_receives
+                        }
+                    );
+                }
+                _ => panic!("Unexpected message type for base token transfer"),
+            }
+
+            // Both fees stay in the treasury in the token they were withheld from
+            let treasury = |denom: &str| {
+                TREASURY
+                    .load(&deps.storage, denom.to_string())
+                    .unwrap()
+                    .accumulated
+            };
+            assert_eq!(treasury("quote_token"), buyer_fee);
+            assert_eq!(treasury("base_token"), seller_fee);
+
+            // Verify response attributes
+            assert_eq!(res.attributes.len(), 17);
+            assert_eq!(res.attributes[0], attr("event_type", "trade"));
+            assert_eq!(res.attributes[1], attr("trade_id", "0")); // First trade ID
+
+            // Verify that orders have been matched and updated in storage
+            let saved_buy_order = USER_ORDERS
+                .load(&deps.storage, (Addr::unchecked("buyer"), 1))
+                .unwrap();
+            assert_eq!(saved_buy_order.filled_amount, Uint128::from(100u128));
+            assert_eq!(saved_buy_order.remaining_amount, Uint128::zero());
+            assert_eq!(saved_buy_order.status, OrderStatus::Filled);
+
+            let saved_sell_order = USER_ORDERS
+                .load(&deps.storage, (Addr::unchecked("seller"), 2))
+                .unwrap();
+            assert_eq!(saved_sell_order.filled_amount, Uint128::from(100u128));
+            assert_eq!(saved_sell_order.remaining_amount, Uint128::zero());
+            assert_eq!(saved_sell_order.status, OrderStatus::Filled);
+
+            // Order book should be emptied
+            let order_book = ORDER_BOOKS
+                .load(&deps.storage, "pair_id".to_string())
+                .unwrap();
+            assert!(order_book.buy_orders.is_empty());
+            assert!(order_book.sell_orders.is_empty());
+
+            // Verify NEXT_TRADE_ID incremented
+            assert_eq!(NEXT_TRADE_ID.load(&deps.storage).unwrap(), 1u64);
+        }
+
+        #[test]
+        fn test_match_orders_with_no_matching_orders() {
+            let mut deps = mock_dependencies();
+            let env = mock_env();
+
+            // Initialize the token pair and config
+            let token_pair = TokenPair {
+                base_token: Denom::Native("base_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
+                base_decimals: 6,
+                quote_decimals: 8,
+                enabled: true,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
+            };
+
+            let config = Config {
+                owner: Addr::unchecked("creator"),
+                token_factory: Addr::unchecked("token_factory_addr"),
+                fee_collector: Addr::unchecked("fee_collector_addr"),
+                enabled: true,
+                quote_token_total_supply: 100_000_000_000u128,
+                bonding_curve_supply: 80_000_000_000u128,
+                lp_supply: 20_000_000_000u128,
+                maker_fee: Decimal::percent(1),
+                taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
+                secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
+                base_token_denom: Denom::Native("ubase_token_denom".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+            };
+
+            CONFIG.save(deps.as_mut().storage, &config).unwrap();
+            TOKEN_PAIRS
+                .save(deps.as_mut().storage, "pair_id".to_string(), &token_pair)
+                .unwrap();
+
+            // Create a buy order and a sell order with non-matching prices
+            let buy_order = Order {
+                id: 1,
+                owner: Addr::unchecked("buyer"),
+                pair_id: "pair_id".to_string(),
+                token_amount: Uint128::from(100u128),
+                price: Price::new(Uint128::from(10u128), 0),
+                timestamp: env.block.time.seconds() as u64,
+                status: OrderStatus::Active,
+                filled_amount: Uint128::zero(),
+                remaining_amount: Uint128::from(100u128),
+                order_type: OrderType::Buy,
+                created_at: env.block.height,
+                deposit: Uint128::zero(),
+            };
+
+            let sell_order = Order {
+                id: 2,
+                owner: Addr::unchecked("seller"),
+                pair_id: "pair_id".to_string(),
+                token_amount: Uint128::from(100u128),
+                price: Price::new(Uint128::from(15u128), 0),
+                timestamp: env.block.time.seconds() as u64,
+                status: OrderStatus::Active,
+                filled_amount: Uint128::zero(),
+                remaining_amount: Uint128::from(100u128),
+                order_type: OrderType::Sell,
+                created_at: env.block.height,
+                deposit: Uint128::zero(),
+            };
+
+            // Save orders to USER_ORDERS storage
+            USER_ORDERS
+                .save(
+                    deps.as_mut().storage,
+                    (buy_order.owner.clone(), buy_order.id),
+                    &buy_order,
+                )
+                .unwrap();
+            USER_ORDERS
+                .save(
+                    deps.as_mut().storage,
+                    (sell_order.owner.clone(), sell_order.id),
+                    &sell_order,
+                )
+                .unwrap();
+
+            // Initialize the order book
+            let mut order_book = OrderBook {
+                pair_id: "pair_id".to_string(),
+                buy_orders: BTreeMap::new(),
+                sell_orders: BTreeMap::new(),
+            };
+
+            order_book
+                .buy_orders
+                .entry(10)
+                .or_insert_with(Vec::new)
+                .push(buy_order.clone());
+            order_book
+                .sell_orders
+                .entry(15)
+                .or_insert_with(Vec::new)
+                .push(sell_order.clone());
+
+            ORDER_BOOKS
+                .save(deps.as_mut().storage, "pair_id".to_string(), &order_book)
+                .unwrap();
+
+            NEXT_TRADE_ID.save(deps.as_mut().storage, &0u64).unwrap();
+
+            // Execute the match_orders function
+            let res = match_orders(deps.as_mut(), &env, "pair_id".to_string(), true).unwrap();
+
+            // Verify response attributes
+            assert_eq!(res.attributes.is_empty(), true);
+
+            // Verify that orders have not been matched and remain unchanged
+            let saved_buy_order = USER_ORDERS
+                .load(&deps.storage, (Addr::unchecked("buyer"), 1))
+                .unwrap();
+            assert_eq!(saved_buy_order.filled_amount, Uint128::zero());
+            assert_eq!(saved_buy_order.remaining_amount, Uint128::from(100u128));
+            assert_eq!(saved_buy_order.status, OrderStatus::Active);
+
+            let saved_sell_order = USER_ORDERS
+                .load(&deps.storage, (Addr::unchecked("seller"), 2))
+                .unwrap();
+            assert_eq!(saved_sell_order.filled_amount, Uint128::zero());
+            assert_eq!(saved_sell_order.remaining_amount, Uint128::from(100u128));
+            assert_eq!(saved_sell_order.status, OrderStatus::Active);
+        }
+
+        #[test]
+        fn test_execute_place_limit_order_buy_partial_fill() {
+            let mut deps = mock_dependencies();
+            let env = mock_env();
+            let info = message_info(
+                &Addr::unchecked("buyer"),
+                &[Coin {
+                    denom: "ubase_token".to_string(),
+                    amount: Uint128::new(500),
+                }],
+            );
+
+            // Initialize the token pair and config
+            let token_pair = TokenPair {
+                base_token: Denom::Native("ubase_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
+                base_decimals: 6,
+                quote_decimals: 8,
+                enabled: true,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
+            };
+
+            let config = Config {
+                owner: Addr::unchecked("creator"),
+                token_factory: Addr::unchecked("token_factory_addr"),
+                fee_collector: Addr::unchecked("fee_collector_addr"),
+                enabled: true,
+                quote_token_total_supply: 100_000_000_000u128,
+                bonding_curve_supply: 80_000_000_000u128,
+                lp_supply: 20_000_000_000u128,
+                maker_fee: Decimal::percent(1),
+                taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
+                secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
+                base_token_denom: Denom::Native("ubase_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+            };
+
+            CONFIG.save(deps.as_mut().storage, &config).unwrap();
+            TOKEN_PAIRS
+                .save(deps.as_mut().storage, "pair_id".to_string(), &token_pair)
+                .unwrap();
+
+            // Initially empty order book and order ID
+            let order_book = OrderBook {
+                pair_id: "pair_id".to_string(),
+                buy_orders: BTreeMap::new(),
+                sell_orders: BTreeMap::new(),
+            };
+            ORDER_BOOKS
+                .save(deps.as_mut().storage, "pair_id".to_string(), &order_book)
+                .unwrap();
+            NEXT_ORDER_ID.save(deps.as_mut().storage, &1).unwrap();
+            NEXT_TRADE_ID.save(deps.as_mut().storage, &1).unwrap();
+
+            // Mock balance and allowance queries for CW20 tokens
+            let sell_info = message_info(&Addr::unchecked("seller"), &[]);
+            let cw20_token_address = String::from("quote_token");
+
+            // Clone necessary variables to avoid moving them into the closure
+            let cw20_token_address_clone = cw20_token_address.clone();
+            let sell_info_sender_clone = sell_info.sender.clone();
+
+            // Mock the balance and allowance queries
+            deps.querier.update_wasm(move |query| {
+                let cw20_token_address = cw20_token_address_clone.clone();
+                let sell_info_sender = sell_info_sender_clone.clone();
+                let env = mock_env();
+
+                match query {
+                    WasmQuery::Smart { contract_addr, msg } => {
+                        if contract_addr == &cw20_token_address.clone() {
+                            if let Ok(cw20::Cw20QueryMsg::Balance { address }) = from_json(&msg) {
+                                if address == sell_info_sender.into_string() {
+                                    return SystemResult::Ok(ContractResult::Ok(
+                                        to_json_binary(&cw20::BalanceResponse {
+                                            balance: Uint128::from(1000u128),
+                                        })
+                                        .unwrap(),
+                                    ));
+                                }
+                            } else if let Ok(cw20::Cw20QueryMsg::Allowance { owner, spender }) =
+                                from_json(&msg)
+                            {
+                                if owner == sell_info_sender.into_string()
+                                    && spender == env.contract.address.to_string()
+                                {
+                                    return SystemResult::Ok(ContractResult::Ok(
+                                        to_json_binary(&cw20::AllowanceResponse {
+                                            allowance: Uint128::from(1000u128),
+                                            expires: cw20::Expiration::Never {},
+                                        })
+                                        .unwrap(),
+                                    ));
+                                }
+                            }
+                        }
+                        SystemResult::Err(SystemError::UnsupportedRequest {
+                            kind: "".to_string(),
+                        })
+                    }
+                    _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                        kind: "".to_string(),
+                    }),
+                }
+            });
+
+            // Place the first limit sell order
+            let sell_info = message_info(&Addr::unchecked("seller"), &[]);
+            execute_place_limit_order(
+                deps.as_mut(),
+                env.clone(),
+                sell_info,
+                "pair_id".to_string(),
+                Uint128::new(1000),             // Sell order amount
+                Price::new(Uint128::new(1), 0), // Price
+                false,
+            )
+            .unwrap();
+
+            // Place a buy order that partially fills the sell order
+            let response = execute_place_limit_order(
+                deps.as_mut(),
+                env.clone(),
+                info,
+                "pair_id".to_string(),
+                Uint128::new(500),              // Buy order amount
+                Price::new(Uint128::new(1), 0), // Price
+                true,
+            )
+            .unwrap();
+
+            // Verify response attributes
+            assert_eq!(response.attributes.len(), 6);
+            assert_eq!(response.attributes[0], attr("action", "place_limit_order"));
+            assert_eq!(response.attributes[1], attr("order_id", "2"));
+            assert_eq!(response.attributes[2], attr("pair_id", "pair_id"));
+            assert_eq!(response.attributes[3], attr("is_buy", "true"));
+            assert_eq!(response.attributes[4], attr("amount", "500"));
+            assert_eq!(response.attributes[5], attr("price", "1"));
+
+            // Verify that the sell order is partially filled
+            let sell_order: Order = USER_ORDERS
+                .load(deps.as_mut().storage, (Addr::unchecked("seller"), 1))
+                .unwrap();
+            assert_eq!(sell_order.filled_amount, Uint128::new(500));
+            assert_eq!(sell_order.remaining_amount, Uint128::new(500));
+            assert_eq!(sell_order.status, OrderStatus::Active); // Still active because it's partially filled
+
+            // Verify that the buy order is fully filled
+            let buy_order: Order = USER_ORDERS
+                .load(deps.as_mut().storage, (Addr::unchecked("buyer"), 2))
+                .unwrap();
+            assert_eq!(buy_order.filled_amount, Uint128::new(500));
+            assert_eq!(buy_order.remaining_amount, Uint128::zero());
+            assert_eq!(buy_order.status, OrderStatus::Filled);
+        }
+
+        #[test]
+        fn test_execute_place_limit_order_sell_partial_fill() {
+            let mut deps = mock_dependencies();
+            let env = mock_env();
+            let info = message_info(&Addr::unchecked("seller"), &[]);
+
+            // Initialize the token pair and config
+            let token_pair = TokenPair {
+                base_token: Denom::Native("ubase_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
+                base_decimals: 6,
+                quote_decimals: 8,
+                enabled: true,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
+            };
+
+            let config = Config {
+                owner: Addr::unchecked("creator"),
+                token_factory: Addr::unchecked("token_factory_addr"),
+                fee_collector: Addr::unchecked("fee_collector_addr"),
+                enabled: true,
+                quote_token_total_supply: 100_000_000_000u128,
+                bonding_curve_supply: 80_000_000_000u128,
+                lp_supply: 20_000_000_000u128,
+                maker_fee: Decimal::percent(1),
+                taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
+                secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
+                base_token_denom: Denom::Native("ubase_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+            };
+
+            CONFIG.save(deps.as_mut().storage, &config).unwrap();
+            TOKEN_PAIRS
+                .save(deps.as_mut().storage, "pair_id".to_string(), &token_pair)
+                .unwrap();
+
+            // Initially empty order book and order ID
+            let order_book = OrderBook {
+                pair_id: "pair_id".to_string(),
+                buy_orders: BTreeMap::new(),
+                sell_orders: BTreeMap::new(),
+            };
+            ORDER_BOOKS
+                .save(deps.as_mut().storage, "pair_id".to_string(), &order_book)
+                .unwrap();
+
+            // Initialize NEXT_ORDER_ID and NEXT_TRADE_ID
+            NEXT_ORDER_ID.save(deps.as_mut().storage, &1).unwrap();
+            NEXT_TRADE_ID.save(deps.as_mut().storage, &1).unwrap();
+
+            // Mock balance and allowance queries for CW20 tokens
+            let buy_info = message_info(
+                &Addr::unchecked("buyer"),
+                &[Coin {
+                    denom: "ubase_token".to_string(),
+                    amount: Uint128::new(500),
+                }],
+            );
+
+            // Mock balance and allowance queries for CW20 tokens
+            let sell_info = message_info(&Addr::unchecked("seller"), &[]);
+            let cw20_token_address = String::from("quote_token");
+
+            // Clone necessary variables to avoid moving them into the closure
+            let cw20_token_address_clone = cw20_token_address.clone();
+            let sell_info_sender_clone = sell_info.sender.clone();
+
+            // Mock the balance and allowance queries
+            deps.querier.update_wasm(move |query| {
+                let cw20_token_address = cw20_token_address_clone.clone();
+                let sell_info_sender = sell_info_sender_clone.clone();
+                let env = mock_env();
+
+                match query {
+                    WasmQuery::Smart { contract_addr, msg } => {
+                        if contract_addr == &cw20_token_address.clone() {
+                            if let Ok(cw20::Cw20QueryMsg::Balance { address }) = from_json(&msg) {
+                                if address == sell_info_sender.into_string() {
+                                    return SystemResult::Ok(ContractResult::Ok(
+                                        to_json_binary(&cw20::BalanceResponse {
+                                            balance: Uint128::from(1000u128),
+                                        })
+                                        .unwrap(),
+                                    ));
+                                }
+                            } else if let Ok(cw20::Cw20QueryMsg::Allowance { owner, spender }) =
+                                from_json(&msg)
+                            {
+                                if owner == sell_info_sender.into_string()
+                                    && spender == env.contract.address.to_string()
+                                {
+                                    return SystemResult::Ok(ContractResult::Ok(
+                                        to_json_binary(&cw20::AllowanceResponse {
+                                            allowance: Uint128::from(1000u128),
+                                            expires: cw20::Expiration::Never {},
+                                        })
+                                        .unwrap(),
+                                    ));
+                                }
+                            }
+                        }
+                        SystemResult::Err(SystemError::UnsupportedRequest {
+                            kind: "".to_string(),
+                        })
+                    }
+                    _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                        kind: "".to_string(),
+                    }),
+                }
+            });
+
+            // Place the first limit buy order
+            execute_place_limit_order(
+                deps.as_mut(),
+                env.clone(),
+                buy_info,
+                "pair_id".to_string(),
+                Uint128::new(500),              // Buy order amount
+                Price::new(Uint128::new(1), 0), // Price
+                true,
+            )
+            .unwrap();
+
+            // Place a sell order that partially fills the buy order
+            let response = execute_place_limit_order(
+                deps.as_mut(),
+                env.clone(),
+                info,
+                "pair_id".to_string(),
+                Uint128::new(1000),             // Sell order amount
+                Price::new(Uint128::new(1), 0), // Price
+                false,
+            )
+            .unwrap();
+
+            // Verify response attributes
+            assert_eq!(response.attributes.len(), 6);
+            assert_eq!(response.attributes[0], attr("action", "place_limit_order"));
+            assert_eq!(response.attributes[1], attr("order_id", "2"));
+            assert_eq!(response.attributes[2], attr("pair_id", "pair_id"));
+            assert_eq!(response.attributes[3], attr("is_buy", "false"));
+            assert_eq!(response.attributes[4], attr("amount", "1000"));
+            assert_eq!(response.attributes[5], attr("price", "1"));
+
+            // Verify that the buy order is fully filled
+            let buy_order: Order = USER_ORDERS
+                .load(deps.as_mut().storage, (Addr::unchecked("buyer"), 1))
+                .unwrap();
+            assert_eq!(buy_order.filled_amount, Uint128::new(500));
+            assert_eq!(buy_order.remaining_amount, Uint128::zero());
+            assert_eq!(buy_order.status, OrderStatus::Filled);
+
+            // Verify that the sell order is partially filled
+            let sell_order: Order = USER_ORDERS
+                .load(deps.as_mut().storage, (Addr::unchecked("seller"), 2))
+                .unwrap();
+            assert_eq!(sell_order.filled_amount, Uint128::new(500));
+            assert_eq!(sell_order.remaining_amount, Uint128::new(500));
+            assert_eq!(sell_order.status, OrderStatus::Active); // Still active because it's partially filled
+        }
+
+        #[test]
+        fn test_validate_and_handle_native_tokens() {
+            let mut deps = mock_dependencies();
+            let env = mock_env();
+            let info = message_info(
+                &Addr::unchecked("buyer"),
+                &[Coin {
+                    denom: "base_token".to_string(),
+                    amount: Uint128::from(1000u128),
+                }],
+            );
+
+            let token_pair = TokenPair {
+                base_token: Denom::Native("base_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
+                base_decimals: 6,
+                quote_decimals: 8,
+                enabled: true,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
+            };
+
+            let result = validate_and_handle_tokens(
+                &deps.as_mut(),
+                &env,
+                &info,
+                &token_pair,
+                Uint128::from(100u128),
+                Price::new(Uint128::from(10u128), 0),
+                true,
+                Uint128::zero(),
+            );
+            assert!(result.is_ok());
+        }
+
+        #[test]
+        fn test_validate_and_handle_cw20_tokens() {
+            let mut deps = mock_dependencies();
+            let env = mock_env();
+            let info = message_info(&Addr::unchecked("seller"), &[]);
+
+            let token_pair = TokenPair {
+                base_token: Denom::Native("base_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
+                base_decimals: 6,
+                quote_decimals: 8,
+                enabled: true,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
+            };
+
+            // Mock balance and allowance queries for CW20 tokens
+            let sell_info = message_info(&Addr::unchecked("seller"), &[]);
+            let cw20_token_address = String::from("quote_token");
+
+            // Clone necessary variables to avoid moving them into the closure
+            let cw20_token_address_clone = cw20_token_address.clone();
+            let sell_info_sender_clone = sell_info.sender.clone();
+
+            // Mock the balance and allowance queries
+            deps.querier.update_wasm(move |query| {
+                let cw20_token_address = cw20_token_address_clone.clone();
+                let sell_info_sender = sell_info_sender_clone.clone();
+                let env = mock_env();
+
+                match query {
+                    WasmQuery::Smart { contract_addr, msg } => {
+                        if contract_addr == &cw20_token_address.clone() {
+                            if let Ok(cw20::Cw20QueryMsg::Balance { address }) = from_json(&msg) {
+                                if address == sell_info_sender.into_string() {
+                                    return SystemResult::Ok(ContractResult::Ok(
+                                        to_json_binary(&BalanceResponse {
+                                            balance: Uint128::from(1000u128),
+                                        })
+                                        .unwrap(),
+                                    ));
+                                }
+                            } else if let Ok(cw20::Cw20QueryMsg::Allowance { owner, spender }) =
+                                from_json(&msg)
+                            {
+                                if owner == sell_info_sender.into_string()
+                                    && spender == env.contract.address.to_string()
+                                {
+                                    return SystemResult::Ok(ContractResult::Ok(
+                                        to_json_binary(&AllowanceResponse {
+                                            allowance: Uint128::from(1000u128),
+                                            expires: Expiration::Never {},
+                                        })
+                                        .unwrap(),
+                                    ));
+                                }
+                            }
+                        }
+                        SystemResult::Err(SystemError::UnsupportedRequest {
+                            kind: "".to_string(),
+                        })
+                    }
+                    _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                        kind: "".to_string(),
+                    }),
+                }
+            });
+
+            // Test case for selling CW20 tokens
+            let result = validate_and_handle_tokens(
+                &deps.as_mut(),
+                &env,
+                &info,
+                &token_pair,
+                Uint128::from(100u128),
+                Price::new(Uint128::from(10u128), 0),
+                false,
+                Uint128::zero(),
+            );
+            assert!(result.is_ok());
+        }
+
+        #[test]
+        fn test_validate_native_token_payment_insufficient_funds() {
+            let info = message_info(
+                &Addr::unchecked("buyer"),
+                &[Coin {
+                    denom: "quote_token".to_string(),
+                    amount: Uint128::from(50u128),
+                }],
+            );
+
+            let result =
+                validate_native_token_payment(&info, "quote_token", Uint128::from(100u128));
+            assert!(result.is_err());
+        }
+
+        #[test]
+        fn test_validate_native_token_payment_excess_funds() {
+            let info = message_info(
+                &Addr::unchecked("buyer"),
+                &[Coin {
+                    denom: "quote_token".to_string(),
+                    amount: Uint128::from(150u128),
+                }],
+            );
+
+            let result =
+                validate_native_token_payment(&info, "quote_token", Uint128::from(100u128));
+            assert!(result.is_err());
+        }
+
+        #[test]
+        fn test_validate_cw20_token_payment_insufficient_allowance() {
+            let mut deps = mock_dependencies();
+            let env = mock_env();
+            let info = message_info(&Addr::unchecked("seller"), &[]);
+
+            // Mock balance and allowance queries for CW20 tokens
+            let sell_info = message_info(&Addr::unchecked("seller"), &[]);
+            let cw20_token_address = String::from("base_token");
+
+            // Clone necessary variables to avoid moving them into the closure
+            let cw20_token_address_clone = cw20_token_address.clone();
+            let sell_info_sender_clone = sell_info.sender.clone();
+
+            // Mock the balance and allowance queries
+            deps.querier.update_wasm(move |query| {
+                let cw20_token_address = cw20_token_address_clone.clone();
+                let sell_info_sender = sell_info_sender_clone.clone();
+                let env = mock_env();
+
+                match query {
+                    WasmQuery::Smart { contract_addr, msg } => {
+                        if contract_addr == &cw20_token_address.clone() {
+                            if let Ok(cw20::Cw20QueryMsg::Allowance { owner, spender }) =
+                                from_json(&msg)
+                            {
+                                if owner == sell_info_sender.into_string()
+                                    && spender == env.contract.address.to_string()
+                                {
+                                    return SystemResult::Ok(ContractResult::Ok(
+                                        to_json_binary(&AllowanceResponse {
+                                            allowance: Uint128::from(50u128),
+                                            expires: Expiration::Never {},
+                                        })
+                                        .unwrap(),
+                                    ));
+                                }
+                            }
+                        }
+                        SystemResult::Err(SystemError::UnsupportedRequest {
+                            kind: "".to_string(),
+                        })
+                    }
+                    _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                        kind: "".to_string(),
+                    }),
+                }
+            });
 
-            // // Verify that the pool information was saved correctly
-            // let pool = POOLS
-            //     .load(&deps.storage, token_address.to_string())
-            //     .unwrap();
-            // assert_eq!(pool.token_address, token_address);
-            // assert_eq!(pool.total_reserve_token, Uint128::zero());
-            // assert_eq!(pool.token_sold, Uint128::zero());
-            // assert_eq!(pool.total_volume, Uint128::zero());
-            // assert_eq!(pool.total_fees_collected, Uint128::zero());
-            // assert_eq!(pool.curve_slope, curve_slope);
-            // assert_eq!(pool.pair_id, pair_id);
-            // assert_eq!(pool.total_trades, Uint128::zero());
-            // assert_eq!(pool.last_price, Uint128::from(BASE_PRICE));
-            // assert_eq!(pool.enabled, true);
+            let result = validate_cw20_token_payment(
+                &deps.as_ref(),
+                &env,
+                &info,
+                "base_token",
+                Uint128::from(100u128),
+            );
+            assert!(result.is_err());
         }
 
         #[test]
-        fn test_create_order_happy_case() {
+        fn test_validate_cw20_token_payment_insufficient_balance() {
             let mut deps = mock_dependencies();
             let env = mock_env();
+            let info = message_info(&Addr::unchecked("seller"), &[]);
 
-            // Create initial buy and sell orders
-            let mut buy_order = Order {
-                id: 1,
-                owner: Addr::unchecked("buyer"),
-                pair_id: "pair_id".to_string(),
-                token_amount: Uint128::from(100u128),
-                price: Uint128::from(10u128),
-                timestamp: env.block.time.seconds() as u64,
-                status: OrderStatus::Active,
-                filled_amount: Uint128::zero(),
-                remaining_amount: Uint128::from(100u128),
-                order_type: OrderType::Buy,
-                created_at: env.block.height,
-            };
+            // Mock balance and allowance queries for CW20 tokens
+            let sell_info = message_info(&Addr::unchecked("seller"), &[]);
+            let cw20_token_address = String::from("base_token");
 
-            let mut sell_order = Order {
-                id: 2,
-                owner: Addr::unchecked("seller"),
-                pair_id: "pair_id".to_string(),
-                token_amount: Uint128::from(100u128),
-                price: Uint128::from(10u128),
-                timestamp: env.block.time.seconds() as u64 + 1, // Sell order is newer for fee testing
-                status: OrderStatus::Active,
-                filled_amount: Uint128::zero(),
-                remaining_amount: Uint128::from(100u128),
-                order_type: OrderType::Sell,
-                created_at: env.block.height,
-            };
+            // Clone necessary variables to avoid moving them into the closure
+            let cw20_token_address_clone = cw20_token_address.clone();
+            let sell_info_sender_clone = sell_info.sender.clone();
 
-            // Define token pair and config
-            let token_pair = TokenPair {
-                base_token: "base_token".to_string(),
-                quote_token: "quote_token".to_string(),
-                base_decimals: 6,
-                quote_decimals: 8,
-                enabled: true,
-            };
+            // Mock the balance and allowance queries
+            deps.querier.update_wasm(move |query| {
+                let cw20_token_address = cw20_token_address_clone.clone();
+                let sell_info_sender = sell_info_sender_clone.clone();
+
+                match query {
+                    WasmQuery::Smart { contract_addr, msg } => {
+                        if contract_addr == &cw20_token_address.clone() {
+                            if let Ok(cw20::Cw20QueryMsg::Balance { address }) = from_json(&msg) {
+                                if address == sell_info_sender.into_string() {
+                                    return SystemResult::Ok(ContractResult::Ok(
+                                        to_json_binary(&BalanceResponse {
+                                            balance: Uint128::from(50u128),
+                                        })
+                                        .unwrap(),
+                                    ));
+                                }
+                            }
+                        }
+                        SystemResult::Err(SystemError::UnsupportedRequest {
+                            kind: "".to_string(),
+                        })
+                    }
+                    _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                        kind: "".to_string(),
+                    }),
+                }
+            });
+
+            let result = validate_cw20_token_payment(
+                &deps.as_ref(),
+                &env,
+                &info,
+                "base_token",
+                Uint128::from(100u128),
+            );
+            assert!(result.is_err());
+        }
+
+        #[test]
+        fn test_execute_cancel_order() {
+            let mut deps = mock_dependencies();
+            let env = mock_env();
+            let info = message_info(&Addr::unchecked("buyer"), &[]);
 
             let config = Config {
                 owner: Addr::unchecked("creator"),
@@ -1764,141 +6151,112 @@ pub mod execute {
                 quote_token_total_supply: 100_000_000_000u128,
                 bonding_curve_supply: 80_000_000_000u128,
                 lp_supply: 20_000_000_000u128,
-                maker_fee: Decimal::percent(1), // 1% maker fee
-                taker_fee: Decimal::percent(2), // 2% taker fee
+                maker_fee: Decimal::percent(1),
+                taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
                 secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
-                base_token_denom: "base_token_denom".to_string(),
+                base_token_denom: Denom::Native("ubase_token_denom".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
             };
 
-            // Execute the create_order function
-            let trade_id = 1;
-            let res = create_order(
-                &mut deps.storage,
-                &env,
-                &mut buy_order,
-                &mut sell_order,
-                trade_id,
-                &token_pair,
-                &config,
-            )
-            .unwrap();
-
-            // Calculate expected values
-            let total_price = Uint128::new(100u128) * Uint128::new(10u128); // 1000 base tokens
-            let maker_fee = (Decimal::new(total_price) * Decimal::percent(1)).to_uint_ceil(); // 10 base tokens (buy order is maker)
-            let taker_fee = (Decimal::new(total_price) * Decimal::percent(2)).to_uint_ceil(); // 20 base tokens (sell order is taker)
-            let seller_receives = total_price - maker_fee - taker_fee; // 970 base tokens
+            let token_pair = TokenPair {
+                base_token: Denom::Native("base_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
+                base_decimals: 6,
+                quote_decimals: 8,
+                enabled: true,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
+            };
 
-            // Check response messages (token transfers)
-            assert_eq!(res.messages.len(), 3); // 3 messages: buyer quote, seller base, fees
+            CONFIG.save(deps.as_mut().storage, &config).unwrap();
+            TOKEN_PAIRS
+                .save(deps.as_mut().storage, "pair_id".to_string(), &token_pair)
+                .unwrap();
 
-            // Message 1: Quote tokens to buyer (100 quote tokens)
-            match &res.messages[0].msg {
-                CosmosMsg::Wasm(WasmMsg::Execute {
-                    contract_addr,
-                    msg,
-                    funds,
-                }) => {
-                    assert_eq!(contract_addr, "quote_token");
-                    let cw20_msg: Cw20ExecuteMsg = from_json(msg).unwrap();
-                    assert_eq!(
-                        cw20_msg,
-                        Cw20ExecuteMsg::Transfer {
-                            recipient: "buyer".to_string(),
-                            amount: Uint128::new(100u128),
-                        }
-                    );
-                    assert!(funds.is_empty());
-                }
-                _ => panic!("Unexpected message type for quote token transfer"),
-            }
+            let mut order_book = OrderBook {
+                pair_id: "pair_id".to_string(),
+                buy_orders: BTreeMap::new(),
+                sell_orders: BTreeMap::new(),
+            };
 
-            // Message 2: Base tokens to seller (970 base tokens after fees)
-            match &res.messages[1].msg {
-                CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => {
-                    assert_eq!(to_address, "seller");
-                    assert_eq!(amount.len(), 1);
-                    assert_eq!(
-                        amount[0],
-                        Coin {
-                            denom: "base_token".to_string(),
-                            amount: seller_receives
-                        }
-                    );
-                }
-                _ => panic!("Unexpected message type for base token transfer"),
-            }
+            let order = Order {
+                id: 1,
+                owner: Addr::unchecked("buyer"),
+                pair_id: "pair_id".to_string(),
+                token_amount: Uint128::from(100u128),
+                price: Price::new(Uint128::from(1_000_000_000u128), 8),
+                timestamp: env.block.time.seconds() as u64,
+                status: OrderStatus::Active,
+                filled_amount: Uint128::zero(),
+                remaining_amount: Uint128::from(100u128),
+                order_type: OrderType::Buy,
+                created_at: env.block.height,
+                deposit: Uint128::zero(),
+            };
 
-            // Message 3: Fees to fee collector (30 base tokens)
-            match &res.messages[2].msg {
-                CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => {
-                    assert_eq!(to_address, "fee_collector_addr");
-                    assert_eq!(amount.len(), 1);
-                    assert_eq!(
-                        amount[0],
-                        Coin {
-                            denom: "base_token".to_string(),
-                            amount: maker_fee + taker_fee
-                        }
-                    );
-                }
-                _ => panic!("Unexpected message type for fee transfer"),
-            }
+            // Add the order to the order book and save the state
+            order_book
+                .buy_orders
+                .entry(order.price.atomics.u128())
+                .or_insert_with(Vec::new)
+                .push(order.clone());
+            ORDER_BOOKS
+                .save(deps.as_mut().storage, "pair_id".to_string(), &order_book)
+                .unwrap();
+            USER_ORDERS
+                .save(
+                    deps.as_mut().storage,
+                    (info.sender.clone(), order.id),
+                    &order,
+                )
+                .unwrap();
 
-            // Check response attributes (updated due to new fields)
-            assert_eq!(res.attributes.len(), 15); // Updated to 15 with buyer_receives and seller_receives
-            assert_eq!(res.attributes[0], attr("event_type", "trade"));
-            assert_eq!(res.attributes[1], attr("trade_id", trade_id.to_string()));
-            assert_eq!(res.attributes[2], attr("pair_id", "pair_id"));
-            assert_eq!(res.attributes[3], attr("buy_order_id", "1"));
-            assert_eq!(res.attributes[4], attr("sell_order_id", "2"));
-            assert_eq!(res.attributes[5], attr("price", "10"));
-            assert_eq!(res.attributes[6], attr("amount", "100"));
-            assert_eq!(res.attributes[7], attr("total", total_price.to_string()));
-            assert_eq!(res.attributes[8], attr("maker_fee", maker_fee.to_string()));
-            assert_eq!(res.attributes[9], attr("taker_fee", taker_fee.to_string()));
-            assert_eq!(res.attributes[10], attr("buyer_receives", "100")); // New attribute
-            assert_eq!(
-                res.attributes[11],
-                attr("seller_receives", seller_receives.to_string())
-            ); // New attribute
-            assert_eq!(res.attributes[12], attr("base_token", "base_token"));
-            assert_eq!(res.attributes[13], attr("quote_token", "quote_token"));
-            assert_eq!(
-                res.attributes[14],
-                attr("timestamp", env.block.time.seconds().to_string())
-            );
+            // Execute the cancel order function
+            let res = execute_cancel_order(
+                deps.as_mut(),
+                env,
+                info.clone(),
+                order.id,
+                "pair_id".to_string(),
+            )
+            .unwrap();
 
-            // Check updated order amounts
-            assert_eq!(buy_order.filled_amount, Uint128::new(100u128));
-            assert_eq!(buy_order.remaining_amount, Uint128::zero());
-            assert_eq!(buy_order.status, OrderStatus::Filled);
-            assert_eq!(sell_order.filled_amount, Uint128::new(100u128));
-            assert_eq!(sell_order.remaining_amount, Uint128::zero());
-            assert_eq!(sell_order.status, OrderStatus::Filled);
+            // Verify response attributes
+            assert_eq!(res.attributes.len(), 2);
+            assert_eq!(res.attributes[0].key, "action");
+            assert_eq!(res.attributes[0].value, "cancel_order");
+            assert_eq!(res.attributes[1].key, "order_id");
+            assert_eq!(res.attributes[1].value, order.id.to_string());
 
-            // Verify orders were saved correctly in storage
-            let saved_buy_order = USER_ORDERS
-                .load(&deps.storage, (Addr::unchecked("buyer"), 1))
+            // Verify the order status is updated to "Cancelled"
+            let updated_order = USER_ORDERS
+                .load(&deps.storage, (info.sender.clone(), order.id))
                 .unwrap();
-            assert_eq!(saved_buy_order, buy_order);
-            let saved_sell_order = USER_ORDERS
-                .load(&deps.storage, (Addr::unchecked("seller"), 2))
+            assert_eq!(updated_order.status, OrderStatus::Cancelled);
+
+            // Verify the order is removed from the order book
+            let updated_order_book = ORDER_BOOKS
+                .load(&deps.storage, "pair_id".to_string())
                 .unwrap();
-            assert_eq!(saved_sell_order, sell_order);
+            assert!(updated_order_book
+                .buy_orders
+                .get(&order.price.atomics.u128())
+                .unwrap()
+                .is_empty());
         }
 
         #[test]
-        fn test_execute_place_limit_order_buy() {
+        fn test_execute_cancel_order_not_owner() {
             let mut deps = mock_dependencies();
             let env = mock_env();
-            let info = message_info(
-                &Addr::unchecked("buyer"),
-                &[Coin {
-                    denom: "base_token".to_string(),
-                    amount: Uint128::from(1000u128),
-                }],
-            );
+            let info = message_info(&Addr::unchecked("buyer"), &[]);
+            let wrong_info = message_info(&Addr::unchecked("wrong_buyer"), &[]);
 
             let config = Config {
                 owner: Addr::unchecked("creator"),
@@ -1910,16 +6268,25 @@ pub mod execute {
                 lp_supply: 20_000_000_000u128,
                 maker_fee: Decimal::percent(1),
                 taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
                 secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
-                base_token_denom: "ubase_token".to_string(),
+                base_token_denom: Denom::Native("ubase_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
             };
 
             let token_pair = TokenPair {
-                base_token: "base_token".to_string(),
-                quote_token: "quote_token".to_string(),
+                base_token: Denom::Native("base_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
                 base_decimals: 6,
                 quote_decimals: 8,
                 enabled: true,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
             };
 
             CONFIG.save(deps.as_mut().storage, &config).unwrap();
@@ -1927,76 +6294,69 @@ pub mod execute {
                 .save(deps.as_mut().storage, "pair_id".to_string(), &token_pair)
                 .unwrap();
 
+            let mut order_book = OrderBook {
+                pair_id: "pair_id".to_string(),
+                buy_orders: BTreeMap::new(),
+                sell_orders: BTreeMap::new(),
+            };
+
+            let order = Order {
+                id: 1,
+                owner: Addr::unchecked("buyer"),
+                pair_id: "pair_id".to_string(),
+                token_amount: Uint128::from(100u128),
+                price: Price::new(Uint128::from(10u128), 0),
+                timestamp: env.block.time.seconds() as u64,
+                status: OrderStatus::Active,
+                filled_amount: Uint128::zero(),
+                remaining_amount: Uint128::from(100u128),
+                order_type: OrderType::Buy,
+                created_at: env.block.height,
+                deposit: Uint128::zero(),
+            };
+
+            // Add the order to the order book and save the state
+            order_book
+                .buy_orders
+                .entry(order.price.atomics.u128())
+                .or_insert_with(Vec::new)
+                .push(order.clone());
             ORDER_BOOKS
+                .save(deps.as_mut().storage, "pair_id".to_string(), &order_book)
+                .unwrap();
+            USER_ORDERS
                 .save(
                     deps.as_mut().storage,
-                    "pair_id".to_string(),
-                    &OrderBook {
-                        pair_id: "pair_id".to_string(),
-                        buy_orders: BTreeMap::new(),
-                        sell_orders: BTreeMap::new(),
-                    },
+                    (info.sender.clone(), order.id),
+                    &order,
                 )
                 .unwrap();
 
-            NEXT_ORDER_ID.save(deps.as_mut().storage, &0u64).unwrap();
-            NEXT_TRADE_ID.save(deps.as_mut().storage, &0u64).unwrap(); // Initialize NEXT_TRADE_ID
-
-            let amount = Uint128::from(100u128);
-            let price = Uint128::from(10u128);
-            let is_buy = true;
-
-            // Execute the place limit order function
-            let res = execute_place_limit_order(
+            // Attempt to cancel the order with the wrong owner
+            let res = execute_cancel_order(
                 deps.as_mut(),
                 env,
-                info.clone(),
+                wrong_info.clone(),
+                order.id,
                 "pair_id".to_string(),
-                amount,
-                price,
-                is_buy,
-            )
-            .unwrap();
-
-            // Verify response attributes
-            assert_eq!(res.attributes.len(), 6);
-            assert_eq!(res.attributes[0].key, "action");
-            assert_eq!(res.attributes[0].value, "place_limit_order");
-            assert_eq!(res.attributes[1].key, "order_id");
-            assert_eq!(res.attributes[1].value, "0");
-            assert_eq!(res.attributes[2].key, "pair_id");
-            assert_eq!(res.attributes[2].value, "pair_id");
-            assert_eq!(res.attributes[3].key, "is_buy");
-            assert_eq!(res.attributes[3].value, "true");
-            assert_eq!(res.attributes[4].key, "amount");
-            assert_eq!(res.attributes[4].value, amount.to_string());
-            assert_eq!(res.attributes[5].key, "price");
-            assert_eq!(res.attributes[5].value, price.to_string());
-
-            // Verify the order was added to the order book
-            let order_book = ORDER_BOOKS
-                .load(&deps.storage, "pair_id".to_string())
-                .unwrap();
-            assert_eq!(order_book.buy_orders.len(), 1);
-            assert_eq!(order_book.buy_orders.get(&price.u128()).unwrap().len(), 1);
+            );
 
-            // Verify the order was saved in user orders
-            let order = USER_ORDERS
-                .load(&deps.storage, (info.sender.clone(), 0))
-                .unwrap();
-            assert_eq!(order.owner, Addr::unchecked("buyer"));
-            assert_eq!(order.pair_id, "pair_id");
-            assert_eq!(order.token_amount, amount);
-            assert_eq!(order.price, price);
-            assert_eq!(order.remaining_amount, amount);
-            assert_eq!(order.order_type, OrderType::Buy);
+            // Verify that the function returned an error
+            assert!(res.is_err());
+            assert_eq!(res.unwrap_err(), StdError::not_found("Order"));
         }
 
         #[test]
-        fn test_execute_place_limit_order_sell() {
+        fn test_execute_place_limit_order_below_min_notional() {
             let mut deps = mock_dependencies();
             let env = mock_env();
-            let info = message_info(&Addr::unchecked("seller"), &[]);
+            let info = message_info(
+                &Addr::unchecked("buyer"),
+                &[Coin {
+                    denom: "base_token".to_string(),
+                    amount: Uint128::from(10u128),
+                }],
+            );
 
             let config = Config {
                 owner: Addr::unchecked("creator"),
@@ -2008,16 +6368,25 @@ pub mod execute {
                 lp_supply: 20_000_000_000u128,
                 maker_fee: Decimal::percent(1),
                 taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
                 secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
-                base_token_denom: "ubase_token_denom".to_string(),
+                base_token_denom: Denom::Native("ubase_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
             };
 
             let token_pair = TokenPair {
-                base_token: "base_token".to_string(),
-                quote_token: "quote_token".to_string(),
+                base_token: Denom::Native("base_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
                 base_decimals: 6,
                 quote_decimals: 8,
                 enabled: true,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::from(1_000u128),
+                order_deposit: Uint128::zero(),
             };
 
             CONFIG.save(deps.as_mut().storage, &config).unwrap();
@@ -2025,135 +6394,128 @@ pub mod execute {
                 .save(deps.as_mut().storage, "pair_id".to_string(), &token_pair)
                 .unwrap();
 
-            ORDER_BOOKS
-                .save(
-                    deps.as_mut().storage,
-                    "pair_id".to_string(),
-                    &OrderBook {
-                        pair_id: "pair_id".to_string(),
-                        buy_orders: BTreeMap::new(),
-                        sell_orders: BTreeMap::new(),
-                    },
-                )
-                .unwrap();
-
-            NEXT_TRADE_ID.save(deps.as_mut().storage, &0u64).unwrap();
-            NEXT_ORDER_ID.save(deps.as_mut().storage, &0u64).unwrap();
+            // 1 token at price 10 is far below the 1_000 minimum notional
+            let res = execute_place_limit_order(
+                deps.as_mut(),
+                env,
+                info,
+                "pair_id".to_string(),
+                Uint128::from(1u128),
+                Price::new(Uint128::from(10u128), 0),
+                true,
+            );
 
-            let amount = Uint128::from(100u128);
-            let price = Uint128::from(10u128);
-            let is_buy = false;
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Order notional below minimum. Required: 1000, Got: 10")
+            );
+        }
 
-            // Mock balance and allowance queries for CW20 tokens
-            let sell_info = message_info(&Addr::unchecked("seller"), &[]);
-            let cw20_token_address = String::from("quote_token");
+        #[test]
+        fn test_execute_place_limit_order_off_grid() {
+            let mut deps = mock_dependencies();
+            let env = mock_env();
+            let info = message_info(
+                &Addr::unchecked("buyer"),
+                &[Coin {
+                    denom: "base_token".to_string(),
+                    amount: Uint128::from(10_000u128),
+                }],
+            );
 
-            // Clone necessary variables to avoid moving them into the closure
-            let cw20_token_address_clone = cw20_token_address.clone();
-            let sell_info_sender_clone = sell_info.sender.clone();
+            let config = Config {
+                owner: Addr::unchecked("creator"),
+                token_factory: Addr::unchecked("token_factory_addr"),
+                fee_collector: Addr::unchecked("fee_collector_addr"),
+                enabled: true,
+                quote_token_total_supply: 100_000_000_000u128,
+                bonding_curve_supply: 80_000_000_000u128,
+                lp_supply: 20_000_000_000u128,
+                maker_fee: Decimal::percent(1),
+                taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
+                secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
+                base_token_denom: Denom::Native("ubase_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+            };
 
-            // Mock the balance and allowance queries
-            deps.querier.update_wasm(move |query| {
-                let cw20_token_address = cw20_token_address_clone.clone();
-                let sell_info_sender = sell_info_sender_clone.clone();
-                let env = mock_env();
+            let token_pair = TokenPair {
+                base_token: Denom::Native("base_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
+                base_decimals: 6,
+                quote_decimals: 8,
+                enabled: true,
+                tick_size: Uint128::from(5u128),
+                lot_size: Uint128::from(100u128),
+                min_notional: Uint128::from(5_000u128),
+                order_deposit: Uint128::zero(),
+            };
 
-                match query {
-                    WasmQuery::Smart { contract_addr, msg } => {
-                        if contract_addr == &cw20_token_address.clone() {
-                            if let Ok(cw20::Cw20QueryMsg::Balance { address }) = from_json(&msg) {
-                                if address == sell_info_sender.into_string() {
-                                    return SystemResult::Ok(ContractResult::Ok(
-                                        to_json_binary(&BalanceResponse {
-                                            balance: Uint128::from(1000u128),
-                                        })
-                                        .unwrap(),
-                                    ));
-                                }
-                            } else if let Ok(cw20::Cw20QueryMsg::Allowance { owner, spender }) =
-                                from_json(&msg)
-                            {
-                                if owner == sell_info_sender.into_string()
-                                    && spender == env.contract.address.to_string()
-                                {
-                                    return SystemResult::Ok(ContractResult::Ok(
-                                        to_json_binary(&AllowanceResponse {
-                                            allowance: Uint128::from(1000u128),
-                                            expires: Expiration::Never {},
-                                        })
-                                        .unwrap(),
-                                    ));
-                                }
-                            }
-                        }
-                        SystemResult::Err(SystemError::UnsupportedRequest {
-                            kind: "".to_string(),
-                        })
-                    }
-                    _ => SystemResult::Err(SystemError::UnsupportedRequest {
-                        kind: "".to_string(),
-                    }),
-                }
-            });
+            CONFIG.save(deps.as_mut().storage, &config).unwrap();
+            TOKEN_PAIRS
+                .save(deps.as_mut().storage, "pair_id".to_string(), &token_pair)
+                .unwrap();
 
-            // Execute the place limit order function
+            // Price not a multiple of the tick size
             let res = execute_place_limit_order(
                 deps.as_mut(),
-                env,
+                env.clone(),
                 info.clone(),
                 "pair_id".to_string(),
-                amount,
-                price,
-                is_buy,
-            )
-            .unwrap();
-
-            // Verify response attributes
-            assert_eq!(res.attributes.len(), 6);
-            assert_eq!(res.attributes[0].key, "action");
-            assert_eq!(res.attributes[0].value, "place_limit_order");
-            assert_eq!(res.attributes[1].key, "order_id");
-            assert_eq!(res.attributes[1].value, "0");
-            assert_eq!(res.attributes[2].key, "pair_id");
-            assert_eq!(res.attributes[2].value, "pair_id");
-            assert_eq!(res.attributes[3].key, "is_buy");
-            assert_eq!(res.attributes[3].value, "false");
-            assert_eq!(res.attributes[4].key, "amount");
-            assert_eq!(res.attributes[4].value, amount.to_string());
-            assert_eq!(res.attributes[5].key, "price");
-            assert_eq!(res.attributes[5].value, price.to_string());
+                Uint128::from(1_000u128),
+                Price::new(Uint128::from(7u128), 8),
+                true,
+            );
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Price must be a non-zero multiple of tick size 5")
+            );
 
-            // Verify the order was added to the order book
-            let order_book = ORDER_BOOKS
-                .load(&deps.storage, "pair_id".to_string())
-                .unwrap();
-            assert_eq!(order_book.sell_orders.len(), 1);
-            assert_eq!(order_book.sell_orders.get(&price.u128()).unwrap().len(), 1);
+            // Amount not a multiple of the lot size
+            let res = execute_place_limit_order(
+                deps.as_mut(),
+                env.clone(),
+                info.clone(),
+                "pair_id".to_string(),
+                Uint128::from(1_050u128),
+                Price::new(Uint128::from(10u128), 0),
+                true,
+            );
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Amount must be a non-zero multiple of lot size 100")
+            );
 
-            // Verify the order was saved in user orders
-            let order = USER_ORDERS
-                .load(&deps.storage, (info.sender.clone(), 0))
-                .unwrap();
-            assert_eq!(order.owner, Addr::unchecked("seller"));
-            assert_eq!(order.pair_id, "pair_id");
-            assert_eq!(order.token_amount, amount);
-            assert_eq!(order.price, price);
-            assert_eq!(order.remaining_amount, amount);
-            assert_eq!(order.order_type, OrderType::Sell);
+            // On the grid but below the pair's minimum notional
+            let res = execute_place_limit_order(
+                deps.as_mut(),
+                env,
+                info,
+                "pair_id".to_string(),
+                Uint128::from(100u128),
+                Price::new(Uint128::from(10u128), 0),
+                true,
+            );
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Order notional below minimum. Required: 5000, Got: 1000")
+            );
         }
 
         #[test]
-        fn test_execute_place_limit_order_matching_orders() {
+        fn test_execute_place_limit_order_across_decimals() {
             let mut deps = mock_dependencies();
             let env = mock_env();
-            let buyer_info = message_info(
+            let info = message_info(
                 &Addr::unchecked("buyer"),
                 &[Coin {
-                    denom: "base_token".to_string(),
-                    amount: Uint128::from(1000u128),
+                    denom: "uhuahua".to_string(),
+                    amount: Uint128::from(30_000u128),
                 }],
             );
-            let seller_info = message_info(&Addr::unchecked("seller"), &[]);
 
             let config = Config {
                 owner: Addr::unchecked("creator"),
@@ -2165,23 +6527,32 @@ pub mod execute {
                 lp_supply: 20_000_000_000u128,
                 maker_fee: Decimal::percent(1),
                 taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
                 secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
-                base_token_denom: "ubase_token_denom".to_string(),
+                base_token_denom: Denom::Native("uhuahua".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
             };
 
+            // 6 decimal native base token against an 8 decimal quote token
             let token_pair = TokenPair {
-                base_token: "base_token".to_string(),
-                quote_token: "quote_token".to_string(),
+                base_token: Denom::Native("uhuahua".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
                 base_decimals: 6,
                 quote_decimals: 8,
                 enabled: true,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
             };
 
             CONFIG.save(deps.as_mut().storage, &config).unwrap();
             TOKEN_PAIRS
                 .save(deps.as_mut().storage, "pair_id".to_string(), &token_pair)
                 .unwrap();
-
             ORDER_BOOKS
                 .save(
                     deps.as_mut().storage,
@@ -2193,207 +6564,153 @@ pub mod execute {
                     },
                 )
                 .unwrap();
-
             NEXT_ORDER_ID.save(deps.as_mut().storage, &0u64).unwrap();
             NEXT_TRADE_ID.save(deps.as_mut().storage, &0u64).unwrap();
 
-            let amount = Uint128::from(100u128);
-            let price = Uint128::from(10u128);
-
-            // Mock balance and allowance queries for CW20 tokens
-            let sell_info = message_info(&Addr::unchecked("seller"), &[]);
-            let cw20_token_address = String::from("quote_token");
-
-            // Clone necessary variables to avoid moving them into the closure
-            let cw20_token_address_clone = cw20_token_address.clone();
-            let sell_info_sender_clone = sell_info.sender.clone();
-
-            // Mock the balance and allowance queries
-            deps.querier.update_wasm(move |query| {
-                let cw20_token_address = cw20_token_address_clone.clone();
-                let sell_info_sender = sell_info_sender_clone.clone();
-                let env = mock_env();
-
-                match query {
-                    WasmQuery::Smart { contract_addr, msg } => {
-                        if contract_addr == &cw20_token_address.clone() {
-                            if let Ok(cw20::Cw20QueryMsg::Balance { address }) = from_json(&msg) {
-                                if address == sell_info_sender.into_string() {
-                                    return SystemResult::Ok(ContractResult::Ok(
-                                        to_json_binary(&BalanceResponse {
-                                            balance: Uint128::from(1000u128),
-                                        })
-                                        .unwrap(),
-                                    ));
-                                }
-                            } else if let Ok(cw20::Cw20QueryMsg::Allowance { owner, spender }) =
-                                from_json(&msg)
-                            {
-                                if owner == sell_info_sender.into_string()
-                                    && spender == env.contract.address.to_string()
-                                {
-                                    return SystemResult::Ok(ContractResult::Ok(
-                                        to_json_binary(&AllowanceResponse {
-                                            allowance: Uint128::from(1000u128),
-                                            expires: Expiration::Never {},
-                                        })
-                                        .unwrap(),
-                                    ));
-                                }
-                            }
-                        }
-                        SystemResult::Err(SystemError::UnsupportedRequest {
-                            kind: "".to_string(),
-                        })
-                    }
-                    _ => SystemResult::Err(SystemError::UnsupportedRequest {
-                        kind: "".to_string(),
-                    }),
-                }
-            });
-
-            // Place buy order
-            let buy_res = execute_place_limit_order(
+            // A price that needs more than 8 decimals can't sit on the book
+            let res = execute_place_limit_order(
                 deps.as_mut(),
                 env.clone(),
-                buyer_info.clone(),
+                info.clone(),
                 "pair_id".to_string(),
-                amount,
-                price,
+                Uint128::from(200_000_000u128),
+                Price::new(Uint128::from(1_500_001u128), 10),
                 true,
-            )
-            .unwrap();
+            );
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Price 0.0001500001 cannot be expressed with 8 decimals")
+            );
 
-            // Place sell order
-            let sell_res = execute_place_limit_order(
+            // 0.015 HUAHUA per whole token, submitted with 10 decimals of precision
+            let price = Price::new(Uint128::from(1_500_000u128), 10);
+            assert_eq!(
+                price.to_whole_units(6, 8).unwrap(),
+                Decimal::from_str("0.015").unwrap()
+            );
+
+            // Two whole tokens cost 30_000 uhuahua
+            execute_place_limit_order(
                 deps.as_mut(),
-                env.clone(),
-                seller_info.clone(),
+                env,
+                info.clone(),
                 "pair_id".to_string(),
-                amount,
+                Uint128::from(200_000_000u128),
                 price,
-                false,
+                true,
             )
             .unwrap();
 
-            // Verify response attributes for buy order
-            assert_eq!(buy_res.attributes.len(), 6);
-            assert_eq!(buy_res.attributes[0].key, "action");
-            assert_eq!(buy_res.attributes[0].value, "place_limit_order");
-            assert_eq!(buy_res.attributes[1].key, "order_id");
-            assert_eq!(buy_res.attributes[1].value, "0");
-            assert_eq!(buy_res.attributes[2].key, "pair_id");
-            assert_eq!(buy_res.attributes[2].value, "pair_id");
-            assert_eq!(buy_res.attributes[3].key, "is_buy");
-            assert_eq!(buy_res.attributes[3].value, "true");
-            assert_eq!(buy_res.attributes[4].key, "amount");
-            assert_eq!(buy_res.attributes[4].value, amount.to_string());
-            assert_eq!(buy_res.attributes[5].key, "price");
-            assert_eq!(buy_res.attributes[5].value, price.to_string());
-
-            // Verify response attributes for sell order
-            assert_eq!(sell_res.attributes.len(), 6);
-            assert_eq!(sell_res.attributes[0].key, "action");
-            assert_eq!(sell_res.attributes[0].value, "place_limit_order");
-            assert_eq!(sell_res.attributes[1].key, "order_id");
-            assert_eq!(sell_res.attributes[1].value, "1");
-            assert_eq!(sell_res.attributes[2].key, "pair_id");
-            assert_eq!(sell_res.attributes[2].value, "pair_id");
-            assert_eq!(sell_res.attributes[3].key, "is_buy");
-            assert_eq!(sell_res.attributes[3].value, "false");
-            assert_eq!(sell_res.attributes[4].key, "amount");
-            assert_eq!(sell_res.attributes[4].value, amount.to_string());
-            assert_eq!(sell_res.attributes[5].key, "price");
-            assert_eq!(sell_res.attributes[5].value, price.to_string());
-
-            // Verify that orders have been matched and updated in storage
-            let saved_buy_order = USER_ORDERS
-                .load(&deps.storage, (Addr::unchecked("buyer"), 0))
-                .unwrap();
-            assert_eq!(saved_buy_order.filled_amount, amount);
-            assert_eq!(saved_buy_order.remaining_amount, Uint128::zero());
-            assert_eq!(saved_buy_order.status, OrderStatus::Filled);
-
-            let saved_sell_order = USER_ORDERS
-                .load(&deps.storage, (Addr::unchecked("seller"), 1))
-                .unwrap();
-            assert_eq!(saved_sell_order.filled_amount, amount);
-            assert_eq!(saved_sell_order.remaining_amount, Uint128::zero());
-            assert_eq!(saved_sell_order.status, OrderStatus::Filled);
-
-            // Verify the order book is empty after matching orders
+            // The order rests at the pair's precision
             let order_book = ORDER_BOOKS
                 .load(&deps.storage, "pair_id".to_string())
                 .unwrap();
-            assert!(order_book.buy_orders.is_empty());
-            assert!(order_book.sell_orders.is_empty());
+            assert_eq!(order_book.buy_orders.get(&15_000u128).unwrap().len(), 1);
+
+            let order = USER_ORDERS
+                .load(&deps.storage, (info.sender.clone(), 0))
+                .unwrap();
+            assert_eq!(order.price, price);
+            assert_eq!(order.price.precision, 8);
+            assert_eq!(order.price.atomics, Uint128::from(15_000u128));
         }
 
         #[test]
-        fn test_execute_place_limit_order_trading_disabled() {
+        fn test_execute_update_pair() {
             let mut deps = mock_dependencies();
             let env = mock_env();
-            let info = message_info(
-                &Addr::unchecked("buyer"),
-                &[Coin {
-                    denom: "base_token".to_string(),
-                    amount: Uint128::from(1000u128),
-                }],
-            );
+            let owner_info = message_info(&Addr::unchecked("owner"), &[]);
+            let non_owner_info = message_info(&Addr::unchecked("non_owner"), &[]);
 
             let config = Config {
-                owner: Addr::unchecked("creator"),
+                owner: Addr::unchecked("owner"),
                 token_factory: Addr::unchecked("token_factory_addr"),
                 fee_collector: Addr::unchecked("fee_collector_addr"),
-                enabled: false, // Trading disabled
+                enabled: true,
                 quote_token_total_supply: 100_000_000_000u128,
                 bonding_curve_supply: 80_000_000_000u128,
                 lp_supply: 20_000_000_000u128,
                 maker_fee: Decimal::percent(1),
                 taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
                 secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
-                base_token_denom: "ubase_token".to_string(),
+                base_token_denom: Denom::Native("ubase_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+            };
+            let token_pair = TokenPair {
+                base_token: Denom::Native("base_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
+                base_decimals: 6,
+                quote_decimals: 8,
+                enabled: true,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
             };
 
             CONFIG.save(deps.as_mut().storage, &config).unwrap();
+            TOKEN_PAIRS
+                .save(deps.as_mut().storage, "pair_id".to_string(), &token_pair)
+                .unwrap();
 
-            let amount = Uint128::from(100u128);
-            let price = Uint128::from(10u128);
-            let is_buy = true;
+            // Test unauthorized update
+            let res = execute_update_pair(
+                deps.as_mut(),
+                non_owner_info,
+                "pair_id".to_string(),
+                Some(false),
+            );
+            assert_eq!(res.unwrap_err(), StdError::generic_err("Unauthorized"));
 
-            // Execute the place limit order function
+            // Halt the pair
+            let res = execute_update_pair(
+                deps.as_mut(),
+                owner_info.clone(),
+                "pair_id".to_string(),
+                Some(false),
+            )
+            .unwrap();
+            assert_eq!(res.attributes.len(), 3);
+            assert_eq!(res.attributes[0], attr("action", "update_pair"));
+            assert_eq!(res.attributes[1], attr("pair_id", "pair_id"));
+            assert_eq!(res.attributes[2], attr("enabled", "false"));
+
+            // Orders are rejected while the pair is halted
             let res = execute_place_limit_order(
                 deps.as_mut(),
                 env,
-                info.clone(),
+                message_info(&Addr::unchecked("buyer"), &[]),
                 "pair_id".to_string(),
-                amount,
-                price,
-                is_buy,
+                Uint128::from(100u128),
+                Price::new(Uint128::from(10u128), 0),
+                true,
             );
-
-            // Verify that the function returned an error
-            assert!(res.is_err());
             assert_eq!(
                 res.unwrap_err(),
-                StdError::generic_err("Trading is currently disabled")
+                StdError::generic_err("Trading pair is disabled")
             );
+
+            // Re-enable it
+            execute_update_pair(deps.as_mut(), owner_info, "pair_id".to_string(), Some(true))
+                .unwrap();
+            let updated_pair = TOKEN_PAIRS
+                .load(&deps.storage, "pair_id".to_string())
+                .unwrap();
+            assert!(updated_pair.enabled);
         }
 
         #[test]
-        fn test_execute_place_limit_order_trading_pair_disabled() {
+        fn test_execute_update_pool() {
             let mut deps = mock_dependencies();
-            let env = mock_env();
-            let info = message_info(
-                &Addr::unchecked("buyer"),
-                &[Coin {
-                    denom: "base_token".to_string(),
-                    amount: Uint128::from(1000u128),
-                }],
-            );
+            let owner_info = message_info(&Addr::unchecked("owner"), &[]);
+            let non_owner_info = message_info(&Addr::unchecked("non_owner"), &[]);
 
             let config = Config {
-                owner: Addr::unchecked("creator"),
+                owner: Addr::unchecked("owner"),
                 token_factory: Addr::unchecked("token_factory_addr"),
                 fee_collector: Addr::unchecked("fee_collector_addr"),
                 enabled: true,
@@ -2402,243 +6719,188 @@ pub mod execute {
                 lp_supply: 20_000_000_000u128,
                 maker_fee: Decimal::percent(1),
                 taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
                 secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
-                base_token_denom: "ubase_token".to_string(),
+                base_token_denom: Denom::Native("ubase_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
             };
-
-            let token_pair = TokenPair {
-                base_token: "base_token".to_string(),
-                quote_token: "quote_token".to_string(),
-                base_decimals: 6,
-                quote_decimals: 8,
-                enabled: false, // Trading pair disabled
+            let pool = Pool {
+                pair_id: "pair_id".to_string(),
+                curve_slope: Uint128::from(500u128),
+                token_address: Addr::unchecked("token_address"),
+                total_reserve_token: Uint128::zero(),
+                token_sold: Uint128::zero(),
+                total_volume: Uint128::zero(),
+                total_trades: Uint128::zero(),
+                total_fees_collected: Uint128::zero(),
+                last_price: Price::new(Uint128::from(BASE_PRICE), 9),
+                enabled: true,
+                launch: LaunchSettings::default(),
+                launch_height: 0,
+                base_currency: "huahua".to_string(),
             };
 
             CONFIG.save(deps.as_mut().storage, &config).unwrap();
-            TOKEN_PAIRS
-                .save(deps.as_mut().storage, "pair_id".to_string(), &token_pair)
+            POOLS
+                .save(deps.as_mut().storage, "token_address".to_string(), &pool)
                 .unwrap();
 
-            let amount = Uint128::from(100u128);
-            let price = Uint128::from(10u128);
-            let is_buy = true;
-
-            // Execute the place limit order function
-            let res = execute_place_limit_order(
-                deps.as_mut(),
-                env,
-                info.clone(),
-                "pair_id".to_string(),
-                amount,
-                price,
-                is_buy,
+            // Test unauthorized update
+            let res = execute_update_pool(
+                deps.as_mut(),
+                non_owner_info,
+                "token_address".to_string(),
+                Some(false),
+                None,
             );
+            assert_eq!(res.unwrap_err(), StdError::generic_err("Unauthorized"));
 
-            // Verify that the function returned an error
-            assert!(res.is_err());
+            // Zero slope is rejected
+            let res = execute_update_pool(
+                deps.as_mut(),
+                owner_info.clone(),
+                "token_address".to_string(),
+                None,
+                Some(Uint128::zero()),
+            );
             assert_eq!(
                 res.unwrap_err(),
-                StdError::generic_err("Trading pair is disabled")
+                StdError::generic_err("Curve slope must be greater than 0.")
             );
+
+            // Test authorized update
+            let res = execute_update_pool(
+                deps.as_mut(),
+                owner_info,
+                "token_address".to_string(),
+                Some(false),
+                Some(Uint128::from(1_000u128)),
+            )
+            .unwrap();
+            assert_eq!(res.attributes.len(), 5);
+            assert_eq!(res.attributes[0], attr("action", "update_pool"));
+            assert_eq!(res.attributes[1], attr("token_address", "token_address"));
+            assert_eq!(res.attributes[2], attr("pair_id", "pair_id"));
+            assert_eq!(res.attributes[3], attr("enabled", "false"));
+            assert_eq!(res.attributes[4], attr("curve_slope", "1000"));
+
+            let updated_pool = POOLS
+                .load(&deps.storage, "token_address".to_string())
+                .unwrap();
+            assert!(!updated_pool.enabled);
+            assert_eq!(updated_pool.curve_slope, Uint128::from(1_000u128));
         }
 
         #[test]
-        fn test_match_orders_with_matching_orders() {
+        fn test_execute_register_pair() {
             let mut deps = mock_dependencies();
-            let env = mock_env();
-
-            // Initialize the token pair and config
-            let token_pair = TokenPair {
-                base_token: "base_token".to_string(),
-                quote_token: "quote_token".to_string(),
-                base_decimals: 6,
-                quote_decimals: 8,
-                enabled: true,
-            };
+            let owner_info = message_info(&Addr::unchecked("owner"), &[]);
+            let non_owner_info = message_info(&Addr::unchecked("non_owner"), &[]);
+            let quote_token = deps.api.addr_make("quote_token");
 
             let config = Config {
-                owner: Addr::unchecked("creator"),
+                owner: Addr::unchecked("owner"),
                 token_factory: Addr::unchecked("token_factory_addr"),
                 fee_collector: Addr::unchecked("fee_collector_addr"),
                 enabled: true,
                 quote_token_total_supply: 100_000_000_000u128,
                 bonding_curve_supply: 80_000_000_000u128,
                 lp_supply: 20_000_000_000u128,
-                maker_fee: Decimal::percent(1), // 1% maker fee
-                taker_fee: Decimal::percent(2), // 2% taker fee
+                maker_fee: Decimal::percent(1),
+                taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
                 secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
-                base_token_denom: "ubase_token_denom".to_string(),
+                base_token_denom: Denom::Native("ubase_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
             };
-
             CONFIG.save(deps.as_mut().storage, &config).unwrap();
-            TOKEN_PAIRS
-                .save(deps.as_mut().storage, "pair_id".to_string(), &token_pair)
-                .unwrap();
-
-            // Create a buy order and a sell order
-            let buy_order = Order {
-                id: 1,
-                owner: Addr::unchecked("buyer"),
-                pair_id: "pair_id".to_string(),
-                token_amount: Uint128::from(100u128),
-                price: Uint128::from(10u128),
-                timestamp: env.block.time.seconds() as u64,
-                status: OrderStatus::Active,
-                filled_amount: Uint128::zero(),
-                remaining_amount: Uint128::from(100u128),
-                order_type: OrderType::Buy,
-                created_at: env.block.height,
-            };
-
-            let sell_order = Order {
-                id: 2,
-                owner: Addr::unchecked("seller"),
-                pair_id: "pair_id".to_string(),
-                token_amount: Uint128::from(100u128),
-                price: Uint128::from(10u128),
-                timestamp: env.block.time.seconds() as u64 + 1, // Sell order is newer
-                status: OrderStatus::Active,
-                filled_amount: Uint128::zero(),
-                remaining_amount: Uint128::from(100u128),
-                order_type: OrderType::Sell,
-                created_at: env.block.height,
-            };
-
-            // Initialize the order book
-            let mut order_book = OrderBook {
-                pair_id: "pair_id".to_string(),
-                buy_orders: BTreeMap::new(),
-                sell_orders: BTreeMap::new(),
-            };
 
-            order_book
-                .buy_orders
-                .entry(10)
-                .or_insert_with(Vec::new)
-                .push(buy_order.clone());
-            order_book
-                .sell_orders
-                .entry(10)
-                .or_insert_with(Vec::new)
-                .push(sell_order.clone());
+            // Test unauthorized registration
+            let res = execute_register_pair(
+                deps.as_mut(),
+                non_owner_info,
+                "QT/base_token".to_string(),
+                UncheckedDenom::Native("ubase_token".to_string()),
+                UncheckedDenom::Cw20(quote_token.to_string()),
+                6,
+                8,
+            );
+            assert_eq!(res.unwrap_err(), StdError::generic_err("Unauthorized"));
 
-            ORDER_BOOKS
-                .save(deps.as_mut().storage, "pair_id".to_string(), &order_book)
+            // Test authorized registration
+            let res = execute_register_pair(
+                deps.as_mut(),
+                owner_info.clone(),
+                "QT/base_token".to_string(),
+                UncheckedDenom::Native("ubase_token".to_string()),
+                UncheckedDenom::Cw20(quote_token.to_string()),
+                6,
+                8,
+            )
+            .unwrap();
+            assert_eq!(res.attributes.len(), 4);
+            assert_eq!(res.attributes[0], attr("action", "register_pair"));
+            assert_eq!(res.attributes[1], attr("pair_id", "QT/base_token"));
+            assert_eq!(res.attributes[2], attr("base_token", "ubase_token"));
+            assert_eq!(res.attributes[3], attr("quote_token", quote_token.as_str()));
+
+            let token_pair = TOKEN_PAIRS
+                .load(&deps.storage, "QT/base_token".to_string())
                 .unwrap();
-            NEXT_TRADE_ID.save(deps.as_mut().storage, &0u64).unwrap();
-
-            // Execute the match_orders function (testing buy-initiated match)
-            let res = match_orders(deps.as_mut(), &env, "pair_id".to_string(), true).unwrap();
-
-            // Calculate expected values
-            let total_price = Uint128::new(100u128) * Uint128::new(10u128); // 1000 base tokens
-            let maker_fee = (Decimal::new(total_price) * Decimal::percent(1)).to_uint_ceil(); // 10 base tokens
-            let taker_fee = (Decimal::new(total_price) * Decimal::percent(2)).to_uint_ceil(); // 20 base tokens
-            let seller_receives = total_price - maker_fee - taker_fee; // 970 base tokens
-
-            // Verify response messages (3 messages expected from create_order)
-            assert_eq!(res.messages.len(), 3);
-
-            // Message 1: Quote tokens to buyer
-            match &res.messages[0].msg {
-                CosmosMsg::Wasm(WasmMsg::Execute {
-                    contract_addr,
-                    msg,
-                    funds,
-                }) => {
-                    assert_eq!(contract_addr, "quote_token");
-                    let cw20_msg: Cw20ExecuteMsg = from_json(msg).unwrap();
-                    assert_eq!(
-                        cw20_msg,
-                        Cw20ExecuteMsg::Transfer {
-                            recipient: "buyer".to_string(),
-                            amount: Uint128::new(100u128),
-                        }
-                    );
-                    assert!(funds.is_empty());
-                }
-                _ => panic!("Unexpected message type for quote token transfer"),
-            }
-
-            // Message 2: Base tokens to seller
-            match &res.messages[1].msg {
-                CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => {
-                    assert_eq!(to_address, "seller");
-                    assert_eq!(amount.len(), 1);
-                    assert_eq!(
-                        amount[0],
-                        Coin {
-                            denom: "base_token".to_string(),
-                            amount: seller_receives
-                        }
-                    );
-                }
-                _ => panic!("Unexpected message type for base token transfer"),
-            }
-
-            // Message 3: Fees to fee collector
-            match &res.messages[2].msg {
-                CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => {
-                    assert_eq!(to_address, "fee_collector_addr");
-                    assert_eq!(amount.len(), 1);
-                    assert_eq!(
-                        amount[0],
-                        Coin {
-                            denom: "base_token".to_string(),
-                            amount: maker_fee + taker_fee
-                        }
-                    );
+            assert_eq!(
+                token_pair,
+                TokenPair {
+                    base_token: Denom::Native("ubase_token".to_string()),
+                    quote_token: Denom::Cw20(quote_token.clone()),
+                    base_decimals: 6,
+                    quote_decimals: 8,
+                    enabled: true,
+                    tick_size: Uint128::one(),
+                    lot_size: Uint128::one(),
+                    min_notional: Uint128::zero(),
+                    order_deposit: Uint128::zero(),
                 }
-                _ => panic!("Unexpected message type for fee transfer"),
-            }
-
-            // Verify response attributes
-            assert_eq!(res.attributes.len(), 15); // Updated to 15 with new fields
-            assert_eq!(res.attributes[0], attr("event_type", "trade"));
-            assert_eq!(res.attributes[1], attr("trade_id", "0")); // First trade ID
-
-            // Verify that orders have been matched and updated in storage
-            let saved_buy_order = USER_ORDERS
-                .load(&deps.storage, (Addr::unchecked("buyer"), 1))
-                .unwrap();
-            assert_eq!(saved_buy_order.filled_amount, Uint128::from(100u128));
-            assert_eq!(saved_buy_order.remaining_amount, Uint128::zero());
-            assert_eq!(saved_buy_order.status, OrderStatus::Filled);
-
-            let saved_sell_order = USER_ORDERS
-                .load(&deps.storage, (Addr::unchecked("seller"), 2))
-                .unwrap();
-            assert_eq!(saved_sell_order.filled_amount, Uint128::from(100u128));
-            assert_eq!(saved_sell_order.remaining_amount, Uint128::zero());
-            assert_eq!(saved_sell_order.status, OrderStatus::Filled);
+            );
 
-            // Order book should be emptied
+            // The pair gets an empty order book to trade on
             let order_book = ORDER_BOOKS
-                .load(&deps.storage, "pair_id".to_string())
+                .load(&deps.storage, "QT/base_token".to_string())
                 .unwrap();
             assert!(order_book.buy_orders.is_empty());
             assert!(order_book.sell_orders.is_empty());
 
-            // Verify NEXT_TRADE_ID incremented
-            assert_eq!(NEXT_TRADE_ID.load(&deps.storage).unwrap(), 1u64);
+            // Registering the same pair twice fails
+            let res = execute_register_pair(
+                deps.as_mut(),
+                owner_info,
+                "QT/base_token".to_string(),
+                UncheckedDenom::Native("ubase_token".to_string()),
+                UncheckedDenom::Cw20(quote_token.to_string()),
+                6,
+                8,
+            );
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Trading pair already exists")
+            );
         }
 
         #[test]
-        fn test_match_orders_with_no_matching_orders() {
+        fn test_execute_transfer_and_accept_ownership() {
             let mut deps = mock_dependencies();
-            let env = mock_env();
-
-            // Initialize the token pair and config
-            let token_pair = TokenPair {
-                base_token: "base_token".to_string(),
-                quote_token: "quote_token".to_string(),
-                base_decimals: 6,
-                quote_decimals: 8,
-                enabled: true,
-            };
+            let mut env = mock_env();
+            let owner_info = message_info(&Addr::unchecked("owner"), &[]);
+            let new_owner = deps.api.addr_make("new_owner");
+            let new_owner_info = message_info(&new_owner, &[]);
 
             let config = Config {
-                owner: Addr::unchecked("creator"),
+                owner: Addr::unchecked("owner"),
                 token_factory: Addr::unchecked("token_factory_addr"),
                 fee_collector: Addr::unchecked("fee_collector_addr"),
                 enabled: true,
@@ -2647,129 +6909,125 @@ pub mod execute {
                 lp_supply: 20_000_000_000u128,
                 maker_fee: Decimal::percent(1),
                 taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
                 secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
-                base_token_denom: "ubase_token_denom".to_string(),
+                base_token_denom: Denom::Native("ubase_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
             };
-
             CONFIG.save(deps.as_mut().storage, &config).unwrap();
-            TOKEN_PAIRS
-                .save(deps.as_mut().storage, "pair_id".to_string(), &token_pair)
-                .unwrap();
-
-            // Create a buy order and a sell order with non-matching prices
-            let buy_order = Order {
-                id: 1,
-                owner: Addr::unchecked("buyer"),
-                pair_id: "pair_id".to_string(),
-                token_amount: Uint128::from(100u128),
-                price: Uint128::from(10u128),
-                timestamp: env.block.time.seconds() as u64,
-                status: OrderStatus::Active,
-                filled_amount: Uint128::zero(),
-                remaining_amount: Uint128::from(100u128),
-                order_type: OrderType::Buy,
-                created_at: env.block.height,
-            };
-
-            let sell_order = Order {
-                id: 2,
-                owner: Addr::unchecked("seller"),
-                pair_id: "pair_id".to_string(),
-                token_amount: Uint128::from(100u128),
-                price: Uint128::from(15u128),
-                timestamp: env.block.time.seconds() as u64,
-                status: OrderStatus::Active,
-                filled_amount: Uint128::zero(),
-                remaining_amount: Uint128::from(100u128),
-                order_type: OrderType::Sell,
-                created_at: env.block.height,
-            };
-
-            // Save orders to USER_ORDERS storage
-            USER_ORDERS
-                .save(
-                    deps.as_mut().storage,
-                    (buy_order.owner.clone(), buy_order.id),
-                    &buy_order,
-                )
-                .unwrap();
-            USER_ORDERS
-                .save(
-                    deps.as_mut().storage,
-                    (sell_order.owner.clone(), sell_order.id),
-                    &sell_order,
-                )
-                .unwrap();
-
-            // Initialize the order book
-            let mut order_book = OrderBook {
-                pair_id: "pair_id".to_string(),
-                buy_orders: BTreeMap::new(),
-                sell_orders: BTreeMap::new(),
-            };
 
-            order_book
-                .buy_orders
-                .entry(10)
-                .or_insert_with(Vec::new)
-                .push(buy_order.clone());
-            order_book
-                .sell_orders
-                .entry(15)
-                .or_insert_with(Vec::new)
-                .push(sell_order.clone());
+            // Only the owner can propose a transfer
+            let res = execute_transfer_ownership(
+                deps.as_mut(),
+                env.clone(),
+                new_owner_info.clone(),
+                new_owner.to_string(),
+                None,
+            );
+            assert_eq!(res.unwrap_err(), StdError::generic_err("Unauthorized"));
 
-            ORDER_BOOKS
-                .save(deps.as_mut().storage, "pair_id".to_string(), &order_book)
-                .unwrap();
+            // An expiry in the past is rejected
+            let res = execute_transfer_ownership(
+                deps.as_mut(),
+                env.clone(),
+                owner_info.clone(),
+                new_owner.to_string(),
+                Some(Expiration::AtHeight(env.block.height)),
+            );
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Expiry has already passed")
+            );
 
-            NEXT_TRADE_ID.save(deps.as_mut().storage, &0u64).unwrap();
+            let expiry = Expiration::AtHeight(env.block.height + 10);
+            let res = execute_transfer_ownership(
+                deps.as_mut(),
+                env.clone(),
+                owner_info.clone(),
+                new_owner.to_string(),
+                Some(expiry),
+            )
+            .unwrap();
+            assert_eq!(res.attributes[0], attr("action", "transfer_ownership"));
+            assert_eq!(res.attributes[2], attr("pending_owner", new_owner.as_str()));
+            assert_eq!(
+                res.attributes[3],
+                attr("pending_expiry", expiry.to_string())
+            );
 
-            // Execute the match_orders function
-            let res = match_orders(deps.as_mut(), &env, "pair_id".to_string(), true).unwrap();
+            // Ownership does not move until the transfer is accepted
+            let ownership = query::query_ownership(deps.as_ref()).unwrap();
+            assert_eq!(ownership.owner, Addr::unchecked("owner"));
+            assert_eq!(ownership.pending_owner, Some(new_owner.clone()));
+            assert_eq!(ownership.pending_expiry, Some(expiry));
 
-            // Verify response attributes
-            assert_eq!(res.attributes.is_empty(), true);
+            // Nobody else can accept it
+            let res = execute_accept_ownership(deps.as_mut(), env.clone(), owner_info.clone());
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Unauthorized: not the pending owner")
+            );
 
-            // Verify that orders have not been matched and remain unchanged
-            let saved_buy_order = USER_ORDERS
-                .load(&deps.storage, (Addr::unchecked("buyer"), 1))
-                .unwrap();
-            assert_eq!(saved_buy_order.filled_amount, Uint128::zero());
-            assert_eq!(saved_buy_order.remaining_amount, Uint128::from(100u128));
-            assert_eq!(saved_buy_order.status, OrderStatus::Active);
+            // And it lapses once the expiry is reached
+            let mut expired_env = env.clone();
+            expired_env.block.height += 10;
+            let res = execute_accept_ownership(deps.as_mut(), expired_env, new_owner_info.clone());
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Ownership transfer has expired")
+            );
 
-            let saved_sell_order = USER_ORDERS
-                .load(&deps.storage, (Addr::unchecked("seller"), 2))
-                .unwrap();
-            assert_eq!(saved_sell_order.filled_amount, Uint128::zero());
-            assert_eq!(saved_sell_order.remaining_amount, Uint128::from(100u128));
-            assert_eq!(saved_sell_order.status, OrderStatus::Active);
+            // Propose again without expiry and accept
+            execute_transfer_ownership(
+                deps.as_mut(),
+                env.clone(),
+                owner_info.clone(),
+                new_owner.to_string(),
+                None,
+            )
+            .unwrap();
+            env.block.height += 100;
+            let res = execute_accept_ownership(deps.as_mut(), env.clone(), new_owner_info).unwrap();
+            assert_eq!(res.attributes[0], attr("action", "accept_ownership"));
+            assert_eq!(res.attributes[1], attr("previous_owner", "owner"));
+            assert_eq!(res.attributes[2], attr("owner", new_owner.as_str()));
+
+            let ownership = query::query_ownership(deps.as_ref()).unwrap();
+            assert_eq!(ownership.owner, new_owner);
+            assert_eq!(ownership.pending_owner, None);
+
+            // The previous owner has lost its rights
+            let res = execute_transfer_ownership(
+                deps.as_mut(),
+                env.clone(),
+                owner_info.clone(),
+                "owner".to_string(),
+                None,
+            );
+            assert_eq!(res.unwrap_err(), StdError::generic_err("Unauthorized"));
+
+            // Nothing is left to accept once the transfer completes
+            let res = execute_accept_ownership(deps.as_mut(), env, owner_info);
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("No pending ownership transfer")
+            );
         }
 
         #[test]
-        fn test_execute_place_limit_order_buy_partial_fill() {
+        fn test_role_gated_admin_actions() {
             let mut deps = mock_dependencies();
             let env = mock_env();
-            let info = message_info(
-                &Addr::unchecked("buyer"),
-                &[Coin {
-                    denom: "ubase_token".to_string(),
-                    amount: Uint128::new(500),
-                }],
-            );
-
-            // Initialize the token pair and config
-            let token_pair = TokenPair {
-                base_token: "ubase_token".to_string(),
-                quote_token: "quote_token".to_string(),
-                base_decimals: 6,
-                quote_decimals: 8,
-                enabled: true,
-            };
+            let owner_info = message_info(&Addr::unchecked("owner"), &[]);
+            let fee_manager = deps.api.addr_make("fee_manager");
+            let pauser = deps.api.addr_make("pauser");
+            let graduator = deps.api.addr_make("graduator");
 
             let config = Config {
-                owner: Addr::unchecked("creator"),
+                owner: Addr::unchecked("owner"),
                 token_factory: Addr::unchecked("token_factory_addr"),
                 fee_collector: Addr::unchecked("fee_collector_addr"),
                 enabled: true,
@@ -2778,147 +7036,199 @@ pub mod execute {
                 lp_supply: 20_000_000_000u128,
                 maker_fee: Decimal::percent(1),
                 taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
                 secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
-                base_token_denom: "ubase_token".to_string(),
+                base_token_denom: Denom::Native("ubase_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+            };
+            let token_pair = TokenPair {
+                base_token: Denom::Native("ubase_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("token_address")),
+                base_decimals: 6,
+                quote_decimals: 8,
+                enabled: true,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
+            };
+
+            let pool = Pool {
+                pair_id: "pair_id".to_string(),
+                curve_slope: Uint128::from(500u128),
+                token_address: Addr::unchecked("token_address"),
+                total_reserve_token: Uint128::zero(),
+                token_sold: Uint128::zero(),
+                total_volume: Uint128::zero(),
+                total_trades: Uint128::zero(),
+                total_fees_collected: Uint128::zero(),
+                last_price: Price::new(Uint128::from(BASE_PRICE), 9),
+                enabled: true,
+                launch: LaunchSettings::default(),
+                launch_height: 0,
+                base_currency: "huahua".to_string(),
+            };
+
+            let token_info = TokenInfo {
+                name: "Test Token".to_owned(),
+                symbol: "TST".to_owned(),
+                decimals: 9,
+                total_supply: 100_000_000_000u128.into(),
+                bonding_curve_supply: Uint128::from(80_000_000_000u128),
+                lp_supply: Uint128::from(20_000_000_000u128),
+                graduation_reserve: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+                initial_price: Price::new(Uint128::from(BASE_PRICE), 9),
+                max_price_impact: Uint128::from(30u128),
+                graduated: true,
             };
 
             CONFIG.save(deps.as_mut().storage, &config).unwrap();
             TOKEN_PAIRS
                 .save(deps.as_mut().storage, "pair_id".to_string(), &token_pair)
                 .unwrap();
-
-            // Initially empty order book and order ID
-            let order_book = OrderBook {
-                pair_id: "pair_id".to_string(),
-                buy_orders: BTreeMap::new(),
-                sell_orders: BTreeMap::new(),
-            };
-            ORDER_BOOKS
-                .save(deps.as_mut().storage, "pair_id".to_string(), &order_book)
+            POOLS
+                .save(deps.as_mut().storage, "token_address".to_string(), &pool)
+                .unwrap();
+            TOKEN_INFO
+                .save(
+                    deps.as_mut().storage,
+                    "token_address".to_string(),
+                    &token_info,
+                )
                 .unwrap();
-            NEXT_ORDER_ID.save(deps.as_mut().storage, &1).unwrap();
-            NEXT_TRADE_ID.save(deps.as_mut().storage, &1).unwrap();
-
-            // Mock balance and allowance queries for CW20 tokens
-            let sell_info = message_info(&Addr::unchecked("seller"), &[]);
-            let cw20_token_address = String::from("quote_token");
 
-            // Clone necessary variables to avoid moving them into the closure
-            let cw20_token_address_clone = cw20_token_address.clone();
-            let sell_info_sender_clone = sell_info.sender.clone();
+            // Only the owner assigns roles
+            let res = execute_update_roles(
+                deps.as_mut(),
+                message_info(&fee_manager, &[]),
+                Some(fee_manager.to_string()),
+                None,
+                None,
+                None,
+            );
+            assert_eq!(res.unwrap_err(), StdError::generic_err("Unauthorized"));
 
-            // Mock the balance and allowance queries
-            deps.querier.update_wasm(move |query| {
-                let cw20_token_address = cw20_token_address_clone.clone();
-                let sell_info_sender = sell_info_sender_clone.clone();
-                let env = mock_env();
+            let res = execute_update_roles(
+                deps.as_mut(),
+                owner_info,
+                Some(fee_manager.to_string()),
+                Some(pauser.to_string()),
+                Some(graduator.to_string()),
+                None,
+            )
+            .unwrap();
+            assert_eq!(res.attributes.len(), 5);
+            assert_eq!(res.attributes[0], attr("action", "update_roles"));
+            assert_eq!(res.attributes[1], attr("fee_manager", fee_manager.as_str()));
 
-                match query {
-                    WasmQuery::Smart { contract_addr, msg } => {
-                        if contract_addr == &cw20_token_address.clone() {
-                            if let Ok(cw20::Cw20QueryMsg::Balance { address }) = from_json(&msg) {
-                                if address == sell_info_sender.into_string() {
-                                    return SystemResult::Ok(ContractResult::Ok(
-                                        to_json_binary(&cw20::BalanceResponse {
-                                            balance: Uint128::from(1000u128),
-                                        })
-                                        .unwrap(),
-                                    ));
-                                }
-                            } else if let Ok(cw20::Cw20QueryMsg::Allowance { owner, spender }) =
-                                from_json(&msg)
-                            {
-                                if owner == sell_info_sender.into_string()
-                                    && spender == env.contract.address.to_string()
-                                {
-                                    return SystemResult::Ok(ContractResult::Ok(
-                                        to_json_binary(&cw20::AllowanceResponse {
-                                            allowance: Uint128::from(1000u128),
-                                            expires: cw20::Expiration::Never {},
-                                        })
-                                        .unwrap(),
-                                    ));
-                                }
-                            }
-                        }
-                        SystemResult::Err(SystemError::UnsupportedRequest {
-                            kind: "".to_string(),
-                        })
-                    }
-                    _ => SystemResult::Err(SystemError::UnsupportedRequest {
-                        kind: "".to_string(),
-                    }),
-                }
-            });
+            let roles = query::query_roles(deps.as_ref()).unwrap().roles;
+            assert_eq!(roles.pauser, Some(pauser.clone()));
+            assert_eq!(roles.graduator, Some(graduator.clone()));
 
-            // Place the first limit sell order
-            let sell_info = message_info(&Addr::unchecked("seller"), &[]);
-            execute_place_limit_order(
+            // The fee manager can change fees but not pause. Only curves may trade for free.
+            let res = execute_update_fees(
                 deps.as_mut(),
-                env.clone(),
-                sell_info,
-                "pair_id".to_string(),
-                Uint128::new(1000), // Sell order amount
-                Uint128::new(1),    // Price
-                false,
+                message_info(&fee_manager, &[]),
+                Some(Decimal::permille(5)),
+                None,
+                Some(Decimal::zero()),
             )
             .unwrap();
-
-            // Place a buy order that partially fills the sell order
-            let response = execute_place_limit_order(
+            assert_eq!(res.attributes[1], attr("maker_fee", "0.005"));
+            assert_eq!(res.attributes[2], attr("taker_fee", "0.01"));
+            assert_eq!(res.attributes[3], attr("curve_fee", "0"));
+            let res = execute_update_fees(
                 deps.as_mut(),
-                env.clone(),
-                info,
+                message_info(&fee_manager, &[]),
+                None,
+                Some(Decimal::zero()),
+                None,
+            );
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Trading fee rate must be between 0 and 1.")
+            );
+            let res = execute_update_fees(
+                deps.as_mut(),
+                message_info(&fee_manager, &[]),
+                Some(Decimal::one()),
+                None,
+                None,
+            );
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Trading fee rate must be between 0 and 1.")
+            );
+            let res =
+                execute_set_trading_enabled(deps.as_mut(), message_info(&fee_manager, &[]), false);
+            assert_eq!(res.unwrap_err(), StdError::generic_err("Unauthorized"));
+
+            // The pauser can halt everything or a single pair, but not touch fees
+            execute_set_trading_enabled(deps.as_mut(), message_info(&pauser, &[]), false).unwrap();
+            assert!(!CONFIG.load(&deps.storage).unwrap().enabled);
+            execute_update_pair(
+                deps.as_mut(),
+                message_info(&pauser, &[]),
                 "pair_id".to_string(),
-                Uint128::new(500), // Buy order amount
-                Uint128::new(1),   // Price
-                true,
+                Some(false),
             )
             .unwrap();
+            assert!(
+                !TOKEN_PAIRS
+                    .load(&deps.storage, "pair_id".to_string())
+                    .unwrap()
+                    .enabled
+            );
+            let res = execute_update_fees(
+                deps.as_mut(),
+                message_info(&pauser, &[]),
+                Some(Decimal::permille(5)),
+                None,
+                None,
+            );
+            assert_eq!(res.unwrap_err(), StdError::generic_err("Unauthorized"));
 
-            // Verify response attributes
-            assert_eq!(response.attributes.len(), 6);
-            assert_eq!(response.attributes[0], attr("action", "place_limit_order"));
-            assert_eq!(response.attributes[1], attr("order_id", "2"));
-            assert_eq!(response.attributes[2], attr("pair_id", "pair_id"));
-            assert_eq!(response.attributes[3], attr("is_buy", "true"));
-            assert_eq!(response.attributes[4], attr("amount", "500"));
-            assert_eq!(response.attributes[5], attr("price", "1"));
-
-            // Verify that the sell order is partially filled
-            let sell_order: Order = USER_ORDERS
-                .load(deps.as_mut().storage, (Addr::unchecked("seller"), 1))
-                .unwrap();
-            assert_eq!(sell_order.filled_amount, Uint128::new(500));
-            assert_eq!(sell_order.remaining_amount, Uint128::new(500));
-            assert_eq!(sell_order.status, OrderStatus::Active); // Still active because it's partially filled
-
-            // Verify that the buy order is fully filled
-            let buy_order: Order = USER_ORDERS
-                .load(deps.as_mut().storage, (Addr::unchecked("buyer"), 2))
-                .unwrap();
-            assert_eq!(buy_order.filled_amount, Uint128::new(500));
-            assert_eq!(buy_order.remaining_amount, Uint128::zero());
-            assert_eq!(buy_order.status, OrderStatus::Filled);
+            // Graduation is open to the graduator only
+            let res = execute_graduate(
+                deps.as_mut(),
+                env.clone(),
+                message_info(&pauser, &[]),
+                "token_address".to_string(),
+            );
+            assert_eq!(res.unwrap_err(), StdError::generic_err("Unauthorized"));
+            let res = execute_graduate(
+                deps.as_mut(),
+                env,
+                message_info(&graduator, &[]),
+                "token_address".to_string(),
+            );
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Token already graduated")
+            );
         }
 
         #[test]
-        fn test_execute_place_limit_order_sell_partial_fill() {
+        fn test_granular_pause() {
             let mut deps = mock_dependencies();
             let env = mock_env();
-            let info = message_info(&Addr::unchecked("seller"), &[]);
-
-            // Initialize the token pair and config
-            let token_pair = TokenPair {
-                base_token: "ubase_token".to_string(),
-                quote_token: "quote_token".to_string(),
-                base_decimals: 6,
-                quote_decimals: 8,
-                enabled: true,
-            };
+            let owner_info = message_info(&Addr::unchecked("owner"), &[]);
+            let pauser = deps.api.addr_make("pauser");
+            let guardian = deps.api.addr_make("guardian");
+            let buyer_info = message_info(
+                &Addr::unchecked("buyer"),
+                &[Coin {
+                    denom: "ubase_token".to_string(),
+                    amount: Uint128::from(100u128),
+                }],
+            );
 
             let config = Config {
-                owner: Addr::unchecked("creator"),
+                owner: Addr::unchecked("owner"),
                 token_factory: Addr::unchecked("token_factory_addr"),
                 fee_collector: Addr::unchecked("fee_collector_addr"),
                 enabled: true,
@@ -2927,398 +7237,314 @@ pub mod execute {
                 lp_supply: 20_000_000_000u128,
                 maker_fee: Decimal::percent(1),
                 taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
                 secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
-                base_token_denom: "ubase_token".to_string(),
+                base_token_denom: Denom::Native("ubase_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+            };
+            let token_pair = TokenPair {
+                base_token: Denom::Native("ubase_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("token_address")),
+                base_decimals: 6,
+                quote_decimals: 8,
+                enabled: true,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
             };
-
-            CONFIG.save(deps.as_mut().storage, &config).unwrap();
-            TOKEN_PAIRS
-                .save(deps.as_mut().storage, "pair_id".to_string(), &token_pair)
-                .unwrap();
-
-            // Initially empty order book and order ID
             let order_book = OrderBook {
                 pair_id: "pair_id".to_string(),
                 buy_orders: BTreeMap::new(),
                 sell_orders: BTreeMap::new(),
             };
+
+            CONFIG.save(deps.as_mut().storage, &config).unwrap();
+            TOKEN_PAIRS
+                .save(deps.as_mut().storage, "pair_id".to_string(), &token_pair)
+                .unwrap();
             ORDER_BOOKS
                 .save(deps.as_mut().storage, "pair_id".to_string(), &order_book)
                 .unwrap();
+            NEXT_ORDER_ID.save(deps.as_mut().storage, &1u64).unwrap();
+            NEXT_TRADE_ID.save(deps.as_mut().storage, &1u64).unwrap();
+            execute_update_roles(
+                deps.as_mut(),
+                owner_info.clone(),
+                None,
+                Some(pauser.to_string()),
+                None,
+                Some(guardian.to_string()),
+            )
+            .unwrap();
 
-            // Initialize NEXT_ORDER_ID and NEXT_TRADE_ID
-            NEXT_ORDER_ID.save(deps.as_mut().storage, &1).unwrap();
-            NEXT_TRADE_ID.save(deps.as_mut().storage, &1).unwrap();
-
-            // Mock balance and allowance queries for CW20 tokens
-            let buy_info = message_info(
-                &Addr::unchecked("buyer"),
-                &[Coin {
-                    denom: "ubase_token".to_string(),
-                    amount: Uint128::new(500),
-                }],
+            // Strangers cannot pause
+            let res = execute_set_paused(
+                deps.as_mut(),
+                buyer_info.clone(),
+                None,
+                vec![PauseAction::BookTrading],
+                true,
             );
+            assert_eq!(res.unwrap_err(), StdError::generic_err("Unauthorized"));
 
-            // Mock balance and allowance queries for CW20 tokens
-            let sell_info = message_info(&Addr::unchecked("seller"), &[]);
-            let cw20_token_address = String::from("quote_token");
-
-            // Clone necessary variables to avoid moving them into the closure
-            let cw20_token_address_clone = cw20_token_address.clone();
-            let sell_info_sender_clone = sell_info.sender.clone();
-
-            // Mock the balance and allowance queries
-            deps.querier.update_wasm(move |query| {
-                let cw20_token_address = cw20_token_address_clone.clone();
-                let sell_info_sender = sell_info_sender_clone.clone();
-                let env = mock_env();
-
-                match query {
-                    WasmQuery::Smart { contract_addr, msg } => {
-                        if contract_addr == &cw20_token_address.clone() {
-                            if let Ok(cw20::Cw20QueryMsg::Balance { address }) = from_json(&msg) {
-                                if address == sell_info_sender.into_string() {
-                                    return SystemResult::Ok(ContractResult::Ok(
-                                        to_json_binary(&cw20::BalanceResponse {
-                                            balance: Uint128::from(1000u128),
-                                        })
-                                        .unwrap(),
-                                    ));
-                                }
-                            } else if let Ok(cw20::Cw20QueryMsg::Allowance { owner, spender }) =
-                                from_json(&msg)
-                            {
-                                if owner == sell_info_sender.into_string()
-                                    && spender == env.contract.address.to_string()
-                                {
-                                    return SystemResult::Ok(ContractResult::Ok(
-                                        to_json_binary(&cw20::AllowanceResponse {
-                                            allowance: Uint128::from(1000u128),
-                                            expires: cw20::Expiration::Never {},
-                                        })
-                                        .unwrap(),
-                                    ));
-                                }
-                            }
-                        }
-                        SystemResult::Err(SystemError::UnsupportedRequest {
-                            kind: "".to_string(),
-                        })
-                    }
-                    _ => SystemResult::Err(SystemError::UnsupportedRequest {
-                        kind: "".to_string(),
-                    }),
-                }
-            });
-
-            // Place the first limit buy order
-            execute_place_limit_order(
+            // The guardian pauses the book everywhere
+            let res = execute_set_paused(
                 deps.as_mut(),
-                env.clone(),
-                buy_info,
-                "pair_id".to_string(),
-                Uint128::new(500), // Buy order amount
-                Uint128::new(1),   // Price
+                message_info(&guardian, &[]),
+                None,
+                vec![PauseAction::BookTrading, PauseAction::TokenCreation],
                 true,
             )
             .unwrap();
+            assert_eq!(res.attributes[0], attr("action", "pause"));
+            assert_eq!(res.attributes[1], attr("scope", "global"));
+            assert_eq!(
+                res.attributes[2],
+                attr("actions", "book_trading,token_creation")
+            );
 
-            // Place a sell order that partially fills the buy order
-            let response = execute_place_limit_order(
+            let res = execute_place_limit_order(
                 deps.as_mut(),
                 env.clone(),
-                info,
+                buyer_info.clone(),
                 "pair_id".to_string(),
-                Uint128::new(1000), // Sell order amount
-                Uint128::new(1),    // Price
-                false,
-            )
-            .unwrap();
-
-            // Verify response attributes
-            assert_eq!(response.attributes.len(), 6);
-            assert_eq!(response.attributes[0], attr("action", "place_limit_order"));
-            assert_eq!(response.attributes[1], attr("order_id", "2"));
-            assert_eq!(response.attributes[2], attr("pair_id", "pair_id"));
-            assert_eq!(response.attributes[3], attr("is_buy", "false"));
-            assert_eq!(response.attributes[4], attr("amount", "1000"));
-            assert_eq!(response.attributes[5], attr("price", "1"));
-
-            // Verify that the buy order is fully filled
-            let buy_order: Order = USER_ORDERS
-                .load(deps.as_mut().storage, (Addr::unchecked("buyer"), 1))
-                .unwrap();
-            assert_eq!(buy_order.filled_amount, Uint128::new(500));
-            assert_eq!(buy_order.remaining_amount, Uint128::zero());
-            assert_eq!(buy_order.status, OrderStatus::Filled);
-
-            // Verify that the sell order is partially filled
-            let sell_order: Order = USER_ORDERS
-                .load(deps.as_mut().storage, (Addr::unchecked("seller"), 2))
-                .unwrap();
-            assert_eq!(sell_order.filled_amount, Uint128::new(500));
-            assert_eq!(sell_order.remaining_amount, Uint128::new(500));
-            assert_eq!(sell_order.status, OrderStatus::Active); // Still active because it's partially filled
-        }
-
-        #[test]
-        fn test_validate_and_handle_native_tokens() {
-            let mut deps = mock_dependencies();
-            let env = mock_env();
-            let info = message_info(
-                &Addr::unchecked("buyer"),
-                &[Coin {
-                    denom: "base_token".to_string(),
-                    amount: Uint128::from(1000u128),
-                }],
-            );
-
-            let token_pair = TokenPair {
-                base_token: "base_token".to_string(),
-                quote_token: "quote_token".to_string(),
-                base_decimals: 6,
-                quote_decimals: 8,
-                enabled: true,
-            };
-
-            let result = validate_and_handle_tokens(
-                &deps.as_mut(),
-                &env,
-                &info,
-                &token_pair,
                 Uint128::from(100u128),
-                Uint128::from(10u128),
+                Price::new(Uint128::one(), 0),
                 true,
             );
-            assert!(result.is_ok());
-        }
-
-        #[test]
-        fn test_validate_and_handle_cw20_tokens() {
-            let mut deps = mock_dependencies();
-            let env = mock_env();
-            let info = message_info(&Addr::unchecked("seller"), &[]);
-
-            let token_pair = TokenPair {
-                base_token: "base_token".to_string(),
-                quote_token: "quote_token".to_string(),
-                base_decimals: 6,
-                quote_decimals: 8,
-                enabled: true,
-            };
-
-            // Mock balance and allowance queries for CW20 tokens
-            let sell_info = message_info(&Addr::unchecked("seller"), &[]);
-            let cw20_token_address = String::from("quote_token");
-
-            // Clone necessary variables to avoid moving them into the closure
-            let cw20_token_address_clone = cw20_token_address.clone();
-            let sell_info_sender_clone = sell_info.sender.clone();
-
-            // Mock the balance and allowance queries
-            deps.querier.update_wasm(move |query| {
-                let cw20_token_address = cw20_token_address_clone.clone();
-                let sell_info_sender = sell_info_sender_clone.clone();
-                let env = mock_env();
-
-                match query {
-                    WasmQuery::Smart { contract_addr, msg } => {
-                        if contract_addr == &cw20_token_address.clone() {
-                            if let Ok(cw20::Cw20QueryMsg::Balance { address }) = from_json(&msg) {
-                                if address == sell_info_sender.into_string() {
-                                    return SystemResult::Ok(ContractResult::Ok(
-                                        to_json_binary(&BalanceResponse {
-                                            balance: Uint128::from(1000u128),
-                                        })
-                                        .unwrap(),
-                                    ));
-                                }
-                            } else if let Ok(cw20::Cw20QueryMsg::Allowance { owner, spender }) =
-                                from_json(&msg)
-                            {
-                                if owner == sell_info_sender.into_string()
-                                    && spender == env.contract.address.to_string()
-                                {
-                                    return SystemResult::Ok(ContractResult::Ok(
-                                        to_json_binary(&AllowanceResponse {
-                                            allowance: Uint128::from(1000u128),
-                                            expires: Expiration::Never {},
-                                        })
-                                        .unwrap(),
-                                    ));
-                                }
-                            }
-                        }
-                        SystemResult::Err(SystemError::UnsupportedRequest {
-                            kind: "".to_string(),
-                        })
-                    }
-                    _ => SystemResult::Err(SystemError::UnsupportedRequest {
-                        kind: "".to_string(),
-                    }),
-                }
-            });
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Book trading is paused")
+            );
+            let res = execute_create_token(
+                deps.as_mut(),
+                env.clone(),
+                buyer_info.clone(),
+                "Test Token".to_string(),
+                "TST".to_string(),
+                6,
+                "uri".to_string(),
+                Uint128::from(30u128),
+                Uint128::from(500u128),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            );
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Token creation is paused")
+            );
 
-            // Test case for selling CW20 tokens
-            let result = validate_and_handle_tokens(
-                &deps.as_mut(),
-                &env,
-                &info,
-                &token_pair,
-                Uint128::from(100u128),
-                Uint128::from(10u128),
+            // The guardian cannot lift a pause, the pauser can
+            let res = execute_set_paused(
+                deps.as_mut(),
+                message_info(&guardian, &[]),
+                None,
+                vec![PauseAction::BookTrading],
                 false,
             );
-            assert!(result.is_ok());
-        }
+            assert_eq!(res.unwrap_err(), StdError::generic_err("Unauthorized"));
+            execute_set_paused(
+                deps.as_mut(),
+                message_info(&pauser, &[]),
+                None,
+                vec![PauseAction::BookTrading],
+                false,
+            )
+            .unwrap();
 
-        #[test]
-        fn test_validate_native_token_payment_insufficient_funds() {
-            let info = message_info(
-                &Addr::unchecked("buyer"),
-                &[Coin {
-                    denom: "quote_token".to_string(),
-                    amount: Uint128::from(50u128),
-                }],
+            execute_place_limit_order(
+                deps.as_mut(),
+                env.clone(),
+                buyer_info.clone(),
+                "pair_id".to_string(),
+                Uint128::from(100u128),
+                Price::new(Uint128::one(), 0),
+                true,
+            )
+            .unwrap();
+            let order_id = NEXT_ORDER_ID.load(&deps.storage).unwrap() - 1;
+
+            // Token creation has no pair to scope to
+            let res = execute_set_paused(
+                deps.as_mut(),
+                message_info(&guardian, &[]),
+                Some("pair_id".to_string()),
+                vec![PauseAction::TokenCreation],
+                true,
+            );
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Token creation can only be paused globally")
             );
 
-            let result =
-                validate_native_token_payment(&info, "quote_token", Uint128::from(100u128));
-            assert!(result.is_err());
-        }
+            // Pausing a single pair leaves the global flags alone
+            let res = execute_set_paused(
+                deps.as_mut(),
+                message_info(&guardian, &[]),
+                Some("pair_id".to_string()),
+                vec![PauseAction::BookTrading, PauseAction::CurveTrading],
+                true,
+            )
+            .unwrap();
+            assert_eq!(res.attributes[1], attr("scope", "pair_id"));
 
-        #[test]
-        fn test_validate_native_token_payment_excess_funds() {
-            let info = message_info(
-                &Addr::unchecked("buyer"),
-                &[Coin {
-                    denom: "quote_token".to_string(),
-                    amount: Uint128::from(150u128),
-                }],
+            let status =
+                query::query_pause_status(deps.as_ref(), Some("pair_id".to_string())).unwrap();
+            assert!(!status.global.book_trading);
+            assert!(status.global.token_creation);
+            assert_eq!(
+                status.pair,
+                Some(PauseFlags {
+                    token_creation: false,
+                    curve_trading: true,
+                    book_trading: true,
+                    graduation: false,
+                })
             );
 
-            let result =
-                validate_native_token_payment(&info, "quote_token", Uint128::from(100u128));
-            assert!(result.is_err());
+            let res = execute_place_limit_order(
+                deps.as_mut(),
+                env.clone(),
+                buyer_info,
+                "pair_id".to_string(),
+                Uint128::from(100u128),
+                Price::new(Uint128::one(), 0),
+                true,
+            );
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Book trading is paused")
+            );
+
+            // Makers can still leave the book while it is paused
+            execute_cancel_order(
+                deps.as_mut(),
+                env,
+                message_info(&Addr::unchecked("buyer"), &[]),
+                order_id,
+                "pair_id".to_string(),
+            )
+            .unwrap();
+            let order = ORDERS.load(&deps.storage, order_id).unwrap();
+            assert_eq!(order.status, OrderStatus::Cancelled);
         }
 
         #[test]
-        fn test_validate_cw20_token_payment_insufficient_allowance() {
+        fn test_execute_update_pair_trading_rules() {
             let mut deps = mock_dependencies();
-            let env = mock_env();
-            let info = message_info(&Addr::unchecked("seller"), &[]);
-
-            // Mock balance and allowance queries for CW20 tokens
-            let sell_info = message_info(&Addr::unchecked("seller"), &[]);
-            let cw20_token_address = String::from("base_token");
+            let owner_info = message_info(&Addr::unchecked("owner"), &[]);
+            let non_owner_info = message_info(&Addr::unchecked("non_owner"), &[]);
 
-            // Clone necessary variables to avoid moving them into the closure
-            let cw20_token_address_clone = cw20_token_address.clone();
-            let sell_info_sender_clone = sell_info.sender.clone();
+            let config = Config {
+                owner: Addr::unchecked("owner"),
+                token_factory: Addr::unchecked("token_factory_addr"),
+                fee_collector: Addr::unchecked("fee_collector_addr"),
+                enabled: true,
+                quote_token_total_supply: 100_000_000_000u128,
+                bonding_curve_supply: 80_000_000_000u128,
+                lp_supply: 20_000_000_000u128,
+                maker_fee: Decimal::percent(1),
+                taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
+                secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
+                base_token_denom: Denom::Native("ubase_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+            };
 
-            // Mock the balance and allowance queries
-            deps.querier.update_wasm(move |query| {
-                let cw20_token_address = cw20_token_address_clone.clone();
-                let sell_info_sender = sell_info_sender_clone.clone();
-                let env = mock_env();
+            let token_pair = TokenPair {
+                base_token: Denom::Native("base_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
+                base_decimals: 6,
+                quote_decimals: 8,
+                enabled: true,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
+            };
 
-                match query {
-                    WasmQuery::Smart { contract_addr, msg } => {
-                        if contract_addr == &cw20_token_address.clone() {
-                            if let Ok(cw20::Cw20QueryMsg::Allowance { owner, spender }) =
-                                from_json(&msg)
-                            {
-                                if owner == sell_info_sender.into_string()
-                                    && spender == env.contract.address.to_string()
-                                {
-                                    return SystemResult::Ok(ContractResult::Ok(
-                                        to_json_binary(&AllowanceResponse {
-                                            allowance: Uint128::from(50u128),
-                                            expires: Expiration::Never {},
-                                        })
-                                        .unwrap(),
-                                    ));
-                                }
-                            }
-                        }
-                        SystemResult::Err(SystemError::UnsupportedRequest {
-                            kind: "".to_string(),
-                        })
-                    }
-                    _ => SystemResult::Err(SystemError::UnsupportedRequest {
-                        kind: "".to_string(),
-                    }),
-                }
-            });
+            CONFIG.save(deps.as_mut().storage, &config).unwrap();
+            TOKEN_PAIRS
+                .save(deps.as_mut().storage, "pair_id".to_string(), &token_pair)
+                .unwrap();
 
-            let result = validate_cw20_token_payment(
-                &deps.as_ref(),
-                &env,
-                &info,
-                "base_token",
-                Uint128::from(100u128),
+            // Test unauthorized update
+            let res = execute_update_pair_trading_rules(
+                deps.as_mut(),
+                non_owner_info,
+                "pair_id".to_string(),
+                Some(Uint128::from(5u128)),
+                None,
+                None,
+                None,
             );
-            assert!(result.is_err());
-        }
-
-        #[test]
-        fn test_validate_cw20_token_payment_insufficient_balance() {
-            let mut deps = mock_dependencies();
-            let env = mock_env();
-            let info = message_info(&Addr::unchecked("seller"), &[]);
-
-            // Mock balance and allowance queries for CW20 tokens
-            let sell_info = message_info(&Addr::unchecked("seller"), &[]);
-            let cw20_token_address = String::from("base_token");
-
-            // Clone necessary variables to avoid moving them into the closure
-            let cw20_token_address_clone = cw20_token_address.clone();
-            let sell_info_sender_clone = sell_info.sender.clone();
+            assert_eq!(res.unwrap_err(), StdError::generic_err("Unauthorized"));
 
-            // Mock the balance and allowance queries
-            deps.querier.update_wasm(move |query| {
-                let cw20_token_address = cw20_token_address_clone.clone();
-                let sell_info_sender = sell_info_sender_clone.clone();
+            // Zero tick size is rejected
+            let res = execute_update_pair_trading_rules(
+                deps.as_mut(),
+                owner_info.clone(),
+                "pair_id".to_string(),
+                Some(Uint128::zero()),
+                None,
+                None,
+                None,
+            );
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Tick size must be greater than 0.")
+            );
 
-                match query {
-                    WasmQuery::Smart { contract_addr, msg } => {
-                        if contract_addr == &cw20_token_address.clone() {
-                            if let Ok(cw20::Cw20QueryMsg::Balance { address }) = from_json(&msg) {
-                                if address == sell_info_sender.into_string() {
-                                    return SystemResult::Ok(ContractResult::Ok(
-                                        to_json_binary(&BalanceResponse {
-                                            balance: Uint128::from(50u128),
-                                        })
-                                        .unwrap(),
-                                    ));
-                                }
-                            }
-                        }
-                        SystemResult::Err(SystemError::UnsupportedRequest {
-                            kind: "".to_string(),
-                        })
-                    }
-                    _ => SystemResult::Err(SystemError::UnsupportedRequest {
-                        kind: "".to_string(),
-                    }),
-                }
-            });
+            // Test authorized update
+            let res = execute_update_pair_trading_rules(
+                deps.as_mut(),
+                owner_info,
+                "pair_id".to_string(),
+                Some(Uint128::from(5u128)),
+                Some(Uint128::from(100u128)),
+                Some(Uint128::from(1_000u128)),
+                Some(Uint128::from(50u128)),
+            )
+            .unwrap();
 
-            let result = validate_cw20_token_payment(
-                &deps.as_ref(),
-                &env,
-                &info,
-                "base_token",
-                Uint128::from(100u128),
+            assert_eq!(res.attributes.len(), 6);
+            assert_eq!(
+                res.attributes[0],
+                attr("action", "update_pair_trading_rules")
             );
-            assert!(result.is_err());
+            assert_eq!(res.attributes[1], attr("pair_id", "pair_id"));
+
+            let updated_pair = TOKEN_PAIRS
+                .load(&deps.storage, "pair_id".to_string())
+                .unwrap();
+            assert_eq!(updated_pair.tick_size, Uint128::from(5u128));
+            assert_eq!(updated_pair.lot_size, Uint128::from(100u128));
+            assert_eq!(updated_pair.min_notional, Uint128::from(1_000u128));
+            assert_eq!(updated_pair.order_deposit, Uint128::from(50u128));
         }
 
         #[test]
-        fn test_execute_cancel_order() {
+        fn test_execute_place_limit_order_too_many_active_orders() {
             let mut deps = mock_dependencies();
             let env = mock_env();
-            let info = message_info(&Addr::unchecked("buyer"), &[]);
+            let info = message_info(
+                &Addr::unchecked("buyer"),
+                &[Coin {
+                    denom: "base_token".to_string(),
+                    amount: Uint128::from(1000u128),
+                }],
+            );
 
             let config = Config {
                 owner: Addr::unchecked("creator"),
@@ -3330,100 +7556,59 @@ pub mod execute {
                 lp_supply: 20_000_000_000u128,
                 maker_fee: Decimal::percent(1),
                 taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
                 secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
-                base_token_denom: "ubase_token_denom".to_string(),
+                base_token_denom: Denom::Native("ubase_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
             };
 
             let token_pair = TokenPair {
-                base_token: "base_token".to_string(),
-                quote_token: "quote_token".to_string(),
+                base_token: Denom::Native("base_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
                 base_decimals: 6,
                 quote_decimals: 8,
                 enabled: true,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
             };
 
-            CONFIG.save(deps.as_mut().storage, &config).unwrap();
-            TOKEN_PAIRS
-                .save(deps.as_mut().storage, "pair_id".to_string(), &token_pair)
-                .unwrap();
-
-            let mut order_book = OrderBook {
-                pair_id: "pair_id".to_string(),
-                buy_orders: BTreeMap::new(),
-                sell_orders: BTreeMap::new(),
-            };
-
-            let order = Order {
-                id: 1,
-                owner: Addr::unchecked("buyer"),
-                pair_id: "pair_id".to_string(),
-                token_amount: Uint128::from(100u128),
-                price: Uint128::from(10u128),
-                timestamp: env.block.time.seconds() as u64,
-                status: OrderStatus::Active,
-                filled_amount: Uint128::zero(),
-                remaining_amount: Uint128::from(100u128),
-                order_type: OrderType::Buy,
-                created_at: env.block.height,
-            };
-
-            // Add the order to the order book and save the state
-            order_book
-                .buy_orders
-                .entry(order.price.u128())
-                .or_insert_with(Vec::new)
-                .push(order.clone());
-            ORDER_BOOKS
-                .save(deps.as_mut().storage, "pair_id".to_string(), &order_book)
+            CONFIG.save(deps.as_mut().storage, &config).unwrap();
+            TOKEN_PAIRS
+                .save(deps.as_mut().storage, "pair_id".to_string(), &token_pair)
                 .unwrap();
-            USER_ORDERS
+            USER_ACTIVE_ORDERS
                 .save(
                     deps.as_mut().storage,
-                    (info.sender.clone(), order.id),
-                    &order,
+                    info.sender.clone(),
+                    &(MAX_ACTIVE_ORDERS_PER_USER as u64),
                 )
                 .unwrap();
 
-            // Execute the cancel order function
-            let res = execute_cancel_order(
+            let res = execute_place_limit_order(
                 deps.as_mut(),
                 env,
-                info.clone(),
-                order.id,
+                info,
                 "pair_id".to_string(),
-            )
-            .unwrap();
-
-            // Verify response attributes
-            assert_eq!(res.attributes.len(), 2);
-            assert_eq!(res.attributes[0].key, "action");
-            assert_eq!(res.attributes[0].value, "cancel_order");
-            assert_eq!(res.attributes[1].key, "order_id");
-            assert_eq!(res.attributes[1].value, order.id.to_string());
-
-            // Verify the order status is updated to "Cancelled"
-            let updated_order = USER_ORDERS
-                .load(&deps.storage, (info.sender.clone(), order.id))
-                .unwrap();
-            assert_eq!(updated_order.status, OrderStatus::Cancelled);
+                Uint128::from(100u128),
+                Price::new(Uint128::from(10u128), 0),
+                true,
+            );
 
-            // Verify the order is removed from the order book
-            let updated_order_book = ORDER_BOOKS
-                .load(&deps.storage, "pair_id".to_string())
-                .unwrap();
-            assert!(updated_order_book
-                .buy_orders
-                .get(&order.price.u128())
-                .unwrap()
-                .is_empty());
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Too many active orders. Maximum: 50")
+            );
         }
 
         #[test]
-        fn test_execute_cancel_order_not_owner() {
+        fn test_order_deposit_refunded_on_cancel() {
             let mut deps = mock_dependencies();
             let env = mock_env();
-            let info = message_info(&Addr::unchecked("buyer"), &[]);
-            let wrong_info = message_info(&Addr::unchecked("wrong_buyer"), &[]);
 
             let config = Config {
                 owner: Addr::unchecked("creator"),
@@ -3435,72 +7620,126 @@ pub mod execute {
                 lp_supply: 20_000_000_000u128,
                 maker_fee: Decimal::percent(1),
                 taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
                 secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
-                base_token_denom: "ubase_token".to_string(),
+                base_token_denom: Denom::Native("base_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
             };
 
             let token_pair = TokenPair {
-                base_token: "base_token".to_string(),
-                quote_token: "quote_token".to_string(),
+                base_token: Denom::Native("base_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
                 base_decimals: 6,
                 quote_decimals: 8,
                 enabled: true,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::from(50u128),
             };
 
             CONFIG.save(deps.as_mut().storage, &config).unwrap();
             TOKEN_PAIRS
                 .save(deps.as_mut().storage, "pair_id".to_string(), &token_pair)
                 .unwrap();
-
-            let mut order_book = OrderBook {
-                pair_id: "pair_id".to_string(),
-                buy_orders: BTreeMap::new(),
-                sell_orders: BTreeMap::new(),
-            };
-
-            let order = Order {
-                id: 1,
-                owner: Addr::unchecked("buyer"),
-                pair_id: "pair_id".to_string(),
-                token_amount: Uint128::from(100u128),
-                price: Uint128::from(10u128),
-                timestamp: env.block.time.seconds() as u64,
-                status: OrderStatus::Active,
-                filled_amount: Uint128::zero(),
-                remaining_amount: Uint128::from(100u128),
-                order_type: OrderType::Buy,
-                created_at: env.block.height,
-            };
-
-            // Add the order to the order book and save the state
-            order_book
-                .buy_orders
-                .entry(order.price.u128())
-                .or_insert_with(Vec::new)
-                .push(order.clone());
             ORDER_BOOKS
-                .save(deps.as_mut().storage, "pair_id".to_string(), &order_book)
-                .unwrap();
-            USER_ORDERS
                 .save(
                     deps.as_mut().storage,
-                    (info.sender.clone(), order.id),
-                    &order,
+                    "pair_id".to_string(),
+                    &OrderBook {
+                        pair_id: "pair_id".to_string(),
+                        buy_orders: BTreeMap::new(),
+                        sell_orders: BTreeMap::new(),
+                    },
                 )
                 .unwrap();
+            NEXT_ORDER_ID.save(deps.as_mut().storage, &0u64).unwrap();
+            NEXT_TRADE_ID.save(deps.as_mut().storage, &0u64).unwrap();
 
-            // Attempt to cancel the order with the wrong owner
-            let res = execute_cancel_order(
+            // Without the deposit the payment is rejected
+            let underpaid = message_info(
+                &Addr::unchecked("buyer"),
+                &[Coin {
+                    denom: "base_token".to_string(),
+                    amount: Uint128::from(1000u128),
+                }],
+            );
+            let res = execute_place_limit_order(
                 deps.as_mut(),
-                env,
-                wrong_info.clone(),
-                order.id,
+                env.clone(),
+                underpaid,
+                "pair_id".to_string(),
+                Uint128::from(100u128),
+                Price::new(Uint128::from(10u128), 0),
+                true,
+            );
+            assert!(res.is_err());
+
+            // Order cost plus deposit is accepted
+            let info = message_info(
+                &Addr::unchecked("buyer"),
+                &[Coin {
+                    denom: "base_token".to_string(),
+                    amount: Uint128::from(1050u128),
+                }],
+            );
+            execute_place_limit_order(
+                deps.as_mut(),
+                env.clone(),
+                info.clone(),
                 "pair_id".to_string(),
+                Uint128::from(100u128),
+                Price::new(Uint128::from(10u128), 0),
+                true,
+            )
+            .unwrap();
+
+            let order = USER_ORDERS
+                .load(&deps.storage, (info.sender.clone(), 0))
+                .unwrap();
+            assert_eq!(order.deposit, Uint128::from(50u128));
+            assert_eq!(
+                USER_ACTIVE_ORDERS
+                    .load(&deps.storage, info.sender.clone())
+                    .unwrap(),
+                1
             );
 
-            // Verify that the function returned an error
-            assert!(res.is_err());
-            assert_eq!(res.unwrap_err(), StdError::not_found("Order"));
+            // Cancelling frees the slot and refunds the deposit and the escrowed cost
+            let res =
+                execute_cancel_order(deps.as_mut(), env, info.clone(), 0, "pair_id".to_string())
+                    .unwrap();
+
+            assert_eq!(res.messages.len(), 2);
+            assert_eq!(
+                res.messages[0].msg,
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "buyer".to_string(),
+                    amount: vec![Coin {
+                        denom: "base_token".to_string(),
+                        amount: Uint128::from(50u128),
+                    }],
+                })
+            );
+            assert_eq!(
+                res.messages[1].msg,
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "buyer".to_string(),
+                    amount: vec![Coin {
+                        denom: "base_token".to_string(),
+                        amount: Uint128::from(1000u128),
+                    }],
+                })
+            );
+            assert_eq!(
+                USER_ACTIVE_ORDERS
+                    .load(&deps.storage, info.sender.clone())
+                    .unwrap(),
+                0
+            );
         }
 
         #[test]
@@ -3509,7 +7748,7 @@ pub mod execute {
             let to = Addr::unchecked("recipient");
             let amount = Uint128::from(100u128);
 
-            let msg = execute_native_transfer(denom, &to, amount).unwrap();
+            let msg = execute_transfer(&Denom::Native(denom.to_string()), &to, amount).unwrap();
 
             // Expected BankMsg
             let expected_msg = CosmosMsg::Bank(BankMsg::Send {
@@ -3526,17 +7765,16 @@ pub mod execute {
         #[test]
         fn test_execute_cw20_transfer() {
             let token_address = "token_contract";
-            let from = Addr::unchecked("sender");
             let to = Addr::unchecked("recipient");
             let amount = Uint128::from(100u128);
 
-            let msg = execute_cw20_transfer(token_address, &from, &to, amount).unwrap();
+            let token = Denom::Cw20(Addr::unchecked(token_address));
+            let msg = execute_transfer(&token, &to, amount).unwrap();
 
-            // Expected WasmMsg
+            // Payouts come out of the contract's own balance
             let expected_msg = CosmosMsg::Wasm(WasmMsg::Execute {
                 contract_addr: token_address.to_string(),
-                msg: to_json_binary(&Cw20ExecuteMsg::TransferFrom {
-                    owner: from.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
                     recipient: to.to_string(),
                     amount,
                 })
@@ -3575,16 +7813,25 @@ pub mod execute {
                 lp_supply: 20_000_000_000u128,
                 maker_fee: Decimal::percent(1),
                 taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
                 secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
-                base_token_denom: "ubase_token".to_string(),
+                base_token_denom: Denom::Native("ubase_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
             };
 
             let token_pair = TokenPair {
                 enabled: true,
-                base_token: "ubase_token".to_string(),
-                quote_token: "quote_token".to_string(),
+                base_token: Denom::Native("ubase_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
                 base_decimals: 6,
                 quote_decimals: 8,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
             };
 
             let pool = Pool {
@@ -3597,7 +7844,10 @@ pub mod execute {
                 total_volume: Uint128::from(100_000u128),
                 total_trades: Uint128::from(100u128),
                 total_fees_collected: Uint128::from(10u128),
-                last_price: Uint128::from(5u128),
+                last_price: Price::new(Uint128::from(5u128), 0),
+                launch: LaunchSettings::default(),
+                launch_height: 0,
+                base_currency: "huahua".to_string(),
             };
 
             let token_info = TokenInfo {
@@ -3605,7 +7855,11 @@ pub mod execute {
                 symbol: "TST".to_owned(),
                 decimals: 9,
                 total_supply: 100_000_000_000u128.into(),
-                initial_price: BASE_PRICE.into(),
+                bonding_curve_supply: Uint128::from(80_000_000_000u128),
+                lp_supply: Uint128::from(20_000_000_000u128),
+                graduation_reserve: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+                initial_price: Price::new(Uint128::from(BASE_PRICE), 9),
                 max_price_impact: Uint128::from(30u128),
                 graduated: false,
             };
@@ -3625,7 +7879,7 @@ pub mod execute {
                 )
                 .unwrap();
 
-            let base_price = Decimal::from_ratio(BASE_PRICE, Uint128::new(1_000_000));
+            let base_price = token_info.initial_price.to_decimal().unwrap();
             let slope = Decimal::from_ratio(pool.curve_slope, Uint128::new(1_000_000));
 
             let current_supply = Uint128::new(100_000);
@@ -3643,11 +7897,7 @@ pub mod execute {
             let avg_price = base_price * (exp_upper - exp_lower)
                 / (slope * Decimal::from_ratio(amount, Uint128::new(1)));
 
-            let expected_price = Uint128::new(
-                (avg_price * Decimal::from_ratio(10_u128.pow(token_info.decimals as u32), 1u128))
-                    .to_uint_ceil()
-                    .into(),
-            );
+            let expected_price = Price::from_decimal(avg_price, token_info.decimals).unwrap();
 
             let price = calculate_exponential_price(
                 deps.as_mut().storage,
@@ -3675,16 +7925,25 @@ pub mod execute {
                 lp_supply: 20_000_000_000u128,
                 maker_fee: Decimal::percent(1),
                 taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
                 secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
-                base_token_denom: "ubase_token".to_string(),
+                base_token_denom: Denom::Native("ubase_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
             };
 
             let token_pair = TokenPair {
                 enabled: true,
-                base_token: "ubase_token".to_string(),
-                quote_token: "quote_token".to_string(),
+                base_token: Denom::Native("ubase_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
                 base_decimals: 6,
                 quote_decimals: 8,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
             };
 
             let pool = Pool {
@@ -3697,7 +7956,10 @@ pub mod execute {
                 total_volume: Uint128::from(100_000u128),
                 total_trades: Uint128::from(100u128),
                 total_fees_collected: Uint128::from(10u128),
-                last_price: Uint128::from(5u128),
+                last_price: Price::new(Uint128::from(5u128), 0),
+                launch: LaunchSettings::default(),
+                launch_height: 0,
+                base_currency: "huahua".to_string(),
             };
 
             let token_info = TokenInfo {
@@ -3705,7 +7967,11 @@ pub mod execute {
                 symbol: "TST".to_owned(),
                 decimals: 9,
                 total_supply: 100_000_000_000u128.into(),
-                initial_price: BASE_PRICE.into(),
+                bonding_curve_supply: Uint128::from(80_000_000_000u128),
+                lp_supply: Uint128::from(20_000_000_000u128),
+                graduation_reserve: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+                initial_price: Price::new(Uint128::from(BASE_PRICE), 9),
                 max_price_impact: Uint128::from(30u128),
                 graduated: false,
             };
@@ -3725,7 +7991,7 @@ pub mod execute {
                 )
                 .unwrap();
 
-            let base_price = Decimal::from_ratio(BASE_PRICE, Uint128::new(1_000_000));
+            let base_price = token_info.initial_price.to_decimal().unwrap();
             let slope = Decimal::from_ratio(pool.curve_slope, Uint128::new(1_000_000));
 
             let current_supply = Uint128::new(100_000);
@@ -3743,11 +8009,7 @@ pub mod execute {
             let avg_price = base_price * (exp_upper - exp_lower)
                 / (slope * Decimal::from_ratio(amount, Uint128::new(1)));
 
-            let expected_price = Uint128::new(
-                (avg_price * Decimal::from_ratio(10_u128.pow(token_info.decimals as u32), 1u128))
-                    .to_uint_ceil()
-                    .into(),
-            );
+            let expected_price = Price::from_decimal(avg_price, token_info.decimals).unwrap();
 
             let price = calculate_exponential_price(
                 deps.as_mut().storage,
@@ -3775,16 +8037,25 @@ pub mod execute {
                 lp_supply: 20_000_000_000u128,
                 maker_fee: Decimal::percent(1),
                 taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
                 secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
-                base_token_denom: "ubase_token".to_string(),
+                base_token_denom: Denom::Native("ubase_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
             };
 
             let token_pair = TokenPair {
                 enabled: true,
-                base_token: "ubase_token".to_string(),
-                quote_token: "quote_token".to_string(),
+                base_token: Denom::Native("ubase_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
                 base_decimals: 6,
                 quote_decimals: 8,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
             };
 
             let pool = Pool {
@@ -3797,7 +8068,10 @@ pub mod execute {
                 total_volume: Uint128::from(100_000u128),
                 total_trades: Uint128::from(100u128),
                 total_fees_collected: Uint128::from(10u128),
-                last_price: Uint128::from(5u128),
+                last_price: Price::new(Uint128::from(5u128), 0),
+                launch: LaunchSettings::default(),
+                launch_height: 0,
+                base_currency: "huahua".to_string(),
             };
 
             let token_info = TokenInfo {
@@ -3805,7 +8079,11 @@ pub mod execute {
                 symbol: "TST".to_owned(),
                 decimals: 9,
                 total_supply: 100_000_000_000u128.into(),
-                initial_price: BASE_PRICE.into(),
+                bonding_curve_supply: Uint128::from(80_000_000_000u128),
+                lp_supply: Uint128::from(20_000_000_000u128),
+                graduation_reserve: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+                initial_price: Price::new(Uint128::from(BASE_PRICE), 9),
                 max_price_impact: Uint128::from(30u128),
                 graduated: false,
             };
@@ -3864,16 +8142,25 @@ pub mod execute {
                 lp_supply: 20_000_000_000u128,
                 maker_fee: Decimal::percent(1),
                 taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
                 secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
-                base_token_denom: "ubase_token".to_string(),
+                base_token_denom: Denom::Native("ubase_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
             };
 
             let token_pair = TokenPair {
                 enabled: true,
-                base_token: "ubase_token".to_string(),
-                quote_token: "quote_token".to_string(),
+                base_token: Denom::Native("ubase_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
                 base_decimals: 6,
                 quote_decimals: 8,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
             };
 
             let pool = Pool {
@@ -3886,7 +8173,10 @@ pub mod execute {
                 total_volume: Uint128::from(100_000u128),
                 total_trades: Uint128::from(100u128),
                 total_fees_collected: Uint128::from(10u128),
-                last_price: Uint128::from(5u128),
+                last_price: Price::new(Uint128::from(5u128), 0),
+                launch: LaunchSettings::default(),
+                launch_height: 0,
+                base_currency: "huahua".to_string(),
             };
 
             let token_info = TokenInfo {
@@ -3894,7 +8184,11 @@ pub mod execute {
                 symbol: "TST".to_owned(),
                 decimals: 9,
                 total_supply: 100_000_000_000u128.into(),
-                initial_price: BASE_PRICE.into(),
+                bonding_curve_supply: Uint128::from(80_000_000_000u128),
+                lp_supply: Uint128::from(20_000_000_000u128),
+                graduation_reserve: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+                initial_price: Price::new(Uint128::from(BASE_PRICE), 9),
                 max_price_impact: Uint128::from(30u128),
                 graduated: false,
             };
@@ -3921,7 +8215,7 @@ pub mod execute {
                 "pair_id".to_string(),
                 "token_address".to_string(),
                 Uint128::new(1000),
-                Uint128::new(1_000_000_000), // High min_return to trigger slippage error
+                Uint128::new(100_000_000_000), // High min_return to trigger slippage error
                 OrderType::Buy,
             );
 
@@ -3929,7 +8223,7 @@ pub mod execute {
                 Err(err) => assert_eq!(
                     err,
                     StdError::generic_err(
-                        "Slippage tolerance exceeded. Expected: 99, Minimum: 1000000000"
+                        "Slippage tolerance exceeded. Expected: 10000000000, Minimum: 100000000000"
                     )
                 ),
                 _ => panic!("Expected error"),
@@ -3958,16 +8252,25 @@ pub mod execute {
                 lp_supply: 20_000_000_000u128,
                 maker_fee: Decimal::percent(1),
                 taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
                 secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
-                base_token_denom: "ubase_token".to_string(),
+                base_token_denom: Denom::Native("ubase_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
             };
 
             let token_pair = TokenPair {
                 enabled: true,
-                base_token: "ubase_token".to_string(),
-                quote_token: "quote_token".to_string(),
+                base_token: Denom::Native("ubase_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
                 base_decimals: 6,
                 quote_decimals: 8,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
             };
 
             let pool = Pool {
@@ -3980,7 +8283,10 @@ pub mod execute {
                 total_volume: Uint128::from(1000u128),
                 total_trades: Uint128::from(100u128),
                 total_fees_collected: Uint128::from(1u128),
-                last_price: Uint128::from(1u128),
+                last_price: Price::new(Uint128::from(1u128), 0),
+                launch: LaunchSettings::default(),
+                launch_height: 0,
+                base_currency: "huahua".to_string(),
             };
 
             let token_info = TokenInfo {
@@ -3988,7 +8294,11 @@ pub mod execute {
                 symbol: "TST".to_owned(),
                 decimals: 9,
                 total_supply: 100_000_000_000u128.into(),
-                initial_price: BASE_PRICE.into(),
+                bonding_curve_supply: Uint128::from(80_000_000_000u128),
+                lp_supply: Uint128::from(20_000_000_000u128),
+                graduation_reserve: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+                initial_price: Price::new(Uint128::from(BASE_PRICE), 9),
                 max_price_impact: Uint128::from(30u128),
                 graduated: false,
             };
@@ -4052,16 +8362,25 @@ pub mod execute {
                 lp_supply: 20_000_000_000u128,
                 maker_fee: Decimal::percent(1),
                 taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
                 secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
-                base_token_denom: "ubase_token".to_string(),
+                base_token_denom: Denom::Native("ubase_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
             };
 
             let token_pair = TokenPair {
                 enabled: true,
-                base_token: "ubase_token".to_string(),
-                quote_token: "quote_token".to_string(),
+                base_token: Denom::Native("ubase_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
                 base_decimals: 6,
                 quote_decimals: 8,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
             };
 
             let pool = Pool {
@@ -4074,7 +8393,10 @@ pub mod execute {
                 total_volume: Uint128::from(1u128),
                 total_trades: Uint128::from(1u128),
                 total_fees_collected: Uint128::from(1u128),
-                last_price: Uint128::from(1u128),
+                last_price: Price::new(Uint128::from(1u128), 0),
+                launch: LaunchSettings::default(),
+                launch_height: 0,
+                base_currency: "huahua".to_string(),
             };
 
             let token_info = TokenInfo {
@@ -4082,7 +8404,11 @@ pub mod execute {
                 symbol: "TST".to_owned(),
                 decimals: 9,
                 total_supply: 100_000_000_000u128.into(),
-                initial_price: BASE_PRICE.into(),
+                bonding_curve_supply: Uint128::from(80_000_000_000u128),
+                lp_supply: Uint128::from(20_000_000_000u128),
+                graduation_reserve: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+                initial_price: Price::new(Uint128::from(BASE_PRICE), 9),
                 max_price_impact: Uint128::from(30u128),
                 graduated: false,
             };
@@ -4104,16 +8430,23 @@ pub mod execute {
 
             // Mock the calculate_exponential_price function to return a known price
             let amount = Uint128::new(1000);
-            let price = calculate_exponential_price(
-                &deps.storage,
-                "token_address".to_owned(),
-                10_000_000_000u128.into(),
-                amount,
-                true,
-            )
-            .unwrap();
+            let price_over = |supply: Uint128| {
+                calculate_exponential_price(
+                    &deps.storage,
+                    "token_address".to_owned(),
+                    10_000_000_000u128.into(),
+                    supply,
+                    true,
+                )
+                .unwrap()
+            };
+            // The spend is converted to quote atomics before the curve prices it
+            let estimate = price_over(Uint128::one())
+                .checked_base_to_quote(amount)
+                .unwrap();
+            let price = price_over(estimate);
 
-            let tokens_to_receive = amount * price / Uint128::new(1_000_000);
+            let tokens_to_receive = price.checked_base_to_quote(amount).unwrap();
 
             // Execute the bonding curve swap function
             let response = execute_bonding_curve_swap(
@@ -4129,7 +8462,7 @@ pub mod execute {
             .unwrap();
 
             // Verify the response attributes and messages
-            assert_eq!(response.attributes.len(), 6);
+            assert_eq!(response.attributes.len(), 7);
             assert_eq!(response.attributes[0], attr("action", "bonding_curve_swap"));
             assert_eq!(response.attributes[1], attr("pair_id", "pair_id"));
             assert_eq!(response.attributes[2], attr("order_type", "Buy"));
@@ -4139,148 +8472,125 @@ pub mod execute {
                 attr("quote_amount", tokens_to_receive)
             );
             assert_eq!(response.attributes[5], attr("price", price.to_string()));
+            assert_eq!(response.attributes[6], attr("curve_fee", "0"));
 
             // Verify messages include transfer of tokens
             assert_eq!(response.messages.len(), 1); // 1 transfer message for Buy
         }
 
         #[test]
-        fn test_match_limit_orders_fully_matched() {
+        fn test_bonding_curve_swap_buy_across_decimals() {
             let mut deps = mock_dependencies();
             let env = mock_env();
-            let info = message_info(&Addr::unchecked("buyer"), &[]);
-
-            // Initialize the token pair and config
-            let token_pair = TokenPair {
-                base_token: "ubase_token".to_string(),
-                quote_token: "quote_token".to_string(),
-                base_decimals: 6,
-                quote_decimals: 8,
-                enabled: true,
-            };
+            // One whole token of an 18 decimals currency
+            let amount = Uint128::new(1_000_000_000_000_000_000);
+            let info = message_info(
+                &Addr::unchecked("buyer"),
+                &[Coin {
+                    denom: "uwide".to_string(),
+                    amount,
+                }],
+            );
 
             let config = Config {
                 owner: Addr::unchecked("creator"),
                 token_factory: Addr::unchecked("token_factory_addr"),
                 fee_collector: Addr::unchecked("fee_collector_addr"),
                 enabled: true,
-                quote_token_total_supply: 100_000_000_000u128,
-                bonding_curve_supply: 80_000_000_000u128,
-                lp_supply: 20_000_000_000u128,
+                quote_token_total_supply: 1_000_000_000u128,
+                bonding_curve_supply: 800_000_000u128,
+                lp_supply: 200_000_000u128,
                 maker_fee: Decimal::percent(1),
                 taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
                 secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
-                base_token_denom: "ubase_token".to_string(),
+                base_token_denom: Denom::Native("ubase_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
             };
-
-            CONFIG.save(deps.as_mut().storage, &config).unwrap();
-            TOKEN_PAIRS
-                .save(deps.as_mut().storage, "pair_id".to_string(), &token_pair)
-                .unwrap();
-
-            // Initialize the order book
-            let mut order_book = OrderBook {
-                pair_id: "pair_id".to_string(),
-                buy_orders: BTreeMap::new(),
-                sell_orders: BTreeMap::new(),
+            let token_pair = TokenPair {
+                enabled: true,
+                base_token: Denom::Native("uwide".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
+                base_decimals: 18,
+                quote_decimals: 6,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
             };
-
-            let sell_order = Order {
-                id: 1,
-                owner: Addr::unchecked("seller"),
-                pair_id: "pair_id".to_string(),
-                token_amount: Uint128::new(1000),
-                price: Uint128::new(1),
-                timestamp: env.block.time.seconds() as u64,
-                status: OrderStatus::Active,
-                filled_amount: Uint128::zero(),
-                remaining_amount: Uint128::new(1000),
-                order_type: OrderType::Sell,
-                created_at: env.block.height,
+            let pool = Pool {
+                enabled: true,
+                token_sold: Uint128::zero(),
+                pair_id: "TST/wide".to_string(),
+                curve_slope: Uint128::from(1u128),
+                token_address: Addr::unchecked("token_address"),
+                total_reserve_token: Uint128::zero(),
+                total_volume: Uint128::zero(),
+                total_trades: Uint128::zero(),
+                total_fees_collected: Uint128::zero(),
+                last_price: Price::zero(6),
+                launch: LaunchSettings::default(),
+                launch_height: 0,
+                base_currency: "wide".to_string(),
+            };
+            // 0.001 of the currency per whole token, the curve holds 800M tokens
+            let token_info = TokenInfo {
+                name: "Test Token".to_owned(),
+                symbol: "TST".to_owned(),
+                decimals: 6,
+                total_supply: Uint128::new(1_000_000_000_000_000),
+                bonding_curve_supply: Uint128::new(800_000_000_000_000),
+                lp_supply: Uint128::new(200_000_000_000_000),
+                graduation_reserve: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+                initial_price: Price::new(Uint128::new(1_000_000_000_000_000), 6),
+                max_price_impact: Uint128::from(30u128),
+                graduated: false,
             };
 
-            order_book.sell_orders.insert(1, vec![sell_order.clone()]);
-            ORDER_BOOKS
-                .save(deps.as_mut().storage, "pair_id".to_string(), &order_book)
-                .unwrap();
-
-            // Initialize NEXT_TRADE_ID
-            NEXT_TRADE_ID.save(deps.as_mut().storage, &1).unwrap();
-
-            // Match limit orders
-            let (matched_amount, remaining_amount, response) = match_limit_orders(
-                deps.as_mut().storage,
-                &info,
-                &env,
-                "pair_id".to_string(),
-                Uint128::new(1000), // Buy order amount
-                &OrderType::Buy,
-                Uint128::zero(),
-            )
-            .unwrap();
-
-            // Calculate expected fees
-            let total_price = Uint128::new(1000) * Uint128::new(1); // amount * price
-            let expected_maker_fee = (Decimal::new(total_price) * config.maker_fee)
-                .checked_div(Decimal::percent(100))
+            CONFIG.save(deps.as_mut().storage, &config).unwrap();
+            TOKEN_PAIRS
+                .save(deps.as_mut().storage, "TST/wide".to_string(), &token_pair)
                 .unwrap();
-            let expected_taker_fee = (Decimal::new(total_price) * config.taker_fee)
-                .checked_div(Decimal::percent(100))
+            POOLS
+                .save(deps.as_mut().storage, "token_address".to_string(), &pool)
+                .unwrap();
+            TOKEN_INFO
+                .save(
+                    deps.as_mut().storage,
+                    "token_address".to_string(),
+                    &token_info,
+                )
                 .unwrap();
 
-            // Verify matched and remaining amounts
-            assert_eq!(matched_amount, Uint128::new(1000));
-            assert_eq!(remaining_amount, Uint128::zero());
-
-            // Verify response attributes
-            assert_eq!(response.attributes.len(), 15);
-            assert_eq!(response.attributes[0], attr("event_type", "trade"));
-            assert_eq!(response.attributes[1], attr("trade_id", "1"));
-            assert_eq!(response.attributes[2], attr("pair_id", "pair_id"));
-            assert_eq!(response.attributes[3], attr("buy_order_id", "market_order"));
-            assert_eq!(response.attributes[4], attr("sell_order_id", "1"));
-            assert_eq!(response.attributes[5], attr("price", "1"));
-            assert_eq!(response.attributes[6], attr("amount", "1000"));
-            assert_eq!(response.attributes[7], attr("total", "1000"));
-            assert_eq!(
-                response.attributes[8],
-                attr("maker_fee", expected_maker_fee.to_string())
-            );
-            assert_eq!(
-                response.attributes[9],
-                attr("taker_fee", expected_taker_fee.to_string())
-            );
-            assert_eq!(response.attributes[10], attr("base_token", "ubase_token"));
-            assert_eq!(response.attributes[11], attr("quote_token", "quote_token"));
-            assert_eq!(
-                response.attributes[12],
-                attr("timestamp", env.block.time.seconds().to_string())
-            );
-            assert_eq!(response.attributes[13], attr("matched_amount", "1000"));
-            assert_eq!(response.attributes[14], attr("remaining_amount", "0"));
-
-            // Verify that the sell order is removed from the order book
-            let order_book: OrderBook = ORDER_BOOKS
-                .load(deps.as_mut().storage, "pair_id".to_string())
+            // The spend is priced over the ~1000 tokens it buys, not over 10^18 of supply
+            execute_bonding_curve_swap(
+                deps.as_mut(),
+                env,
+                info,
+                "TST/wide".to_string(),
+                "token_address".to_string(),
+                amount,
+                Uint128::new(1),
+                OrderType::Buy,
+            )
+            .unwrap();
+            let pool = POOLS
+                .load(&deps.storage, "token_address".to_string())
                 .unwrap();
-            assert!(order_book.sell_orders.get(&1).is_none());
+            assert!(pool.token_sold > Uint128::new(990_000_000));
+            assert!(pool.token_sold < Uint128::new(1_010_000_000));
+            assert_eq!(pool.total_reserve_token, amount);
+            assert_eq!(pool.last_price.precision, 6);
         }
 
         #[test]
-        fn test_match_limit_orders_partially_matched() {
+        fn test_buyback_and_burn_from_curve_fees() {
             let mut deps = mock_dependencies();
             let env = mock_env();
-            let info = message_info(&Addr::unchecked("buyer"), &[]);
-
-            // Initialize the token pair and config
-            let token_pair = TokenPair {
-                base_token: "ubase_token".to_string(),
-                quote_token: "quote_token".to_string(),
-                base_decimals: 6,
-                quote_decimals: 8,
-                enabled: true,
-            };
-
             let config = Config {
                 owner: Addr::unchecked("creator"),
                 token_factory: Addr::unchecked("token_factory_addr"),
@@ -4291,121 +8601,259 @@ pub mod execute {
                 lp_supply: 20_000_000_000u128,
                 maker_fee: Decimal::percent(1),
                 taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::percent(1),
                 secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
-                base_token_denom: "ubase_token".to_string(),
+                base_token_denom: Denom::Native("ubase_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+            };
+            let token_pair = TokenPair {
+                enabled: true,
+                base_token: Denom::Native("ubase_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("token_address")),
+                base_decimals: 6,
+                quote_decimals: 9,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
+            };
+            let pool = Pool {
+                enabled: true,
+                token_sold: Uint128::new(10_000_000_000u128),
+                pair_id: "pair_id".to_string(),
+                curve_slope: Uint128::from(1u128),
+                token_address: Addr::unchecked("token_address"),
+                total_reserve_token: Uint128::zero(),
+                total_volume: Uint128::zero(),
+                total_trades: Uint128::zero(),
+                total_fees_collected: Uint128::zero(),
+                last_price: Price::new(Uint128::from(1u128), 9),
+                launch: LaunchSettings::default(),
+                launch_height: 0,
+                base_currency: "huahua".to_string(),
+            };
+            let token_info = TokenInfo {
+                name: "Test Token".to_owned(),
+                symbol: "TST".to_owned(),
+                decimals: 9,
+                total_supply: 100_000_000_000u128.into(),
+                bonding_curve_supply: Uint128::from(80_000_000_000u128),
+                lp_supply: Uint128::from(20_000_000_000u128),
+                graduation_reserve: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+                initial_price: Price::new(Uint128::from(BASE_PRICE), 9),
+                max_price_impact: Uint128::from(30u128),
+                graduated: false,
             };
-
             CONFIG.save(deps.as_mut().storage, &config).unwrap();
             TOKEN_PAIRS
                 .save(deps.as_mut().storage, "pair_id".to_string(), &token_pair)
                 .unwrap();
+            POOLS
+                .save(deps.as_mut().storage, "token_address".to_string(), &pool)
+                .unwrap();
+            TOKEN_INFO
+                .save(
+                    deps.as_mut().storage,
+                    "token_address".to_string(),
+                    &token_info,
+                )
+                .unwrap();
 
-            // Initialize the order book
-            let mut order_book = OrderBook {
-                pair_id: "pair_id".to_string(),
-                buy_orders: BTreeMap::new(),
-                sell_orders: BTreeMap::new(),
+            // Only the fee manager or owner sets a policy, with a share of at most 1
+            let set_policy = |deps: DepsMut, sender: &str, share: Decimal| {
+                execute_set_buyback_policy(
+                    deps,
+                    message_info(&Addr::unchecked(sender), &[]),
+                    "token_address".to_string(),
+                    share,
+                )
             };
-
-            let sell_order = Order {
-                id: 1,
-                owner: Addr::unchecked("seller"),
-                pair_id: "pair_id".to_string(),
-                token_amount: Uint128::new(1000),
-                price: Uint128::new(1),
-                timestamp: env.block.time.seconds() as u64,
-                status: OrderStatus::Active,
-                filled_amount: Uint128::zero(),
-                remaining_amount: Uint128::new(1000),
-                order_type: OrderType::Sell,
-                created_at: env.block.height,
+            let res = set_policy(deps.as_mut(), "random", Decimal::percent(50));
+            assert_eq!(res.unwrap_err(), StdError::generic_err("Unauthorized"));
+            let res = set_policy(deps.as_mut(), "creator", Decimal::percent(150));
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Buyback share must be between 0 and 1.")
+            );
+            set_policy(deps.as_mut(), "creator", Decimal::percent(50)).unwrap();
+
+            // A curve buy pays 1% of what it spends, half of it is set aside
+            let buy = |deps: DepsMut| {
+                execute_bonding_curve_swap(
+                    deps,
+                    env.clone(),
+                    message_info(&Addr::unchecked("buyer"), &coins(1_000, "ubase_token")),
+                    "pair_id".to_string(),
+                    "token_address".to_string(),
+                    Uint128::new(1_000),
+                    Uint128::zero(),
+                    OrderType::Buy,
+                )
             };
-
-            order_book.sell_orders.insert(1, vec![sell_order.clone()]);
-            ORDER_BOOKS
-                .save(deps.as_mut().storage, "pair_id".to_string(), &order_book)
+            let res = buy(deps.as_mut()).unwrap();
+            assert_eq!(res.attributes[6], attr("curve_fee", "10"));
+            let pool = POOLS
+                .load(&deps.storage, "token_address".to_string())
+                .unwrap();
+            assert_eq!(pool.total_reserve_token, Uint128::new(990));
+            assert_eq!(pool.total_fees_collected, Uint128::new(10));
+            let treasury = TREASURY
+                .load(&deps.storage, "ubase_token".to_string())
+                .unwrap();
+            assert_eq!(treasury.accumulated, Uint128::new(5));
+            let buyback = BUYBACKS
+                .load(&deps.storage, "token_address".to_string())
+                .unwrap();
+            assert_eq!(buyback.pending, Uint128::new(5));
+
+            // Launch rules only bind traders, the protocol's buyback is not on the
+            // allow list and still goes through
+            let mut launched = pool.clone();
+            launched.launch = LaunchSettings {
+                allow_list: vec![Addr::unchecked("buyer")],
+                allow_list_end: Some(Expiration::Never {}),
+                ..LaunchSettings::default()
+            };
+            POOLS
+                .save(
+                    deps.as_mut().storage,
+                    "token_address".to_string(),
+                    &launched,
+                )
                 .unwrap();
 
-            // Initialize NEXT_TRADE_ID
-            NEXT_TRADE_ID.save(deps.as_mut().storage, &1).unwrap();
-
-            // Match limit orders
-            let (matched_amount, remaining_amount, response) = match_limit_orders(
-                deps.as_mut().storage,
-                &info,
-                &env,
-                "pair_id".to_string(),
-                Uint128::new(500), // Buy order amount
-                &OrderType::Buy,
+            // The buyback runs fee free on the curve and burns what it bought
+            let res = execute_buyback(
+                deps.as_mut(),
+                env.clone(),
+                message_info(&Addr::unchecked("random"), &[]),
+                "token_address".to_string(),
+                Uint128::zero(),
+            );
+            assert_eq!(res.unwrap_err(), StdError::generic_err("Unauthorized"));
+            let res = execute_buyback(
+                deps.as_mut(),
+                env.clone(),
+                message_info(&Addr::unchecked("creator"), &[]),
+                "token_address".to_string(),
                 Uint128::zero(),
             )
             .unwrap();
-
-            // Calculate expected fees
-            let total_price = Uint128::new(500) * Uint128::new(1); // amount * price
-            let expected_maker_fee = (Decimal::new(total_price) * config.maker_fee)
-                .checked_div(Decimal::percent(100))
+            let after = POOLS
+                .load(&deps.storage, "token_address".to_string())
                 .unwrap();
-            let expected_taker_fee = (Decimal::new(total_price) * config.taker_fee)
-                .checked_div(Decimal::percent(100))
-                .unwrap();
-
-            // Verify matched and remaining amounts
-            assert_eq!(matched_amount, Uint128::new(500));
-            assert_eq!(remaining_amount, Uint128::zero());
-
-            // Verify response attributes
-            assert_eq!(response.attributes.len(), 15);
-            assert_eq!(response.attributes[0], attr("event_type", "trade"));
-            assert_eq!(response.attributes[1], attr("trade_id", "1"));
-            assert_eq!(response.attributes[2], attr("pair_id", "pair_id"));
-            assert_eq!(response.attributes[3], attr("buy_order_id", "market_order"));
-            assert_eq!(response.attributes[4], attr("sell_order_id", "1"));
-            assert_eq!(response.attributes[5], attr("price", "1"));
-            assert_eq!(response.attributes[6], attr("amount", "500"));
-            assert_eq!(response.attributes[7], attr("total", "500"));
+            let bought = after.token_sold - pool.token_sold;
+            assert!(!bought.is_zero());
+            assert_eq!(after.total_reserve_token, Uint128::new(995));
+            assert_eq!(after.total_fees_collected, Uint128::new(10));
+            assert_eq!(
+                res.messages[0].msg,
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: "token_address".to_string(),
+                    msg: to_json_binary(&Cw20ExecuteMsg::Burn { amount: bought }).unwrap(),
+                    funds: vec![],
+                })
+            );
+            let res = execute_buyback(
+                deps.as_mut(),
+                env.clone(),
+                message_info(&Addr::unchecked("creator"), &[]),
+                "token_address".to_string(),
+                Uint128::zero(),
+            );
             assert_eq!(
-                response.attributes[8],
-                attr("maker_fee", expected_maker_fee.to_string())
+                res.unwrap_err(),
+                StdError::generic_err("No fees set aside for a buyback")
             );
+
+            // Once graduated, the buyback swaps on the secondary AMM and the reply
+            // burns the tokens that came back
+            buy(deps.as_mut()).unwrap();
+            let mut token_info = token_info;
+            token_info.graduated = true;
+            TOKEN_INFO
+                .save(
+                    deps.as_mut().storage,
+                    "token_address".to_string(),
+                    &token_info,
+                )
+                .unwrap();
+            POOLS.remove(deps.as_mut().storage, "token_address".to_string());
+            // The contract holds 1_000 tokens ahead of the swap
+            deps.querier.update_wasm(|_| {
+                SystemResult::Ok(ContractResult::Ok(
+                    to_json_binary(&BalanceResponse {
+                        balance: Uint128::new(1_000),
+                    })
+                    .unwrap(),
+                ))
+            });
+            let res = execute_buyback(
+                deps.as_mut(),
+                env.clone(),
+                message_info(&Addr::unchecked("creator"), &[]),
+                "token_address".to_string(),
+                Uint128::new(300),
+            )
+            .unwrap();
             assert_eq!(
-                response.attributes[9],
-                attr("taker_fee", expected_taker_fee.to_string())
+                res.messages,
+                vec![SubMsg::reply_on_success(
+                    WasmMsg::Execute {
+                        contract_addr: "secondary_amm_addr".to_string(),
+                        msg: to_json_binary(&SecondaryAmmMsg::Swap {
+                            offer_denom: token_pair.base_token.clone(),
+                            offer_amount: Uint128::new(5),
+                            ask_denom: token_pair.quote_token.clone(),
+                            min_output: Uint128::new(300),
+                        })
+                        .unwrap(),
+                        funds: coins(5, "ubase_token"),
+                    },
+                    REPLY_BUYBACK_ID,
+                )]
             );
-            assert_eq!(response.attributes[10], attr("base_token", "ubase_token"));
-            assert_eq!(response.attributes[11], attr("quote_token", "quote_token"));
+
+            deps.querier.update_wasm(|_| {
+                SystemResult::Ok(ContractResult::Ok(
+                    to_json_binary(&BalanceResponse {
+                        balance: Uint128::new(1_400),
+                    })
+                    .unwrap(),
+                ))
+            });
+            let res = reply_buyback(deps.as_mut(), env).unwrap();
             assert_eq!(
-                response.attributes[12],
-                attr("timestamp", env.block.time.seconds().to_string())
+                res.messages[0].msg,
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: "token_address".to_string(),
+                    msg: to_json_binary(&Cw20ExecuteMsg::Burn {
+                        amount: Uint128::new(400)
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                })
             );
-            assert_eq!(response.attributes[13], attr("matched_amount", "500"));
-            assert_eq!(response.attributes[14], attr("remaining_amount", "0"));
 
-            // Verify that the sell order is partially filled and still in the order book
-            let order_book: OrderBook = ORDER_BOOKS
-                .load(deps.as_mut().storage, "pair_id".to_string())
+            // Holders can check the burned total next to the token
+            let buyback = query::query_token_info(deps.as_ref(), "token_address".to_string())
+                .unwrap()
+                .buyback
                 .unwrap();
-            let remaining_orders = order_book.sell_orders.get(&1).unwrap();
-            assert_eq!(remaining_orders.len(), 1);
-            assert_eq!(remaining_orders[0].remaining_amount, Uint128::new(500));
-            assert_eq!(remaining_orders[0].filled_amount, Uint128::new(500));
-            assert_eq!(remaining_orders[0].status, OrderStatus::Active);
+            assert_eq!(buyback.spent, Uint128::new(10));
+            assert_eq!(buyback.burned, bought + Uint128::new(400));
+            assert!(PENDING_BUYBACK.may_load(&deps.storage).unwrap().is_none());
         }
 
         #[test]
-        fn test_match_limit_orders_no_match() {
+        fn test_bonding_curve_swap_launch_protections() {
             let mut deps = mock_dependencies();
-            let env = mock_env();
-            let info = message_info(&Addr::unchecked("buyer"), &[]);
-
-            // Initialize the token pair and config
-            let token_pair = TokenPair {
-                base_token: "ubase_token".to_string(),
-                quote_token: "quote_token".to_string(),
-                base_decimals: 6,
-                quote_decimals: 8,
-                enabled: true,
-            };
+            let launch_env = mock_env();
+            let launch_height = launch_env.block.height;
 
             let config = Config {
                 owner: Addr::unchecked("creator"),
@@ -4417,89 +8865,152 @@ pub mod execute {
                 lp_supply: 20_000_000_000u128,
                 maker_fee: Decimal::percent(1),
                 taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
                 secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
-                base_token_denom: "ubase_token".to_string(),
+                base_token_denom: Denom::Native("ubase_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+            };
+
+            let token_pair = TokenPair {
+                enabled: true,
+                base_token: Denom::Native("ubase_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
+                base_decimals: 6,
+                quote_decimals: 8,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
+            };
+
+            let token_info = TokenInfo {
+                name: "Test Token".to_owned(),
+                symbol: "TST".to_owned(),
+                decimals: 9,
+                total_supply: 100_000_000_000u128.into(),
+                bonding_curve_supply: Uint128::from(80_000_000_000u128),
+                lp_supply: Uint128::from(20_000_000_000u128),
+                graduation_reserve: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+                initial_price: Price::new(Uint128::from(BASE_PRICE), 9),
+                max_price_impact: Uint128::from(30u128),
+                graduated: false,
             };
 
             CONFIG.save(deps.as_mut().storage, &config).unwrap();
             TOKEN_PAIRS
                 .save(deps.as_mut().storage, "pair_id".to_string(), &token_pair)
                 .unwrap();
+            TOKEN_INFO
+                .save(
+                    deps.as_mut().storage,
+                    "token_address".to_string(),
+                    &token_info,
+                )
+                .unwrap();
 
-            // Initialize the order book with non-matching orders
-            let mut order_book = OrderBook {
-                pair_id: "pair_id".to_string(),
-                buy_orders: BTreeMap::new(),
-                sell_orders: BTreeMap::new(),
-            };
-
-            let sell_order = Order {
-                id: 1,
-                owner: Addr::unchecked("seller"),
+            let mut pool = Pool {
+                enabled: true,
+                token_sold: Uint128::new(10_000_000_000u128),
                 pair_id: "pair_id".to_string(),
-                token_amount: Uint128::new(10),
-                price: Uint128::new(1000), // Higher price to ensure no match
-                timestamp: env.block.time.seconds() as u64,
-                status: OrderStatus::Active,
-                filled_amount: Uint128::zero(),
-                remaining_amount: Uint128::new(1000),
-                order_type: OrderType::Sell,
-                created_at: env.block.height,
+                curve_slope: Uint128::from(1u128),
+                token_address: Addr::unchecked("token_address"),
+                total_reserve_token: Uint128::from(1u128),
+                total_volume: Uint128::from(1u128),
+                total_trades: Uint128::from(1u128),
+                total_fees_collected: Uint128::from(1u128),
+                last_price: Price::new(Uint128::from(1u128), 0),
+                launch: LaunchSettings::default(),
+                launch_height,
+                base_currency: "huahua".to_string(),
             };
-
-            order_book.sell_orders.insert(2, vec![sell_order.clone()]);
-            ORDER_BOOKS
-                .save(deps.as_mut().storage, "pair_id".to_string(), &order_book)
+            POOLS
+                .save(deps.as_mut().storage, "token_address".to_string(), &pool)
                 .unwrap();
 
-            // Initialize NEXT_TRADE_ID
-            NEXT_TRADE_ID.save(deps.as_mut().storage, &1).unwrap();
-
-            // Match limit orders
-            let (matched_amount, remaining_amount, response) = match_limit_orders(
-                deps.as_mut().storage,
-                &info,
-                &env,
-                "pair_id".to_string(),
-                Uint128::new(1000), // Buy order amount
-                &OrderType::Buy,
-                Uint128::new(100_000_000_000u128),
+            // One buy of 1000 fits under the cap, a second one does not
+            let amount = Uint128::new(1000);
+            let first_buy = calculate_exponential_price(
+                &deps.storage,
+                "token_address".to_owned(),
+                10_000_000_000u128.into(),
+                amount,
+                true,
             )
+            .unwrap()
+            .checked_base_to_quote(amount)
             .unwrap();
+            pool.launch = LaunchSettings {
+                trading_start: Some(Expiration::AtHeight(launch_height + 10)),
+                allow_list: vec![Addr::unchecked("early")],
+                allow_list_end: Some(Expiration::AtHeight(launch_height + 20)),
+                max_buy_per_address: Some(first_buy + Uint128::one()),
+                max_buy_blocks: 30,
+            };
+            POOLS
+                .save(deps.as_mut().storage, "token_address".to_string(), &pool)
+                .unwrap();
 
-            // Verify matched and remaining amounts
-            assert_eq!(matched_amount, Uint128::zero());
-            assert_eq!(remaining_amount, Uint128::new(1000));
+            let buy = |deps: DepsMut, blocks: u64, buyer: &str| {
+                let mut env = launch_env.clone();
+                env.block.height += blocks;
+                execute_bonding_curve_swap(
+                    deps,
+                    env,
+                    message_info(&Addr::unchecked(buyer), &coins(1000, "ubase_token")),
+                    "pair_id".to_string(),
+                    "token_address".to_string(),
+                    amount,
+                    Uint128::one(),
+                    OrderType::Buy,
+                )
+            };
 
-            // Verify response attributes
-            assert_eq!(response.attributes.len(), 2);
-            assert_eq!(response.attributes[0], attr("matched_amount", "0"));
-            assert_eq!(response.attributes[1], attr("remaining_amount", "1000"));
+            assert_eq!(
+                buy(deps.as_mut(), 0, "early").unwrap_err(),
+                StdError::generic_err("Trading has not started yet")
+            );
 
-            // Verify that the non-matching order is still in the order book
-            let order_book: OrderBook = ORDER_BOOKS
-                .load(deps.as_mut().storage, "pair_id".to_string())
-                .unwrap();
-            let remaining_orders = order_book.sell_orders.get(&2).unwrap();
-            assert_eq!(remaining_orders.len(), 1);
-            assert_eq!(remaining_orders[0].remaining_amount, Uint128::new(1000));
-            assert_eq!(remaining_orders[0].filled_amount, Uint128::zero());
-            assert_eq!(remaining_orders[0].status, OrderStatus::Active);
+            // Allow-list phase
+            assert_eq!(
+                buy(deps.as_mut(), 10, "sniper").unwrap_err(),
+                StdError::generic_err("Address is not on the launch allow list")
+            );
+            buy(deps.as_mut(), 10, "early").unwrap();
+            let err = buy(deps.as_mut(), 11, "early").unwrap_err();
+            assert!(err
+                .to_string()
+                .contains("Launch buy limit exceeded. Limit: "));
+
+            // Open to everyone, still capped per address
+            buy(deps.as_mut(), 20, "sniper").unwrap();
+            assert!(buy(deps.as_mut(), 21, "sniper").is_err());
+
+            // Once the window closes the cap no longer applies
+            buy(deps.as_mut(), 30, "early").unwrap();
+            buy(deps.as_mut(), 30, "sniper").unwrap();
         }
 
         #[test]
         fn test_execute_swap_fully_matched() {
             let mut deps = mock_dependencies();
             let env = mock_env();
-            let info = message_info(&Addr::unchecked("buyer"), &[]);
+            let info = message_info(&Addr::unchecked("buyer"), &coins(1000, "ubase_token"));
 
             // Initialize the token pair and config
             let token_pair = TokenPair {
-                base_token: "ubase_token".to_string(),
-                quote_token: "quote_token".to_string(),
+                base_token: Denom::Native("ubase_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
                 base_decimals: 6,
                 quote_decimals: 8,
                 enabled: true,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
             };
 
             let config = Config {
@@ -4512,8 +9023,13 @@ pub mod execute {
                 lp_supply: 20_000_000_000u128,
                 maker_fee: Decimal::percent(1),
                 taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
                 secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
-                base_token_denom: "ubase_token".to_string(),
+                base_token_denom: Denom::Native("ubase_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
             };
 
             CONFIG.save(deps.as_mut().storage, &config).unwrap();
@@ -4533,13 +9049,14 @@ pub mod execute {
                 owner: Addr::unchecked("seller"),
                 pair_id: "pair_id".to_string(),
                 token_amount: Uint128::new(1000),
-                price: Uint128::new(1),
+                price: Price::new(Uint128::new(1), 0),
                 timestamp: env.block.time.seconds() as u64,
                 status: OrderStatus::Active,
                 filled_amount: Uint128::zero(),
                 remaining_amount: Uint128::new(1000),
                 order_type: OrderType::Sell,
                 created_at: env.block.height,
+                deposit: Uint128::zero(),
             };
 
             order_book.sell_orders.insert(1, vec![sell_order.clone()]);
@@ -4550,56 +9067,76 @@ pub mod execute {
             // Initialize NEXT_TRADE_ID
             NEXT_TRADE_ID.save(deps.as_mut().storage, &1).unwrap();
 
-            // Execute swap
+            // Execute swap, spending 1000 base tokens at a price of 1
             let response = execute_swap(
                 deps.as_mut(),
                 env.clone(),
                 info.clone(),
                 "pair_id".to_string(),
-                "token_address".to_string(),
+                "quote_token".to_string(),
                 Uint128::new(1000),
-                Uint128::new(1000), // min_return
+                Uint128::new(990), // min_return
                 OrderType::Buy,
             )
             .unwrap();
 
-            // Calculate expected fees
-            let total_price = Uint128::new(1000) * Uint128::new(1); // amount * price
-            let expected_maker_fee = (Decimal::new(total_price) * config.maker_fee)
-                .checked_div(Decimal::percent(100))
-                .unwrap();
-            let expected_taker_fee = (Decimal::new(total_price) * config.taker_fee)
-                .checked_div(Decimal::percent(100))
-                .unwrap();
-
-            // Verify response attributes
-            assert_eq!(response.attributes.len(), 17);
+            // The buyer takes and pays the 1% taker fee out of the quote tokens, the
+            // resting seller pays the 1% maker fee out of the base tokens
+            assert_eq!(response.attributes.len(), 20);
             assert_eq!(response.attributes[0], attr("event_type", "trade"));
             assert_eq!(response.attributes[1], attr("trade_id", "1"));
             assert_eq!(response.attributes[2], attr("pair_id", "pair_id"));
-            assert_eq!(response.attributes[3], attr("buy_order_id", "market_order"));
-            assert_eq!(response.attributes[4], attr("sell_order_id", "1"));
-            assert_eq!(response.attributes[5], attr("price", "1"));
-            assert_eq!(response.attributes[6], attr("amount", "1000"));
-            assert_eq!(response.attributes[7], attr("total", "1000"));
-            assert_eq!(
-                response.attributes[8],
-                attr("maker_fee", expected_maker_fee.to_string())
-            );
-            assert_eq!(
-                response.attributes[9],
-                attr("taker_fee", expected_taker_fee.to_string())
-            );
-            assert_eq!(response.attributes[10], attr("base_token", "ubase_token"));
-            assert_eq!(response.attributes[11], attr("quote_token", "quote_token"));
+            assert_eq!(response.attributes[3], attr("price", "1"));
+            assert_eq!(response.attributes[4], attr("amount", "1000"));
+            assert_eq!(response.attributes[5], attr("total", "1000"));
+            assert_eq!(response.attributes[6], attr("maker_side", "Sell"));
+            assert_eq!(response.attributes[7], attr("buyer_fee", "10"));
+            assert_eq!(response.attributes[8], attr("seller_fee", "10"));
+            assert_eq!(response.attributes[11], attr("hop_offer_amount", "1000"));
+            assert_eq!(response.attributes[12], attr("hop_book_amount", "1000"));
+            assert_eq!(response.attributes[13], attr("hop_curve_amount", "0"));
+            assert_eq!(response.attributes[14], attr("hop_return_amount", "990"));
+            assert_eq!(response.attributes[19], attr("action", "swap"));
+
+            let msgs: Vec<_> = response.messages.iter().map(|m| m.msg.clone()).collect();
             assert_eq!(
-                response.attributes[12],
-                attr("timestamp", env.block.time.seconds().to_string())
+                msgs,
+                vec![
+                    CosmosMsg::Bank(BankMsg::Send {
+                        to_address: "seller".to_string(),
+                        amount: coins(990, "ubase_token"),
+                    }),
+                    CosmosMsg::Wasm(WasmMsg::Execute {
+                        contract_addr: "quote_token".to_string(),
+                        msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                            recipient: "buyer".to_string(),
+                            amount: Uint128::new(990),
+                        })
+                        .unwrap(),
+                        funds: vec![],
+                    }),
+                ]
             );
-            assert_eq!(response.attributes[13], attr("matched_amount", "1000"));
-            assert_eq!(response.attributes[14], attr("remaining_amount", "0"));
-            assert_eq!(response.attributes[15], attr("matched_amount", "1000"));
-            assert_eq!(response.attributes[16], attr("remaining_amount", "0"));
+
+            // The trade records both fees against the right party, the treasury holds them
+            let trade = TRADES.load(deps.as_ref().storage, 1).unwrap();
+            assert_eq!(trade.buyer, Addr::unchecked("buyer"));
+            assert_eq!(trade.seller, Addr::unchecked("seller"));
+            assert_eq!(trade.maker_side, OrderType::Sell);
+            assert_eq!(trade.buyer_fee, Uint128::new(10));
+            assert_eq!(trade.seller_fee, Uint128::new(10));
+            for denom in ["quote_token", "ubase_token"] {
+                let balance = TREASURY
+                    .load(deps.as_ref().storage, denom.to_string())
+                    .unwrap();
+                assert_eq!(balance.accumulated, Uint128::new(10));
+            }
+
+            // The filled sell order leaves the book
+            let order_book = ORDER_BOOKS
+                .load(deps.as_ref().storage, "pair_id".to_string())
+                .unwrap();
+            assert!(order_book.sell_orders.is_empty());
         }
 
         #[test]
@@ -4610,17 +9147,21 @@ pub mod execute {
                 &Addr::unchecked("buyer"),
                 &[Coin {
                     denom: "ubase_token".to_string(),
-                    amount: Uint128::new(500),
+                    amount: Uint128::new(10),
                 }],
             );
 
             // Initialize the token pair and config
             let token_pair = TokenPair {
-                base_token: "ubase_token".to_string(),
-                quote_token: "quote_token".to_string(),
+                base_token: Denom::Native("ubase_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
                 base_decimals: 6,
                 quote_decimals: 8,
                 enabled: true,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
             };
 
             let config = Config {
@@ -4633,8 +9174,13 @@ pub mod execute {
                 lp_supply: 20_000_000_000u128,
                 maker_fee: Decimal::percent(1),
                 taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
                 secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
-                base_token_denom: "ubase_token".to_string(),
+                base_token_denom: Denom::Native("ubase_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
             };
 
             let token_address = Addr::unchecked("quote_token");
@@ -4647,8 +9193,11 @@ pub mod execute {
                 total_volume: Uint128::one(),
                 total_trades: Uint128::one(),
                 total_fees_collected: Uint128::zero(),
-                last_price: Uint128::zero(),
+                last_price: Price::new(Uint128::zero(), 0),
                 enabled: true,
+                launch: LaunchSettings::default(),
+                launch_height: 0,
+                base_currency: "huahua".to_string(),
             };
 
             let token_info = TokenInfo {
@@ -4656,7 +9205,11 @@ pub mod execute {
                 symbol: "TST".to_owned(),
                 decimals: 9,
                 total_supply: 100_000_000_000u128.into(),
-                initial_price: BASE_PRICE.into(),
+                bonding_curve_supply: Uint128::from(80_000_000_000u128),
+                lp_supply: Uint128::from(20_000_000_000u128),
+                graduation_reserve: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+                initial_price: Price::new(Uint128::from(BASE_PRICE), 9),
                 max_price_impact: Uint128::from(30u128),
                 graduated: false,
             };
@@ -4689,13 +9242,14 @@ pub mod execute {
                 owner: Addr::unchecked("seller"),
                 pair_id: "pair_id".to_string(),
                 token_amount: Uint128::new(500),
-                price: Uint128::new(1),
+                price: Price::new(Uint128::new(1), 0),
                 timestamp: env.block.time.seconds() as u64,
                 status: OrderStatus::Active,
                 filled_amount: Uint128::zero(),
                 remaining_amount: Uint128::new(500),
                 order_type: OrderType::Sell,
                 created_at: env.block.height,
+                deposit: Uint128::zero(),
             };
 
             order_book.sell_orders.insert(1, vec![sell_order.clone()]);
@@ -4720,15 +9274,14 @@ pub mod execute {
             .unwrap();
 
             // Verify response attributes
-            assert_eq!(response.attributes.len(), 17);
+            assert_eq!(response.attributes.len(), 20);
             assert_eq!(response.attributes[0], attr("event_type", "trade"));
             assert_eq!(response.attributes[1], attr("trade_id", "1"));
             assert_eq!(response.attributes[2], attr("pair_id", "pair_id"));
-            assert_eq!(response.attributes[3], attr("buy_order_id", "market_order"));
-            assert_eq!(response.attributes[4], attr("sell_order_id", "1"));
-            assert_eq!(response.attributes[5], attr("price", "1"));
-            assert_eq!(response.attributes[6], attr("amount", "10"));
-            assert_eq!(response.attributes[7], attr("total", "10"));
+            assert_eq!(response.attributes[3], attr("price", "1"));
+            assert_eq!(response.attributes[4], attr("amount", "10"));
+            assert_eq!(response.attributes[5], attr("total", "10"));
+            assert_eq!(response.attributes[14], attr("hop_return_amount", "9"));
 
             // Verify order book updates
             let updated_order_book: OrderBook = ORDER_BOOKS
@@ -4754,11 +9307,15 @@ pub mod execute {
 
             // Initialize the token pair and config
             let token_pair = TokenPair {
-                base_token: "ubase_token".to_string(),
-                quote_token: "quote_token".to_string(),
+                base_token: Denom::Native("ubase_token".to_string()),
+                quote_token: Denom::Cw20(Addr::unchecked("quote_token")),
                 base_decimals: 6,
                 quote_decimals: 8,
                 enabled: true,
+                tick_size: Uint128::one(),
+                lot_size: Uint128::one(),
+                min_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
             };
 
             let config = Config {
@@ -4771,8 +9328,13 @@ pub mod execute {
                 lp_supply: 20_000_000_000u128,
                 maker_fee: Decimal::percent(1),
                 taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
                 secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
-                base_token_denom: "ubase_token".to_string(),
+                base_token_denom: Denom::Native("ubase_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
             };
 
             CONFIG.save(deps.as_mut().storage, &config).unwrap();
@@ -4804,8 +9366,11 @@ pub mod execute {
                 total_volume: Uint128::one(),
                 total_trades: Uint128::one(),
                 total_fees_collected: Uint128::zero(),
-                last_price: Uint128::zero(),
+                last_price: Price::new(Uint128::zero(), 0),
                 enabled: true,
+                launch: LaunchSettings::default(),
+                launch_height: 0,
+                base_currency: "huahua".to_string(),
             };
             POOLS
                 .save(
@@ -4820,7 +9385,11 @@ pub mod execute {
                 symbol: "TST".to_owned(),
                 decimals: 9,
                 total_supply: 100_000_000_000u128.into(),
-                initial_price: BASE_PRICE.into(),
+                bonding_curve_supply: Uint128::from(80_000_000_000u128),
+                lp_supply: Uint128::from(20_000_000_000u128),
+                graduation_reserve: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+                initial_price: Price::new(Uint128::from(BASE_PRICE), 9),
                 max_price_impact: Uint128::from(30u128),
                 graduated: false,
             };
@@ -4847,9 +9416,9 @@ pub mod execute {
             .unwrap();
 
             // Verify response attributes
-            assert_eq!(response.attributes.len(), 10);
-            assert_eq!(response.attributes[0], attr("matched_amount", "0"));
-            assert_eq!(response.attributes[1], attr("remaining_amount", "1000"));
+            assert_eq!(response.attributes.len(), 11);
+            assert_eq!(response.attributes[3], attr("hop_book_amount", "0"));
+            assert_eq!(response.attributes[4], attr("hop_curve_amount", "1000"));
 
             // Verify order book updates
             let updated_order_book: OrderBook = ORDER_BOOKS
@@ -4877,11 +9446,47 @@ pub mod execute {
                 lp_supply: Uint128::new(20_000_000_000).into(),
                 maker_fee: Decimal::percent(1),
                 taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
                 secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
-                base_token_denom: "ubase_token".to_string(),
+                base_token_denom: Denom::Native("ubase_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
             };
 
             CONFIG.save(deps.as_mut().storage, &config).unwrap();
+            // The factory knows who created the token
+            let creator = Addr::unchecked("token_creator");
+            let factory_creator = creator.clone();
+            deps.querier.update_wasm(move |query| match query {
+                WasmQuery::Smart { contract_addr, msg } if contract_addr == "initial_factory" => {
+                    let TokenFactoryQueryMsg::GetTokenInfo { address } = from_json(msg).unwrap()
+                    else {
+                        panic!("unexpected factory query");
+                    };
+                    SystemResult::Ok(ContractResult::Ok(
+                        to_json_binary(&GetTokenInfoResponse {
+                            token_info: token_factory::state::TokenInfo {
+                                name: "Test Token".to_string(),
+                                symbol: "TST".to_string(),
+                                decimals: 9,
+                                uri: String::new(),
+                                creator: factory_creator.clone(),
+                                address: Addr::unchecked(address),
+                                creation_time: 0,
+                                total_supply: Uint128::new(100_000_000_000),
+                                metadata: Default::default(),
+                                kind: Default::default(),
+                            },
+                        })
+                        .unwrap(),
+                    ))
+                }
+                _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                    kind: "".to_string(),
+                }),
+            });
 
             // Initialize the token info
             let token_info = TokenInfo {
@@ -4889,7 +9494,11 @@ pub mod execute {
                 symbol: "TST".to_owned(),
                 decimals: 9,
                 total_supply: 100_000_000_000u128.into(),
-                initial_price: BASE_PRICE.into(),
+                bonding_curve_supply: Uint128::from(80_000_000_000u128),
+                lp_supply: Uint128::from(20_000_000_000u128),
+                graduation_reserve: Uint128::zero(),
+                lp_policy: LpPolicy::Burn,
+                initial_price: Price::new(Uint128::from(BASE_PRICE), 9),
                 max_price_impact: Uint128::from(30u128),
                 graduated: false,
             };
@@ -4913,13 +9522,33 @@ pub mod execute {
                 total_volume: Uint128::one(),
                 total_trades: Uint128::one(),
                 total_fees_collected: Uint128::zero(),
-                last_price: Uint128::zero(),
+                last_price: Price::new(Uint128::zero(), 0),
                 enabled: true,
+                launch: LaunchSettings::default(),
+                launch_height: 0,
+                base_currency: "huahua".to_string(),
             };
 
             POOLS
                 .save(deps.as_mut().storage, token_address.to_string(), &pool)
                 .unwrap();
+            TOKEN_PAIRS
+                .save(
+                    deps.as_mut().storage,
+                    "pair_id".to_string(),
+                    &TokenPair {
+                        base_token: Denom::Native("ubase_token".to_string()),
+                        quote_token: Denom::Cw20(token_address.clone()),
+                        base_decimals: 6,
+                        quote_decimals: 9,
+                        enabled: true,
+                        tick_size: Uint128::one(),
+                        lot_size: Uint128::one(),
+                        min_notional: Uint128::zero(),
+                        order_deposit: Uint128::zero(),
+                    },
+                )
+                .unwrap();
 
             // Test unauthorized graduation
             let unauthorized_graduation = execute_graduate(
@@ -4935,7 +9564,43 @@ pub mod execute {
                 StdError::generic_err("Unauthorized")
             );
 
-            // Test successful graduation
+            // The curve has not sold out yet, and the token launched without a
+            // graduation reserve, so raising the currency's one later changes nothing
+            let mut unsold = pool.clone();
+            unsold.token_sold = Uint128::from(70_000_000_000u128);
+            POOLS
+                .save(deps.as_mut().storage, token_address.to_string(), &unsold)
+                .unwrap();
+            BASE_CURRENCIES
+                .save(
+                    deps.as_mut().storage,
+                    "huahua".to_string(),
+                    &BaseCurrency {
+                        symbol: "huahua".to_string(),
+                        denom: Denom::Native("ubase_token".to_string()),
+                        decimals: 6,
+                        base_price: Uint128::from(BASE_PRICE),
+                        graduation_reserve: Uint128::new(1_000_000u128),
+                        min_order_notional: Uint128::zero(),
+                        order_deposit: Uint128::zero(),
+                    },
+                )
+                .unwrap();
+            let res = execute_graduate(
+                deps.as_mut(),
+                env.clone(),
+                owner_info.clone(),
+                token_address.to_string(),
+            );
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Some token have not been sold")
+            );
+
+            // Test successful graduation once sold out
+            POOLS
+                .save(deps.as_mut().storage, token_address.to_string(), &pool)
+                .unwrap();
             let successful_graduation = execute_graduate(
                 deps.as_mut(),
                 env.clone(),
@@ -4983,8 +9648,173 @@ pub mod execute {
             });
 
             // Verify messages
-            assert_eq!(successful_graduation.messages.len(), 1);
+            assert_eq!(successful_graduation.messages.len(), 2);
             assert_eq!(successful_graduation.messages[0], SubMsg::new(expected_msg));
+
+            // The reserve is sent along with the liquidity, the reply handles the LP tokens
+            assert_eq!(
+                successful_graduation.messages[1],
+                SubMsg::reply_on_success(
+                    WasmMsg::Execute {
+                        contract_addr: "secondary_amm_addr".to_string(),
+                        msg: to_json_binary(&SecondaryAmmMsg::ProvideLiquidity {
+                            token_denom: Denom::Cw20(token_address.clone()),
+                            token_amount: Uint128::new(20_000_000_000),
+                            base_denom: Denom::Native("ubase_token".to_string()),
+                            base_amount: Uint128::new(1_000_000),
+                        })
+                        .unwrap(),
+                        funds: coins(1_000_000, "ubase_token"),
+                    },
+                    REPLY_GRADUATION_ID,
+                )
+            );
+            let record = GRADUATIONS
+                .load(&deps.storage, token_address.to_string())
+                .unwrap();
+            assert_eq!(record.lp_outcome, LpOutcome::Pending);
+            assert_eq!(record.graduated_at, env.block.time);
+            assert_eq!(record.unsold_burned, Uint128::zero());
+            assert_eq!(record.lp_recipient, creator);
+
+            // The token launched under the burn policy, a later lock in the
+            // config does not reach it and the LP tokens are burned right away
+            let mut config = CONFIG.load(&deps.storage).unwrap();
+            config.lp_policy = LpPolicy::Lock {
+                duration: MIN_LP_LOCK_DURATION,
+            };
+            CONFIG.save(deps.as_mut().storage, &config).unwrap();
+            let liquidity = ProvideLiquidityResponse {
+                lp_token: Denom::Cw20(Addr::unchecked("lp_token")),
+                lp_amount: Uint128::new(5_000),
+            };
+            let res = reply_graduation(deps.as_mut(), env.clone(), liquidity.clone()).unwrap();
+            assert_eq!(
+                res.messages[0].msg,
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: "lp_token".to_string(),
+                    msg: to_json_binary(&Cw20ExecuteMsg::Burn {
+                        amount: Uint128::new(5_000)
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                })
+            );
+            let record = query::query_graduation_record(deps.as_ref(), token_address.to_string())
+                .unwrap()
+                .record;
+            assert_eq!(record.lp_outcome, LpOutcome::Burned);
+            assert_eq!(record.lp_amount, Uint128::new(5_000));
+            assert!(PENDING_GRADUATION
+                .may_load(&deps.storage)
+                .unwrap()
+                .is_none());
+
+            // Locked LP tokens only go to the creator once the lock ran out
+            let mut locked_info = TOKEN_INFO
+                .load(&deps.storage, token_address.to_string())
+                .unwrap();
+            locked_info.lp_policy = LpPolicy::Lock { duration: 100 };
+            TOKEN_INFO
+                .save(
+                    deps.as_mut().storage,
+                    token_address.to_string(),
+                    &locked_info,
+                )
+                .unwrap();
+            PENDING_GRADUATION
+                .save(deps.as_mut().storage, &token_address.to_string())
+                .unwrap();
+            reply_graduation(deps.as_mut(), env.clone(), liquidity).unwrap();
+            let unlocks_at = env.block.time.plus_seconds(100);
+            assert_eq!(
+                GRADUATIONS
+                    .load(&deps.storage, token_address.to_string())
+                    .unwrap()
+                    .lp_outcome,
+                LpOutcome::Locked { unlocks_at }
+            );
+            let claim = |deps: DepsMut, info: &MessageInfo, env: Env| {
+                execute_claim_locked_lp(deps, env, info.clone(), token_address.to_string())
+            };
+            let res = claim(deps.as_mut(), &non_owner_info, env.clone());
+            assert_eq!(res.unwrap_err(), StdError::generic_err("Unauthorized"));
+            let res = claim(deps.as_mut(), &owner_info, env.clone());
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err(format!("LP tokens are locked until {}", unlocks_at))
+            );
+            let mut later = env.clone();
+            later.block.time = unlocks_at;
+            // The creator claims them, and even the owner can only send them there
+            let res = claim(deps.as_mut(), &owner_info, later.clone()).unwrap();
+            assert_eq!(res.attributes[2], attr("recipient", "token_creator"));
+            assert_eq!(
+                res.messages[0].msg,
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: "lp_token".to_string(),
+                    msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                        recipient: "token_creator".to_string(),
+                        amount: Uint128::new(5_000)
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                })
+            );
+            let res = claim(deps.as_mut(), &message_info(&creator, &[]), later.clone());
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("No locked LP tokens for this token")
+            );
+
+            // A token that launched with a graduation reserve graduates once it
+            // raised it, even after its currency was removed
+            let reserve_token = Addr::unchecked("reserve_token");
+            TOKEN_INFO
+                .save(
+                    deps.as_mut().storage,
+                    reserve_token.to_string(),
+                    &TokenInfo {
+                        graduation_reserve: Uint128::new(1_000_000u128),
+                        ..token_info
+                    },
+                )
+                .unwrap();
+            POOLS
+                .save(
+                    deps.as_mut().storage,
+                    reserve_token.to_string(),
+                    &Pool {
+                        token_address: reserve_token.clone(),
+                        ..unsold
+                    },
+                )
+                .unwrap();
+            BASE_CURRENCIES.remove(deps.as_mut().storage, "huahua".to_string());
+            let res = execute_graduate(
+                deps.as_mut(),
+                env.clone(),
+                owner_info.clone(),
+                reserve_token.to_string(),
+            )
+            .unwrap();
+            assert_eq!(res.attributes[0], attr("action", "graduate"));
+            // The 10B tokens the curve never sold are burned, not left behind
+            assert_eq!(
+                res.messages[1].msg,
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: "quote_token".to_string(),
+                    msg: to_json_binary(&Cw20ExecuteMsg::Burn {
+                        amount: Uint128::new(10_000_000_000)
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                })
+            );
+            let record = GRADUATIONS
+                .load(&deps.storage, reserve_token.to_string())
+                .unwrap();
+            assert_eq!(record.unsold_burned, Uint128::new(10_000_000_000));
         }
 
         #[test]
@@ -5005,8 +9835,15 @@ pub mod execute {
                 lp_supply: 20_000_000_000u128,
                 maker_fee: Decimal::percent(1),
                 taker_fee: Decimal::percent(1),
+                curve_fee: Decimal::zero(),
                 secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
-                base_token_denom: "ubase_token".to_string(),
+                base_token_denom: Denom::Native("ubase_token".to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                token_creation_fee: Uint128::zero(),
+                lp_policy: LpPolicy::Lock {
+                    duration: MIN_LP_LOCK_DURATION,
+                },
             };
 
             CONFIG.save(deps.as_mut().storage, &initial_config).unwrap();
@@ -5024,6 +9861,8 @@ pub mod execute {
                 Some(Uint128::new(160_000_000_000)),
                 Some(Uint128::new(40_000_000_000)),
                 Some(false),
+                Some(Uint128::new(5_000_000)),
+                None,
             );
 
             assert!(unauthorized_update.is_err());
@@ -5032,6 +9871,27 @@ pub mod execute {
                 StdError::generic_err("Unauthorized")
             );
 
+            // A 100% fee is rejected with an error rather than a panic
+            let res = execute_update_config(
+                deps.as_mut(),
+                env.clone(),
+                owner_info.clone(),
+                None,
+                None,
+                None,
+                Some(Decimal::one()),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            );
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("Trading fee rate must be between 0 and 1.")
+            );
+
             // Test authorized update
             let authorized_update = execute_update_config(
                 deps.as_mut(),
@@ -5045,6 +9905,10 @@ pub mod execute {
                 Some(Uint128::new(160_000_000_000)),
                 Some(Uint128::new(40_000_000_000)),
                 Some(false),
+                Some(Uint128::new(5_000_000)),
+                Some(LpPolicy::Lock {
+                    duration: MIN_LP_LOCK_DURATION * 2,
+                }),
             )
             .unwrap();
 
@@ -5076,12 +9940,94 @@ pub mod execute {
                 Uint128::new(40_000_000_000).into()
             );
             assert_eq!(updated_config.enabled, false);
+            assert_eq!(updated_config.token_creation_fee, Uint128::new(5_000_000));
+            assert_eq!(
+                updated_config.lp_policy,
+                LpPolicy::Lock {
+                    duration: MIN_LP_LOCK_DURATION * 2
+                }
+            );
+
+            // A lock must last, and can only grow or turn into a burn
+            let update_lp_policy = |deps: DepsMut, lp_policy: LpPolicy| {
+                execute_update_config(
+                    deps,
+                    env.clone(),
+                    owner_info.clone(),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    Some(lp_policy),
+                )
+            };
+            let res = update_lp_policy(deps.as_mut(), LpPolicy::Lock { duration: 0 });
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err(format!(
+                    "LP lock duration must be at least {} seconds",
+                    MIN_LP_LOCK_DURATION
+                ))
+            );
+            let res = update_lp_policy(
+                deps.as_mut(),
+                LpPolicy::Lock {
+                    duration: MIN_LP_LOCK_DURATION,
+                },
+            );
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("LP policy can only be made stricter")
+            );
+            update_lp_policy(deps.as_mut(), LpPolicy::Burn).unwrap();
+            let res = update_lp_policy(
+                deps.as_mut(),
+                LpPolicy::Lock {
+                    duration: MIN_LP_LOCK_DURATION * 3,
+                },
+            );
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err("LP policy can only be made stricter")
+            );
+            assert_eq!(
+                CONFIG.load(&deps.storage).unwrap().lp_policy,
+                LpPolicy::Burn
+            );
+
+            // The curve and LP supplies must fit within the total
+            let res = execute_update_config(
+                deps.as_mut(),
+                env.clone(),
+                owner_info.clone(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some(Uint128::new(50_000_000_000)),
+                None,
+                None,
+                None,
+            );
+            assert_eq!(
+                res.unwrap_err(),
+                StdError::generic_err(
+                    "Bonding curve and LP supplies must fit within the total supply"
+                )
+            );
         }
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         // User queries
         QueryMsg::GetUserTrades {
@@ -5113,6 +10059,9 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::GetUserTradeCount { address } => {
             to_json_binary(&query::query_user_trade_count(deps, address)?)
         }
+        QueryMsg::GetUserActiveOrderCount { address } => {
+            to_json_binary(&query::query_user_active_order_count(deps, address)?)
+        }
 
         // Order book queries
         QueryMsg::GetOrder { order_id } => to_json_binary(&query::query_order(deps, order_id)?),
@@ -5149,10 +10098,31 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::ListTokenPairs { start_after, limit } => {
             to_json_binary(&query::query_token_pairs(deps, start_after, limit)?)
         }
+        QueryMsg::ListBaseCurrencies {} => to_json_binary(&query::query_base_currencies(deps)?),
+        QueryMsg::SimulateSwapOperations {
+            offer_amount,
+            operations,
+        } => to_json_binary(&query::query_simulate_swap_operations(
+            deps,
+            offer_amount,
+            operations,
+        )?),
 
         // System queries
         QueryMsg::GetConfig {} => to_json_binary(&query::query_config(deps)?),
         QueryMsg::GetSystemStats {} => to_json_binary(&query::query_system_stats(deps)?),
+        QueryMsg::GetOwnership {} => to_json_binary(&query::query_ownership(deps)?),
+        QueryMsg::GetRoles {} => to_json_binary(&query::query_roles(deps)?),
+        QueryMsg::GetTreasury {} => to_json_binary(&query::query_treasury(deps)?),
+        QueryMsg::GetGraduationRecord { token_address } => {
+            to_json_binary(&query::query_graduation_record(deps, token_address)?)
+        }
+        QueryMsg::GetVestingSchedule { token_address } => {
+            to_json_binary(&query::query_vesting_schedule(deps, env, token_address)?)
+        }
+        QueryMsg::GetPauseStatus { pair_id } => {
+            to_json_binary(&query::query_pause_status(deps, pair_id)?)
+        }
     }
 }
 
@@ -5162,14 +10132,18 @@ pub mod query {
 
     use crate::{
         msg::{
-            GetConfigResponse, GetCountResponse, GetCurrentPriceResponse, GetOrderBookResponse,
-            GetOrderResponse, GetPoolResponse, GetRecentTradesResponse, GetSystemStatsResponse,
-            GetTokenInfoResponse, GetTokenPairResponse, GetUserOrdersResponse,
-            GetUserTradesResponse, ListTokenPairsResponse,
+            GetConfigResponse, GetCountResponse, GetCurrentPriceResponse,
+            GetGraduationRecordResponse, GetOrderBookResponse, GetOrderResponse,
+            GetOwnershipResponse, GetPauseStatusResponse, GetPoolResponse, GetRecentTradesResponse,
+            GetRolesResponse, GetSystemStatsResponse, GetTokenInfoResponse, GetTokenPairResponse,
+            GetTreasuryResponse, GetUserOrdersResponse, GetUserTradesResponse,
+            GetVestingScheduleResponse, ListBaseCurrenciesResponse, ListTokenPairsResponse,
+            SimulateSwapOperationsResponse, SwapOperation,
         },
         state::{
-            Order, OrderStatus, PriceLevel, TokenPair, Trade, ORDERS, ORDER_BOOKS, POOLS,
-            TOKEN_INFO, TOKEN_PAIRS, TRADES, USER_ORDERS, USER_TRADES, USER_TRADE_COUNT,
+            Order, OrderStatus, PriceLevel, TokenPair, Trade, BUYBACKS, GLOBAL_PAUSE, GRADUATIONS,
+            ORDERS, ORDER_BOOKS, PAIR_PAUSE, PENDING_OWNER, POOLS, TOKEN_INFO, TOKEN_PAIRS, TRADES,
+            TREASURY, USER_ACTIVE_ORDERS, USER_ORDERS, USER_TRADES, USER_TRADE_COUNT, VESTING,
         },
     };
 
@@ -5249,6 +10223,14 @@ pub mod query {
         Ok(GetCountResponse { count })
     }
 
+    pub fn query_user_active_order_count(deps: Deps, address: Addr) -> StdResult<GetCountResponse> {
+        let count = USER_ACTIVE_ORDERS
+            .may_load(deps.storage, address)?
+            .unwrap_or_default();
+
+        Ok(GetCountResponse { count })
+    }
+
     pub fn query_order(deps: Deps, order_id: u64) -> StdResult<GetOrderResponse> {
         let order = ORDERS.load(deps.storage, order_id);
         match order {
@@ -5275,7 +10257,7 @@ pub mod query {
             .map(|(price, orders)| {
                 let total_quantity: Uint128 = orders.iter().map(|o| o.remaining_amount).sum();
                 PriceLevel {
-                    price: Uint128::from(*price),
+                    price: Price::new(Uint128::from(*price), token_pair.quote_decimals),
                     quantity: total_quantity,
                     order_count: orders.len() as u32,
                 }
@@ -5290,23 +10272,41 @@ pub mod query {
             .map(|(price, orders)| {
                 let total_quantity: Uint128 = orders.iter().map(|o| o.remaining_amount).sum();
                 PriceLevel {
-                    price: Uint128::from(*price),
+                    price: Price::new(Uint128::from(*price), token_pair.quote_decimals),
                     quantity: total_quantity,
                     order_count: orders.len() as u32,
                 }
             })
             .collect();
 
-        // Get pool for last price and volume
-        let pool = POOLS.load(deps.storage, token_pair.quote_token)?;
+        // Launched pairs take the last price and volume from their pool. Registered
+        // pairs have none and fall back to their latest trade, without volume.
+        let pool = POOLS.may_load(deps.storage, denom_id(&token_pair.quote_token).to_string())?;
+        let (last_price, base_volume_24h, quote_volume_24h) = match pool {
+            Some(pool) => (
+                pool.last_price,
+                pool.total_volume,
+                pool.last_price.checked_quote_to_base(pool.total_volume)?,
+            ),
+            None => {
+                let last_trade = TRADES
+                    .range(deps.storage, None, None, CosmwasmOrder::Descending)
+                    .filter_map(|item| item.ok().map(|(_, trade)| trade))
+                    .find(|trade| trade.pair_id == pair_id);
+                let last_price = last_trade
+                    .map(|trade| trade.price)
+                    .unwrap_or_else(|| Price::zero(token_pair.quote_decimals));
+                (last_price, Uint128::zero(), Uint128::zero())
+            }
+        };
 
         Ok(GetOrderBookResponse {
             pair_id,
             bids,
             asks,
-            last_price: pool.last_price,
-            base_volume_24h: pool.total_volume,
-            quote_volume_24h: pool.total_volume * pool.last_price,
+            last_price,
+            base_volume_24h,
+            quote_volume_24h,
         })
     }
 
@@ -5314,7 +10314,10 @@ pub mod query {
         let pool = POOLS.load(deps.storage, token_address.clone());
 
         match pool {
-            Ok(pool) => Ok(GetPoolResponse { pool }),
+            Ok(pool) => Ok(GetPoolResponse {
+                pool,
+                buyback: BUYBACKS.may_load(deps.storage, token_address)?,
+            }),
             Err(_) => Err(StdError::not_found(token_address)),
         }
     }
@@ -5323,7 +10326,10 @@ pub mod query {
         let token_info = TOKEN_INFO.load(deps.storage, token_address.clone());
 
         match token_info {
-            Ok(token_info) => Ok(GetTokenInfoResponse { token_info }),
+            Ok(token_info) => Ok(GetTokenInfoResponse {
+                token_info,
+                buyback: BUYBACKS.may_load(deps.storage, token_address)?,
+            }),
             Err(_) => Err(StdError::not_found(token_address)),
         }
     }
@@ -5335,7 +10341,7 @@ pub mod query {
         let pool = POOLS.load(deps.storage, token_address)?;
 
         Ok(GetCurrentPriceResponse {
-            price: pool.last_price.into(),
+            price: pool.last_price,
         })
     }
 
@@ -5396,6 +10402,93 @@ pub mod query {
         }
     }
 
+    pub fn query_ownership(deps: Deps) -> StdResult<GetOwnershipResponse> {
+        let config = CONFIG.load(deps.storage)?;
+        let pending = PENDING_OWNER.may_load(deps.storage)?;
+
+        Ok(GetOwnershipResponse {
+            owner: config.owner,
+            pending_owner: pending.as_ref().map(|p| p.address.clone()),
+            pending_expiry: pending.and_then(|p| p.expiry),
+        })
+    }
+
+    pub fn query_base_currencies(deps: Deps) -> StdResult<ListBaseCurrenciesResponse> {
+        let base_currencies = BASE_CURRENCIES
+            .range(deps.storage, None, None, CosmwasmOrder::Ascending)
+            .map(|item| item.map(|(_, currency)| currency))
+            .collect::<StdResult<Vec<_>>>()?;
+        Ok(ListBaseCurrenciesResponse { base_currencies })
+    }
+
+    pub fn query_simulate_swap_operations(
+        deps: Deps,
+        offer_amount: Uint128,
+        operations: Vec<SwapOperation>,
+    ) -> StdResult<SimulateSwapOperationsResponse> {
+        let amount = execute::simulate_swap_operations(deps.storage, offer_amount, &operations)?;
+        Ok(SimulateSwapOperationsResponse { amount })
+    }
+
+    pub fn query_graduation_record(
+        deps: Deps,
+        token_address: String,
+    ) -> StdResult<GetGraduationRecordResponse> {
+        let record = GRADUATIONS
+            .may_load(deps.storage, token_address.clone())?
+            .ok_or_else(|| StdError::not_found(token_address))?;
+        Ok(GetGraduationRecordResponse { record })
+    }
+
+    pub fn query_vesting_schedule(
+        deps: Deps,
+        env: Env,
+        token_address: String,
+    ) -> StdResult<GetVestingScheduleResponse> {
+        let schedule = VESTING
+            .may_load(deps.storage, token_address.clone())?
+            .ok_or_else(|| StdError::not_found(token_address))?;
+        let vested = schedule.vested(env.block.time);
+        Ok(GetVestingScheduleResponse {
+            claimable: vested.checked_sub(schedule.claimed)?,
+            vested,
+            schedule,
+        })
+    }
+
+    pub fn query_treasury(deps: Deps) -> StdResult<GetTreasuryResponse> {
+        let balances = TREASURY
+            .range(deps.storage, None, None, CosmwasmOrder::Ascending)
+            .map(|item| item.map(|(_, balance)| balance))
+            .collect::<StdResult<Vec<_>>>()?;
+        let distribution = FEE_DISTRIBUTION.may_load(deps.storage)?.unwrap_or_default();
+        Ok(GetTreasuryResponse {
+            balances,
+            distribution,
+        })
+    }
+
+    pub fn query_roles(deps: Deps) -> StdResult<GetRolesResponse> {
+        let roles = ROLES.may_load(deps.storage)?.unwrap_or_default();
+        Ok(GetRolesResponse { roles })
+    }
+
+    pub fn query_pause_status(
+        deps: Deps,
+        pair_id: Option<String>,
+    ) -> StdResult<GetPauseStatusResponse> {
+        let global = GLOBAL_PAUSE.may_load(deps.storage)?.unwrap_or_default();
+        let pair = match pair_id {
+            Some(pair_id) => Some(
+                PAIR_PAUSE
+                    .may_load(deps.storage, pair_id)?
+                    .unwrap_or_default(),
+            ),
+            None => None,
+        };
+        Ok(GetPauseStatusResponse { global, pair })
+    }
+
     pub fn query_system_stats(deps: Deps) -> StdResult<GetSystemStatsResponse> {
         // Count total pairs
         let total_pairs = TOKEN_PAIRS
@@ -5463,11 +10556,12 @@ mod tests {
             buyer: user_address.clone(),
             seller: Addr::unchecked("seller"),
             token_amount: Uint128::new(100),
-            price: Uint128::new(10),
+            price: Price::new(Uint128::new(10), 0),
             timestamp: env.block.time.seconds(),
             total_price: Uint128::new(1000),
-            maker_fee_amount: Uint128::new(10),
-            taker_fee_amount: Uint128::new(5),
+            maker_side: OrderType::Sell,
+            buyer_fee: Uint128::new(10),
+            seller_fee: Uint128::new(5),
         };
 
         let trade2 = Trade {
@@ -5478,11 +10572,12 @@ mod tests {
             buyer: user_address.clone(),
             seller: Addr::unchecked("seller"),
             token_amount: Uint128::new(200),
-            price: Uint128::new(20),
+            price: Price::new(Uint128::new(20), 0),
             timestamp: env.block.time.seconds(),
             total_price: Uint128::new(4000),
-            maker_fee_amount: Uint128::new(20),
-            taker_fee_amount: Uint128::new(10),
+            maker_side: OrderType::Sell,
+            buyer_fee: Uint128::new(20),
+            seller_fee: Uint128::new(10),
         };
 
         USER_TRADES
@@ -5517,11 +10612,12 @@ mod tests {
             buyer: user_address.clone(),
             seller: Addr::unchecked("seller"),
             token_amount: Uint128::new(100),
-            price: Uint128::new(10),
+            price: Price::new(Uint128::new(10), 0),
             timestamp: env.block.time.seconds(),
             total_price: Uint128::new(1000),
-            maker_fee_amount: Uint128::new(10),
-            taker_fee_amount: Uint128::new(5),
+            maker_side: OrderType::Sell,
+            buyer_fee: Uint128::new(10),
+            seller_fee: Uint128::new(5),
         };
 
         let trade2 = Trade {
@@ -5532,11 +10628,12 @@ mod tests {
             buyer: user_address.clone(),
             seller: Addr::unchecked("seller"),
             token_amount: Uint128::new(200),
-            price: Uint128::new(20),
+            price: Price::new(Uint128::new(20), 0),
             timestamp: env.block.time.seconds(),
             total_price: Uint128::new(4000),
-            maker_fee_amount: Uint128::new(20),
-            taker_fee_amount: Uint128::new(10),
+            maker_side: OrderType::Sell,
+            buyer_fee: Uint128::new(20),
+            seller_fee: Uint128::new(10),
         };
 
         USER_TRADES
@@ -5576,11 +10673,12 @@ mod tests {
             buyer: user_address.clone(),
             seller: Addr::unchecked("seller"),
             token_amount: Uint128::new(100),
-            price: Uint128::new(10),
+            price: Price::new(Uint128::new(10), 0),
             timestamp: env.block.time.seconds(),
             total_price: Uint128::new(1000),
-            maker_fee_amount: Uint128::new(10),
-            taker_fee_amount: Uint128::new(5),
+            maker_side: OrderType::Sell,
+            buyer_fee: Uint128::new(10),
+            seller_fee: Uint128::new(5),
         };
 
         let trade2 = Trade {
@@ -5591,11 +10689,12 @@ mod tests {
             buyer: user_address.clone(),
             seller: Addr::unchecked("seller"),
             token_amount: Uint128::new(200),
-            price: Uint128::new(20),
+            price: Price::new(Uint128::new(20), 0),
             timestamp: env.block.time.seconds(),
             total_price: Uint128::new(4000),
-            maker_fee_amount: Uint128::new(20),
-            taker_fee_amount: Uint128::new(10),
+            maker_side: OrderType::Sell,
+            buyer_fee: Uint128::new(20),
+            seller_fee: Uint128::new(10),
         };
 
         let trade3 = Trade {
@@ -5606,11 +10705,12 @@ mod tests {
             buyer: user_address.clone(),
             seller: Addr::unchecked("seller"),
             token_amount: Uint128::new(150),
-            price: Uint128::new(15),
+            price: Price::new(Uint128::new(15), 0),
             timestamp: env.block.time.seconds(),
             total_price: Uint128::new(2250),
-            maker_fee_amount: Uint128::new(15),
-            taker_fee_amount: Uint128::new(7),
+            maker_side: OrderType::Sell,
+            buyer_fee: Uint128::new(15),
+            seller_fee: Uint128::new(7),
         };
 
         USER_TRADES
@@ -5645,13 +10745,14 @@ mod tests {
             owner: user_address.clone(),
             pair_id: "pair1".to_string(),
             token_amount: Uint128::new(100),
-            price: Uint128::new(10),
+            price: Price::new(Uint128::new(10), 0),
             timestamp: env.block.time.seconds(),
             status: OrderStatus::Active,
             filled_amount: Uint128::zero(),
             remaining_amount: Uint128::new(100),
             order_type: OrderType::Buy,
             created_at: env.block.height,
+            deposit: Uint128::zero(),
         };
 
         let order2 = Order {
@@ -5659,13 +10760,14 @@ mod tests {
             owner: user_address.clone(),
             pair_id: "pair2".to_string(),
             token_amount: Uint128::new(200),
-            price: Uint128::new(20),
+            price: Price::new(Uint128::new(20), 0),
             timestamp: env.block.time.seconds(),
             status: OrderStatus::Filled,
             filled_amount: Uint128::new(200),
             remaining_amount: Uint128::zero(),
             order_type: OrderType::Sell,
             created_at: env.block.height,
+            deposit: Uint128::zero(),
         };
 
         USER_ORDERS
@@ -5697,13 +10799,14 @@ mod tests {
             owner: user_address.clone(),
             pair_id: "pair1".to_string(),
             token_amount: Uint128::new(100),
-            price: Uint128::new(10),
+            price: Price::new(Uint128::new(10), 0),
             timestamp: env.block.time.seconds(),
             status: OrderStatus::Active,
             filled_amount: Uint128::zero(),
             remaining_amount: Uint128::new(100),
             order_type: OrderType::Buy,
             created_at: env.block.height,
+            deposit: Uint128::zero(),
         };
 
         let order2 = Order {
@@ -5711,13 +10814,14 @@ mod tests {
             owner: user_address.clone(),
             pair_id: "pair2".to_string(),
             token_amount: Uint128::new(200),
-            price: Uint128::new(20),
+            price: Price::new(Uint128::new(20), 0),
             timestamp: env.block.time.seconds(),
             status: OrderStatus::Filled,
             filled_amount: Uint128::new(200),
             remaining_amount: Uint128::zero(),
             order_type: OrderType::Sell,
             created_at: env.block.height,
+            deposit: Uint128::zero(),
         };
 
         USER_ORDERS
@@ -5755,13 +10859,14 @@ mod tests {
             owner: user_address.clone(),
             pair_id: "pair1".to_string(),
             token_amount: Uint128::new(100),
-            price: Uint128::new(10),
+            price: Price::new(Uint128::new(10), 0),
             timestamp: env.block.time.seconds(),
             status: OrderStatus::Active,
             filled_amount: Uint128::zero(),
             remaining_amount: Uint128::new(100),
             order_type: OrderType::Buy,
             created_at: env.block.height,
+            deposit: Uint128::zero(),
         };
 
         let order2 = Order {
@@ -5769,13 +10874,14 @@ mod tests {
             owner: user_address.clone(),
             pair_id: "pair2".to_string(),
             token_amount: Uint128::new(200),
-            price: Uint128::new(20),
+            price: Price::new(Uint128::new(20), 0),
             timestamp: env.block.time.seconds(),
             status: OrderStatus::Filled,
             filled_amount: Uint128::new(200),
             remaining_amount: Uint128::zero(),
             order_type: OrderType::Sell,
             created_at: env.block.height,
+            deposit: Uint128::zero(),
         };
 
         USER_ORDERS
@@ -5813,13 +10919,14 @@ mod tests {
             owner: user_address.clone(),
             pair_id: "pair1".to_string(),
             token_amount: Uint128::new(100),
-            price: Uint128::new(10),
+            price: Price::new(Uint128::new(10), 0),
             timestamp: env.block.time.seconds(),
             status: OrderStatus::Active,
             filled_amount: Uint128::zero(),
             remaining_amount: Uint128::new(100),
             order_type: OrderType::Buy,
             created_at: env.block.height,
+            deposit: Uint128::zero(),
         };
 
         let order2 = Order {
@@ -5827,13 +10934,14 @@ mod tests {
             owner: user_address.clone(),
             pair_id: "pair2".to_string(),
             token_amount: Uint128::new(200),
-            price: Uint128::new(20),
+            price: Price::new(Uint128::new(20), 0),
             timestamp: env.block.time.seconds(),
             status: OrderStatus::Filled,
             filled_amount: Uint128::new(200),
             remaining_amount: Uint128::zero(),
             order_type: OrderType::Sell,
             created_at: env.block.height,
+            deposit: Uint128::zero(),
         };
 
         let order3 = Order {
@@ -5841,13 +10949,14 @@ mod tests {
             owner: user_address.clone(),
             pair_id: "pair1".to_string(),
             token_amount: Uint128::new(150),
-            price: Uint128::new(15),
+            price: Price::new(Uint128::new(15), 0),
             timestamp: env.block.time.seconds(),
             status: OrderStatus::Active,
             filled_amount: Uint128::zero(),
             remaining_amount: Uint128::new(150),
             order_type: OrderType::Buy,
             created_at: env.block.height,
+            deposit: Uint128::zero(),
         };
 
         USER_ORDERS
@@ -5917,13 +11026,14 @@ mod tests {
             owner: Addr::unchecked("user"),
             pair_id: "pair1".to_string(),
             token_amount: Uint128::new(100),
-            price: Uint128::new(10),
+            price: Price::new(Uint128::new(10), 0),
             timestamp: env.block.time.seconds(),
             status: OrderStatus::Active,
             filled_amount: Uint128::zero(),
             remaining_amount: Uint128::new(100),
             order_type: OrderType::Buy,
             created_at: env.block.height,
+            deposit: Uint128::zero(),
         };
 
         ORDERS.save(deps.as_mut().storage, 1, &order).unwrap();
@@ -5955,69 +11065,73 @@ mod tests {
         // Initialize order book
         let mut buy_orders = BTreeMap::new();
         buy_orders.insert(
-            Uint128::new(10).u128(),
+            1_000_000_000u128,
             vec![Order {
                 id: 1,
                 owner: Addr::unchecked("buyer1"),
                 pair_id: "pair1".to_string(),
                 token_amount: Uint128::new(100),
-                price: Uint128::new(10),
+                price: Price::new(Uint128::new(1_000_000_000), 8),
                 timestamp: env.block.time.seconds(),
                 status: OrderStatus::Active,
                 filled_amount: Uint128::zero(),
                 remaining_amount: Uint128::new(100),
                 order_type: OrderType::Buy,
                 created_at: env.block.height,
+                deposit: Uint128::zero(),
             }],
         );
         buy_orders.insert(
-            Uint128::new(9).u128(),
+            900_000_000u128,
             vec![Order {
                 id: 2,
                 owner: Addr::unchecked("buyer2"),
                 pair_id: "pair1".to_string(),
                 token_amount: Uint128::new(200),
-                price: Uint128::new(9),
+                price: Price::new(Uint128::new(900_000_000), 8),
                 timestamp: env.block.time.seconds(),
                 status: OrderStatus::Active,
                 filled_amount: Uint128::zero(),
                 remaining_amount: Uint128::new(200),
                 order_type: OrderType::Buy,
                 created_at: env.block.height,
+                deposit: Uint128::zero(),
             }],
         );
 
         let mut sell_orders = BTreeMap::new();
         sell_orders.insert(
-            Uint128::new(11).u128(),
+            1_100_000_000u128,
             vec![Order {
                 id: 3,
                 owner: Addr::unchecked("seller1"),
                 pair_id: "pair1".to_string(),
                 token_amount: Uint128::new(150),
-                price: Uint128::new(11),
+                price: Price::new(Uint128::new(1_100_000_000), 8),
                 timestamp: env.block.time.seconds(),
                 status: OrderStatus::Active,
                 filled_amount: Uint128::zero(),
                 remaining_amount: Uint128::new(150),
                 order_type: OrderType::Sell,
                 created_at: env.block.height,
+                deposit: Uint128::zero(),
             }],
         );
         sell_orders.insert(
-            Uint128::new(12).u128(),
+            1_200_000_000u128,
             vec![Order {
                 id: 4,
                 owner: Addr::unchecked("seller2"),
                 pair_id: "pair1".to_string(),
                 token_amount: Uint128::new(100),
-                price: Uint128::new(12),
+                price: Price::new(Uint128::new(1_200_000_000), 8),
                 timestamp: env.block.time.seconds(),
                 status: OrderStatus::Active,
                 filled_amount: Uint128::zero(),
                 remaining_amount: Uint128::new(100),
                 order_type: OrderType::Sell,
                 created_at: env.block.height,
+                deposit: Uint128::zero(),
             }],
         );
 
@@ -6041,8 +11155,11 @@ mod tests {
             total_volume: Uint128::new(10000),
             total_trades: Uint128::new(100),
             total_fees_collected: Uint128::new(500),
-            last_price: Uint128::new(10),
+            last_price: Price::new(Uint128::new(1_000_000_000), 8),
             enabled: true,
+            launch: LaunchSettings::default(),
+            launch_height: 0,
+            base_currency: "huahua".to_string(),
         };
 
         POOLS
@@ -6051,11 +11168,15 @@ mod tests {
 
         // Initialize token pair
         let token_pair = TokenPair {
-            base_token: "token1".to_string(),
-            quote_token: "token_address".to_string(),
+            base_token: Denom::Native("token1".to_string()),
+            quote_token: Denom::Cw20(Addr::unchecked("token_address")),
             base_decimals: 6,
             quote_decimals: 8,
             enabled: true,
+            tick_size: Uint128::one(),
+            lot_size: Uint128::one(),
+            min_notional: Uint128::zero(),
+            order_deposit: Uint128::zero(),
         };
 
         TOKEN_PAIRS
@@ -6068,22 +11189,64 @@ mod tests {
         // Verify response
         assert_eq!(response.pair_id, "pair1");
         assert_eq!(response.bids.len(), 2);
-        assert_eq!(response.bids[0].price, Uint128::new(10));
+        assert_eq!(
+            response.bids[0].price,
+            Price::new(Uint128::new(1_000_000_000), 8)
+        );
         assert_eq!(response.bids[0].quantity, Uint128::new(100));
         assert_eq!(response.bids[0].order_count, 1);
-        assert_eq!(response.bids[1].price, Uint128::new(9));
+        assert_eq!(
+            response.bids[1].price,
+            Price::new(Uint128::new(900_000_000), 8)
+        );
         assert_eq!(response.bids[1].quantity, Uint128::new(200));
         assert_eq!(response.bids[1].order_count, 1);
         assert_eq!(response.asks.len(), 2);
-        assert_eq!(response.asks[0].price, Uint128::new(11));
+        assert_eq!(
+            response.asks[0].price,
+            Price::new(Uint128::new(1_100_000_000), 8)
+        );
         assert_eq!(response.asks[0].quantity, Uint128::new(150));
         assert_eq!(response.asks[0].order_count, 1);
-        assert_eq!(response.asks[1].price, Uint128::new(12));
+        assert_eq!(
+            response.asks[1].price,
+            Price::new(Uint128::new(1_200_000_000), 8)
+        );
         assert_eq!(response.asks[1].quantity, Uint128::new(100));
         assert_eq!(response.asks[1].order_count, 1);
-        assert_eq!(response.last_price, Uint128::new(10));
+        assert_eq!(response.last_price, Price::new(Uint128::new(10), 0));
         assert_eq!(response.base_volume_24h, Uint128::new(10000));
         assert_eq!(response.quote_volume_24h, Uint128::new(100000));
+
+        // A registered pair has no pool, its last price comes from its latest trade
+        POOLS.remove(deps.as_mut().storage, "token_address".to_string());
+        let response = query_order_book(deps.as_ref(), "pair1".to_string(), Some(10)).unwrap();
+        assert_eq!(response.last_price, Price::zero(8));
+        assert_eq!(response.base_volume_24h, Uint128::zero());
+        for (id, pair_id, price) in [(1, "pair1", 1_050_000_000u128), (2, "pair2", 7)] {
+            let trade = Trade {
+                id,
+                pair_id: pair_id.to_string(),
+                buy_order_id: 1,
+                sell_order_id: 3,
+                buyer: Addr::unchecked("buyer1"),
+                seller: Addr::unchecked("seller1"),
+                token_amount: Uint128::new(10),
+                price: Price::new(Uint128::new(price), 8),
+                timestamp: env.block.time.seconds(),
+                total_price: Uint128::new(105),
+                maker_side: OrderType::Sell,
+                buyer_fee: Uint128::zero(),
+                seller_fee: Uint128::zero(),
+            };
+            TRADES.save(deps.as_mut().storage, id, &trade).unwrap();
+        }
+        let response = query_order_book(deps.as_ref(), "pair1".to_string(), Some(10)).unwrap();
+        assert_eq!(
+            response.last_price,
+            Price::new(Uint128::new(1_050_000_000), 8)
+        );
+        assert_eq!(response.quote_volume_24h, Uint128::zero());
     }
 
     #[test]
@@ -6111,8 +11274,11 @@ mod tests {
             total_volume: Uint128::new(10000),
             total_trades: Uint128::new(100),
             total_fees_collected: Uint128::new(500),
-            last_price: Uint128::new(10),
+            last_price: Price::new(Uint128::new(10), 0),
             enabled: true,
+            launch: LaunchSettings::default(),
+            launch_height: 0,
+            base_currency: "huahua".to_string(),
         };
 
         POOLS
@@ -6151,7 +11317,11 @@ mod tests {
             symbol: "TT".to_string(),
             decimals: 6,
             total_supply: Uint128::new(1_000_000),
-            initial_price: Uint128::new(10),
+            bonding_curve_supply: Uint128::new(800_000),
+            lp_supply: Uint128::new(200_000),
+            graduation_reserve: Uint128::zero(),
+            lp_policy: LpPolicy::Burn,
+            initial_price: Price::new(Uint128::new(10), 6),
             max_price_impact: Uint128::new(100),
             graduated: false,
         };
@@ -6200,8 +11370,11 @@ mod tests {
             total_volume: Uint128::new(10000),
             total_trades: Uint128::new(100),
             total_fees_collected: Uint128::new(500),
-            last_price: Uint128::new(10),
+            last_price: Price::new(Uint128::new(10), 0),
             enabled: true,
+            launch: LaunchSettings::default(),
+            launch_height: 0,
+            base_currency: "huahua".to_string(),
         };
 
         POOLS
@@ -6212,7 +11385,7 @@ mod tests {
         let response = query_current_price(deps.as_ref(), "token_address".to_string()).unwrap();
 
         // Verify response
-        assert_eq!(response.price, 10u128);
+        assert_eq!(response.price, Price::new(Uint128::new(10), 0));
     }
 
     #[test]
@@ -6240,11 +11413,12 @@ mod tests {
             buyer: Addr::unchecked("buyer"),
             seller: Addr::unchecked("seller"),
             token_amount: Uint128::new(100),
-            price: Uint128::new(10),
+            price: Price::new(Uint128::new(10), 0),
             timestamp: env.block.time.seconds(),
             total_price: Uint128::new(1000),
-            maker_fee_amount: Uint128::new(10),
-            taker_fee_amount: Uint128::new(5),
+            maker_side: OrderType::Sell,
+            buyer_fee: Uint128::new(10),
+            seller_fee: Uint128::new(5),
         };
 
         let trade2 = Trade {
@@ -6255,11 +11429,12 @@ mod tests {
             buyer: Addr::unchecked("buyer"),
             seller: Addr::unchecked("seller"),
             token_amount: Uint128::new(200),
-            price: Uint128::new(20),
+            price: Price::new(Uint128::new(20), 0),
             timestamp: env.block.time.seconds(),
             total_price: Uint128::new(4000),
-            maker_fee_amount: Uint128::new(20),
-            taker_fee_amount: Uint128::new(10),
+            maker_side: OrderType::Sell,
+            buyer_fee: Uint128::new(20),
+            seller_fee: Uint128::new(10),
         };
 
         TRADES.save(deps.as_mut().storage, 1, &trade1).unwrap();
@@ -6288,11 +11463,12 @@ mod tests {
             buyer: Addr::unchecked("buyer"),
             seller: Addr::unchecked("seller"),
             token_amount: Uint128::new(100),
-            price: Uint128::new(10),
+            price: Price::new(Uint128::new(10), 0),
             timestamp: env.block.time.seconds(),
             total_price: Uint128::new(1000),
-            maker_fee_amount: Uint128::new(10),
-            taker_fee_amount: Uint128::new(5),
+            maker_side: OrderType::Sell,
+            buyer_fee: Uint128::new(10),
+            seller_fee: Uint128::new(5),
         };
 
         let trade2 = Trade {
@@ -6303,11 +11479,12 @@ mod tests {
             buyer: Addr::unchecked("buyer"),
             seller: Addr::unchecked("seller"),
             token_amount: Uint128::new(200),
-            price: Uint128::new(20),
+            price: Price::new(Uint128::new(20), 0),
             timestamp: env.block.time.seconds(),
             total_price: Uint128::new(4000),
-            maker_fee_amount: Uint128::new(20),
-            taker_fee_amount: Uint128::new(10),
+            maker_side: OrderType::Sell,
+            buyer_fee: Uint128::new(20),
+            seller_fee: Uint128::new(10),
         };
 
         let trade3 = Trade {
@@ -6318,11 +11495,12 @@ mod tests {
             buyer: Addr::unchecked("buyer"),
             seller: Addr::unchecked("seller"),
             token_amount: Uint128::new(150),
-            price: Uint128::new(15),
+            price: Price::new(Uint128::new(15), 0),
             timestamp: env.block.time.seconds(),
             total_price: Uint128::new(2250),
-            maker_fee_amount: Uint128::new(15),
-            taker_fee_amount: Uint128::new(7),
+            maker_side: OrderType::Sell,
+            buyer_fee: Uint128::new(15),
+            seller_fee: Uint128::new(7),
         };
 
         TRADES.save(deps.as_mut().storage, 1, &trade1).unwrap();
@@ -6352,11 +11530,12 @@ mod tests {
             buyer: Addr::unchecked("buyer"),
             seller: Addr::unchecked("seller"),
             token_amount: Uint128::new(100),
-            price: Uint128::new(10),
+            price: Price::new(Uint128::new(10), 0),
             timestamp: env.block.time.seconds(),
             total_price: Uint128::new(1000),
-            maker_fee_amount: Uint128::new(10),
-            taker_fee_amount: Uint128::new(5),
+            maker_side: OrderType::Sell,
+            buyer_fee: Uint128::new(10),
+            seller_fee: Uint128::new(5),
         };
 
         let trade2 = Trade {
@@ -6367,11 +11546,12 @@ mod tests {
             buyer: Addr::unchecked("buyer"),
             seller: Addr::unchecked("seller"),
             token_amount: Uint128::new(200),
-            price: Uint128::new(20),
+            price: Price::new(Uint128::new(20), 0),
             timestamp: env.block.time.seconds(),
             total_price: Uint128::new(4000),
-            maker_fee_amount: Uint128::new(20),
-            taker_fee_amount: Uint128::new(10),
+            maker_side: OrderType::Sell,
+            buyer_fee: Uint128::new(20),
+            seller_fee: Uint128::new(10),
         };
 
         let trade3 = Trade {
@@ -6382,11 +11562,12 @@ mod tests {
             buyer: Addr::unchecked("buyer"),
             seller: Addr::unchecked("seller"),
             token_amount: Uint128::new(150),
-            price: Uint128::new(15),
+            price: Price::new(Uint128::new(15), 0),
             timestamp: env.block.time.seconds(),
             total_price: Uint128::new(2250),
-            maker_fee_amount: Uint128::new(15),
-            taker_fee_amount: Uint128::new(7),
+            maker_side: OrderType::Sell,
+            buyer_fee: Uint128::new(15),
+            seller_fee: Uint128::new(7),
         };
 
         TRADES.save(deps.as_mut().storage, 1, &trade1).unwrap();
@@ -6408,11 +11589,15 @@ mod tests {
 
         // Initialize token pair
         let token_pair = TokenPair {
-            base_token: "token1".to_string(),
-            quote_token: "token2".to_string(),
+            base_token: Denom::Native("token1".to_string()),
+            quote_token: Denom::Cw20(Addr::unchecked("token2")),
             base_decimals: 6,
             quote_decimals: 8,
             enabled: true,
+            tick_size: Uint128::from(5u128),
+            lot_size: Uint128::from(100u128),
+            min_notional: Uint128::from(1_000u128),
+            order_deposit: Uint128::zero(),
         };
 
         TOKEN_PAIRS
@@ -6424,6 +11609,9 @@ mod tests {
 
         // Verify response
         assert_eq!(response.token_pair, token_pair);
+        assert_eq!(response.token_pair.tick_size, Uint128::from(5u128));
+        assert_eq!(response.token_pair.lot_size, Uint128::from(100u128));
+        assert_eq!(response.token_pair.min_notional, Uint128::from(1_000u128));
     }
 
     #[test]
@@ -6447,19 +11635,27 @@ mod tests {
 
         // Initialize token pairs
         let token_pair1 = TokenPair {
-            base_token: "token1".to_string(),
-            quote_token: "token2".to_string(),
+            base_token: Denom::Native("token1".to_string()),
+            quote_token: Denom::Cw20(Addr::unchecked("token2")),
             base_decimals: 6,
             quote_decimals: 8,
             enabled: true,
+            tick_size: Uint128::one(),
+            lot_size: Uint128::one(),
+            min_notional: Uint128::zero(),
+            order_deposit: Uint128::zero(),
         };
 
         let token_pair2 = TokenPair {
-            base_token: "token3".to_string(),
-            quote_token: "token4".to_string(),
+            base_token: Denom::Native("token3".to_string()),
+            quote_token: Denom::Cw20(Addr::unchecked("token4")),
             base_decimals: 6,
             quote_decimals: 8,
             enabled: true,
+            tick_size: Uint128::one(),
+            lot_size: Uint128::one(),
+            min_notional: Uint128::zero(),
+            order_deposit: Uint128::zero(),
         };
 
         TOKEN_PAIRS
@@ -6484,19 +11680,27 @@ mod tests {
 
         // Initialize token pairs
         let token_pair1 = TokenPair {
-            base_token: "token1".to_string(),
-            quote_token: "token2".to_string(),
+            base_token: Denom::Native("token1".to_string()),
+            quote_token: Denom::Cw20(Addr::unchecked("token2")),
             base_decimals: 6,
             quote_decimals: 8,
             enabled: true,
+            tick_size: Uint128::one(),
+            lot_size: Uint128::one(),
+            min_notional: Uint128::zero(),
+            order_deposit: Uint128::zero(),
         };
 
         let token_pair2 = TokenPair {
-            base_token: "token3".to_string(),
-            quote_token: "token4".to_string(),
+            base_token: Denom::Native("token3".to_string()),
+            quote_token: Denom::Cw20(Addr::unchecked("token4")),
             base_decimals: 6,
             quote_decimals: 8,
             enabled: true,
+            tick_size: Uint128::one(),
+            lot_size: Uint128::one(),
+            min_notional: Uint128::zero(),
+            order_deposit: Uint128::zero(),
         };
 
         TOKEN_PAIRS
@@ -6528,9 +11732,14 @@ mod tests {
             lp_supply: 20_000_000_000,
             maker_fee: Decimal::percent(1),
             taker_fee: Decimal::percent(1),
+            curve_fee: Decimal::zero(),
             enabled: true,
             secondary_amm_address: Addr::unchecked("secondary_amm"),
-            base_token_denom: "ubase_token".to_string(),
+            base_token_denom: Denom::Native("ubase_token".to_string()),
+            base_token_symbol: "huahua".to_string(),
+            base_token_decimals: 6,
+            token_creation_fee: Uint128::zero(),
+            lp_policy: LpPolicy::Burn,
         };
 
         CONFIG.save(deps.as_mut().storage, &config).unwrap();
@@ -6560,19 +11769,27 @@ mod tests {
 
         // Initialize token pairs
         let token_pair1 = TokenPair {
-            base_token: "token1".to_string(),
-            quote_token: "token2".to_string(),
+            base_token: Denom::Native("token1".to_string()),
+            quote_token: Denom::Cw20(Addr::unchecked("token2")),
             base_decimals: 6,
             quote_decimals: 8,
             enabled: true,
+            tick_size: Uint128::one(),
+            lot_size: Uint128::one(),
+            min_notional: Uint128::zero(),
+            order_deposit: Uint128::zero(),
         };
 
         let token_pair2 = TokenPair {
-            base_token: "token3".to_string(),
-            quote_token: "token4".to_string(),
+            base_token: Denom::Native("token3".to_string()),
+            quote_token: Denom::Cw20(Addr::unchecked("token4")),
             base_decimals: 6,
             quote_decimals: 8,
             enabled: true,
+            tick_size: Uint128::one(),
+            lot_size: Uint128::one(),
+            min_notional: Uint128::zero(),
+            order_deposit: Uint128::zero(),
         };
 
         TOKEN_PAIRS
@@ -6592,8 +11809,11 @@ mod tests {
             total_volume: Uint128::new(10000),
             total_trades: Uint128::new(50),
             total_fees_collected: Uint128::new(500),
-            last_price: Uint128::new(10),
+            last_price: Price::new(Uint128::new(10), 0),
             enabled: true,
+            launch: LaunchSettings::default(),
+            launch_height: 0,
+            base_currency: "huahua".to_string(),
         };
 
         let pool2 = Pool {
@@ -6605,8 +11825,11 @@ mod tests {
             total_volume: Uint128::new(20000),
             total_trades: Uint128::new(100),
             total_fees_collected: Uint128::new(1000),
-            last_price: Uint128::new(20),
+            last_price: Price::new(Uint128::new(20), 0),
             enabled: true,
+            launch: LaunchSettings::default(),
+            launch_height: 0,
+            base_currency: "huahua".to_string(),
         };
 
         POOLS
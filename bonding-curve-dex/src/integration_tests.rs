@@ -0,0 +1,1845 @@
+use cosmwasm_std::testing::{MockApi, MockStorage};
+use cosmwasm_std::{
+    coin, coins, to_json_binary, Addr, Api, BankMsg, Binary, BlockInfo, CustomMsg, CustomQuery,
+    Decimal, Deps, DepsMut, Empty, Env, HexBinary, MessageInfo, Response, StdError, StdResult,
+    Storage, Uint128, WasmMsg,
+};
+use cw20::{
+    BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg, Denom, MarketingInfoResponse, UncheckedDenom,
+};
+use cw_multi_test::error::{bail, AnyResult};
+use cw_multi_test::{
+    App, AppBuilder, AppResponse, BankKeeper, BankSudo, Contract, ContractWrapper, CosmosRouter,
+    DistributionKeeper, Executor, FailingModule, GovFailingModule, IbcFailingModule, StakeKeeper,
+    Stargate, WasmKeeper,
+};
+use prost::Message;
+use serde::de::DeserializeOwned;
+use token_factory::msg::{
+    ExecuteMsg as FactoryExecuteMsg, GetAllowedCallersResponse, GetTokenAddressResponse,
+    GetTokenInfoResponse as FactoryTokenInfoResponse, InstantiateMsg as FactoryInstantiateMsg,
+    PredictTokenAddressResponse, QueryMsg as FactoryQueryMsg,
+};
+use token_factory::state::{
+    MsgMint, TokenKind, TokenMetadata, MSG_CREATE_DENOM_TYPE_URL, MSG_MINT_TYPE_URL,
+    MSG_SET_DENOM_METADATA_TYPE_URL, PENDING_CREATIONS,
+};
+
+use crate::msg::{
+    BaseCurrencyMsg, ExecuteMsg, GetConfigResponse, GetGraduationRecordResponse, GetOrderResponse,
+    GetPoolResponse, GetTokenInfoResponse, GetTokenPairResponse, GetTreasuryResponse,
+    GetVestingScheduleResponse, InitialBuy, InstantiateMsg, ProvideLiquidityResponse, QueryMsg,
+    SecondaryAmmMsg, SimulateSwapOperationsResponse, SwapOperation, VestingAllocation,
+};
+use crate::price::Price;
+use crate::state::{denom_id, LpPolicy, OrderType};
+
+const DENOM: &str = "uhuahua";
+
+fn cw20_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(
+        cw20_base::contract::execute,
+        cw20_base::contract::instantiate,
+        cw20_base::contract::query,
+    ))
+}
+
+fn factory_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(
+        ContractWrapper::new(
+            token_factory::contract::execute,
+            token_factory::contract::instantiate,
+            token_factory::contract::query,
+        )
+        .with_reply(token_factory::contract::reply),
+    )
+}
+
+fn dex_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(
+        ContractWrapper::new(
+            crate::contract::execute,
+            crate::contract::instantiate,
+            crate::contract::query,
+        )
+        .with_reply(crate::contract::reply),
+    )
+}
+
+// Stand-in for the secondary AMM. It pays LP_DENOM for liquidity and fills swaps
+// at AMM_RATE asked tokens per offered one, out of what it holds.
+const LP_DENOM: &str = "ulp";
+const AMM_RATE: u128 = 10;
+
+fn amm_contract() -> Box<dyn Contract<Empty>> {
+    fn instantiate(_: DepsMut, _: Env, _: MessageInfo, _: Empty) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+
+    fn execute(
+        _: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        msg: SecondaryAmmMsg,
+    ) -> StdResult<Response> {
+        // CW20 inputs come from the caller's allowance, native ones with the call
+        let pull = |denom: &Denom, amount: Uint128| -> StdResult<Vec<WasmMsg>> {
+            Ok(match denom {
+                Denom::Cw20(token) => vec![WasmMsg::Execute {
+                    contract_addr: token.to_string(),
+                    msg: to_json_binary(&Cw20ExecuteMsg::TransferFrom {
+                        owner: info.sender.to_string(),
+                        recipient: env.contract.address.to_string(),
+                        amount,
+                    })?,
+                    funds: vec![],
+                }],
+                Denom::Native(denom) => {
+                    let paid = cw_utils::must_pay(&info, denom)
+                        .map_err(|e| StdError::generic_err(e.to_string()))?;
+                    if paid != amount {
+                        return Err(StdError::generic_err("Wrong native amount"));
+                    }
+                    vec![]
+                }
+            })
+        };
+        match msg {
+            SecondaryAmmMsg::ProvideLiquidity {
+                token_denom,
+                token_amount,
+                ..
+            } => {
+                let lp_amount = Uint128::new(1_000);
+                Ok(Response::new()
+                    .add_messages(pull(&token_denom, token_amount)?)
+                    .add_message(BankMsg::Send {
+                        to_address: info.sender.to_string(),
+                        amount: coins(lp_amount.u128(), LP_DENOM),
+                    })
+                    .set_data(to_json_binary(&ProvideLiquidityResponse {
+                        lp_token: Denom::Native(LP_DENOM.to_string()),
+                        lp_amount,
+                    })?))
+            }
+            SecondaryAmmMsg::Swap {
+                offer_denom,
+                offer_amount,
+                ask_denom,
+                min_output,
+            } => {
+                let output = offer_amount * Uint128::new(AMM_RATE);
+                if output < min_output {
+                    return Err(StdError::generic_err("Output below minimum"));
+                }
+                let Denom::Cw20(ask_token) = ask_denom else {
+                    return Err(StdError::generic_err("Only CW20 outputs are supported"));
+                };
+                Ok(Response::new()
+                    .add_messages(pull(&offer_denom, offer_amount)?)
+                    .add_message(WasmMsg::Execute {
+                        contract_addr: ask_token.to_string(),
+                        msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                            recipient: info.sender.to_string(),
+                            amount: output,
+                        })?,
+                        funds: vec![],
+                    }))
+            }
+        }
+    }
+
+    fn query(_: Deps, _: Env, _: Empty) -> StdResult<Binary> {
+        Err(StdError::generic_err("No queries"))
+    }
+
+    Box::new(ContractWrapper::new(execute, instantiate, query))
+}
+
+// Stand-in for the chain's x/tokenfactory module, minting straight through the bank
+struct TokenFactoryModule;
+
+impl Stargate for TokenFactoryModule {
+    fn execute_stargate<ExecC, QueryC>(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+        _sender: Addr,
+        type_url: String,
+        value: Binary,
+    ) -> AnyResult<AppResponse>
+    where
+        ExecC: CustomMsg + DeserializeOwned + 'static,
+        QueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        match type_url.as_str() {
+            MSG_CREATE_DENOM_TYPE_URL | MSG_SET_DENOM_METADATA_TYPE_URL => {
+                Ok(AppResponse::default())
+            }
+            MSG_MINT_TYPE_URL => {
+                let msg = MsgMint::decode(value.as_slice())?;
+                let Some(amount) = msg.amount else {
+                    bail!("MsgMint without an amount");
+                };
+                let mint = BankSudo::Mint {
+                    to_address: msg.mint_to_address,
+                    amount: coins(amount.amount.parse()?, amount.denom),
+                };
+                router.sudo(api, storage, block, mint.into())
+            }
+            _ => bail!("Unexpected stargate message {}", type_url),
+        }
+    }
+}
+
+type TestApp = App<
+    BankKeeper,
+    MockApi,
+    MockStorage,
+    FailingModule<Empty, Empty, Empty>,
+    WasmKeeper<Empty, Empty>,
+    StakeKeeper,
+    DistributionKeeper,
+    IbcFailingModule,
+    GovFailingModule,
+    TokenFactoryModule,
+>;
+
+struct Suite {
+    app: TestApp,
+    creator: Addr,
+    factory: Addr,
+    dex: Addr,
+}
+
+// Stores the codes and deploys the factory and AMM, then the DEX against them
+fn setup() -> Suite {
+    let api = MockApi::default();
+    let owner = api.addr_make("owner");
+    let creator = api.addr_make("creator");
+    let fee_collector = api.addr_make("fee_collector");
+    let mut app =
+        AppBuilder::new()
+            .with_stargate(TokenFactoryModule)
+            .build(|router, _, storage| {
+                router
+                    .bank
+                    .init_balance(storage, &creator, coins(1_000_000, DENOM))
+                    .unwrap();
+            });
+
+    let cw20_id = app.store_code(cw20_contract());
+    let factory_id = app.store_code(factory_contract());
+    let dex_id = app.store_code(dex_contract());
+    let amm_id = app.store_code(amm_contract());
+    let checksum = app.wrap().query_wasm_code_info(cw20_id).unwrap().checksum;
+
+    let factory = app
+        .instantiate_contract(
+            factory_id,
+            owner.clone(),
+            &FactoryInstantiateMsg {
+                token_code_id: cw20_id,
+                token_code_hash: HexBinary::from(checksum.as_slice()),
+                allowed_callers: vec![],
+            },
+            &[],
+            "token-factory",
+            None,
+        )
+        .unwrap();
+
+    let amm = app
+        .instantiate_contract(amm_id, owner.clone(), &Empty {}, &[], "amm", None)
+        .unwrap();
+
+    let dex = app
+        .instantiate_contract(
+            dex_id,
+            owner.clone(),
+            &InstantiateMsg {
+                token_factory: factory.clone(),
+                fee_collector,
+                quote_token_total_supply: Uint128::new(1_000_000_000),
+                bonding_curve_supply: Uint128::new(800_000_000),
+                lp_supply: Uint128::new(200_000_000),
+                maker_fee: Decimal::permille(1),
+                taker_fee: Decimal::permille(2),
+                curve_fee: Decimal::zero(),
+                secondary_amm_address: amm,
+                base_token_denom: Denom::Native(DENOM.to_string()),
+                base_token_symbol: "huahua".to_string(),
+                base_token_decimals: 6,
+                min_order_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
+                token_creation_fee: Uint128::new(1_000),
+                lp_policy: LpPolicy::Burn,
+            },
+            &[],
+            "bonding-curve-dex",
+            None,
+        )
+        .unwrap();
+
+    // Instantiated by the factory owner, the DEX registered itself
+    let allowed: GetAllowedCallersResponse = app
+        .wrap()
+        .query_wasm_smart(&factory, &FactoryQueryMsg::GetAllowedCallers {})
+        .unwrap();
+    assert_eq!(allowed.allowed_callers, vec![dex.clone()]);
+
+    Suite {
+        app,
+        creator,
+        factory,
+        dex,
+    }
+}
+
+#[test]
+fn create_token_through_factory() {
+    let Suite {
+        mut app,
+        creator,
+        factory,
+        dex,
+    } = setup();
+
+    let metadata = TokenMetadata {
+        description: Some("Much wow".to_string()),
+        project_url: Some("https://woof.example".to_string()),
+        logo_url: None,
+        socials: vec![],
+    };
+    let predicted = app
+        .wrap()
+        .query_wasm_smart::<PredictTokenAddressResponse>(
+            &factory,
+            &FactoryQueryMsg::PredictTokenAddress {
+                name: "Woof".to_string(),
+                symbol: "WOOF".to_string(),
+            },
+        )
+        .unwrap()
+        .address;
+    app.execute_contract(
+        creator.clone(),
+        dex.clone(),
+        &ExecuteMsg::CreateToken {
+            name: "Woof".to_string(),
+            symbol: "WOOF".to_string(),
+            decimals: 6,
+            uri: "ipfs://woof".to_string(),
+            max_price_impact: Uint128::new(25),
+            curve_slope: Uint128::new(3),
+            initial_buy: None,
+            launch: None,
+            metadata: Some(metadata.clone()),
+            kind: None,
+            base_currency: None,
+            vesting: None,
+        },
+        &coins(1_000, DENOM),
+    )
+    .unwrap();
+
+    let token_address = app
+        .wrap()
+        .query_wasm_smart::<GetTokenAddressResponse>(
+            &factory,
+            &FactoryQueryMsg::GetTokenAddress {
+                name: "Woof".to_string(),
+                symbol: "WOOF".to_string(),
+            },
+        )
+        .unwrap()
+        .address;
+    assert_eq!(token_address, predicted);
+
+    // The DEX sets the pool up with the parameters given to CreateToken
+    let pool = app
+        .wrap()
+        .query_wasm_smart::<GetPoolResponse>(
+            &dex,
+            &QueryMsg::GetPool {
+                token_address: token_address.to_string(),
+            },
+        )
+        .unwrap()
+        .pool;
+    assert_eq!(pool.curve_slope, Uint128::new(3));
+    assert_eq!(pool.pair_id, "WOOF/huahua");
+    let token_info = app
+        .wrap()
+        .query_wasm_smart::<GetTokenInfoResponse>(
+            &dex,
+            &QueryMsg::GetTokenInfo {
+                token_address: token_address.to_string(),
+            },
+        )
+        .unwrap()
+        .token_info;
+    assert_eq!(token_info.max_price_impact, Uint128::new(25));
+    assert_eq!(
+        token_info.bonding_curve_supply,
+        Uint128::new(800_000_000_000_000)
+    );
+    assert_eq!(token_info.lp_supply, Uint128::new(200_000_000_000_000));
+
+    // Supply changes must stay consistent and only reach tokens launched later
+    let owner = app.api().addr_make("owner");
+    let update_supplies = |app: &mut TestApp, bonding_curve_supply: u128| {
+        app.execute_contract(
+            owner.clone(),
+            dex.clone(),
+            &ExecuteMsg::UpdateConfig {
+                token_factory: None,
+                fee_collector: None,
+                maker_fee: None,
+                taker_fee: None,
+                quote_token_total_supply: None,
+                bonding_curve_supply: Some(Uint128::new(bonding_curve_supply)),
+                lp_supply: None,
+                enabled: None,
+                token_creation_fee: None,
+                lp_policy: None,
+            },
+            &[],
+        )
+    };
+    let err = update_supplies(&mut app, 900_000_000).unwrap_err();
+    assert!(err.root_cause().to_string().contains("must fit within"));
+    update_supplies(&mut app, 700_000_000).unwrap();
+    let snapshot = app
+        .wrap()
+        .query_wasm_smart::<GetTokenInfoResponse>(
+            &dex,
+            &QueryMsg::GetTokenInfo {
+                token_address: token_address.to_string(),
+            },
+        )
+        .unwrap()
+        .token_info;
+    assert_eq!(snapshot, token_info);
+
+    // The factory records the user as creator and drops the pending parameters
+    let factory_info = app
+        .wrap()
+        .query_wasm_smart::<FactoryTokenInfoResponse>(
+            &factory,
+            &FactoryQueryMsg::GetTokenInfo {
+                address: token_address.to_string(),
+            },
+        )
+        .unwrap()
+        .token_info;
+    assert_eq!(factory_info.creator, creator);
+    assert_eq!(factory_info.metadata, metadata);
+    let pending_key = PENDING_CREATIONS.key(token_address.as_str());
+    assert_eq!(
+        app.wrap()
+            .query_wasm_raw(&factory, pending_key.to_vec())
+            .unwrap(),
+        None
+    );
+
+    // The CW20 carries the marketing info and its whole supply sits in the DEX
+    let marketing: MarketingInfoResponse = app
+        .wrap()
+        .query_wasm_smart(&token_address, &Cw20QueryMsg::MarketingInfo {})
+        .unwrap();
+    assert_eq!(marketing.project, metadata.project_url);
+    assert_eq!(marketing.description, metadata.description);
+    assert_eq!(marketing.marketing, Some(factory));
+    let balance: BalanceResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &token_address,
+            &Cw20QueryMsg::Balance {
+                address: dex.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(balance.balance, factory_info.total_supply);
+
+    // The creation fee sits in the treasury until it is distributed
+    let fee = app.wrap().query_balance(&dex, DENOM).unwrap().amount;
+    assert!(!fee.is_zero());
+    app.execute_contract(
+        creator.clone(),
+        dex.clone(),
+        &ExecuteMsg::DistributeFees {},
+        &[],
+    )
+    .unwrap();
+    let fee_collector = app.api().addr_make("fee_collector");
+    assert_eq!(
+        app.wrap()
+            .query_balance(&fee_collector, DENOM)
+            .unwrap()
+            .amount,
+        fee
+    );
+    assert!(app
+        .wrap()
+        .query_balance(&dex, DENOM)
+        .unwrap()
+        .amount
+        .is_zero());
+
+    // Another token with the same symbol would land on the live WOOF/huahua pair
+    let err = app
+        .execute_contract(
+            creator.clone(),
+            dex.clone(),
+            &ExecuteMsg::CreateToken {
+                name: "Woof Two".to_string(),
+                symbol: "WOOF".to_string(),
+                decimals: 6,
+                uri: "ipfs://woof2".to_string(),
+                max_price_impact: Uint128::new(25),
+                curve_slope: Uint128::new(3),
+                initial_buy: None,
+                launch: None,
+                metadata: None,
+                kind: None,
+                base_currency: None,
+                vesting: None,
+            },
+            &coins(1_000, DENOM),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Generic error: Trading pair already exists"
+    );
+}
+
+#[test]
+fn dex_deployed_by_another_account() {
+    let Suite {
+        mut app,
+        creator,
+        factory,
+        dex,
+    } = setup();
+    let owner = app.api().addr_make("owner");
+    let deployer = app.api().addr_make("deployer");
+
+    // The factory refuses the registration, the deployment still goes through
+    let dex_id = app.wrap().query_wasm_contract_info(&dex).unwrap().code_id;
+    let config = app
+        .wrap()
+        .query_wasm_smart::<GetConfigResponse>(&dex, &QueryMsg::GetConfig {})
+        .unwrap()
+        .config;
+    let second_dex = app
+        .instantiate_contract(
+            dex_id,
+            deployer,
+            &InstantiateMsg {
+                token_factory: factory.clone(),
+                fee_collector: config.fee_collector,
+                quote_token_total_supply: Uint128::new(config.quote_token_total_supply),
+                bonding_curve_supply: Uint128::new(config.bonding_curve_supply),
+                lp_supply: Uint128::new(config.lp_supply),
+                maker_fee: config.maker_fee,
+                taker_fee: config.taker_fee,
+                curve_fee: config.curve_fee,
+                secondary_amm_address: config.secondary_amm_address,
+                base_token_denom: config.base_token_denom,
+                base_token_symbol: config.base_token_symbol,
+                base_token_decimals: config.base_token_decimals,
+                min_order_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
+                token_creation_fee: Uint128::new(1_000),
+                lp_policy: LpPolicy::Burn,
+            },
+            &[],
+            "second-dex",
+            None,
+        )
+        .unwrap();
+    let allowed = |app: &TestApp| {
+        app.wrap()
+            .query_wasm_smart::<GetAllowedCallersResponse>(
+                &factory,
+                &FactoryQueryMsg::GetAllowedCallers {},
+            )
+            .unwrap()
+            .allowed_callers
+    };
+    assert_eq!(allowed(&app), vec![dex]);
+
+    // The factory owner allows it, then it launches tokens
+    app.execute_contract(
+        owner,
+        factory.clone(),
+        &FactoryExecuteMsg::UpdateAllowedCallers {
+            add: vec![second_dex.to_string()],
+            remove: vec![],
+        },
+        &[],
+    )
+    .unwrap();
+    assert!(allowed(&app).contains(&second_dex));
+    app.execute_contract(
+        creator,
+        second_dex,
+        &ExecuteMsg::CreateToken {
+            name: "Woof".to_string(),
+            symbol: "WOOF".to_string(),
+            decimals: 6,
+            uri: "ipfs://woof".to_string(),
+            max_price_impact: Uint128::new(25),
+            curve_slope: Uint128::new(3),
+            initial_buy: None,
+            launch: None,
+            metadata: None,
+            kind: None,
+            base_currency: None,
+            vesting: None,
+        },
+        &coins(1_000, DENOM),
+    )
+    .unwrap();
+}
+
+#[test]
+fn creator_dev_buy_runs_in_the_launch() {
+    let Suite {
+        mut app,
+        creator,
+        factory,
+        dex,
+    } = setup();
+    let create = |app: &mut TestApp, min_tokens_out: Uint128| {
+        app.execute_contract(
+            creator.clone(),
+            dex.clone(),
+            &ExecuteMsg::CreateToken {
+                name: "Woof".to_string(),
+                symbol: "WOOF".to_string(),
+                decimals: 6,
+                uri: "ipfs://woof".to_string(),
+                max_price_impact: Uint128::new(25),
+                curve_slope: Uint128::new(3),
+                initial_buy: Some(InitialBuy {
+                    amount: Uint128::new(10_000),
+                    min_tokens_out,
+                }),
+                launch: None,
+                metadata: None,
+                kind: None,
+                base_currency: None,
+                vesting: None,
+            },
+            &coins(11_000, DENOM),
+        )
+    };
+
+    // An unmet minimum reverts the whole launch, fee included
+    let err = create(&mut app, Uint128::MAX).unwrap_err();
+    assert!(err.root_cause().to_string().contains("Slippage"));
+    assert!(app
+        .wrap()
+        .query_wasm_smart::<GetTokenAddressResponse>(
+            &factory,
+            &FactoryQueryMsg::GetTokenAddress {
+                name: "Woof".to_string(),
+                symbol: "WOOF".to_string(),
+            },
+        )
+        .is_err());
+    assert_eq!(
+        app.wrap().query_balance(&creator, DENOM).unwrap().amount,
+        Uint128::new(1_000_000)
+    );
+
+    create(&mut app, Uint128::one()).unwrap();
+    let token_address = app
+        .wrap()
+        .query_wasm_smart::<GetTokenAddressResponse>(
+            &factory,
+            &FactoryQueryMsg::GetTokenAddress {
+                name: "Woof".to_string(),
+                symbol: "WOOF".to_string(),
+            },
+        )
+        .unwrap()
+        .address;
+
+    // The creator holds everything the curve sold, paid for by the dev buy
+    let pool = app
+        .wrap()
+        .query_wasm_smart::<GetPoolResponse>(
+            &dex,
+            &QueryMsg::GetPool {
+                token_address: token_address.to_string(),
+            },
+        )
+        .unwrap()
+        .pool;
+    assert_eq!(pool.total_reserve_token, Uint128::new(10_000));
+    assert!(!pool.token_sold.is_zero());
+    let balance: BalanceResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &token_address,
+            &Cw20QueryMsg::Balance {
+                address: creator.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(balance.balance, pool.token_sold);
+    assert_eq!(
+        app.wrap().query_balance(&creator, DENOM).unwrap().amount,
+        Uint128::new(989_000)
+    );
+}
+
+#[test]
+fn native_token_settles_by_bank() {
+    let Suite {
+        mut app,
+        creator,
+        factory,
+        dex,
+    } = setup();
+
+    app.execute_contract(
+        creator.clone(),
+        dex.clone(),
+        &ExecuteMsg::CreateToken {
+            name: "Woof".to_string(),
+            symbol: "WOOF".to_string(),
+            decimals: 6,
+            uri: "ipfs://woof".to_string(),
+            max_price_impact: Uint128::new(25),
+            curve_slope: Uint128::new(3),
+            initial_buy: None,
+            launch: None,
+            metadata: None,
+            kind: Some(TokenKind::Native),
+            base_currency: None,
+            vesting: None,
+        },
+        &coins(1_000, DENOM),
+    )
+    .unwrap();
+
+    // The whole supply is minted as a factory denom and held by the DEX
+    let denom = format!("factory/{}/WOOF", factory);
+    let factory_info = app
+        .wrap()
+        .query_wasm_smart::<FactoryTokenInfoResponse>(
+            &factory,
+            &FactoryQueryMsg::GetTokenInfo {
+                address: denom.clone(),
+            },
+        )
+        .unwrap()
+        .token_info;
+    assert_eq!(factory_info.kind, TokenKind::Native);
+    assert_eq!(
+        app.wrap().query_balance(&dex, &denom).unwrap().amount,
+        factory_info.total_supply
+    );
+    let token_pair = app
+        .wrap()
+        .query_wasm_smart::<GetTokenPairResponse>(
+            &dex,
+            &QueryMsg::GetTokenPair {
+                pair_id: "WOOF/huahua".to_string(),
+            },
+        )
+        .unwrap()
+        .token_pair;
+    assert_eq!(token_pair.quote_token, Denom::Native(denom.clone()));
+
+    // A curve buy pays the tokens out with a bank send
+    app.execute_contract(
+        creator.clone(),
+        dex.clone(),
+        &ExecuteMsg::Swap {
+            pair_id: "WOOF/huahua".to_string(),
+            token_address: denom.clone(),
+            amount: Uint128::new(10_000),
+            min_return: Uint128::new(10_000),
+            order_type: OrderType::Buy,
+        },
+        &coins(10_000, DENOM),
+    )
+    .unwrap();
+    let bought = app.wrap().query_balance(&creator, &denom).unwrap().amount;
+    assert!(!bought.is_zero());
+
+    // A sell order escrows the denom and cancelling hands it back
+    let sell_amount = bought.multiply_ratio(1u128, 2u128);
+    app.execute_contract(
+        creator.clone(),
+        dex.clone(),
+        &ExecuteMsg::PlaceLimitOrder {
+            token_address: "WOOF/huahua".to_string(),
+            amount: sell_amount,
+            price: Price::new(Uint128::new(1_000_000), 6),
+            is_buy: false,
+        },
+        &coins(sell_amount.u128(), &denom),
+    )
+    .unwrap();
+    assert_eq!(
+        app.wrap().query_balance(&creator, &denom).unwrap().amount,
+        bought - sell_amount
+    );
+    app.execute_contract(
+        creator.clone(),
+        dex.clone(),
+        &ExecuteMsg::CancelOrder {
+            order_id: 0,
+            pair_id: "WOOF/huahua".to_string(),
+        },
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        app.wrap().query_balance(&creator, &denom).unwrap().amount,
+        bought
+    );
+
+    // Selling back on the curve takes the denom as funds and pays out the base denom
+    let base_before = app.wrap().query_balance(&creator, DENOM).unwrap().amount;
+    app.execute_contract(
+        creator.clone(),
+        dex.clone(),
+        &ExecuteMsg::Swap {
+            pair_id: "WOOF/huahua".to_string(),
+            token_address: denom.clone(),
+            amount: sell_amount,
+            min_return: Uint128::one(),
+            order_type: OrderType::Sell,
+        },
+        &coins(sell_amount.u128(), &denom),
+    )
+    .unwrap();
+    assert_eq!(
+        app.wrap().query_balance(&creator, &denom).unwrap().amount,
+        bought - sell_amount
+    );
+    assert!(app.wrap().query_balance(&creator, DENOM).unwrap().amount > base_before);
+}
+
+#[test]
+fn cw20_base_pair_settles_by_transfer() {
+    let Suite {
+        mut app,
+        creator,
+        dex,
+        ..
+    } = setup();
+    let owner = app.api().addr_make("owner");
+    let buyer = app.api().addr_make("buyer");
+
+    // A pair quoted in a CW20 stablecoin, trading the native denom
+    let cw20_id = app.store_code(cw20_contract());
+    let usdx = app
+        .instantiate_contract(
+            cw20_id,
+            owner.clone(),
+            &cw20_base::msg::InstantiateMsg {
+                name: "Dollar".to_string(),
+                symbol: "USDX".to_string(),
+                decimals: 6,
+                initial_balances: vec![cw20::Cw20Coin {
+                    address: buyer.to_string(),
+                    amount: Uint128::new(1_000),
+                }],
+                mint: None,
+                marketing: None,
+            },
+            &[],
+            "usdx",
+            None,
+        )
+        .unwrap();
+    app.execute_contract(
+        owner,
+        dex.clone(),
+        &ExecuteMsg::RegisterPair {
+            pair_id: "HUAHUA/USDX".to_string(),
+            base_token: UncheckedDenom::Cw20(usdx.to_string()),
+            quote_token: UncheckedDenom::Native(DENOM.to_string()),
+            base_decimals: 6,
+            quote_decimals: 0,
+        },
+        &[],
+    )
+    .unwrap();
+
+    // The buy escrows its cost with TransferFrom against the buyer's allowance
+    app.execute_contract(
+        buyer.clone(),
+        usdx.clone(),
+        &Cw20ExecuteMsg::IncreaseAllowance {
+            spender: dex.to_string(),
+            amount: Uint128::new(200),
+            expires: None,
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        buyer.clone(),
+        dex.clone(),
+        &ExecuteMsg::PlaceLimitOrder {
+            token_address: "HUAHUA/USDX".to_string(),
+            amount: Uint128::new(100),
+            price: Price::new(Uint128::new(2), 0),
+            is_buy: true,
+        },
+        &[],
+    )
+    .unwrap();
+    let cw20_balance = |app: &TestApp, address: &Addr| {
+        app.wrap()
+            .query_wasm_smart::<BalanceResponse>(
+                &usdx,
+                &Cw20QueryMsg::Balance {
+                    address: address.to_string(),
+                },
+            )
+            .unwrap()
+            .balance
+    };
+    assert_eq!(cw20_balance(&app, &buyer), Uint128::new(800));
+    assert_eq!(cw20_balance(&app, &dex), Uint128::new(200));
+
+    // A matching sell settles the base side with CW20 transfers out of the escrow
+    app.execute_contract(
+        creator.clone(),
+        dex.clone(),
+        &ExecuteMsg::PlaceLimitOrder {
+            token_address: "HUAHUA/USDX".to_string(),
+            amount: Uint128::new(100),
+            price: Price::new(Uint128::new(2), 0),
+            is_buy: false,
+        },
+        &coins(100, DENOM),
+    )
+    .unwrap();
+    // The resting buyer pays the maker fee, the incoming seller the taker fee
+    assert_eq!(
+        app.wrap().query_balance(&buyer, DENOM).unwrap().amount,
+        Uint128::new(99)
+    );
+    assert_eq!(cw20_balance(&app, &creator), Uint128::new(199));
+    // Both fees wait in the DEX until anyone distributes them
+    assert_eq!(cw20_balance(&app, &dex), Uint128::one());
+    app.execute_contract(
+        buyer.clone(),
+        dex.clone(),
+        &ExecuteMsg::DistributeFees {},
+        &[],
+    )
+    .unwrap();
+    let fee_collector = app.api().addr_make("fee_collector");
+    assert_eq!(
+        app.wrap()
+            .query_balance(&fee_collector, DENOM)
+            .unwrap()
+            .amount,
+        Uint128::one()
+    );
+    assert_eq!(cw20_balance(&app, &fee_collector), Uint128::one());
+    assert_eq!(cw20_balance(&app, &dex), Uint128::zero());
+}
+
+#[test]
+fn launch_against_whitelisted_currency() {
+    let Suite {
+        mut app,
+        creator,
+        dex,
+        ..
+    } = setup();
+    let owner = app.api().addr_make("owner");
+    app.sudo(
+        BankSudo::Mint {
+            to_address: creator.to_string(),
+            amount: coins(10_000, "uatom"),
+        }
+        .into(),
+    )
+    .unwrap();
+
+    app.execute_contract(
+        owner,
+        dex.clone(),
+        &ExecuteMsg::UpdateBaseCurrencies {
+            add: vec![BaseCurrencyMsg {
+                symbol: "atom".to_string(),
+                denom: UncheckedDenom::Native("uatom".to_string()),
+                decimals: 6,
+                base_price: Uint128::new(1_000),
+                graduation_reserve: Uint128::new(5_000_000),
+                min_order_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
+            }],
+            remove: vec![],
+        },
+        &[],
+    )
+    .unwrap();
+
+    // The fee is paid in the default currency, the dev buy in the picked one
+    app.execute_contract(
+        creator.clone(),
+        dex.clone(),
+        &ExecuteMsg::CreateToken {
+            name: "Woof".to_string(),
+            symbol: "WOOF".to_string(),
+            decimals: 6,
+            uri: "ipfs://woof".to_string(),
+            max_price_impact: Uint128::new(25),
+            curve_slope: Uint128::new(3),
+            initial_buy: Some(InitialBuy {
+                amount: Uint128::new(10_000),
+                min_tokens_out: Uint128::one(),
+            }),
+            launch: None,
+            metadata: None,
+            kind: Some(TokenKind::Native),
+            base_currency: Some("atom".to_string()),
+            vesting: None,
+        },
+        &[coin(1_000, DENOM), coin(10_000, "uatom")],
+    )
+    .unwrap();
+
+    let token_pair = app
+        .wrap()
+        .query_wasm_smart::<GetTokenPairResponse>(
+            &dex,
+            &QueryMsg::GetTokenPair {
+                pair_id: "WOOF/atom".to_string(),
+            },
+        )
+        .unwrap()
+        .token_pair;
+    assert_eq!(token_pair.base_token, Denom::Native("uatom".to_string()));
+    let denom = denom_id(&token_pair.quote_token).to_string();
+    let pool = app
+        .wrap()
+        .query_wasm_smart::<GetPoolResponse>(
+            &dex,
+            &QueryMsg::GetPool {
+                token_address: denom.clone(),
+            },
+        )
+        .unwrap()
+        .pool;
+    assert_eq!(pool.base_currency, "atom");
+    assert_eq!(pool.total_reserve_token, Uint128::new(10_000));
+    let token_info = app
+        .wrap()
+        .query_wasm_smart::<GetTokenInfoResponse>(
+            &dex,
+            &QueryMsg::GetTokenInfo {
+                token_address: denom.clone(),
+            },
+        )
+        .unwrap()
+        .token_info;
+    assert_eq!(token_info.graduation_reserve, Uint128::new(5_000_000));
+    assert_eq!(
+        app.wrap().query_balance(&dex, "uatom").unwrap().amount,
+        Uint128::new(10_000)
+    );
+    assert!(!app
+        .wrap()
+        .query_balance(&creator, &denom)
+        .unwrap()
+        .amount
+        .is_zero());
+}
+
+#[test]
+fn order_rules_follow_the_pair_currency() {
+    let Suite {
+        mut app,
+        creator,
+        dex,
+        ..
+    } = setup();
+    let owner = app.api().addr_make("owner");
+    // One whole token of an 18 decimals currency
+    let wide = 1_000_000_000_000_000_000u128;
+    app.sudo(
+        BankSudo::Mint {
+            to_address: creator.to_string(),
+            amount: coins(2 * wide, "uwide"),
+        }
+        .into(),
+    )
+    .unwrap();
+
+    // Each currency carries its own rules, in its own atomics
+    let currency =
+        |symbol: &str, denom: &str, decimals, base_price, min_notional, deposit| BaseCurrencyMsg {
+            symbol: symbol.to_string(),
+            denom: UncheckedDenom::Native(denom.to_string()),
+            decimals,
+            base_price: Uint128::new(base_price),
+            graduation_reserve: Uint128::zero(),
+            min_order_notional: Uint128::new(min_notional),
+            order_deposit: Uint128::new(deposit),
+        };
+    app.execute_contract(
+        owner,
+        dex.clone(),
+        &ExecuteMsg::UpdateBaseCurrencies {
+            add: vec![
+                currency("huahua", DENOM, 6, 100, 1_000, 100),
+                currency("wide", "uwide", 18, wide / 1_000, wide / 100, wide / 1_000),
+            ],
+            remove: vec![],
+        },
+        &[],
+    )
+    .unwrap();
+    for (symbol, base_currency) in [("WOOF", None), ("BARK", Some("wide".to_string()))] {
+        app.execute_contract(
+            creator.clone(),
+            dex.clone(),
+            &ExecuteMsg::CreateToken {
+                name: symbol.to_string(),
+                symbol: symbol.to_string(),
+                decimals: 6,
+                uri: "ipfs://launch".to_string(),
+                max_price_impact: Uint128::new(25),
+                curve_slope: Uint128::new(3),
+                initial_buy: None,
+                launch: None,
+                metadata: None,
+                kind: None,
+                base_currency,
+                vesting: None,
+            },
+            &coins(1_000, DENOM),
+        )
+        .unwrap();
+    }
+    let token_pair = |app: &TestApp, pair_id: &str| {
+        app.wrap()
+            .query_wasm_smart::<GetTokenPairResponse>(
+                &dex,
+                &QueryMsg::GetTokenPair {
+                    pair_id: pair_id.to_string(),
+                },
+            )
+            .unwrap()
+            .token_pair
+    };
+    let woof = token_pair(&app, "WOOF/huahua");
+    assert_eq!(woof.min_notional, Uint128::new(1_000));
+    assert_eq!(woof.order_deposit, Uint128::new(100));
+    let bark = token_pair(&app, "BARK/wide");
+    assert_eq!(bark.min_notional, Uint128::new(wide / 100));
+    assert_eq!(bark.order_deposit, Uint128::new(wide / 1_000));
+
+    // A dust order on the wide pair is measured against its own minimum
+    let place_buy = |app: &mut TestApp, amount: u128, funds: u128| {
+        app.execute_contract(
+            creator.clone(),
+            dex.clone(),
+            &ExecuteMsg::PlaceLimitOrder {
+                token_address: "BARK/wide".to_string(),
+                amount: Uint128::new(amount),
+                price: Price::new(Uint128::new(wide / 1_000), 6),
+                is_buy: true,
+            },
+            &coins(funds, "uwide"),
+        )
+    };
+    let err = place_buy(&mut app, 1_000_000, wide).unwrap_err();
+    assert!(err
+        .root_cause()
+        .to_string()
+        .contains("Order notional below minimum"));
+
+    // 1000 BARK at 0.001 escrow one wide token plus the wide deposit
+    place_buy(&mut app, 1_000_000_000, wide + wide / 1_000).unwrap();
+    let order = app
+        .wrap()
+        .query_wasm_smart::<GetOrderResponse>(&dex, &QueryMsg::GetOrder { order_id: 0 })
+        .unwrap()
+        .order;
+    assert_eq!(order.deposit, Uint128::new(wide / 1_000));
+    app.execute_contract(
+        creator.clone(),
+        dex.clone(),
+        &ExecuteMsg::CancelOrder {
+            order_id: 0,
+            pair_id: "BARK/wide".to_string(),
+        },
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        app.wrap().query_balance(&creator, "uwide").unwrap().amount,
+        Uint128::new(2 * wide)
+    );
+}
+
+#[test]
+fn route_sells_one_launch_into_another() {
+    let Suite {
+        mut app,
+        creator,
+        factory,
+        dex,
+    } = setup();
+    let trader = app.api().addr_make("trader");
+
+    for symbol in ["WOOF", "MEOW"] {
+        app.execute_contract(
+            creator.clone(),
+            dex.clone(),
+            &ExecuteMsg::CreateToken {
+                name: symbol.to_string(),
+                symbol: symbol.to_string(),
+                decimals: 6,
+                uri: "ipfs://token".to_string(),
+                max_price_impact: Uint128::new(25),
+                curve_slope: Uint128::new(3),
+                initial_buy: Some(InitialBuy {
+                    amount: Uint128::new(10_000),
+                    min_tokens_out: Uint128::one(),
+                }),
+                launch: None,
+                metadata: None,
+                kind: Some(TokenKind::Native),
+                base_currency: None,
+                vesting: None,
+            },
+            &coins(11_000, DENOM),
+        )
+        .unwrap();
+    }
+    let woof = format!("factory/{}/WOOF", factory);
+    let meow = format!("factory/{}/MEOW", factory);
+
+    // A resting MEOW sell order is crossed before the curve
+    app.execute_contract(
+        creator.clone(),
+        dex.clone(),
+        &ExecuteMsg::PlaceLimitOrder {
+            token_address: "MEOW/huahua".to_string(),
+            amount: Uint128::new(1_000),
+            price: Price::new(Uint128::new(1_000_000), 6),
+            is_buy: false,
+        },
+        &coins(1_000, &meow),
+    )
+    .unwrap();
+
+    let offer = app
+        .wrap()
+        .query_balance(&creator, &woof)
+        .unwrap()
+        .amount
+        .multiply_ratio(1u128, 2u128);
+    app.send_tokens(creator.clone(), trader.clone(), &coins(offer.u128(), &woof))
+        .unwrap();
+
+    let operations = vec![
+        SwapOperation {
+            pair_id: "WOOF/huahua".to_string(),
+            order_type: OrderType::Sell,
+        },
+        SwapOperation {
+            pair_id: "MEOW/huahua".to_string(),
+            order_type: OrderType::Buy,
+        },
+    ];
+    let simulated = app
+        .wrap()
+        .query_wasm_smart::<SimulateSwapOperationsResponse>(
+            &dex,
+            &QueryMsg::SimulateSwapOperations {
+                offer_amount: offer,
+                operations: operations.clone(),
+            },
+        )
+        .unwrap()
+        .amount;
+    assert!(simulated > Uint128::new(1_000));
+
+    // Only the final output is checked against the minimum
+    let err = app
+        .execute_contract(
+            trader.clone(),
+            dex.clone(),
+            &ExecuteMsg::ExecuteSwapOperations {
+                operations: operations.clone(),
+                offer_amount: offer,
+                minimum_receive: simulated + Uint128::one(),
+            },
+            &coins(offer.u128(), &woof),
+        )
+        .unwrap_err();
+    assert!(err.root_cause().to_string().contains("Slippage"));
+
+    // Hops must chain through the denom the previous one returned
+    let err = app
+        .execute_contract(
+            trader.clone(),
+            dex.clone(),
+            &ExecuteMsg::ExecuteSwapOperations {
+                operations: vec![operations[0].clone(), operations[0].clone()],
+                offer_amount: offer,
+                minimum_receive: Uint128::zero(),
+            },
+            &coins(offer.u128(), &woof),
+        )
+        .unwrap_err();
+    assert!(err.root_cause().to_string().contains("more than once"));
+
+    // Overlong routes are refused before any hop is planned
+    let err = app
+        .execute_contract(
+            trader.clone(),
+            dex.clone(),
+            &ExecuteMsg::ExecuteSwapOperations {
+                operations: vec![operations[0].clone(); 5],
+                offer_amount: offer,
+                minimum_receive: Uint128::zero(),
+            },
+            &coins(offer.u128(), &woof),
+        )
+        .unwrap_err();
+    assert!(err
+        .root_cause()
+        .to_string()
+        .contains("at most 4 swap operations"));
+
+    let creator_base = app.wrap().query_balance(&creator, DENOM).unwrap().amount;
+    let treasury = |app: &TestApp, denom: &str| {
+        app.wrap()
+            .query_wasm_smart::<GetTreasuryResponse>(&dex, &QueryMsg::GetTreasury {})
+            .unwrap()
+            .balances
+            .into_iter()
+            .find(|balance| denom_id(&balance.denom) == denom)
+            .map(|balance| balance.accumulated)
+            .unwrap_or_default()
+    };
+    let treasury_before = (treasury(&app, DENOM), treasury(&app, &meow));
+    app.execute_contract(
+        trader.clone(),
+        dex.clone(),
+        &ExecuteMsg::ExecuteSwapOperations {
+            operations,
+            offer_amount: offer,
+            minimum_receive: simulated,
+        },
+        &coins(offer.u128(), &woof),
+    )
+    .unwrap();
+
+    assert_eq!(
+        app.wrap().query_balance(&trader, &meow).unwrap().amount,
+        simulated
+    );
+    assert!(app
+        .wrap()
+        .query_balance(&trader, &woof)
+        .unwrap()
+        .amount
+        .is_zero());
+    assert!(app
+        .wrap()
+        .query_balance(&trader, DENOM)
+        .unwrap()
+        .amount
+        .is_zero());
+    // The filled maker is paid in the base denom the route carried over, less
+    // its maker fee
+    assert_eq!(
+        app.wrap().query_balance(&creator, DENOM).unwrap().amount,
+        creator_base + Uint128::new(999)
+    );
+    // Routed fills pay the usual fees: the maker's in base, the taker's in MEOW
+    assert_eq!(
+        (treasury(&app, DENOM), treasury(&app, &meow)),
+        (
+            treasury_before.0 + Uint128::one(),
+            treasury_before.1 + Uint128::new(2)
+        )
+    );
+}
+
+#[test]
+fn curve_fees_buy_back_and_burn() {
+    let Suite {
+        mut app,
+        creator,
+        factory,
+        dex,
+    } = setup();
+    let owner = app.api().addr_make("owner");
+
+    app.execute_contract(
+        creator.clone(),
+        dex.clone(),
+        &ExecuteMsg::CreateToken {
+            name: "Woof".to_string(),
+            symbol: "WOOF".to_string(),
+            decimals: 6,
+            uri: "ipfs://woof".to_string(),
+            max_price_impact: Uint128::new(25),
+            curve_slope: Uint128::new(3),
+            initial_buy: None,
+            launch: None,
+            metadata: None,
+            kind: None,
+            base_currency: None,
+            vesting: None,
+        },
+        &coins(1_000, DENOM),
+    )
+    .unwrap();
+    let token_address = app
+        .wrap()
+        .query_wasm_smart::<GetTokenAddressResponse>(
+            &factory,
+            &FactoryQueryMsg::GetTokenAddress {
+                name: "Woof".to_string(),
+                symbol: "WOOF".to_string(),
+            },
+        )
+        .unwrap()
+        .address;
+
+    // Curve trades pay 1%, half of which buys WOOF back
+    app.execute_contract(
+        owner.clone(),
+        dex.clone(),
+        &ExecuteMsg::UpdateFees {
+            maker_fee: None,
+            taker_fee: None,
+            curve_fee: Some(Decimal::percent(1)),
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        owner.clone(),
+        dex.clone(),
+        &ExecuteMsg::SetBuybackPolicy {
+            token_address: token_address.to_string(),
+            share: Decimal::percent(50),
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        creator.clone(),
+        dex.clone(),
+        &ExecuteMsg::Swap {
+            pair_id: "WOOF/huahua".to_string(),
+            token_address: token_address.to_string(),
+            amount: Uint128::new(10_000),
+            min_return: Uint128::one(),
+            order_type: OrderType::Buy,
+        },
+        &coins(10_000, DENOM),
+    )
+    .unwrap();
+
+    let query_buyback = |app: &TestApp| {
+        app.wrap()
+            .query_wasm_smart::<GetPoolResponse>(
+                &dex,
+                &QueryMsg::GetPool {
+                    token_address: token_address.to_string(),
+                },
+            )
+            .unwrap()
+            .buyback
+            .unwrap()
+    };
+    let total_supply = |app: &TestApp| {
+        app.wrap()
+            .query_wasm_smart::<cw20::TokenInfoResponse>(
+                &token_address,
+                &Cw20QueryMsg::TokenInfo {},
+            )
+            .unwrap()
+            .total_supply
+    };
+    assert_eq!(query_buyback(&app).pending, Uint128::new(50));
+    let supply_before = total_supply(&app);
+
+    // The buyback buys on the curve and burns it all, shrinking the CW20 supply
+    app.execute_contract(
+        owner,
+        dex.clone(),
+        &ExecuteMsg::ExecuteBuyback {
+            token_address: token_address.to_string(),
+            min_tokens_out: Uint128::one(),
+        },
+        &[],
+    )
+    .unwrap();
+    let buyback = query_buyback(&app);
+    assert!(buyback.pending.is_zero());
+    assert_eq!(buyback.spent, Uint128::new(50));
+    assert!(!buyback.burned.is_zero());
+    assert_eq!(total_supply(&app), supply_before - buyback.burned);
+}
+
+#[test]
+fn graduated_buyback_swaps_on_the_amm_and_burns() {
+    let Suite {
+        mut app,
+        creator,
+        factory,
+        dex,
+    } = setup();
+    let owner = app.api().addr_make("owner");
+    let amm = app
+        .wrap()
+        .query_wasm_smart::<GetConfigResponse>(&dex, &QueryMsg::GetConfig {})
+        .unwrap()
+        .config
+        .secondary_amm_address;
+    app.sudo(
+        BankSudo::Mint {
+            to_address: amm.to_string(),
+            amount: coins(1_000, LP_DENOM),
+        }
+        .into(),
+    )
+    .unwrap();
+
+    // Tokens launched from now on graduate once their curve raised 5_000
+    app.execute_contract(
+        owner.clone(),
+        dex.clone(),
+        &ExecuteMsg::UpdateBaseCurrencies {
+            add: vec![BaseCurrencyMsg {
+                symbol: "huahua".to_string(),
+                denom: UncheckedDenom::Native(DENOM.to_string()),
+                decimals: 6,
+                base_price: Uint128::new(100),
+                graduation_reserve: Uint128::new(5_000),
+                min_order_notional: Uint128::zero(),
+                order_deposit: Uint128::zero(),
+            }],
+            remove: vec![],
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        creator.clone(),
+        dex.clone(),
+        &ExecuteMsg::CreateToken {
+            name: "Woof".to_string(),
+            symbol: "WOOF".to_string(),
+            decimals: 6,
+            uri: "ipfs://woof".to_string(),
+            max_price_impact: Uint128::new(25),
+            curve_slope: Uint128::new(3),
+            initial_buy: None,
+            launch: None,
+            metadata: None,
+            kind: None,
+            base_currency: None,
+            vesting: None,
+        },
+        &coins(1_000, DENOM),
+    )
+    .unwrap();
+    let token_address = app
+        .wrap()
+        .query_wasm_smart::<GetTokenAddressResponse>(
+            &factory,
+            &FactoryQueryMsg::GetTokenAddress {
+                name: "Woof".to_string(),
+                symbol: "WOOF".to_string(),
+            },
+        )
+        .unwrap()
+        .address;
+
+    // Half of a 1% curve fee on 10_000 is set aside, then the pool graduates
+    app.execute_contract(
+        owner.clone(),
+        dex.clone(),
+        &ExecuteMsg::UpdateFees {
+            maker_fee: None,
+            taker_fee: None,
+            curve_fee: Some(Decimal::percent(1)),
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        owner.clone(),
+        dex.clone(),
+        &ExecuteMsg::SetBuybackPolicy {
+            token_address: token_address.to_string(),
+            share: Decimal::percent(50),
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        creator.clone(),
+        dex.clone(),
+        &ExecuteMsg::Swap {
+            pair_id: "WOOF/huahua".to_string(),
+            token_address: token_address.to_string(),
+            amount: Uint128::new(10_000),
+            min_return: Uint128::one(),
+            order_type: OrderType::Buy,
+        },
+        &coins(10_000, DENOM),
+    )
+    .unwrap();
+    app.execute_contract(
+        owner.clone(),
+        dex.clone(),
+        &ExecuteMsg::Graduate {
+            token_address: token_address.to_string(),
+        },
+        &[],
+    )
+    .unwrap();
+    // The AMM pulled the LP supply through its allowance and got the reserve as funds
+    assert_eq!(
+        app.wrap().query_balance(&amm, DENOM).unwrap().amount,
+        Uint128::new(9_900)
+    );
+    // The LP tokens it paid out were burned under the default policy
+    for holder in [&amm, &dex] {
+        assert!(app
+            .wrap()
+            .query_balance(holder, LP_DENOM)
+            .unwrap()
+            .amount
+            .is_zero());
+    }
+    // The factory's creator is on record for LP tokens a lock would release
+    let record = app
+        .wrap()
+        .query_wasm_smart::<GetGraduationRecordResponse>(
+            &dex,
+            &QueryMsg::GetGraduationRecord {
+                token_address: token_address.to_string(),
+            },
+        )
+        .unwrap()
+        .record;
+    assert_eq!(record.lp_recipient, creator);
+
+    let query_buyback = |app: &TestApp| {
+        app.wrap()
+            .query_wasm_smart::<GetTokenInfoResponse>(
+                &dex,
+                &QueryMsg::GetTokenInfo {
+                    token_address: token_address.to_string(),
+                },
+            )
+            .unwrap()
+            .buyback
+            .unwrap()
+    };
+    let cw20_balance = |app: &TestApp, address: &Addr| {
+        app.wrap()
+            .query_wasm_smart::<BalanceResponse>(
+                &token_address,
+                &Cw20QueryMsg::Balance {
+                    address: address.to_string(),
+                },
+            )
+            .unwrap()
+            .balance
+    };
+    let total_supply = |app: &TestApp| {
+        app.wrap()
+            .query_wasm_smart::<cw20::TokenInfoResponse>(
+                &token_address,
+                &Cw20QueryMsg::TokenInfo {},
+            )
+            .unwrap()
+            .total_supply
+    };
+    assert_eq!(query_buyback(&app).pending, Uint128::new(50));
+    let supply_before = total_supply(&app);
+    let dex_before = cw20_balance(&app, &dex);
+
+    let buyback = |app: &mut TestApp, min_tokens_out: u128| {
+        app.execute_contract(
+            owner.clone(),
+            dex.clone(),
+            &ExecuteMsg::ExecuteBuyback {
+                token_address: token_address.to_string(),
+                min_tokens_out: Uint128::new(min_tokens_out),
+            },
+            &[],
+        )
+    };
+    // A swap below the minimum fails on the AMM and keeps the fees set aside
+    buyback(&mut app, 501).unwrap_err();
+    assert_eq!(query_buyback(&app).pending, Uint128::new(50));
+
+    // The fees are sent to the AMM as native funds, and the reply burns exactly
+    // what the swap added to the contract's balance
+    buyback(&mut app, 500).unwrap();
+    let record = query_buyback(&app);
+    assert!(record.pending.is_zero());
+    assert_eq!(record.spent, Uint128::new(50));
+    assert_eq!(record.burned, Uint128::new(500));
+    assert_eq!(total_supply(&app), supply_before - Uint128::new(500));
+    assert_eq!(cw20_balance(&app, &dex), dex_before);
+    assert_eq!(
+        app.wrap().query_balance(&amm, DENOM).unwrap().amount,
+        Uint128::new(9_950)
+    );
+}
+
+#[test]
+fn creator_allocation_vests_after_cliff() {
+    let Suite {
+        mut app,
+        creator,
+        factory,
+        dex,
+    } = setup();
+    let owner = app.api().addr_make("owner");
+
+    // Leave 50M WOOF outside the curve and LP supplies for allocations
+    app.execute_contract(
+        owner,
+        dex.clone(),
+        &ExecuteMsg::UpdateConfig {
+            token_factory: None,
+            fee_collector: None,
+            maker_fee: None,
+            taker_fee: None,
+            quote_token_total_supply: None,
+            bonding_curve_supply: None,
+            lp_supply: Some(Uint128::new(150_000_000)),
+            enabled: None,
+            token_creation_fee: None,
+            lp_policy: None,
+        },
+        &[],
+    )
+    .unwrap();
+    let create = |app: &mut TestApp, amount: u128| {
+        app.execute_contract(
+            creator.clone(),
+            dex.clone(),
+            &ExecuteMsg::CreateToken {
+                name: "Woof".to_string(),
+                symbol: "WOOF".to_string(),
+                decimals: 6,
+                uri: "ipfs://woof".to_string(),
+                max_price_impact: Uint128::new(25),
+                curve_slope: Uint128::new(3),
+                initial_buy: None,
+                launch: None,
+                metadata: None,
+                kind: None,
+                base_currency: None,
+                vesting: Some(VestingAllocation {
+                    beneficiary: None,
+                    amount: Uint128::new(amount),
+                    cliff: 100,
+                    duration: 1_000,
+                }),
+            },
+            &coins(1_000, DENOM),
+        )
+    };
+    let err = create(&mut app, 50_000_000_000_001).unwrap_err();
+    assert!(err
+        .root_cause()
+        .to_string()
+        .contains("left outside the curve"));
+    create(&mut app, 1_000_000).unwrap();
+    let token_address = app
+        .wrap()
+        .query_wasm_smart::<GetTokenAddressResponse>(
+            &factory,
+            &FactoryQueryMsg::GetTokenAddress {
+                name: "Woof".to_string(),
+                symbol: "WOOF".to_string(),
+            },
+        )
+        .unwrap()
+        .address;
+
+    let query_vesting = |app: &TestApp| {
+        app.wrap()
+            .query_wasm_smart::<GetVestingScheduleResponse>(
+                &dex,
+                &QueryMsg::GetVestingSchedule {
+                    token_address: token_address.to_string(),
+                },
+            )
+            .unwrap()
+    };
+    let claim = |app: &mut TestApp| {
+        app.execute_contract(
+            creator.clone(),
+            dex.clone(),
+            &ExecuteMsg::ClaimVested {
+                token_address: token_address.to_string(),
+            },
+            &[],
+        )
+    };
+    let balance = |app: &TestApp| {
+        app.wrap()
+            .query_wasm_smart::<BalanceResponse>(
+                &token_address,
+                &Cw20QueryMsg::Balance {
+                    address: creator.to_string(),
+                },
+            )
+            .unwrap()
+            .balance
+    };
+    let wait = |app: &mut TestApp, seconds: u64| {
+        app.update_block(|block| block.time = block.time.plus_seconds(seconds))
+    };
+
+    // Nothing is claimable before the cliff
+    let vesting = query_vesting(&app);
+    assert_eq!(vesting.schedule.beneficiary, creator);
+    assert_eq!(vesting.schedule.pair_id, "WOOF/huahua");
+    assert!(vesting.vested.is_zero());
+    wait(&mut app, 99);
+    let err = claim(&mut app).unwrap_err();
+    assert!(err.root_cause().to_string().contains("No vested tokens"));
+
+    // Past the cliff it vests linearly from launch
+    wait(&mut app, 401);
+    assert_eq!(query_vesting(&app).claimable, Uint128::new(500_000));
+    claim(&mut app).unwrap();
+    assert_eq!(balance(&app), Uint128::new(500_000));
+
+    wait(&mut app, 10_000);
+    claim(&mut app).unwrap();
+    assert_eq!(balance(&app), Uint128::new(1_000_000));
+    let vesting = query_vesting(&app);
+    assert_eq!(vesting.schedule.claimed, Uint128::new(1_000_000));
+    assert!(vesting.claimable.is_zero());
+}
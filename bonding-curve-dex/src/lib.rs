@@ -1,6 +1,10 @@
 pub mod contract;
 mod error;
+#[cfg(test)]
+mod integration_tests;
+mod migrations;
 pub mod msg;
+pub mod price;
 pub mod state;
 
 pub use crate::error::ContractError;
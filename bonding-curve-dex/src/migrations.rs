@@ -0,0 +1,651 @@
+use std::collections::{BTreeMap, HashMap};
+
+use cosmwasm_std::{Addr, Decimal, Order as CosmwasmOrder, StdResult, Storage, Uint128};
+use cw20::Denom;
+use cw_storage_plus::{Item, Map};
+use serde::{Deserialize, Serialize};
+
+use crate::msg::UpgradeToV02;
+use crate::price::Price;
+use crate::state::{
+    BaseCurrency, Config, FeeDistribution, LaunchSettings, LpPolicy, Order, OrderBook, OrderStatus,
+    OrderType, PauseFlags, Pool, Roles, TokenInfo, TokenPair, Trade, BASE_CURRENCIES, BASE_PRICE,
+    CONFIG, FEE_DISTRIBUTION, GLOBAL_PAUSE, ORDERS, ORDER_BOOKS, POOLS, ROLES, TOKEN_INFO,
+    TOKEN_PAIRS, TRADES, USER_ACTIVE_ORDERS, USER_ORDERS, USER_TRADES,
+};
+
+// Storage layouts written by 0.1.x. Prices were bare integers: order book and
+// trade prices in base atomics per quote atomic, token and pool prices in base
+// atomics per whole token.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ConfigV01 {
+    pub owner: Addr,
+    pub token_factory: Addr,
+    pub fee_collector: Addr,
+    pub quote_token_total_supply: u128,
+    pub bonding_curve_supply: u128,
+    pub lp_supply: u128,
+    pub maker_fee: Decimal,
+    pub taker_fee: Decimal,
+    pub enabled: bool,
+    pub secondary_amm_address: Addr,
+    pub base_token_denom: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TokenInfoV01 {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub total_supply: Uint128,
+    pub initial_price: Uint128,
+    pub max_price_impact: Uint128,
+    pub graduated: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OrderBookV01 {
+    pub pair_id: String,
+    pub buy_orders: BTreeMap<u128, Vec<OrderV01>>,
+    pub sell_orders: BTreeMap<u128, Vec<OrderV01>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OrderV01 {
+    pub id: u64,
+    pub owner: Addr,
+    pub pair_id: String,
+    pub token_amount: Uint128,
+    pub price: Uint128,
+    pub timestamp: u64,
+    pub status: OrderStatus,
+    pub filled_amount: Uint128,
+    pub remaining_amount: Uint128,
+    pub order_type: OrderType,
+    pub created_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TradeV01 {
+    pub id: u64,
+    pub pair_id: String,
+    pub buy_order_id: u64,
+    pub sell_order_id: u64,
+    pub buyer: Addr,
+    pub seller: Addr,
+    pub token_amount: Uint128,
+    pub price: Uint128,
+    pub timestamp: u64,
+    pub total_price: Uint128,
+    pub maker_fee_amount: Uint128,
+    pub taker_fee_amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PoolV01 {
+    pub pair_id: String,
+    pub curve_slope: Uint128,
+    pub token_address: Addr,
+    pub total_reserve_token: Uint128,
+    pub token_sold: Uint128,
+    pub total_volume: Uint128,
+    pub total_trades: Uint128,
+    pub total_fees_collected: Uint128,
+    pub last_price: Uint128,
+    pub enabled: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TokenPairV01 {
+    pub base_token: String,
+    pub quote_token: String,
+    pub base_decimals: u8,
+    pub quote_decimals: u8,
+    pub enabled: bool,
+}
+
+pub const CONFIG_V01: Item<ConfigV01> = Item::new("config");
+pub const TOKEN_PAIRS_V01: Map<String, TokenPairV01> = Map::new("token_pairs");
+pub const ORDER_BOOKS_V01: Map<String, OrderBookV01> = Map::new("order_books");
+pub const TRADES_V01: Map<u64, TradeV01> = Map::new("trades");
+pub const ORDERS_V01: Map<u64, OrderV01> = Map::new("orders");
+pub const POOLS_V01: Map<String, PoolV01> = Map::new("pools");
+pub const USER_ORDERS_V01: Map<(Addr, u64), OrderV01> = Map::new("user_orders");
+pub const TOKEN_INFO_V01: Map<String, TokenInfoV01> = Map::new("token_info");
+pub const USER_TRADES_V01: Map<(Addr, u64), TradeV01> = Map::new("user_trades");
+
+/// Rewrites 0.1.x state into the 0.2.0 layout. Every record is rewritten in
+/// place, so this is meant for the small state of an early deployment.
+pub fn migrate_from_v0_1(storage: &mut dyn Storage, upgrade: &UpgradeToV02) -> StdResult<()> {
+    let config = CONFIG_V01.load(storage)?;
+    CONFIG.save(
+        storage,
+        &Config {
+            owner: config.owner,
+            token_factory: config.token_factory,
+            fee_collector: config.fee_collector,
+            quote_token_total_supply: config.quote_token_total_supply,
+            bonding_curve_supply: config.bonding_curve_supply,
+            lp_supply: config.lp_supply,
+            maker_fee: config.maker_fee,
+            taker_fee: config.taker_fee,
+            // Curve trades were free before 0.2
+            curve_fee: Decimal::zero(),
+            enabled: config.enabled,
+            secondary_amm_address: config.secondary_amm_address,
+            base_token_denom: Denom::Native(config.base_token_denom.clone()),
+            base_token_symbol: upgrade.base_token_symbol.clone(),
+            base_token_decimals: upgrade.base_token_decimals,
+            token_creation_fee: upgrade.token_creation_fee,
+            lp_policy: LpPolicy::Burn,
+        },
+    )?;
+    BASE_CURRENCIES.save(
+        storage,
+        upgrade.base_token_symbol.clone(),
+        &BaseCurrency {
+            symbol: upgrade.base_token_symbol.clone(),
+            denom: Denom::Native(config.base_token_denom.clone()),
+            decimals: upgrade.base_token_decimals,
+            base_price: Uint128::from(BASE_PRICE),
+            graduation_reserve: Uint128::zero(),
+            min_order_notional: upgrade.min_order_notional,
+            order_deposit: upgrade.order_deposit,
+        },
+    )?;
+    ROLES.save(storage, &Roles::default())?;
+    GLOBAL_PAUSE.save(storage, &PauseFlags::default())?;
+    FEE_DISTRIBUTION.save(storage, &FeeDistribution::default())?;
+
+    let tokens = TOKEN_INFO_V01
+        .range(storage, None, None, CosmwasmOrder::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut token_decimals = HashMap::new();
+    for (address, token) in tokens {
+        token_decimals.insert(address.clone(), token.decimals);
+        let token_info = TokenInfo {
+            name: token.name,
+            symbol: token.symbol,
+            decimals: token.decimals,
+            total_supply: token.total_supply,
+            // 0.1 compared the config supplies unscaled, live pools keep doing so
+            bonding_curve_supply: Uint128::from(config.bonding_curve_supply),
+            lp_supply: Uint128::from(config.lp_supply),
+            // Graduating on a reserve did not exist in 0.1
+            graduation_reserve: Uint128::zero(),
+            // Same policy the migrated config starts with
+            lp_policy: LpPolicy::Burn,
+            initial_price: Price::new(token.initial_price, token.decimals),
+            max_price_impact: token.max_price_impact,
+            graduated: token.graduated,
+        };
+        TOKEN_INFO.save(storage, address, &token_info)?;
+    }
+
+    let pools = POOLS_V01
+        .range(storage, None, None, CosmwasmOrder::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (address, pool) in pools {
+        let decimals = token_decimals.get(&address).copied().unwrap_or_default();
+        let pool = Pool {
+            pair_id: pool.pair_id,
+            curve_slope: pool.curve_slope,
+            token_address: pool.token_address,
+            total_reserve_token: pool.total_reserve_token,
+            token_sold: pool.token_sold,
+            total_volume: pool.total_volume,
+            total_trades: pool.total_trades,
+            total_fees_collected: pool.total_fees_collected,
+            last_price: Price::new(pool.last_price, decimals),
+            enabled: pool.enabled,
+            // Pools live before 0.2 are long past their launch
+            launch: LaunchSettings::default(),
+            launch_height: 0,
+            base_currency: upgrade.base_token_symbol.clone(),
+        };
+        POOLS.save(storage, address, &pool)?;
+    }
+
+    // Pairs default to the loosest grid and take the order book rules of the
+    // default currency, the only one 0.1 listed against
+    let pairs = TOKEN_PAIRS_V01
+        .range(storage, None, None, CosmwasmOrder::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut quote_decimals = HashMap::new();
+    for (pair_id, pair) in pairs {
+        quote_decimals.insert(pair_id.clone(), pair.quote_decimals);
+        let token_pair = TokenPair {
+            // 0.1 only listed launchpad tokens against the native denom
+            base_token: Denom::Native(pair.base_token),
+            quote_token: Denom::Cw20(Addr::unchecked(pair.quote_token)),
+            base_decimals: upgrade.base_token_decimals,
+            quote_decimals: pair.quote_decimals,
+            enabled: pair.enabled,
+            tick_size: Uint128::one(),
+            lot_size: Uint128::one(),
+            min_notional: upgrade.min_order_notional,
+            order_deposit: upgrade.order_deposit,
+        };
+        TOKEN_PAIRS.save(storage, pair_id, &token_pair)?;
+    }
+    let decimals_of = |pair_id: &str| quote_decimals.get(pair_id).copied().unwrap_or_default();
+
+    let orders = ORDERS_V01
+        .range(storage, None, None, CosmwasmOrder::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut active_orders: HashMap<Addr, u64> = HashMap::new();
+    for (id, order) in orders {
+        if matches!(
+            order.status,
+            OrderStatus::Active | OrderStatus::PartiallyFilled { .. }
+        ) {
+            *active_orders.entry(order.owner.clone()).or_default() += 1;
+        }
+        let decimals = decimals_of(&order.pair_id);
+        ORDERS.save(storage, id, &migrate_order(order, decimals)?)?;
+    }
+    for (owner, count) in active_orders {
+        USER_ACTIVE_ORDERS.save(storage, owner, &count)?;
+    }
+
+    let user_orders = USER_ORDERS_V01
+        .range(storage, None, None, CosmwasmOrder::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (key, order) in user_orders {
+        let decimals = decimals_of(&order.pair_id);
+        USER_ORDERS.save(storage, key, &migrate_order(order, decimals)?)?;
+    }
+
+    // Book levels are keyed by price atomics, so they move with the price scale
+    let books = ORDER_BOOKS_V01
+        .range(storage, None, None, CosmwasmOrder::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (pair_id, book) in books {
+        let decimals = decimals_of(&pair_id);
+        let order_book = OrderBook {
+            pair_id: book.pair_id,
+            buy_orders: migrate_levels(book.buy_orders, decimals)?,
+            sell_orders: migrate_levels(book.sell_orders, decimals)?,
+        };
+        ORDER_BOOKS.save(storage, pair_id, &order_book)?;
+    }
+    // 0.1.x only created a book on the first order
+    for pair_id in quote_decimals.keys() {
+        if !ORDER_BOOKS.has(storage, pair_id.clone()) {
+            let order_book = OrderBook {
+                pair_id: pair_id.clone(),
+                buy_orders: BTreeMap::new(),
+                sell_orders: BTreeMap::new(),
+            };
+            ORDER_BOOKS.save(storage, pair_id.clone(), &order_book)?;
+        }
+    }
+
+    let trades = TRADES_V01
+        .range(storage, None, None, CosmwasmOrder::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (id, trade) in trades {
+        let decimals = decimals_of(&trade.pair_id);
+        TRADES.save(storage, id, &migrate_trade(trade, decimals)?)?;
+    }
+
+    let user_trades = USER_TRADES_V01
+        .range(storage, None, None, CosmwasmOrder::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (key, trade) in user_trades {
+        let decimals = decimals_of(&trade.pair_id);
+        USER_TRADES.save(storage, key, &migrate_trade(trade, decimals)?)?;
+    }
+
+    Ok(())
+}
+
+// Book and trade prices were per quote atomic; 0.2.0 keeps them at quote decimals
+fn book_price(price: Uint128, quote_decimals: u8) -> StdResult<Price> {
+    Price::new(price, 0).to_precision(quote_decimals)
+}
+
+fn migrate_order(order: OrderV01, quote_decimals: u8) -> StdResult<Order> {
+    Ok(Order {
+        id: order.id,
+        owner: order.owner,
+        pair_id: order.pair_id,
+        token_amount: order.token_amount,
+        price: book_price(order.price, quote_decimals)?,
+        timestamp: order.timestamp,
+        status: order.status,
+        filled_amount: order.filled_amount,
+        remaining_amount: order.remaining_amount,
+        order_type: order.order_type,
+        created_at: order.created_at,
+        deposit: Uint128::zero(),
+    })
+}
+
+fn migrate_levels(
+    levels: BTreeMap<u128, Vec<OrderV01>>,
+    quote_decimals: u8,
+) -> StdResult<BTreeMap<u128, Vec<Order>>> {
+    levels
+        .into_iter()
+        .map(|(price, orders)| {
+            let price = book_price(Uint128::new(price), quote_decimals)?;
+            let orders = orders
+                .into_iter()
+                .map(|order| migrate_order(order, quote_decimals))
+                .collect::<StdResult<Vec<_>>>()?;
+            Ok((price.atomics.u128(), orders))
+        })
+        .collect()
+}
+
+fn migrate_trade(trade: TradeV01, quote_decimals: u8) -> StdResult<Trade> {
+    Ok(Trade {
+        id: trade.id,
+        pair_id: trade.pair_id,
+        buy_order_id: trade.buy_order_id,
+        sell_order_id: trade.sell_order_id,
+        buyer: trade.buyer,
+        seller: trade.seller,
+        token_amount: trade.token_amount,
+        price: book_price(trade.price, quote_decimals)?,
+        timestamp: trade.timestamp,
+        total_price: trade.total_price,
+        // 0.1.x took both fees out of the seller's proceeds. The older order was
+        // the one resting on the book.
+        maker_side: if trade.buy_order_id < trade.sell_order_id {
+            OrderType::Buy
+        } else {
+            OrderType::Sell
+        },
+        buyer_fee: Uint128::zero(),
+        seller_fee: trade.maker_fee_amount + trade.taker_fee_amount,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use cosmwasm_std::{attr, StdError};
+
+    use crate::contract::migrate;
+    use crate::msg::MigrateMsg;
+    use crate::state::USER_TRADE_COUNT;
+
+    const CONTRACT_NAME: &str = "crates.io:bonding-curve-dex";
+
+    fn upgrade() -> UpgradeToV02 {
+        UpgradeToV02 {
+            base_token_symbol: "huahua".to_string(),
+            base_token_decimals: 6,
+            min_order_notional: Uint128::from(1_000u128),
+            order_deposit: Uint128::from(10u128),
+            token_creation_fee: Uint128::from(50u128),
+        }
+    }
+
+    fn legacy_order(id: u64, owner: &str, price: u128, status: OrderStatus) -> OrderV01 {
+        OrderV01 {
+            id,
+            owner: Addr::unchecked(owner),
+            pair_id: "WOOF/huahua".to_string(),
+            token_amount: Uint128::from(1_000u128),
+            price: Uint128::from(price),
+            timestamp: 1,
+            status,
+            filled_amount: Uint128::zero(),
+            remaining_amount: Uint128::from(1_000u128),
+            order_type: OrderType::Buy,
+            created_at: 1,
+        }
+    }
+
+    #[test]
+    fn test_migrate_from_v0_1() {
+        let mut deps = mock_dependencies();
+        let storage = deps.as_mut().storage;
+        cw2::set_contract_version(storage, CONTRACT_NAME, "0.1.0").unwrap();
+
+        CONFIG_V01
+            .save(
+                storage,
+                &ConfigV01 {
+                    owner: Addr::unchecked("owner"),
+                    token_factory: Addr::unchecked("token_factory_addr"),
+                    fee_collector: Addr::unchecked("fee_collector_addr"),
+                    quote_token_total_supply: 100_000_000_000u128,
+                    bonding_curve_supply: 80_000_000_000u128,
+                    lp_supply: 20_000_000_000u128,
+                    maker_fee: Decimal::percent(1),
+                    taker_fee: Decimal::percent(1),
+                    enabled: true,
+                    secondary_amm_address: Addr::unchecked("secondary_amm_addr"),
+                    base_token_denom: "uhuahua".to_string(),
+                },
+            )
+            .unwrap();
+        TOKEN_INFO_V01
+            .save(
+                storage,
+                "token_addr".to_string(),
+                &TokenInfoV01 {
+                    name: "Woof".to_string(),
+                    symbol: "WOOF".to_string(),
+                    decimals: 6,
+                    total_supply: Uint128::from(100_000_000_000_000_000u128),
+                    initial_price: Uint128::from(100u128),
+                    max_price_impact: Uint128::from(1_000u128),
+                    graduated: false,
+                },
+            )
+            .unwrap();
+        POOLS_V01
+            .save(
+                storage,
+                "token_addr".to_string(),
+                &PoolV01 {
+                    pair_id: "WOOF/huahua".to_string(),
+                    curve_slope: Uint128::from(1_000u128),
+                    token_address: Addr::unchecked("token_addr"),
+                    total_reserve_token: Uint128::from(5_000u128),
+                    token_sold: Uint128::from(50_000_000u128),
+                    total_volume: Uint128::from(5_000u128),
+                    total_trades: Uint128::from(2u128),
+                    total_fees_collected: Uint128::zero(),
+                    last_price: Uint128::from(120u128),
+                    enabled: true,
+                },
+            )
+            .unwrap();
+        let pairs = [("WOOF/huahua", 6), ("BARK/huahua", 0)];
+        for (pair_id, quote_decimals) in pairs {
+            TOKEN_PAIRS_V01
+                .save(
+                    storage,
+                    pair_id.to_string(),
+                    &TokenPairV01 {
+                        base_token: "uhuahua".to_string(),
+                        quote_token: "token_addr".to_string(),
+                        base_decimals: 6,
+                        quote_decimals,
+                        enabled: true,
+                    },
+                )
+                .unwrap();
+        }
+
+        let active = legacy_order(1, "alice", 2, OrderStatus::Active);
+        let filled = legacy_order(2, "alice", 3, OrderStatus::Filled);
+        for order in [&active, &filled] {
+            ORDERS_V01.save(storage, order.id, order).unwrap();
+            USER_ORDERS_V01
+                .save(storage, (order.owner.clone(), order.id), order)
+                .unwrap();
+        }
+        ORDER_BOOKS_V01
+            .save(
+                storage,
+                "WOOF/huahua".to_string(),
+                &OrderBookV01 {
+                    pair_id: "WOOF/huahua".to_string(),
+                    buy_orders: BTreeMap::from([(2u128, vec![active.clone()])]),
+                    sell_orders: BTreeMap::new(),
+                },
+            )
+            .unwrap();
+        let trade = TradeV01 {
+            id: 1,
+            pair_id: "WOOF/huahua".to_string(),
+            buy_order_id: 2,
+            sell_order_id: 3,
+            buyer: Addr::unchecked("alice"),
+            seller: Addr::unchecked("bob"),
+            token_amount: Uint128::from(1_000u128),
+            price: Uint128::from(3u128),
+            timestamp: 1,
+            total_price: Uint128::from(3_000u128),
+            maker_fee_amount: Uint128::from(30u128),
+            taker_fee_amount: Uint128::from(30u128),
+        };
+        TRADES_V01.save(storage, 1, &trade).unwrap();
+        USER_TRADES_V01
+            .save(storage, (Addr::unchecked("alice"), 1), &trade)
+            .unwrap();
+        USER_TRADE_COUNT
+            .save(storage, Addr::unchecked("alice"), &1)
+            .unwrap();
+
+        let res = migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                v0_2: Some(upgrade()),
+            },
+        )
+        .unwrap();
+        assert_eq!(res.attributes[1], attr("from_version", "0.1.0"));
+        assert_eq!(res.attributes[2], attr("to_version", "0.2.0"));
+
+        let version = cw2::get_contract_version(&deps.storage).unwrap();
+        assert_eq!(version.version, "0.2.0");
+
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.owner, Addr::unchecked("owner"));
+        assert_eq!(config.base_token_decimals, 6);
+        assert_eq!(config.token_creation_fee, Uint128::from(50u128));
+        assert_eq!(
+            config.base_token_denom,
+            Denom::Native("uhuahua".to_string())
+        );
+        let currency = BASE_CURRENCIES
+            .load(&deps.storage, "huahua".to_string())
+            .unwrap();
+        assert_eq!(currency.denom, config.base_token_denom);
+        assert_eq!(currency.base_price, Uint128::from(BASE_PRICE));
+        assert_eq!(currency.min_order_notional, Uint128::from(1_000u128));
+        assert_eq!(currency.order_deposit, Uint128::from(10u128));
+        assert_eq!(ROLES.load(&deps.storage).unwrap(), Roles::default());
+        assert_eq!(
+            GLOBAL_PAUSE.load(&deps.storage).unwrap(),
+            PauseFlags::default()
+        );
+
+        let token_info = TOKEN_INFO
+            .load(&deps.storage, "token_addr".to_string())
+            .unwrap();
+        assert_eq!(
+            token_info.initial_price,
+            Price::new(Uint128::from(100u128), 6)
+        );
+        assert_eq!(
+            token_info.bonding_curve_supply,
+            Uint128::from(80_000_000_000u128)
+        );
+        let pool = POOLS.load(&deps.storage, "token_addr".to_string()).unwrap();
+        assert_eq!(pool.last_price, Price::new(Uint128::from(120u128), 6));
+        assert_eq!(pool.total_reserve_token, Uint128::from(5_000u128));
+
+        let pair = TOKEN_PAIRS
+            .load(&deps.storage, "WOOF/huahua".to_string())
+            .unwrap();
+        assert_eq!(pair.tick_size, Uint128::one());
+        assert_eq!(pair.lot_size, Uint128::one());
+        assert_eq!(pair.min_notional, Uint128::from(1_000u128));
+        assert_eq!(pair.order_deposit, Uint128::from(10u128));
+
+        // Book prices keep their value and move to the pair's precision
+        let order = ORDERS.load(&deps.storage, 1).unwrap();
+        assert_eq!(order.price.atomics, Uint128::from(2_000_000u128));
+        assert_eq!(order.price, Price::new(Uint128::from(2u128), 0));
+        assert_eq!(order.deposit, Uint128::zero());
+        let user_order = USER_ORDERS
+            .load(&deps.storage, (Addr::unchecked("alice"), 2))
+            .unwrap();
+        assert_eq!(user_order.price.atomics, Uint128::from(3_000_000u128));
+
+        let book = ORDER_BOOKS
+            .load(&deps.storage, "WOOF/huahua".to_string())
+            .unwrap();
+        assert_eq!(book.buy_orders.len(), 1);
+        assert_eq!(book.buy_orders[&2_000_000u128][0], order);
+        // Pairs without a book get an empty one
+        let book = ORDER_BOOKS
+            .load(&deps.storage, "BARK/huahua".to_string())
+            .unwrap();
+        assert!(book.buy_orders.is_empty() && book.sell_orders.is_empty());
+
+        // Only the order still on the book counts towards the cap
+        let active_orders = USER_ACTIVE_ORDERS
+            .load(&deps.storage, Addr::unchecked("alice"))
+            .unwrap();
+        assert_eq!(active_orders, 1);
+
+        let trade = TRADES.load(&deps.storage, 1).unwrap();
+        assert_eq!(trade.price, Price::new(Uint128::from(3_000_000u128), 6));
+        assert_eq!(trade.buyer_fee, Uint128::zero());
+        assert_eq!(trade.seller_fee, Uint128::from(60u128));
+        let user_trade = USER_TRADES
+            .load(&deps.storage, (Addr::unchecked("alice"), 1))
+            .unwrap();
+        assert_eq!(user_trade, trade);
+    }
+
+    #[test]
+    fn test_migrate_version_checks() {
+        let mut deps = mock_dependencies();
+
+        // Another contract's state is refused
+        cw2::set_contract_version(deps.as_mut().storage, "crates.io:token-factory", "0.1.0")
+            .unwrap();
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg { v0_2: None });
+        assert_eq!(
+            res.unwrap_err(),
+            StdError::generic_err(
+                "Cannot migrate from a different contract: crates.io:token-factory"
+            )
+        );
+
+        // Downgrades are refused
+        cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.3.0").unwrap();
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg { v0_2: None });
+        assert_eq!(
+            res.unwrap_err(),
+            StdError::generic_err("Cannot migrate from newer version 0.3.0 to 0.2.0")
+        );
+
+        // Upgrading from 0.1 needs the new settings
+        cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.1.0").unwrap();
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg { v0_2: None });
+        assert_eq!(
+            res.unwrap_err(),
+            StdError::generic_err("Migrating from 0.1 requires the v0_2 upgrade settings")
+        );
+
+        // Re-running the current version is a no-op
+        cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.2.0").unwrap();
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg { v0_2: None }).unwrap();
+        assert_eq!(res.attributes[0], attr("action", "migrate"));
+    }
+}
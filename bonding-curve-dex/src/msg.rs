@@ -1,8 +1,14 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
 use cosmwasm_std::{Addr, Decimal, Uint128};
+use cw20::{Denom, UncheckedDenom};
+use cw_utils::Expiration;
+use token_factory::state::{TokenKind, TokenMetadata};
 
+use crate::price::Price;
 use crate::state::{
-    Config, Order, OrderStatus, OrderType, Pool, PriceLevel, TokenInfo, TokenPair, Trade,
+    BaseCurrency, Buyback, Config, FeeDistribution, GraduationRecord, LaunchSettings, LpPolicy,
+    Order, OrderStatus, OrderType, PauseAction, PauseFlags, Pool, PriceLevel, Roles, TokenInfo,
+    TokenPair, Trade, TreasuryBalance, VestingSchedule,
 };
 
 #[cw_serde]
@@ -14,8 +20,16 @@ pub struct InstantiateMsg {
     pub lp_supply: Uint128,
     pub maker_fee: Decimal,
     pub taker_fee: Decimal,
+    pub curve_fee: Decimal,
     pub secondary_amm_address: Addr,
-    pub base_token_denom: String,
+    pub base_token_denom: Denom,
+    pub base_token_symbol: String,
+    pub base_token_decimals: u8,
+    // Order book rules of the default currency, in base_token_denom
+    pub min_order_notional: Uint128,
+    pub order_deposit: Uint128,
+    pub token_creation_fee: Uint128,
+    pub lp_policy: LpPolicy,
 }
 
 #[cw_serde]
@@ -27,14 +41,33 @@ pub enum ExecuteMsg {
         uri: String,
         max_price_impact: Uint128,
         curve_slope: Uint128,
+        // Creator buy on the curve, run atomically with the launch
+        initial_buy: Option<InitialBuy>,
+        launch: Option<LaunchSettings>,
+        metadata: Option<TokenMetadata>,
+        // CW20 unless set, Native issues an x/tokenfactory denom
+        kind: Option<TokenKind>,
+        // Symbol of a whitelisted base currency, the default one unless set
+        base_currency: Option<String>,
+        // Creator or team allocation, carved out of the supply the curve and LP leave
+        vesting: Option<VestingAllocation>,
     },
     Graduate {
         token_address: String,
     },
+    // Hands locked LP tokens of a graduated token to its creator once unlocked,
+    // run by the creator or the owner
+    ClaimLockedLp {
+        token_address: String,
+    },
+    // Sends the beneficiary whatever has vested and not been claimed yet
+    ClaimVested {
+        token_address: String,
+    },
     PlaceLimitOrder {
         token_address: String,
         amount: Uint128,
-        price: Uint128,
+        price: Price,
         is_buy: bool,
     },
     CancelOrder {
@@ -48,6 +81,12 @@ pub enum ExecuteMsg {
         min_return: Uint128,
         order_type: OrderType,
     },
+    // Chains swaps across pairs, each hop trading the previous hop's output
+    ExecuteSwapOperations {
+        operations: Vec<SwapOperation>,
+        offer_amount: Uint128,    // Input of the first hop
+        minimum_receive: Uint128, // Slippage bound on the output of the last hop
+    },
     UpdateConfig {
         token_factory: Option<Addr>,
         fee_collector: Option<Addr>,
@@ -57,9 +96,162 @@ pub enum ExecuteMsg {
         bonding_curve_supply: Option<Uint128>,
         lp_supply: Option<Uint128>,
         enabled: Option<bool>,
+        token_creation_fee: Option<Uint128>,
+        lp_policy: Option<LpPolicy>,
+    },
+    UpdatePairTradingRules {
+        pair_id: String,
+        tick_size: Option<Uint128>,
+        lot_size: Option<Uint128>,
+        min_notional: Option<Uint128>,
+        order_deposit: Option<Uint128>,
+    },
+    UpdatePair {
+        pair_id: String,
+        enabled: Option<bool>,
+    },
+    UpdatePool {
+        token_address: String,
+        enabled: Option<bool>,
+        curve_slope: Option<Uint128>,
+    },
+    RegisterPair {
+        pair_id: String,
+        base_token: UncheckedDenom,
+        quote_token: UncheckedDenom,
+        base_decimals: u8,
+        quote_decimals: u8,
+    },
+    // Adds or replaces whitelisted base currencies, then removes the listed symbols
+    UpdateBaseCurrencies {
+        add: Vec<BaseCurrencyMsg>,
+        remove: Vec<String>,
+    },
+    // Ownership and roles
+    TransferOwnership {
+        new_owner: String,
+        expiry: Option<Expiration>,
+    },
+    AcceptOwnership {},
+    UpdateRoles {
+        // Replaces every role, a missing address clears it
+        fee_manager: Option<String>,
+        pauser: Option<String>,
+        graduator: Option<String>,
+        guardian: Option<String>,
+    },
+    UpdateFees {
+        maker_fee: Option<Decimal>,
+        taker_fee: Option<Decimal>,
+        curve_fee: Option<Decimal>,
+    },
+    // Sets how DistributeFees splits the treasury
+    UpdateFeeDistribution {
+        fee_collector_share: Decimal,
+        buyback_share: Decimal,
+        staker_share: Decimal,
+        buyback_address: Option<String>,
+        staking_address: Option<String>,
+    },
+    // Pays out every undistributed fee, callable by anyone
+    DistributeFees {},
+    // Sets aside this share of the token's curve fees for buybacks, zero stops it
+    SetBuybackPolicy {
+        token_address: String,
+        share: Decimal,
+    },
+    // Spends the set aside fees on the token, on its curve or once graduated on the
+    // secondary AMM, and burns what it bought
+    ExecuteBuyback {
+        token_address: String,
+        min_tokens_out: Uint128,
+    },
+    SetTradingEnabled {
+        enabled: bool,
+    },
+    // Pauses the given actions for one pair, or everywhere when no pair is given
+    Pause {
+        pair_id: Option<String>,
+        actions: Vec<PauseAction>,
+    },
+    Unpause {
+        pair_id: Option<String>,
+        actions: Vec<PauseAction>,
+    },
+}
+
+#[cw_serde]
+pub struct InitialBuy {
+    pub amount: Uint128, // Base tokens to spend, sent on top of the creation fee
+    pub min_tokens_out: Uint128, // Slippage bound on the tokens bought
+}
+
+#[cw_serde]
+pub struct VestingAllocation {
+    pub beneficiary: Option<String>, // The creator unless set
+    pub amount: Uint128,             // In token atomics
+    pub cliff: u64,                  // Seconds before anything vests
+    pub duration: u64,               // Seconds until everything has vested
+}
+
+// One hop of a routed swap. Buy spends the pair's base token, sell its quote token.
+#[cw_serde]
+pub struct SwapOperation {
+    pub pair_id: String,
+    pub order_type: OrderType,
+}
+
+#[cw_serde]
+pub struct BaseCurrencyMsg {
+    pub symbol: String,
+    pub denom: UncheckedDenom,
+    pub decimals: u8,
+    pub base_price: Uint128,
+    pub graduation_reserve: Uint128,
+    pub min_order_notional: Uint128,
+    pub order_deposit: Uint128,
+}
+
+// Swap entry point expected from the secondary AMM. A CW20 offer is pulled from
+// the caller's allowance, a native one is sent along. The output goes to the caller.
+#[cw_serde]
+pub enum SecondaryAmmMsg {
+    Swap {
+        offer_denom: Denom,
+        offer_amount: Uint128,
+        ask_denom: Denom,
+        min_output: Uint128,
+    },
+    // Seeds a pool, answering with ProvideLiquidityResponse as data
+    ProvideLiquidity {
+        token_denom: Denom,
+        token_amount: Uint128,
+        base_denom: Denom,
+        base_amount: Uint128,
     },
 }
 
+#[cw_serde]
+pub struct ProvideLiquidityResponse {
+    pub lp_token: Denom,
+    pub lp_amount: Uint128,
+}
+
+#[cw_serde]
+pub struct MigrateMsg {
+    // Settings for fields added in 0.2.0, required when migrating from 0.1.x
+    pub v0_2: Option<UpgradeToV02>,
+}
+
+#[cw_serde]
+pub struct UpgradeToV02 {
+    pub base_token_symbol: String,
+    pub base_token_decimals: u8,
+    pub min_order_notional: Uint128,
+    pub order_deposit: Uint128,
+    pub token_creation_fee: Uint128,
+}
+
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
@@ -83,6 +275,9 @@ pub enum QueryMsg {
     #[returns(GetCountResponse)]
     GetUserTradeCount { address: Addr },
 
+    #[returns(GetCountResponse)]
+    GetUserActiveOrderCount { address: Addr },
+
     // Order book queries
     #[returns(GetOrderResponse)]
     GetOrder { order_id: u64 },
@@ -121,6 +316,13 @@ pub enum QueryMsg {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    #[returns(ListBaseCurrenciesResponse)]
+    ListBaseCurrencies {},
+    #[returns(SimulateSwapOperationsResponse)]
+    SimulateSwapOperations {
+        offer_amount: Uint128,
+        operations: Vec<SwapOperation>,
+    },
 
     // System queries
     #[returns(GetSystemStatsResponse)]
@@ -128,6 +330,20 @@ pub enum QueryMsg {
 
     #[returns(GetConfigResponse)]
     GetConfig {},
+
+    #[returns(GetOwnershipResponse)]
+    GetOwnership {},
+
+    #[returns(GetRolesResponse)]
+    GetRoles {},
+    #[returns(GetTreasuryResponse)]
+    GetTreasury {},
+    #[returns(GetGraduationRecordResponse)]
+    GetGraduationRecord { token_address: String },
+    #[returns(GetVestingScheduleResponse)]
+    GetVestingSchedule { token_address: String },
+    #[returns(GetPauseStatusResponse)]
+    GetPauseStatus { pair_id: Option<String> },
 }
 
 // We define a custom struct for each query response
@@ -161,7 +377,7 @@ pub struct GetOrderBookResponse {
     pub pair_id: String,
     pub bids: Vec<PriceLevel>,
     pub asks: Vec<PriceLevel>,
-    pub last_price: Uint128,
+    pub last_price: Price,
     pub base_volume_24h: Uint128,
     pub quote_volume_24h: Uint128,
 }
@@ -169,16 +385,18 @@ pub struct GetOrderBookResponse {
 #[cw_serde]
 pub struct GetPoolResponse {
     pub pool: Pool,
+    pub buyback: Option<Buyback>,
 }
 
 #[cw_serde]
 pub struct GetCurrentPriceResponse {
-    pub price: u128,
+    pub price: Price,
 }
 
 #[cw_serde]
 pub struct GetTokenInfoResponse {
     pub token_info: TokenInfo,
+    pub buyback: Option<Buyback>,
 }
 
 #[cw_serde]
@@ -196,6 +414,16 @@ pub struct ListTokenPairsResponse {
     pub token_pairs: Vec<TokenPair>,
 }
 
+#[cw_serde]
+pub struct ListBaseCurrenciesResponse {
+    pub base_currencies: Vec<BaseCurrency>,
+}
+
+#[cw_serde]
+pub struct SimulateSwapOperationsResponse {
+    pub amount: Uint128,
+}
+
 #[cw_serde]
 pub struct GetSystemStatsResponse {
     pub total_pairs: u64,
@@ -205,3 +433,39 @@ pub struct GetSystemStatsResponse {
     pub total_users: u64,
     pub total_fees_collected: Uint128,
 }
+
+#[cw_serde]
+pub struct GetOwnershipResponse {
+    pub owner: Addr,
+    pub pending_owner: Option<Addr>,
+    pub pending_expiry: Option<Expiration>,
+}
+
+#[cw_serde]
+pub struct GetRolesResponse {
+    pub roles: Roles,
+}
+
+#[cw_serde]
+pub struct GetGraduationRecordResponse {
+    pub record: GraduationRecord,
+}
+
+#[cw_serde]
+pub struct GetVestingScheduleResponse {
+    pub schedule: VestingSchedule,
+    pub vested: Uint128,
+    pub claimable: Uint128,
+}
+
+#[cw_serde]
+pub struct GetTreasuryResponse {
+    pub balances: Vec<TreasuryBalance>,
+    pub distribution: FeeDistribution,
+}
+
+#[cw_serde]
+pub struct GetPauseStatusResponse {
+    pub global: PauseFlags,
+    pub pair: Option<PauseFlags>,
+}
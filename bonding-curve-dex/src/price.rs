@@ -0,0 +1,315 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Decimal, StdError, StdResult, Uint128, Uint256};
+
+/// Price of the quote token (the launched token) in units of the base currency
+/// it trades against.
+///
+/// `atomics / 10^precision` is the number of base atomics paid for one quote
+/// atomic. Stored with the quote token's decimals as precision, `atomics` reads
+/// as base atomics per whole quote token, which is how the order book keys its
+/// price levels.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, JsonSchema)]
+pub struct Price {
+    pub atomics: Uint128,
+    pub precision: u8,
+}
+
+impl Price {
+    pub const fn new(atomics: Uint128, precision: u8) -> Self {
+        Price { atomics, precision }
+    }
+
+    pub const fn zero(precision: u8) -> Self {
+        Price {
+            atomics: Uint128::zero(),
+            precision,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.atomics.is_zero()
+    }
+
+    /// Price paid when `base_amount` base atomics buy `quote_amount` quote atomics
+    pub fn from_ratio(
+        base_amount: Uint128,
+        quote_amount: Uint128,
+        precision: u8,
+    ) -> StdResult<Self> {
+        if quote_amount.is_zero() {
+            return Err(StdError::generic_err("Cannot price a zero quote amount"));
+        }
+        let atomics = mul_div(base_amount, pow10(precision)?, quote_amount, true)?;
+        Ok(Price::new(atomics, precision))
+    }
+
+    /// Builds a price from base atomics per quote atomic, rounding up
+    pub fn from_decimal(value: Decimal, precision: u8) -> StdResult<Self> {
+        let atomics = pow10(precision)?
+            .checked_mul_ceil(value)
+            .map_err(|e| StdError::generic_err(e.to_string()))?;
+        Ok(Price::new(atomics, precision))
+    }
+
+    /// Base atomics per quote atomic
+    pub fn to_decimal(&self) -> StdResult<Decimal> {
+        Decimal::checked_from_ratio(self.atomics, pow10(self.precision)?)
+            .map_err(|e| StdError::generic_err(e.to_string()))
+    }
+
+    /// Whole base tokens per whole quote token, for display
+    pub fn to_whole_units(&self, base_decimals: u8, quote_decimals: u8) -> StdResult<Decimal> {
+        let numerator = self.atomics.full_mul(pow10(quote_decimals)?);
+        let denominator = pow10(self.precision)?.full_mul(pow10(base_decimals)?);
+        Decimal::checked_from_ratio(to_uint128(numerator)?, to_uint128(denominator)?)
+            .map_err(|e| StdError::generic_err(e.to_string()))
+    }
+
+    /// Re-expresses the price with `precision` decimals. Fails rather than round.
+    pub fn to_precision(&self, precision: u8) -> StdResult<Self> {
+        let atomics = match precision.cmp(&self.precision) {
+            Ordering::Equal => self.atomics,
+            Ordering::Greater => self
+                .atomics
+                .checked_mul(pow10(precision - self.precision)?)?,
+            Ordering::Less => {
+                let factor = pow10(self.precision - precision)?;
+                if !(self.atomics % factor).is_zero() {
+                    return Err(StdError::generic_err(format!(
+                        "Price {} cannot be expressed with {} decimals",
+                        self, precision
+                    )));
+                }
+                self.atomics / factor
+            }
+        };
+        Ok(Price::new(atomics, precision))
+    }
+
+    /// Base atomics owed for `quote_amount` quote atomics, rounded down
+    pub fn checked_quote_to_base(&self, quote_amount: Uint128) -> StdResult<Uint128> {
+        mul_div(quote_amount, self.atomics, pow10(self.precision)?, false)
+    }
+
+    /// Base atomics owed for `quote_amount` quote atomics, rounded up
+    pub fn checked_quote_to_base_ceil(&self, quote_amount: Uint128) -> StdResult<Uint128> {
+        mul_div(quote_amount, self.atomics, pow10(self.precision)?, true)
+    }
+
+    /// Quote atomics bought with `base_amount` base atomics, rounded down
+    pub fn checked_base_to_quote(&self, base_amount: Uint128) -> StdResult<Uint128> {
+        if self.is_zero() {
+            return Err(StdError::generic_err("Price is zero"));
+        }
+        mul_div(base_amount, pow10(self.precision)?, self.atomics, false)
+    }
+}
+
+impl PartialEq for Price {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Price {}
+
+impl PartialOrd for Price {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Price {
+    // Compares by value, so prices recorded at different precisions line up
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self.precision.cmp(&other.precision) {
+            Ordering::Equal => self.atomics.cmp(&other.atomics),
+            Ordering::Greater => cmp_scaled(
+                self.atomics,
+                other.atomics,
+                self.precision - other.precision,
+            ),
+            Ordering::Less => cmp_scaled(
+                other.atomics,
+                self.atomics,
+                other.precision - self.precision,
+            )
+            .reverse(),
+        }
+    }
+}
+
+impl fmt::Display for Price {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = self.atomics.to_string();
+        let precision = self.precision as usize;
+        if precision == 0 {
+            return write!(f, "{}", digits);
+        }
+
+        let padded = format!("{:0>width$}", digits, width = precision + 1);
+        let (whole, fractional) = padded.split_at(padded.len() - precision);
+        let fractional = fractional.trim_end_matches('0');
+        if fractional.is_empty() {
+            write!(f, "{}", whole)
+        } else {
+            write!(f, "{}.{}", whole, fractional)
+        }
+    }
+}
+
+fn pow10(exponent: u8) -> StdResult<Uint128> {
+    10u128
+        .checked_pow(exponent as u32)
+        .map(Uint128::new)
+        .ok_or_else(|| StdError::generic_err("Price precision overflow"))
+}
+
+// Compares `atomics` with `other * 10^shift`. A scaled value that overflows
+// Uint256 is larger than any Uint128, so only a zero `other` can still tie.
+fn cmp_scaled(atomics: Uint128, other: Uint128, shift: u8) -> Ordering {
+    if other.is_zero() {
+        return atomics.cmp(&other);
+    }
+    Uint256::from(10u128)
+        .checked_pow(shift as u32)
+        .and_then(|factor| Uint256::from(other).checked_mul(factor))
+        .map_or(Ordering::Less, |scaled| Uint256::from(atomics).cmp(&scaled))
+}
+
+fn to_uint128(value: Uint256) -> StdResult<Uint128> {
+    Uint128::try_from(value).map_err(|e| StdError::generic_err(e.to_string()))
+}
+
+fn mul_div(a: Uint128, b: Uint128, denominator: Uint128, round_up: bool) -> StdResult<Uint128> {
+    let denominator = Uint256::from(denominator);
+    let product = a.full_mul(b);
+    let quotient = product.checked_div(denominator)?;
+    if round_up && !(product % denominator).is_zero() {
+        return to_uint128(quotient + Uint256::one());
+    }
+    to_uint128(quotient)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_price_conversions_across_decimals() {
+        // 0.5 HUAHUA (6 decimals) per whole token with 8 decimals
+        let price = Price::new(Uint128::from(500_000u128), 8);
+
+        // One whole token costs 500_000 uhuahua
+        assert_eq!(
+            price
+                .checked_quote_to_base(Uint128::from(100_000_000u128))
+                .unwrap(),
+            Uint128::from(500_000u128)
+        );
+        // 1 uhuahua buys 200 quote atomics
+        assert_eq!(
+            price.checked_base_to_quote(Uint128::one()).unwrap(),
+            Uint128::from(200u128)
+        );
+        assert_eq!(
+            price.to_whole_units(6, 8).unwrap(),
+            Decimal::from_ratio(1u128, 2u128)
+        );
+
+        // The same price for a token with 18 decimals
+        let price = Price::new(Uint128::from(500_000u128), 18);
+        assert_eq!(
+            price
+                .checked_quote_to_base(Uint128::from(10u128.pow(18)))
+                .unwrap(),
+            Uint128::from(500_000u128)
+        );
+        assert_eq!(
+            price.to_whole_units(6, 18).unwrap(),
+            Decimal::from_ratio(1u128, 2u128)
+        );
+
+        // And for a token with no decimals at all
+        let price = Price::new(Uint128::from(500_000u128), 0);
+        assert_eq!(
+            price.checked_quote_to_base(Uint128::from(3u128)).unwrap(),
+            Uint128::from(1_500_000u128)
+        );
+        assert_eq!(
+            price.to_whole_units(6, 0).unwrap(),
+            Decimal::from_ratio(1u128, 2u128)
+        );
+    }
+
+    #[test]
+    fn test_price_rounding() {
+        // 0.00000003 base atomics per quote atomic
+        let price = Price::new(Uint128::from(3u128), 8);
+
+        assert_eq!(
+            price.checked_quote_to_base(Uint128::from(10u128)).unwrap(),
+            Uint128::zero()
+        );
+        assert_eq!(
+            price
+                .checked_quote_to_base_ceil(Uint128::from(10u128))
+                .unwrap(),
+            Uint128::one()
+        );
+        assert_eq!(
+            Price::from_ratio(Uint128::from(10u128), Uint128::from(3u128), 0).unwrap(),
+            Price::new(Uint128::from(4u128), 0)
+        );
+        assert!(Price::zero(6)
+            .checked_base_to_quote(Uint128::one())
+            .is_err());
+    }
+
+    #[test]
+    fn test_price_precision() {
+        let price = Price::new(Uint128::from(1_500u128), 3);
+
+        assert_eq!(price.to_string(), "1.5");
+        assert_eq!(
+            Price::new(Uint128::from(7u128), 8).to_string(),
+            "0.00000007"
+        );
+        assert_eq!(Price::new(Uint128::from(10u128), 0).to_string(), "10");
+
+        // Rescaling keeps the value and compares equal
+        let rescaled = price.to_precision(8).unwrap();
+        assert_eq!(rescaled.atomics, Uint128::from(150_000_000u128));
+        assert_eq!(rescaled, price);
+        assert_eq!(
+            rescaled.to_precision(1).unwrap().atomics,
+            Uint128::from(15u128)
+        );
+
+        // Dropping significant digits is refused
+        assert_eq!(
+            price.to_precision(0).unwrap_err(),
+            StdError::generic_err("Price 1.5 cannot be expressed with 0 decimals")
+        );
+
+        assert!(Price::new(Uint128::from(2u128), 0) > price);
+        assert!(Price::new(Uint128::one(), 0) < price);
+
+        // Precisions too far apart to rescale still compare without panicking
+        let tiny = Price::new(Uint128::MAX, u8::MAX);
+        assert!(tiny < Price::new(Uint128::one(), 0));
+        assert!(Price::new(Uint128::one(), 0) > tiny);
+        assert_eq!(Price::zero(u8::MAX), Price::zero(0));
+        assert!(Price::zero(0) < tiny);
+        assert_eq!(
+            Price::from_decimal(Decimal::percent(150), 3).unwrap(),
+            price
+        );
+        assert_eq!(price.to_decimal().unwrap(), Decimal::percent(150));
+    }
+}
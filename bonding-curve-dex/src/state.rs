@@ -3,20 +3,26 @@ use std::collections::BTreeMap;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, Decimal, Uint128};
+use cosmwasm_std::{Addr, Decimal, Timestamp, Uint128};
+use cw20::Denom;
 use cw_storage_plus::{Item, Map};
+use cw_utils::Expiration;
+
+use crate::price::Price;
 
 // Add constants for data structure limits
 pub const MAX_ORDERS_PER_PRICE: usize = 100_000;
 pub const MAX_TRADES_PER_USER: usize = 100;
 pub const MAX_ACTIVE_ORDERS_PER_USER: usize = 50;
+pub const MAX_SWAP_OPERATIONS: usize = 4; // Hops in one routed swap
 pub const PRUNE_THRESHOLD: u64 = 7 * 24 * 60 * 60; // 7 days in seconds
+pub const MIN_LP_LOCK_DURATION: u64 = 30 * 24 * 60 * 60; // 30 days in seconds
 
 // Constants for bonding curve
 pub const BASE_PRICE: u128 = 100; // 0.0001 Huahua
 
 // Configuration
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
     pub owner: Addr,
     pub token_factory: Addr,
@@ -26,9 +32,74 @@ pub struct Config {
     pub lp_supply: u128,
     pub maker_fee: Decimal, // in basis points (1/10000)
     pub taker_fee: Decimal, // in basis points (1/10000)
+    pub curve_fee: Decimal, // Taken on curve trades, out of the base currency side
     pub enabled: bool,
     pub secondary_amm_address: Addr,
-    pub base_token_denom: String,
+    pub base_token_denom: Denom,
+    pub base_token_symbol: String, // Used in pair ids, e.g. WOOF/huahua
+    pub base_token_decimals: u8,
+    pub token_creation_fee: Uint128, // Booked to the treasury on CreateToken, in base_token_denom
+    pub lp_policy: LpPolicy,         // Applied to the LP tokens a graduation mints
+}
+
+// What graduation does with the LP tokens minted by the secondary AMM
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub enum LpPolicy {
+    Burn,
+    Lock { duration: u64 }, // Seconds before the token creator can claim them
+}
+
+impl LpPolicy {
+    // Whether moving to `next` keeps the LP tokens at least as tied up: a lock
+    // may only grow or turn into a burn, and a burn stays a burn
+    pub fn allows_change_to(&self, next: &LpPolicy) -> bool {
+        match (self, next) {
+            (_, LpPolicy::Burn) => true,
+            (LpPolicy::Burn, LpPolicy::Lock { .. }) => false,
+            (LpPolicy::Lock { duration }, LpPolicy::Lock { duration: next }) => next >= duration,
+        }
+    }
+}
+
+// Ownership transfer proposed by the owner, waiting to be accepted
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingOwner {
+    pub address: Addr,
+    pub expiry: Option<Expiration>,
+}
+
+// Creator buy requested with CreateToken, run by the reply once the pool exists
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PendingInitialBuy {
+    pub creator: Addr,
+    pub amount: Uint128,
+    pub min_tokens_out: Uint128,
+}
+
+// Operator roles that can run a subset of admin actions next to the owner
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct Roles {
+    pub fee_manager: Option<Addr>, // Can update maker and taker fees
+    pub pauser: Option<Addr>,      // Can halt and resume trading
+    pub graduator: Option<Addr>,   // Can graduate tokens to the secondary AMM
+    pub guardian: Option<Addr>,    // Can pause but never unpause
+}
+
+// Actions that can be paused on their own, globally or for a single pair
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+pub enum PauseAction {
+    TokenCreation,
+    CurveTrading,
+    BookTrading,
+    Graduation,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct PauseFlags {
+    pub token_creation: bool,
+    pub curve_trading: bool,
+    pub book_trading: bool,
+    pub graduation: bool,
 }
 
 // Token information
@@ -38,7 +109,14 @@ pub struct TokenInfo {
     pub symbol: String,
     pub decimals: u8,
     pub total_supply: Uint128,
-    pub initial_price: Uint128,
+    // Snapshot of the config supplies at creation, in token atomics, so config
+    // changes only reach tokens launched afterwards
+    pub bonding_curve_supply: Uint128,
+    pub lp_supply: Uint128,
+    // The currency's graduation reserve at creation, in base atomics
+    pub graduation_reserve: Uint128,
+    pub lp_policy: LpPolicy, // Config policy at creation, applied on graduation
+    pub initial_price: Price,
     pub max_price_impact: Uint128, // To guard against massive buys and sells
     pub graduated: bool,
 }
@@ -58,13 +136,14 @@ pub struct Order {
     pub owner: Addr,
     pub pair_id: String,
     pub token_amount: Uint128,
-    pub price: Uint128,
+    pub price: Price,
     pub timestamp: u64,
     pub status: OrderStatus,
     pub filled_amount: Uint128,
     pub remaining_amount: Uint128,
     pub order_type: OrderType,
     pub created_at: u64,
+    pub deposit: Uint128, // Placement deposit refunded once the order leaves the book
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -91,11 +170,12 @@ pub struct Trade {
     pub buyer: Addr,
     pub seller: Addr,
     pub token_amount: Uint128,
-    pub price: Uint128,
+    pub price: Price,
     pub timestamp: u64,
     pub total_price: Uint128,
-    pub maker_fee_amount: Uint128,
-    pub taker_fee_amount: Uint128,
+    pub maker_side: OrderType, // Side of the order that was resting on the book
+    pub buyer_fee: Uint128,    // Quote tokens withheld from what the buyer received
+    pub seller_fee: Uint128,   // Base tokens withheld from the seller's proceeds
 }
 
 // Pool information
@@ -109,39 +189,198 @@ pub struct Pool {
     pub total_volume: Uint128,
     pub total_trades: Uint128, // Track total trading volume
     pub total_fees_collected: Uint128,
-    pub last_price: Uint128, // Last traded price
+    pub last_price: Price, // Last traded price
     pub enabled: bool,
+    pub launch: LaunchSettings,
+    pub launch_height: u64,    // Block the pool went live in
+    pub base_currency: String, // Symbol of the whitelisted currency the token launched against
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+// Anti-sniper rules applied to curve trades right after a launch
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct LaunchSettings {
+    // No curve trades before this
+    pub trading_start: Option<Expiration>,
+    // Only allow-listed addresses may buy until allow_list_end passes
+    pub allow_list: Vec<Addr>,
+    pub allow_list_end: Option<Expiration>,
+    // Cap on tokens bought per address during the first max_buy_blocks blocks
+    pub max_buy_per_address: Option<Uint128>,
+    pub max_buy_blocks: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct TokenPair {
-    pub base_token: String,  // Native or CW20 token address
-    pub quote_token: String, // Native or CW20 token address
+    pub base_token: Denom,
+    pub quote_token: Denom,
     pub base_decimals: u8,
     pub quote_decimals: u8,
     pub enabled: bool,
+    pub tick_size: Uint128, // Price atomics, at quote_decimals, must be a multiple of this
+    pub lot_size: Uint128,  // Order amounts must be a multiple of this
+    pub min_notional: Uint128, // Smallest price * amount accepted on this pair, in base_token
+    pub order_deposit: Uint128, // Refundable deposit per limit order, in base_token
+}
+
+// Currency a token can be launched against, picked by the creator at CreateToken
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BaseCurrency {
+    pub symbol: String, // Used in pair ids, e.g. WOOF/atom
+    pub denom: Denom,
+    pub decimals: u8,
+    pub base_price: Uint128, // Starting curve price, like BASE_PRICE
+    // Base reserve raised on the curve that lets a pool graduate before it sells
+    // out, zero keeps the sold-out rule only. Tokens keep the value they launched with.
+    pub graduation_reserve: Uint128,
+    // Order book rules given to pairs quoted in this currency, in its atomics
+    pub min_order_notional: Uint128,
+    pub order_deposit: Uint128,
+}
+
+// Bank denom or CW20 address, whichever identifies the asset
+pub fn denom_id(denom: &Denom) -> &str {
+    match denom {
+        Denom::Native(denom) => denom,
+        Denom::Cw20(address) => address.as_str(),
+    }
+}
+
+// Protocol fees held by the contract in one denom
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TreasuryBalance {
+    pub denom: Denom,
+    pub accumulated: Uint128, // Every fee collected so far
+    pub distributed: Uint128, // Paid out by DistributeFees so far
+}
+
+// How DistributeFees splits the treasury. The shares add up to one and any
+// rounding dust goes to the fee collector.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct FeeDistribution {
+    pub fee_collector_share: Decimal,
+    pub buyback_share: Decimal,
+    pub staker_share: Decimal,
+    pub buyback_address: Option<Addr>,
+    pub staking_address: Option<Addr>, // Receives the stakers' share, e.g. a rewards contract
+}
+
+impl Default for FeeDistribution {
+    fn default() -> Self {
+        FeeDistribution {
+            fee_collector_share: Decimal::one(),
+            buyback_share: Decimal::zero(),
+            staker_share: Decimal::zero(),
+            buyback_address: None,
+            staking_address: None,
+        }
+    }
+}
+
+// Optional per-token policy spending part of the token's curve fees on buying it
+// back and burning it. Amounts are in the pool's base currency unless noted.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Buyback {
+    pub pair_id: String,  // Kept for graduated tokens, whose pool is gone
+    pub share: Decimal,   // Part of each curve fee set aside for buybacks
+    pub pending: Uint128, // Set aside and not yet spent
+    pub spent: Uint128,   // Spent on buybacks so far
+    pub burned: Uint128,  // Launched tokens bought back and burned, in token atomics
+}
+
+// Fate of a graduated token's LP tokens
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub enum LpOutcome {
+    Pending, // Waiting on the secondary AMM
+    Burned,
+    Locked { unlocks_at: Timestamp },
+    Released { at: Timestamp }, // Sent to the token creator once unlocked
+}
+
+// Record of how a token graduated, kept for holders to audit
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GraduationRecord {
+    pub pair_id: String,
+    pub graduated_at: Timestamp,
+    pub token_amount: Uint128,  // Launched tokens seeded into the AMM
+    pub base_amount: Uint128,   // Currency seeded next to them
+    pub unsold_burned: Uint128, // Curve supply left unsold by an early graduation
+    pub lp_recipient: Addr,     // Token creator, who gets locked LP tokens once unlocked
+    pub lp_token: Option<Denom>,
+    pub lp_amount: Uint128,
+    pub lp_outcome: LpOutcome,
+}
+
+// Creator or team allocation held by the contract and released linearly from
+// start, with nothing claimable before the cliff. Amounts are in token atomics.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct VestingSchedule {
+    pub pair_id: String, // Kept for graduated tokens, whose pool is gone
+    pub beneficiary: Addr,
+    pub total: Uint128,
+    pub claimed: Uint128,
+    pub start: Timestamp,
+    pub cliff: u64,    // Seconds after start before anything vests
+    pub duration: u64, // Seconds after start until everything has vested
+}
+
+impl VestingSchedule {
+    pub fn vested(&self, now: Timestamp) -> Uint128 {
+        let elapsed = now.seconds().saturating_sub(self.start.seconds());
+        if elapsed < self.cliff {
+            return Uint128::zero();
+        }
+        if elapsed >= self.duration {
+            return self.total;
+        }
+        self.total.multiply_ratio(elapsed, self.duration)
+    }
+}
+
+// Buyback routed through the secondary AMM, burned by the reply
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PendingBuyback {
+    pub token_address: String,
+    pub balance_before: Uint128, // Contract balance of the token ahead of the swap
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct PriceLevel {
-    pub price: Uint128,
+    pub price: Price,
     pub quantity: Uint128,
     pub order_count: u32,
 }
 
 // Storage items
 pub const CONFIG: Item<Config> = Item::new("config");
+pub const PENDING_OWNER: Item<PendingOwner> = Item::new("pending_owner");
+pub const ROLES: Item<Roles> = Item::new("roles");
+pub const GLOBAL_PAUSE: Item<PauseFlags> = Item::new("global_pause");
+pub const PENDING_INITIAL_BUY: Item<PendingInitialBuy> = Item::new("pending_initial_buy");
+pub const PENDING_LAUNCH: Item<LaunchSettings> = Item::new("pending_launch");
+pub const PENDING_BASE_CURRENCY: Item<BaseCurrency> = Item::new("pending_base_currency");
+pub const BASE_CURRENCIES: Map<String, BaseCurrency> = Map::new("base_currencies");
+pub const PAIR_PAUSE: Map<String, PauseFlags> = Map::new("pair_pause");
+pub const TREASURY: Map<String, TreasuryBalance> = Map::new("treasury");
+pub const FEE_DISTRIBUTION: Item<FeeDistribution> = Item::new("fee_distribution");
+pub const BUYBACKS: Map<String, Buyback> = Map::new("buybacks");
+pub const PENDING_BUYBACK: Item<PendingBuyback> = Item::new("pending_buyback");
+pub const GRADUATIONS: Map<String, GraduationRecord> = Map::new("graduations");
+pub const PENDING_GRADUATION: Item<String> = Item::new("pending_graduation");
+pub const VESTING: Map<String, VestingSchedule> = Map::new("vesting");
+pub const PENDING_VESTING: Item<VestingSchedule> = Item::new("pending_vesting");
 pub const TOKEN_PAIRS: Map<String, TokenPair> = Map::new("token_pairs");
 pub const ORDER_BOOKS: Map<String, OrderBook> = Map::new("order_books");
 pub const TRADES: Map<u64, Trade> = Map::new("trades");
 pub const ORDERS: Map<u64, Order> = Map::new("orders");
 pub const POOLS: Map<String, Pool> = Map::new("pools");
+pub const LAUNCH_BUYS: Map<(String, Addr), Uint128> = Map::new("launch_buys");
 pub const NEXT_ORDER_ID: Item<u64> = Item::new("next_order_id");
 pub const NEXT_TRADE_ID: Item<u64> = Item::new("next_trade_id");
 pub const USER_ORDERS: Map<(Addr, u64), Order> = Map::new("user_orders");
 pub const TOKEN_INFO: Map<String, TokenInfo> = Map::new("token_info");
 pub const USER_TRADES: Map<(Addr, u64), Trade> = Map::new("user_trades");
 pub const USER_TRADE_COUNT: Map<Addr, u64> = Map::new("user_trade_count");
+pub const USER_ACTIVE_ORDERS: Map<Addr, u64> = Map::new("user_active_orders");
 
 // Add pruning timestamp
 pub const LAST_PRUNED: Item<u64> = Item::new("last_pruned");
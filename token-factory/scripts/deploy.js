@@ -32,7 +32,9 @@ async function main() {
 
   const initMsg = {
     token_code_id: 11031,
-    token_code_hash: "5fee983db91565497b13238c30935d39ff15bd7fe12bb6c4946a5d18b41fa58e"
+    token_code_hash: "5fee983db91565497b13238c30935d39ff15bd7fe12bb6c4946a5d18b41fa58e",
+    // The DEX registers itself when instantiated from this same account
+    allowed_callers: []
   };
 
   const instantiateReceipt = await client.instantiate(
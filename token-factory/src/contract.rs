@@ -1,20 +1,33 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdError, StdResult,
+    instantiate2_address, to_json_binary, Addr, Binary, CosmosMsg, Deps, DepsMut, Env, HexBinary,
+    MessageInfo, Reply, Response, StdError, StdResult, Uint128,
 };
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
 use execute::{
-    execute_create_token, execute_transfer_ownership, execute_update_token_code_id,
-    handle_token_creation_reply,
+    execute_accept_ownership, execute_claim_airdrop, execute_clawback_airdrop,
+    execute_create_token, execute_receive, execute_register_airdrop, execute_register_dex,
+    execute_transfer_ownership, execute_update_allowed_callers, execute_update_token_code_id,
+    execute_update_token_metadata, handle_token_creation_reply,
 };
 use query::{
-    query_config, query_list_tokens, query_owner, query_token_address, query_token_count, query_token_info, query_tokens_by_creator
+    query_airdrop, query_airdrop_claim, query_allowed_callers, query_config, query_list_tokens,
+    query_newest_tokens, query_owner, query_ownership, query_predict_token_address,
+    query_search_tokens_by_symbol, query_token_address, query_token_count, query_token_info,
+    query_tokens_by_creator,
 };
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{State, STATE};
+use crate::migrations::migrate_from_v0_1;
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use crate::state::{
+    DenomMetadata, DenomUnit, MsgSetDenomMetadata, State, TokenInfo, ALLOWED_CALLERS,
+    MAX_SUBDENOM_LENGTH, MSG_SET_DENOM_METADATA_TYPE_URL, STATE,
+};
+use prost::Message;
+use semver::Version;
+use sha2::{Digest, Sha256};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:token-factory";
@@ -39,10 +52,18 @@ pub fn instantiate(
 
     STATE.save(deps.storage, &state)?;
 
+    let allowed_callers = msg
+        .allowed_callers
+        .iter()
+        .map(|addr| deps.api.addr_validate(addr))
+        .collect::<StdResult<Vec<_>>>()?;
+    ALLOWED_CALLERS.save(deps.storage, &allowed_callers)?;
+
     Ok(Response::new()
         .add_attribute("method", "instantiate")
         .add_attribute("owner", info.sender)
-        .add_attribute("token_code_id", msg.token_code_id.to_string()))
+        .add_attribute("token_code_id", msg.token_code_id.to_string())
+        .add_attribute("allowed_callers", msg.allowed_callers.join(",")))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -61,6 +82,9 @@ pub fn execute(
             max_price_impact,
             curve_slope,
             initial_balances,
+            creator,
+            metadata,
+            kind,
         } => Ok(execute_create_token(
             deps,
             env,
@@ -72,10 +96,14 @@ pub fn execute(
             max_price_impact,
             curve_slope,
             initial_balances,
+            creator,
+            metadata,
+            kind,
         )?),
-        ExecuteMsg::TransferOwnership { new_owner } => {
-            Ok(execute_transfer_ownership(deps, info, new_owner)?)
-        }
+        ExecuteMsg::TransferOwnership { new_owner, expiry } => Ok(execute_transfer_ownership(
+            deps, env, info, new_owner, expiry,
+        )?),
+        ExecuteMsg::AcceptOwnership {} => Ok(execute_accept_ownership(deps, env, info)?),
         ExecuteMsg::UpdateTokenCodeId {
             new_token_code_id,
             new_token_code_hash,
@@ -85,6 +113,36 @@ pub fn execute(
             new_token_code_id,
             new_token_code_hash,
         )?),
+        ExecuteMsg::UpdateAllowedCallers { add, remove } => {
+            Ok(execute_update_allowed_callers(deps, info, add, remove)?)
+        }
+        ExecuteMsg::RegisterDex {} => Ok(execute_register_dex(deps, info)?),
+        ExecuteMsg::UpdateTokenMetadata { address, metadata } => Ok(execute_update_token_metadata(
+            deps, env, info, address, metadata,
+        )?),
+        ExecuteMsg::RegisterAirdrop {
+            token,
+            merkle_root,
+            expiration,
+        } => Ok(execute_register_airdrop(
+            deps,
+            env,
+            info,
+            token,
+            merkle_root,
+            expiration,
+        )?),
+        ExecuteMsg::Receive(msg) => Ok(execute_receive(deps, env, info, msg)?),
+        ExecuteMsg::ClaimAirdrop {
+            airdrop_id,
+            amount,
+            proof,
+        } => Ok(execute_claim_airdrop(
+            deps, env, info, airdrop_id, amount, proof,
+        )?),
+        ExecuteMsg::ClawbackAirdrop { airdrop_id } => {
+            Ok(execute_clawback_airdrop(deps, env, info, airdrop_id)?)
+        }
     }
 }
 
@@ -103,9 +161,165 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> StdResult<Response> {
     }
 }
 
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> StdResult<Response> {
+    let stored = get_contract_version(deps.storage)?;
+    if stored.contract != CONTRACT_NAME {
+        return Err(StdError::generic_err(format!(
+            "Cannot migrate from a different contract: {}",
+            stored.contract
+        )));
+    }
+
+    let stored_version = parse_version(&stored.version)?;
+    if stored_version > parse_version(CONTRACT_VERSION)? {
+        return Err(StdError::generic_err(format!(
+            "Cannot migrate from newer version {} to {}",
+            stored.version, CONTRACT_VERSION
+        )));
+    }
+
+    // Upgrade steps run in order, starting from the stored version
+    if stored_version < Version::new(0, 2, 0) {
+        // 0.1.x let anyone create tokens, 0.2.0 needs the DEX on the allow-list
+        let allowed_callers = msg
+            .allowed_callers
+            .ok_or_else(|| StdError::generic_err("Migrating from 0.1 requires allowed_callers"))?;
+        let allowed_callers = allowed_callers
+            .iter()
+            .map(|addr| deps.api.addr_validate(addr))
+            .collect::<StdResult<Vec<_>>>()?;
+        ALLOWED_CALLERS.save(deps.storage, &allowed_callers)?;
+        migrate_from_v0_1(deps.storage)?;
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "migrate"),
+        ("from_version", stored.version.as_str()),
+        ("to_version", CONTRACT_VERSION),
+    ]))
+}
+
+fn parse_version(version: &str) -> StdResult<Version> {
+    version
+        .parse()
+        .map_err(|_| StdError::generic_err(format!("Invalid contract version: {}", version)))
+}
+
+// Instantiate2 salt, unique per name and symbol pair. The name is length-prefixed
+// so different splits of the same string cannot collide.
+fn token_salt(name: &str, symbol: &str) -> Binary {
+    let mut hasher = Sha256::new();
+    hasher.update((name.len() as u64).to_be_bytes());
+    hasher.update(name.as_bytes());
+    hasher.update(symbol.as_bytes());
+    Binary::new(hasher.finalize().to_vec())
+}
+
+// Walks a proof up from the claimer's leaf, hashing each pair in sorted order
+fn verify_merkle_proof(
+    root: &HexBinary,
+    address: &str,
+    amount: Uint128,
+    proof: &[HexBinary],
+) -> bool {
+    let leaf = Sha256::digest(format!("{}{}", address, amount)).to_vec();
+    let computed = proof.iter().fold(leaf, |hash, sibling| {
+        let (first, second) = if hash.as_slice() <= sibling.as_slice() {
+            (hash.as_slice(), sibling.as_slice())
+        } else {
+            (sibling.as_slice(), hash.as_slice())
+        };
+        Sha256::new()
+            .chain_update(first)
+            .chain_update(second)
+            .finalize()
+            .to_vec()
+    });
+    computed.as_slice() == root.as_slice()
+}
+
+// Instantiate2 addresses derive from the instantiating contract, which is the factory
+fn predict_token_address(
+    deps: Deps,
+    env: &Env,
+    code_hash: &HexBinary,
+    salt: &Binary,
+) -> StdResult<Addr> {
+    let factory = deps.api.addr_canonicalize(env.contract.address.as_str())?;
+    let address = instantiate2_address(code_hash, &factory, salt)
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+    deps.api.addr_humanize(&address)
+}
+
+// The symbol becomes the subdenom, so it must be a valid denom segment
+fn validate_subdenom(subdenom: &str) -> StdResult<()> {
+    let valid_chars = subdenom
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "/:._-".contains(c));
+    if subdenom.is_empty() || subdenom.len() > MAX_SUBDENOM_LENGTH || !valid_chars {
+        return Err(StdError::generic_err(format!(
+            "Invalid subdenom: {}",
+            subdenom
+        )));
+    }
+    Ok(())
+}
+
+#[allow(deprecated)]
+fn stargate_msg(type_url: &str, msg: &impl Message) -> CosmosMsg {
+    CosmosMsg::Stargate {
+        type_url: type_url.to_string(),
+        value: Binary::new(msg.encode_to_vec()),
+    }
+}
+
+// Bank metadata for a native token, so wallets know its symbol and decimals
+fn denom_metadata_msg(env: &Env, token_info: &TokenInfo) -> CosmosMsg {
+    let denom = token_info.address.to_string();
+    stargate_msg(
+        MSG_SET_DENOM_METADATA_TYPE_URL,
+        &MsgSetDenomMetadata {
+            sender: env.contract.address.to_string(),
+            metadata: Some(DenomMetadata {
+                description: token_info.metadata.description.clone().unwrap_or_default(),
+                denom_units: vec![
+                    DenomUnit {
+                        denom: denom.clone(),
+                        exponent: 0,
+                        aliases: vec![],
+                    },
+                    DenomUnit {
+                        denom: token_info.symbol.clone(),
+                        exponent: token_info.decimals as u32,
+                        aliases: vec![],
+                    },
+                ],
+                base: denom,
+                display: token_info.symbol.clone(),
+                name: token_info.name.clone(),
+                symbol: token_info.symbol.clone(),
+                uri: token_info.uri.clone(),
+                uri_hash: String::new(),
+            }),
+        },
+    )
+}
+
 pub mod execute {
-    use crate::state::{Cw20Coin, TokenCreationResponse, TokenInfo, TOKEN_ADDRESS, TOKEN_INFO};
-    use cosmwasm_std::{instantiate2_address, Addr, HexBinary, SubMsg, Uint128, WasmMsg};
+    use crate::msg::ReceiveMsg;
+    use crate::state::{
+        Airdrop, Cw20Coin, MsgCreateDenom, MsgMint, PendingCreation, PendingOwner, ProtoCoin,
+        TokenCreationResponse, TokenKind, TokenMetadata, AIRDROPS, AIRDROP_CLAIMS,
+        MSG_CREATE_DENOM_TYPE_URL, MSG_MINT_TYPE_URL, NEXT_AIRDROP_ID, PENDING_CREATIONS,
+        PENDING_OWNER, TOKEN_ADDRESS, TOKEN_INFO,
+    };
+    use cosmwasm_std::{coins, from_json, BankMsg, HexBinary, SubMsg, Uint128, WasmMsg};
+    use cw20::{Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg, Logo, MarketingInfoResponse};
+    use cw20_base::msg::InstantiateMarketingInfo;
+    use cw_utils::Expiration;
 
     use super::*;
 
@@ -120,11 +334,20 @@ pub mod execute {
         max_price_impact: Uint128, 
         curve_slope: Uint128,      
         initial_balances: Vec<Cw20Coin>,
+        creator: Option<String>,
+        metadata: Option<TokenMetadata>,
+        kind: Option<TokenKind>,
     ) -> StdResult<Response> {
         let mut state = STATE.load(deps.storage)?;
+
+        let allowed_callers = ALLOWED_CALLERS.may_load(deps.storage)?.unwrap_or_default();
+        if !allowed_callers.contains(&info.sender) {
+            return Err(StdError::generic_err(
+                "Unauthorized: caller is not allowed to create tokens",
+            ));
+        }
     
-        let is_unique = TOKEN_ADDRESS.has(deps.storage, (&name, &symbol));
-        if !is_unique {
+        if TOKEN_ADDRESS.has(deps.storage, (&name, &symbol)) {
             return Err(StdError::generic_err("Token name and symbol pair must be unique"));
         }
     
@@ -132,13 +355,32 @@ pub mod execute {
         for coin in &initial_balances {
             deps.api.addr_validate(&coin.address)?;
         }
+        let creator = match creator {
+            Some(creator) => deps.api.addr_validate(&creator)?,
+            None => sender.clone(),
+        };
+        let metadata = metadata.unwrap_or_default();
+        let kind = kind.unwrap_or_default();
     
         let token_count = state.token_count + 1;
-        let creator_canon = deps.api.addr_canonicalize(sender.as_str())?;
-        let salt = Binary::new(format!("woof_token_{}", token_count).into_bytes());
-    
-        let address = instantiate2_address(&state.token_code_hash, &creator_canon, &salt).unwrap();
-        let human_address = deps.api.addr_humanize(&address)?;
+        let salt = token_salt(&name, &symbol);
+        // Native denoms are namespaced by the factory and the symbol alone
+        let human_address = match kind {
+            TokenKind::Cw20 => {
+                predict_token_address(deps.as_ref(), &env, &state.token_code_hash, &salt)?
+            }
+            TokenKind::Native => {
+                validate_subdenom(&symbol)?;
+                Addr::unchecked(format!("factory/{}/{}", env.contract.address, symbol))
+            }
+        };
+        // Same symbol under a different name still maps to the same denom
+        if kind == TokenKind::Native && TOKEN_INFO.has(deps.storage, human_address.as_str()) {
+            return Err(StdError::generic_err(format!(
+                "Token denom already exists: {}",
+                human_address
+            )));
+        }
     
         let total_supply = initial_balances
             .iter()
@@ -149,16 +391,92 @@ pub mod execute {
             symbol: symbol.clone(),
             decimals,
             uri: uri.clone(),
-            creator: sender.clone(),
+            creator: creator.clone(),
             address: human_address.clone(),
             creation_time: env.block.time.seconds(),
             total_supply,
+            metadata: metadata.clone(),
+            kind,
         };
     
         TOKEN_ADDRESS.save(deps.storage, (&name, &symbol), &human_address)?;
         TOKEN_INFO.save(deps.storage, &human_address.as_str(), &token_info)?;
         state.token_count = token_count;
         STATE.save(deps.storage, &state)?;
+
+        let attributes = vec![
+            ("action", "create_token".to_string()),
+            ("name", name.clone()),
+            ("symbol", symbol.clone()),
+            ("decimals", decimals.to_string()),
+            ("uri", uri),
+            ("address", human_address.to_string()),
+            ("creator", creator.to_string()),
+            ("max_price_impact", max_price_impact.to_string()),
+            ("curve_slope", curve_slope.to_string()),
+            ("kind", kind.to_string()),
+        ];
+
+        if kind == TokenKind::Native {
+            let denom = human_address.to_string();
+            // The supply is minted to the factory and handed out from there, which
+            // works whether or not the chain's MsgMint honours mint_to_address
+            let mut messages = vec![stargate_msg(
+                MSG_CREATE_DENOM_TYPE_URL,
+                &MsgCreateDenom {
+                    sender: env.contract.address.to_string(),
+                    subdenom: symbol.clone(),
+                },
+            )];
+            // The module rejects a zero mint
+            if !total_supply.is_zero() {
+                messages.push(stargate_msg(
+                    MSG_MINT_TYPE_URL,
+                    &MsgMint {
+                        sender: env.contract.address.to_string(),
+                        amount: Some(ProtoCoin {
+                            denom: denom.clone(),
+                            amount: total_supply.to_string(),
+                        }),
+                        mint_to_address: env.contract.address.to_string(),
+                    },
+                ));
+            }
+            messages.push(denom_metadata_msg(&env, &token_info));
+            for coin in initial_balances.into_iter().filter(|c| !c.amount.is_zero()) {
+                messages.push(
+                    BankMsg::Send {
+                        to_address: coin.address,
+                        amount: coins(coin.amount.u128(), &denom),
+                    }
+                    .into(),
+                );
+            }
+
+            // Nothing to wait for, the caller gets the creation data straight away
+            let response_data = TokenCreationResponse {
+                token_address: denom,
+                name,
+                symbol,
+                decimals,
+                max_price_impact,
+                curve_slope,
+                kind,
+            };
+            return Ok(Response::new()
+                .add_messages(messages)
+                .add_attributes(attributes)
+                .set_data(to_json_binary(&response_data)?));
+        }
+
+        PENDING_CREATIONS.save(
+            deps.storage,
+            human_address.as_str(),
+            &PendingCreation {
+                max_price_impact,
+                curve_slope,
+            },
+        )?;
     
         let instantiate_msg = cw20_base::msg::InstantiateMsg {
             name: name.clone(),
@@ -172,7 +490,13 @@ pub mod execute {
                 })
                 .collect(),
             mint: None,
-            marketing: None,
+            // The factory stays the marketing address so UpdateTokenMetadata can keep it in sync
+            marketing: Some(InstantiateMarketingInfo {
+                project: metadata.project_url,
+                description: metadata.description,
+                marketing: Some(env.contract.address.to_string()),
+                logo: metadata.logo_url.map(Logo::Url),
+            }),
         };
     
         let instantiate = WasmMsg::Instantiate2 {
@@ -186,37 +510,75 @@ pub mod execute {
     
         Ok(Response::new()
             .add_submessage(SubMsg::reply_on_success(instantiate, state.token_creation_reply_id))
-            .add_attributes(vec![
-                ("action", "create_token"),
-                ("name", &name),
-                ("symbol", &symbol),
-                ("decimals", &decimals.to_string()),
-                ("uri", &uri),
-                ("address", human_address.as_str()),
-                ("creator", sender.as_str()),
-                ("max_price_impact", &max_price_impact.to_string()),
-                ("curve_slope", &curve_slope.to_string()),
-            ]))
+            .add_attributes(attributes))
     }
 
     pub fn execute_transfer_ownership(
         deps: DepsMut,
+        env: Env,
         info: MessageInfo,
-        new_owner: Addr,
+        new_owner: String,
+        expiry: Option<Expiration>,
     ) -> StdResult<Response> {
-        let mut state = STATE.load(deps.storage)?;
+        let state = STATE.load(deps.storage)?;
         let owner = state.owner;
         if info.sender != owner {
             return Err(StdError::generic_err("Unauthorized"));
         }
 
-        state.owner = new_owner.clone();
+        let new_owner = deps.api.addr_validate(&new_owner)?;
+        if let Some(expiry) = expiry {
+            if expiry.is_expired(&env.block) {
+                return Err(StdError::generic_err("Expiry has already passed"));
+            }
+        }
 
-        STATE.save(deps.storage, &state)?;
+        // The new owner takes over only once it accepts
+        PENDING_OWNER.save(
+            deps.storage,
+            &PendingOwner {
+                address: new_owner.clone(),
+                expiry,
+            },
+        )?;
 
         Ok(Response::new().add_attributes(vec![
             ("action", "transfer_ownership"),
-            ("new_owner", &new_owner.to_string()),
+            ("pending_owner", new_owner.as_str()),
+            (
+                "pending_expiry",
+                &expiry.map_or("none".to_string(), |e| e.to_string()),
+            ),
+        ]))
+    }
+
+    pub fn execute_accept_ownership(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+    ) -> StdResult<Response> {
+        let mut state = STATE.load(deps.storage)?;
+
+        let pending = match PENDING_OWNER.may_load(deps.storage)? {
+            Some(pending) => pending,
+            None => return Err(StdError::generic_err("No pending ownership transfer")),
+        };
+        if info.sender != pending.address {
+            return Err(StdError::generic_err("Unauthorized: not the pending owner"));
+        }
+        if let Some(expiry) = pending.expiry {
+            if expiry.is_expired(&env.block) {
+                return Err(StdError::generic_err("Ownership transfer has expired"));
+            }
+        }
+
+        state.owner = pending.address;
+        STATE.save(deps.storage, &state)?;
+        PENDING_OWNER.remove(deps.storage);
+
+        Ok(Response::new().add_attributes(vec![
+            ("action", "accept_ownership"),
+            ("new_owner", state.owner.as_str()),
         ]))
     }
 
@@ -249,18 +611,328 @@ pub mod execute {
         ]))
     }
 
+    pub fn execute_update_allowed_callers(
+        deps: DepsMut,
+        info: MessageInfo,
+        add: Vec<String>,
+        remove: Vec<String>,
+    ) -> StdResult<Response> {
+        let state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(StdError::generic_err("Unauthorized"));
+        }
+
+        let mut allowed_callers = ALLOWED_CALLERS.may_load(deps.storage)?.unwrap_or_default();
+        for addr in &remove {
+            let addr = deps.api.addr_validate(addr)?;
+            allowed_callers.retain(|caller| *caller != addr);
+        }
+        for addr in &add {
+            let addr = deps.api.addr_validate(addr)?;
+            if !allowed_callers.contains(&addr) {
+                allowed_callers.push(addr);
+            }
+        }
+        ALLOWED_CALLERS.save(deps.storage, &allowed_callers)?;
+
+        Ok(Response::new().add_attributes(vec![
+            ("action", "update_allowed_callers"),
+            ("added", &add.join(",")),
+            ("removed", &remove.join(",")),
+        ]))
+    }
+
+    pub fn execute_register_dex(deps: DepsMut, info: MessageInfo) -> StdResult<Response> {
+        let state = STATE.load(deps.storage)?;
+        // Only a contract the owner instantiated can register itself
+        let contract = deps.querier.query_wasm_contract_info(&info.sender)?;
+        if contract.creator != state.owner {
+            return Err(StdError::generic_err(
+                "Unauthorized: DEX was not instantiated by the factory owner",
+            ));
+        }
+
+        let mut allowed_callers = ALLOWED_CALLERS.may_load(deps.storage)?.unwrap_or_default();
+        if !allowed_callers.contains(&info.sender) {
+            allowed_callers.push(info.sender.clone());
+        }
+        ALLOWED_CALLERS.save(deps.storage, &allowed_callers)?;
+
+        Ok(Response::new().add_attributes(vec![
+            ("action", "register_dex"),
+            ("dex", info.sender.as_str()),
+        ]))
+    }
+
+    pub fn execute_update_token_metadata(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        address: String,
+        metadata: TokenMetadata,
+    ) -> StdResult<Response> {
+        let mut token_info = TOKEN_INFO.load(deps.storage, &address)?;
+        if info.sender != token_info.creator {
+            return Err(StdError::generic_err("Unauthorized: not the token creator"));
+        }
+
+        token_info.metadata = metadata.clone();
+        TOKEN_INFO.save(deps.storage, &address, &token_info)?;
+
+        if token_info.kind == TokenKind::Native {
+            return Ok(Response::new()
+                .add_message(denom_metadata_msg(&env, &token_info))
+                .add_attributes(vec![
+                    ("action", "update_token_metadata"),
+                    ("address", address.as_str()),
+                    ("synced", "true"),
+                ]));
+        }
+
+        // Tokens created before 0.2 have no marketing address, only the factory copy changes
+        let marketing: MarketingInfoResponse = deps
+            .querier
+            .query_wasm_smart(&address, &Cw20QueryMsg::MarketingInfo {})?;
+        let synced = marketing.marketing.as_ref() == Some(&env.contract.address);
+
+        let mut messages = vec![];
+        if synced {
+            // Empty strings clear the CW20 fields
+            messages.push(WasmMsg::Execute {
+                contract_addr: address.clone(),
+                msg: to_json_binary(&Cw20ExecuteMsg::UpdateMarketing {
+                    project: Some(metadata.project_url.clone().unwrap_or_default()),
+                    description: Some(metadata.description.clone().unwrap_or_default()),
+                    marketing: None,
+                })?,
+                funds: vec![],
+            });
+            // CW20 cannot drop a logo, so clearing the URL leaves the old one in place
+            if let Some(logo_url) = &metadata.logo_url {
+                messages.push(WasmMsg::Execute {
+                    contract_addr: address.clone(),
+                    msg: to_json_binary(&Cw20ExecuteMsg::UploadLogo(Logo::Url(logo_url.clone())))?,
+                    funds: vec![],
+                });
+            }
+        }
+
+        Ok(Response::new().add_messages(messages).add_attributes(vec![
+            ("action", "update_token_metadata"),
+            ("address", address.as_str()),
+            ("synced", &synced.to_string()),
+        ]))
+    }
+
+    pub fn execute_register_airdrop(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token: String,
+        merkle_root: HexBinary,
+        expiration: Expiration,
+    ) -> StdResult<Response> {
+        let token_info = TOKEN_INFO
+            .may_load(deps.storage, &token)?
+            .ok_or_else(|| StdError::generic_err("Token was not created by this factory"))?;
+        if token_info.kind != TokenKind::Cw20 {
+            return Err(StdError::generic_err("Airdrops only support CW20 tokens"));
+        }
+        if merkle_root.len() != 32 {
+            return Err(StdError::generic_err("Merkle root must be a sha256 hash"));
+        }
+        // Without an expiry unclaimed tokens could never be clawed back
+        if matches!(expiration, Expiration::Never {}) || expiration.is_expired(&env.block) {
+            return Err(StdError::generic_err(
+                "Airdrop expiration must be set and in the future",
+            ));
+        }
+
+        let id = NEXT_AIRDROP_ID.may_load(deps.storage)?.unwrap_or(1);
+        NEXT_AIRDROP_ID.save(deps.storage, &(id + 1))?;
+        AIRDROPS.save(
+            deps.storage,
+            id,
+            &Airdrop {
+                id,
+                creator: info.sender.clone(),
+                token: token_info.address,
+                merkle_root: merkle_root.clone(),
+                expiration,
+                funded: Uint128::zero(),
+                claimed: Uint128::zero(),
+                clawed_back: false,
+            },
+        )?;
+
+        Ok(Response::new().add_attributes(vec![
+            ("action", "register_airdrop"),
+            ("airdrop_id", &id.to_string()),
+            ("creator", info.sender.as_str()),
+            ("token", &token),
+            ("merkle_root", &merkle_root.to_hex()),
+            ("expiration", &expiration.to_string()),
+        ]))
+    }
+
+    pub fn execute_receive(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        msg: Cw20ReceiveMsg,
+    ) -> StdResult<Response> {
+        match from_json(&msg.msg)? {
+            ReceiveMsg::FundAirdrop { airdrop_id } => {
+                let mut airdrop = AIRDROPS.load(deps.storage, airdrop_id)?;
+                // The sender of a Receive is the token contract itself
+                if info.sender != airdrop.token {
+                    return Err(StdError::generic_err("Wrong token for this airdrop"));
+                }
+                if msg.sender != airdrop.creator.as_str() {
+                    return Err(StdError::generic_err(
+                        "Unauthorized: not the airdrop creator",
+                    ));
+                }
+                if airdrop.clawed_back || airdrop.expiration.is_expired(&env.block) {
+                    return Err(StdError::generic_err("Airdrop has expired"));
+                }
+
+                airdrop.funded = airdrop.funded.checked_add(msg.amount)?;
+                AIRDROPS.save(deps.storage, airdrop_id, &airdrop)?;
+
+                Ok(Response::new().add_attributes(vec![
+                    ("action", "fund_airdrop"),
+                    ("airdrop_id", &airdrop_id.to_string()),
+                    ("amount", &msg.amount.to_string()),
+                    ("funded", &airdrop.funded.to_string()),
+                ]))
+            }
+        }
+    }
+
+    pub fn execute_claim_airdrop(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        airdrop_id: u64,
+        amount: Uint128,
+        proof: Vec<HexBinary>,
+    ) -> StdResult<Response> {
+        let mut airdrop = AIRDROPS.load(deps.storage, airdrop_id)?;
+        if airdrop.clawed_back || airdrop.expiration.is_expired(&env.block) {
+            return Err(StdError::generic_err("Airdrop has expired"));
+        }
+        if AIRDROP_CLAIMS.has(deps.storage, (airdrop_id, &info.sender)) {
+            return Err(StdError::generic_err("Airdrop already claimed"));
+        }
+        if !verify_merkle_proof(&airdrop.merkle_root, info.sender.as_str(), amount, &proof) {
+            return Err(StdError::generic_err("Invalid Merkle proof"));
+        }
+
+        // Claims are paid out of what has been funded so far
+        airdrop.claimed = airdrop.claimed.checked_add(amount)?;
+        if airdrop.claimed > airdrop.funded {
+            return Err(StdError::generic_err(
+                "Airdrop is not funded enough for this claim",
+            ));
+        }
+        AIRDROPS.save(deps.storage, airdrop_id, &airdrop)?;
+        AIRDROP_CLAIMS.save(deps.storage, (airdrop_id, &info.sender), &amount)?;
+
+        let transfer = WasmMsg::Execute {
+            contract_addr: airdrop.token.to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: info.sender.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        };
+
+        Ok(Response::new().add_message(transfer).add_attributes(vec![
+            ("action", "claim_airdrop"),
+            ("airdrop_id", &airdrop_id.to_string()),
+            ("claimer", info.sender.as_str()),
+            ("amount", &amount.to_string()),
+        ]))
+    }
+
+    pub fn execute_clawback_airdrop(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        airdrop_id: u64,
+    ) -> StdResult<Response> {
+        let mut airdrop = AIRDROPS.load(deps.storage, airdrop_id)?;
+        if info.sender != airdrop.creator {
+            return Err(StdError::generic_err(
+                "Unauthorized: not the airdrop creator",
+            ));
+        }
+        if !airdrop.expiration.is_expired(&env.block) {
+            return Err(StdError::generic_err("Airdrop has not expired yet"));
+        }
+        if airdrop.clawed_back {
+            return Err(StdError::generic_err("Airdrop was already clawed back"));
+        }
+
+        let remaining = airdrop.funded.checked_sub(airdrop.claimed)?;
+        airdrop.clawed_back = true;
+        AIRDROPS.save(deps.storage, airdrop_id, &airdrop)?;
+
+        let mut messages = vec![];
+        if !remaining.is_zero() {
+            messages.push(WasmMsg::Execute {
+                contract_addr: airdrop.token.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: airdrop.creator.to_string(),
+                    amount: remaining,
+                })?,
+                funds: vec![],
+            });
+        }
+
+        Ok(Response::new().add_messages(messages).add_attributes(vec![
+            ("action", "clawback_airdrop"),
+            ("airdrop_id", &airdrop_id.to_string()),
+            ("amount", &remaining.to_string()),
+        ]))
+    }
+
     pub fn handle_token_creation_reply(deps: DepsMut, msg: Reply) -> StdResult<Response> {
-        let res = cw_utils::parse_instantiate_response_data(&msg.payload).unwrap();
-        let token_address = res.contract_address;    
+        let response = msg.result.into_result().map_err(StdError::generic_err)?;
+        // Chains on CosmWasm 2 fill msg_responses, older ones only the data field
+        #[allow(deprecated)]
+        let data = match response.msg_responses.into_iter().next() {
+            Some(msg_response) => msg_response.value,
+            None => response
+                .data
+                .ok_or_else(|| StdError::generic_err("Missing instantiate response data"))?,
+        };
+        let res = cw_utils::parse_instantiate_response_data(&data)
+            .map_err(|e| StdError::generic_err(e.to_string()))?;
+        let token_address = res.contract_address;
+
+        // Pending creations are keyed by the predicted address, so a miss means the
+        // token landed somewhere else than the address the factory handed out
+        let pending = PENDING_CREATIONS
+            .may_load(deps.storage, &token_address)?
+            .ok_or_else(|| {
+                StdError::generic_err(format!(
+                    "Token address mismatch: no creation was predicted at {}",
+                    token_address
+                ))
+            })?;
+        PENDING_CREATIONS.remove(deps.storage, &token_address);
         let token_info = TOKEN_INFO.load(deps.storage, &token_address)?;
-    
+
         let response_data = TokenCreationResponse {
             token_address: token_address.clone(),
             name: token_info.name.clone(),
             symbol: token_info.symbol.clone(),
             decimals: token_info.decimals,
-            max_price_impact: 100u128.into(),
-            curve_slope: 500u128.into(),
+            max_price_impact: pending.max_price_impact,
+            curve_slope: pending.curve_slope,
+            kind: TokenKind::Cw20,
         };
     
         Ok(Response::new()
@@ -270,32 +942,63 @@ pub mod execute {
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::GetTokenAddress { name, symbol } => {
             to_json_binary(&query_token_address(deps, name, symbol)?)
         }
         QueryMsg::GetTokenInfo { address } => to_json_binary(&query_token_info(deps, address)?),
-        QueryMsg::GetTokensByCreator { creator } => {
-            to_json_binary(&query_tokens_by_creator(deps, creator)?)
-        }
+        QueryMsg::GetTokensByCreator {
+            creator,
+            start_after,
+            limit,
+        } => to_json_binary(&query_tokens_by_creator(deps, creator, start_after, limit)?),
         QueryMsg::GetTokenCount {} => to_json_binary(&query_token_count(deps)?),
         QueryMsg::GetOwner {} => to_json_binary(&query_owner(deps)?),
+        QueryMsg::GetOwnership {} => to_json_binary(&query_ownership(deps)?),
         QueryMsg::GetListTokens { start_after, limit } => {
             to_json_binary(&query_list_tokens(deps, start_after, limit)?)
         }
+        QueryMsg::SearchTokensBySymbol {
+            prefix,
+            start_after,
+            limit,
+        } => to_json_binary(&query_search_tokens_by_symbol(
+            deps,
+            prefix,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::GetNewestTokens { start_after, limit } => {
+            to_json_binary(&query_newest_tokens(deps, start_after, limit)?)
+        }
         QueryMsg::GetConfig { } => {
             to_json_binary(&query_config(deps)?)
         }
+        QueryMsg::GetAllowedCallers {} => to_json_binary(&query_allowed_callers(deps)?),
+        QueryMsg::PredictTokenAddress { name, symbol } => {
+            to_json_binary(&query_predict_token_address(deps, env, name, symbol)?)
+        }
+        QueryMsg::GetAirdrop { airdrop_id } => to_json_binary(&query_airdrop(deps, airdrop_id)?),
+        QueryMsg::GetAirdropClaim {
+            airdrop_id,
+            address,
+        } => to_json_binary(&query_airdrop_claim(deps, airdrop_id, address)?),
     }
 }
 
 pub mod query {
     use crate::{
         msg::{
-            GetConfigResponse, GetListTokensResponse, GetOwnerResponse, GetTokenAddressResponse, GetTokenCountResponse, GetTokenInfoResponse, GetTokensByCreatorResponse
+            GetAirdropClaimResponse, GetAirdropResponse, GetAllowedCallersResponse,
+            GetConfigResponse, GetListTokensResponse, GetOwnerResponse, GetOwnershipResponse,
+            GetTokenAddressResponse, GetTokenCountResponse, GetTokenInfoResponse,
+            GetTokensByCreatorResponse, PredictTokenAddressResponse,
+        },
+        state::{
+            symbol_index_key, TokenInfo, AIRDROPS, AIRDROP_CLAIMS, ALLOWED_CALLERS, DEFAULT_LIMIT,
+            MAX_LIMIT, PENDING_OWNER, TOKEN_ADDRESS, TOKEN_INFO,
         },
-        state::{TokenInfo, DEFAULT_LIMIT, MAX_LIMIT, TOKEN_ADDRESS, TOKEN_INFO},
     };
     use cosmwasm_std::{Addr, Order};
     use cw_storage_plus::Bound;
@@ -323,19 +1026,24 @@ pub mod query {
     pub fn query_tokens_by_creator(
         deps: Deps,
         creator: Addr,
+        start_after: Option<String>,
+        limit: Option<u32>,
     ) -> StdResult<GetTokensByCreatorResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
         let tokens: Vec<TokenInfo> = TOKEN_INFO
-            .range(deps.storage, None, None, Order::Ascending)
-            .filter_map(|item| {
-                item.ok().and_then(|(_, token_info)| {
-                    if token_info.creator == creator {
-                        Some(token_info)
-                    } else {
-                        None
-                    }
-                })
-            })
-            .collect();
+            .idx
+            .creator
+            .prefix(creator)
+            .range(
+                deps.storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .take(limit)
+            .map(|item| item.map(|(_, token_info)| token_info))
+            .collect::<StdResult<Vec<_>>>()?;
 
         Ok(GetTokensByCreatorResponse { tokens })
     }
@@ -354,18 +1062,28 @@ pub mod query {
         Ok(GetOwnerResponse { owner })
     }
 
+    pub fn query_ownership(deps: Deps) -> StdResult<GetOwnershipResponse> {
+        let state = STATE.load(deps.storage)?;
+        let pending = PENDING_OWNER.may_load(deps.storage)?;
+
+        Ok(GetOwnershipResponse {
+            owner: state.owner,
+            pending_owner: pending.as_ref().map(|p| p.address.clone()),
+            pending_expiry: pending.and_then(|p| p.expiry),
+        })
+    }
+
     pub fn query_list_tokens(
         deps: Deps,
         start_from: Option<String>,
         limit: Option<u32>,
     ) -> StdResult<GetListTokensResponse> {
         let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-        let start = start_from.map(|s| s).unwrap();
 
         let tokens: Vec<TokenInfo> = TOKEN_INFO
             .range(
                 deps.storage,
-                Some(Bound::inclusive(&*start)),
+                start_from.as_deref().map(Bound::exclusive),
                 None,
                 Order::Ascending,
             )
@@ -376,6 +1094,63 @@ pub mod query {
         Ok(GetListTokensResponse { tokens })
     }
 
+    pub fn query_search_tokens_by_symbol(
+        deps: Deps,
+        prefix: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<GetListTokensResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let prefix = prefix.to_uppercase();
+        let start = match start_after {
+            Some(address) => {
+                let token_info = TOKEN_INFO.load(deps.storage, &address)?;
+                Bound::exclusive(symbol_index_key(&token_info))
+            }
+            None => Bound::inclusive(prefix.clone()),
+        };
+
+        let tokens: Vec<TokenInfo> = TOKEN_INFO
+            .idx
+            .symbol
+            .range(deps.storage, Some(start), None, Order::Ascending)
+            .map(|item| item.map(|(_, token_info)| token_info))
+            .take_while(|item| {
+                item.as_ref().map_or(true, |token_info| {
+                    symbol_index_key(token_info).starts_with(&prefix)
+                })
+            })
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(GetListTokensResponse { tokens })
+    }
+
+    pub fn query_newest_tokens(
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<GetListTokensResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let end = match start_after {
+            Some(address) => {
+                let token_info = TOKEN_INFO.load(deps.storage, &address)?;
+                Some(Bound::exclusive((token_info.creation_time, address)))
+            }
+            None => None,
+        };
+
+        let tokens: Vec<TokenInfo> = TOKEN_INFO
+            .idx
+            .creation_time
+            .range(deps.storage, None, end, Order::Descending)
+            .take(limit)
+            .map(|item| item.map(|(_, token_info)| token_info))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(GetListTokensResponse { tokens })
+    }
+
     pub fn query_config(
         deps: Deps
     ) -> StdResult<GetConfigResponse> {
@@ -383,25 +1158,73 @@ pub mod query {
 
         Ok(GetConfigResponse { config })
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::state::{ContractAddress, Cw20Coin, TokenInfo, TOKEN_INFO};
-    use cosmwasm_std::testing::{message_info, mock_dependencies, mock_env};
-    use cosmwasm_std::{attr, Addr, Event, HexBinary, SubMsgResponse, SubMsgResult, Uint128};
-    use cw_multi_test::App;
-    use cw_multi_test::ContractWrapper;
-    use prost::Message;
 
-    #[test]
-    fn test_instantiate() {
-        let mut deps = mock_dependencies();
+    pub fn query_allowed_callers(deps: Deps) -> StdResult<GetAllowedCallersResponse> {
+        let allowed_callers = ALLOWED_CALLERS.may_load(deps.storage)?.unwrap_or_default();
+        Ok(GetAllowedCallersResponse { allowed_callers })
+    }
 
-        let msg = InstantiateMsg {
-            token_code_id: 10,
+    pub fn query_predict_token_address(
+        deps: Deps,
+        env: Env,
+        name: String,
+        symbol: String,
+    ) -> StdResult<PredictTokenAddressResponse> {
+        let state = STATE.load(deps.storage)?;
+        let address = predict_token_address(
+            deps,
+            &env,
+            &state.token_code_hash,
+            &token_salt(&name, &symbol),
+        )?;
+
+        Ok(PredictTokenAddressResponse { address })
+    }
+
+    pub fn query_airdrop(deps: Deps, airdrop_id: u64) -> StdResult<GetAirdropResponse> {
+        let airdrop = AIRDROPS.load(deps.storage, airdrop_id)?;
+
+        Ok(GetAirdropResponse { airdrop })
+    }
+
+    pub fn query_airdrop_claim(
+        deps: Deps,
+        airdrop_id: u64,
+        address: String,
+    ) -> StdResult<GetAirdropClaimResponse> {
+        let address = deps.api.addr_validate(&address)?;
+        let claimed = AIRDROP_CLAIMS.may_load(deps.storage, (airdrop_id, &address))?;
+
+        Ok(GetAirdropClaimResponse { claimed })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{
+        ContractAddress, Cw20Coin, MsgMint, PendingCreation, ProtoCoin, SocialLink,
+        TokenCreationResponse, TokenInfo, TokenKind, TokenMetadata, MSG_CREATE_DENOM_TYPE_URL,
+        MSG_MINT_TYPE_URL, PENDING_CREATIONS, TOKEN_INFO,
+    };
+    use cosmwasm_std::testing::{message_info, mock_dependencies, mock_env};
+    use cosmwasm_std::{
+        attr, coins, from_json, Addr, BankMsg, Event, HexBinary, SubMsgResponse, SubMsgResult,
+        Uint128,
+    };
+    use cw_multi_test::App;
+    use cw_multi_test::ContractWrapper;
+    use cw_utils::Expiration;
+    use prost::Message;
+
+    #[test]
+    fn test_instantiate() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            token_code_id: 10,
             token_code_hash: HexBinary::default(),
+            allowed_callers: vec![],
         };
         let info = message_info(&Addr::unchecked("creator"), &[]);
         let env = mock_env();
@@ -413,6 +1236,7 @@ mod tests {
                 attr("method", "instantiate"),
                 attr("owner", info.sender.clone()),
                 attr("token_code_id", "10"),
+                attr("allowed_callers", ""),
             ]
         );
 
@@ -447,6 +1271,7 @@ mod tests {
                 &"528E5F16D05CDE640CDEF6D779A458CBF566AA4820E40ACFCF5066978D388CAD",
             )
             .unwrap(),
+            allowed_callers: vec![creator.to_string()],
         };
         let info = message_info(&Addr::unchecked(creator), &[]);
         let env = mock_env();
@@ -464,6 +1289,9 @@ mod tests {
             }],
             max_price_impact: 10u128.into(),
             curve_slope: 500u128.into(),
+            creator: None,
+            metadata: None,
+            kind: None,
         };
 
         // Execute the CreateToken message
@@ -487,6 +1315,9 @@ mod tests {
                 attr("uri", "URL"),
                 attr("address", token_address_event.as_str()),
                 attr("creator", info.sender.as_str()),
+                attr("max_price_impact", "10"),
+                attr("curve_slope", "500"),
+                attr("kind", "cw20"),
             ]
         );
 
@@ -511,6 +1342,177 @@ mod tests {
             Uint128::new(1000 * 10_i32.pow(9) as u128)
         );
         assert_eq!(token_info.creator, info.sender);
+        assert_eq!(
+            PENDING_CREATIONS
+                .load(&deps.storage, token_address_query.as_str())
+                .unwrap(),
+            PendingCreation {
+                max_price_impact: 10u128.into(),
+                curve_slope: 500u128.into(),
+            }
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_execute_create_native_token() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let dex = deps.api.addr_make("dex");
+        let info = message_info(&dex, &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg {
+                token_code_id: 1,
+                token_code_hash: HexBinary::default(),
+                allowed_callers: vec![dex.to_string()],
+            },
+        )
+        .unwrap();
+
+        let create_token_msg = |name: &str| ExecuteMsg::CreateToken {
+            name: name.to_string(),
+            symbol: "WOOF".to_string(),
+            decimals: 6,
+            uri: "URL".to_string(),
+            max_price_impact: 10u128.into(),
+            curve_slope: 500u128.into(),
+            initial_balances: vec![Cw20Coin {
+                address: dex.to_string(),
+                amount: Uint128::new(5000),
+            }],
+            creator: None,
+            metadata: None,
+            kind: Some(TokenKind::Native),
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            create_token_msg("Woof"),
+        )
+        .unwrap();
+
+        // Denom creation, mint, bank metadata, then the supply goes out by bank send
+        let denom = format!("factory/{}/WOOF", env.contract.address);
+        assert_eq!(res.messages.len(), 4);
+        let type_urls: Vec<&str> = res.messages[..3]
+            .iter()
+            .map(|sub| match &sub.msg {
+                CosmosMsg::Stargate { type_url, .. } => type_url.as_str(),
+                other => panic!("unexpected message {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            type_urls,
+            vec![
+                MSG_CREATE_DENOM_TYPE_URL,
+                MSG_MINT_TYPE_URL,
+                MSG_SET_DENOM_METADATA_TYPE_URL
+            ]
+        );
+        let CosmosMsg::Stargate { value, .. } = &res.messages[1].msg else {
+            unreachable!()
+        };
+        assert_eq!(
+            MsgMint::decode(value.as_slice()).unwrap(),
+            MsgMint {
+                sender: env.contract.address.to_string(),
+                amount: Some(ProtoCoin {
+                    denom: denom.clone(),
+                    amount: "5000".to_string(),
+                }),
+                mint_to_address: env.contract.address.to_string(),
+            }
+        );
+        assert_eq!(
+            res.messages[3].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: dex.to_string(),
+                amount: coins(5000, &denom),
+            })
+        );
+
+        // No reply is involved, the data is set on the execute response itself
+        let data: TokenCreationResponse = from_json(res.data.unwrap()).unwrap();
+        assert_eq!(data.token_address, denom);
+        assert_eq!(data.kind, TokenKind::Native);
+        let token_info = TOKEN_INFO.load(&deps.storage, &denom).unwrap();
+        assert_eq!(token_info.kind, TokenKind::Native);
+        assert!(!PENDING_CREATIONS.has(&deps.storage, &denom));
+
+        // Another name with the same symbol would need the same denom
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            create_token_msg("Woof Two"),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("Generic error: Token denom already exists: {}", denom)
+        );
+
+        // A token without supply is created but never minted
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::CreateToken {
+                name: "Empty".to_string(),
+                symbol: "EMPTY".to_string(),
+                decimals: 6,
+                uri: "URL".to_string(),
+                max_price_impact: 10u128.into(),
+                curve_slope: 500u128.into(),
+                initial_balances: vec![],
+                creator: None,
+                metadata: None,
+                kind: Some(TokenKind::Native),
+            },
+        )
+        .unwrap();
+        let type_urls: Vec<&str> = res
+            .messages
+            .iter()
+            .map(|sub| match &sub.msg {
+                CosmosMsg::Stargate { type_url, .. } => type_url.as_str(),
+                other => panic!("unexpected message {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            type_urls,
+            vec![MSG_CREATE_DENOM_TYPE_URL, MSG_SET_DENOM_METADATA_TYPE_URL]
+        );
+
+        // Symbols that are not valid subdenoms are rejected before any message
+        for symbol in ["", "WO OF", "WOOF!", &"W".repeat(45)] {
+            let err = execute(
+                deps.as_mut(),
+                env.clone(),
+                info.clone(),
+                ExecuteMsg::CreateToken {
+                    name: "Bad".to_string(),
+                    symbol: symbol.to_string(),
+                    decimals: 6,
+                    uri: "URL".to_string(),
+                    max_price_impact: 10u128.into(),
+                    curve_slope: 500u128.into(),
+                    initial_balances: vec![],
+                    creator: None,
+                    metadata: None,
+                    kind: Some(TokenKind::Native),
+                },
+            )
+            .unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("Generic error: Invalid subdenom: {}", symbol)
+            );
+        }
     }
 
     #[test]
@@ -520,16 +1522,32 @@ mod tests {
         let instantiate_msg = InstantiateMsg {
             token_code_id: 10,
             token_code_hash: HexBinary::default(),
+            allowed_callers: vec![],
         };
         let info = message_info(&Addr::unchecked("creator"), &[]);
-        let env = mock_env();
+        let mut env = mock_env();
         instantiate(deps.as_mut(), env.clone(), info.clone(), instantiate_msg).unwrap();
 
-        let new_owner = Addr::unchecked("new_owner");
+        let new_owner = deps.api.addr_make("new_owner");
+        let expiry = Expiration::AtHeight(env.block.height + 10);
         let transfer_ownership_msg = ExecuteMsg::TransferOwnership {
-            new_owner: new_owner.clone(),
+            new_owner: new_owner.to_string(),
+            expiry: Some(expiry),
         };
 
+        // Unvalidated addresses are rejected
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::TransferOwnership {
+                new_owner: "not an address".to_string(),
+                expiry: None,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Std(_)));
+
         let res = execute(
             deps.as_mut(),
             env.clone(),
@@ -541,12 +1559,66 @@ mod tests {
             res.attributes,
             vec![
                 attr("action", "transfer_ownership"),
+                attr("pending_owner", new_owner.as_str()),
+                attr("pending_expiry", expiry.to_string()),
+            ]
+        );
+
+        // Ownership stays put until the new owner accepts
+        let state = STATE.load(&deps.storage).unwrap();
+        assert_eq!(state.owner, info.sender);
+        let ownership = query_ownership(deps.as_ref()).unwrap();
+        assert_eq!(ownership.pending_owner, Some(new_owner.clone()));
+        assert_eq!(ownership.pending_expiry, Some(expiry));
+
+        // Only the pending owner can accept
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::AcceptOwnership {},
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Generic error: Unauthorized: not the pending owner"
+        );
+
+        // Not after the expiry though
+        let mut expired_env = env.clone();
+        expired_env.block.height += 10;
+        let err = execute(
+            deps.as_mut(),
+            expired_env,
+            message_info(&new_owner, &[]),
+            ExecuteMsg::AcceptOwnership {},
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Generic error: Ownership transfer has expired"
+        );
+
+        env.block.height += 5;
+        let res = execute(
+            deps.as_mut(),
+            env,
+            message_info(&new_owner, &[]),
+            ExecuteMsg::AcceptOwnership {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "accept_ownership"),
                 attr("new_owner", new_owner.as_str()),
             ]
         );
 
         let state = STATE.load(&deps.storage).unwrap();
         assert_eq!(state.owner, new_owner);
+        let ownership = query_ownership(deps.as_ref()).unwrap();
+        assert_eq!(ownership.pending_owner, None);
     }
 
     #[test]
@@ -556,6 +1628,7 @@ mod tests {
         let instantiate_msg = InstantiateMsg {
             token_code_id: 10,
             token_code_hash: HexBinary::default(),
+            allowed_callers: vec![],
         };
         let info = message_info(&Addr::unchecked("creator"), &[]);
         let env = mock_env();
@@ -586,43 +1659,516 @@ mod tests {
     }
 
     #[test]
-    #[allow(deprecated)]
-    fn test_reply() {
+    fn test_migrate() {
+        use crate::migrations::{TokenInfoV01, TOKEN_INFO_V01};
+        use cosmwasm_std::Storage;
+
         let mut deps = mock_dependencies();
 
+        // State as written by 0.1.0
+        cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.1.0").unwrap();
+        deps.storage.set(
+            b"state",
+            br#"{"owner":"creator","token_count":2,"token_code_id":10,"token_code_hash":"","token_creation_reply_id":1}"#,
+        );
+        TOKEN_INFO_V01
+            .save(
+                deps.as_mut().storage,
+                "woof1abcd",
+                &TokenInfoV01 {
+                    name: "Token".to_string(),
+                    symbol: "TKN".to_string(),
+                    decimals: 9,
+                    uri: "URL".to_string(),
+                    creator: Addr::unchecked("creator"),
+                    address: Addr::unchecked("woof1abcd"),
+                    creation_time: 1234567890,
+                    total_supply: Uint128::new(1000),
+                },
+            )
+            .unwrap();
+
+        // 0.2.0 needs to know which contracts may create tokens
+        let res = migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                allowed_callers: None,
+            },
+        );
+        assert_eq!(
+            res.unwrap_err(),
+            StdError::generic_err("Migrating from 0.1 requires allowed_callers")
+        );
+
+        let dex = deps.api.addr_make("dex");
+        let msg = MigrateMsg {
+            allowed_callers: Some(vec![dex.to_string()]),
+        };
+        let res = migrate(deps.as_mut(), mock_env(), msg.clone()).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "migrate"),
+                attr("from_version", "0.1.0"),
+                attr("to_version", CONTRACT_VERSION),
+            ]
+        );
+
+        let version = cw2::get_contract_version(&deps.storage).unwrap();
+        assert_eq!(version.version, CONTRACT_VERSION);
+        let state = STATE.load(&deps.storage).unwrap();
+        assert_eq!(state.owner, Addr::unchecked("creator"));
+        assert_eq!(state.token_count, 2);
+        let token_info = TOKEN_INFO.load(&deps.storage, "woof1abcd").unwrap();
+        assert_eq!(token_info.name, "Token");
+        assert_eq!(token_info.metadata, TokenMetadata::default());
+        assert_eq!(
+            query_allowed_callers(deps.as_ref())
+                .unwrap()
+                .allowed_callers,
+            vec![dex]
+        );
+
+        // Downgrades are refused
+        cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "9.0.0").unwrap();
+        let res = migrate(deps.as_mut(), mock_env(), msg.clone());
+        assert_eq!(
+            res.unwrap_err(),
+            StdError::generic_err(format!(
+                "Cannot migrate from newer version 9.0.0 to {}",
+                CONTRACT_VERSION
+            ))
+        );
+
+        // Another contract's state is refused
+        cw2::set_contract_version(deps.as_mut().storage, "crates.io:cw20-base", "0.1.0").unwrap();
+        let res = migrate(deps.as_mut(), mock_env(), msg.clone());
+        assert_eq!(
+            res.unwrap_err(),
+            StdError::generic_err("Cannot migrate from a different contract: crates.io:cw20-base")
+        );
+    }
+
+    #[test]
+    fn test_execute_update_allowed_callers() {
+        let mut deps = mock_dependencies();
+        let dex = deps.api.addr_make("dex");
+        let other = deps.api.addr_make("other");
+
         let instantiate_msg = InstantiateMsg {
             token_code_id: 10,
             token_code_hash: HexBinary::default(),
+            allowed_callers: vec![dex.to_string()],
         };
         let info = message_info(&Addr::unchecked("creator"), &[]);
         let env = mock_env();
         instantiate(deps.as_mut(), env.clone(), info.clone(), instantiate_msg).unwrap();
 
-        // Create a Protobuf message for the payload
+        // Only allow-listed contracts can create tokens
+        let create_token_msg = ExecuteMsg::CreateToken {
+            name: "Token".to_string(),
+            symbol: "TKN".to_string(),
+            decimals: 9,
+            uri: "URL".to_string(),
+            initial_balances: vec![],
+            max_price_impact: 10u128.into(),
+            curve_slope: 500u128.into(),
+            creator: None,
+            metadata: None,
+            kind: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&other, &[]),
+            create_token_msg,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Generic error: Unauthorized: caller is not allowed to create tokens"
+        );
+
+        // Only the owner edits the list
+        let update_msg = ExecuteMsg::UpdateAllowedCallers {
+            add: vec![other.to_string()],
+            remove: vec![dex.to_string()],
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&other, &[]),
+            update_msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "Generic error: Unauthorized");
+
+        let res = execute(deps.as_mut(), env, info, update_msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "update_allowed_callers"),
+                attr("added", other.as_str()),
+                attr("removed", dex.as_str()),
+            ]
+        );
+        assert_eq!(
+            query_allowed_callers(deps.as_ref())
+                .unwrap()
+                .allowed_callers,
+            vec![other]
+        );
+    }
+
+    #[test]
+    fn test_execute_register_dex() {
+        use cosmwasm_std::{ContractInfoResponse, ContractResult, SystemResult, WasmQuery};
+
+        let mut deps = mock_dependencies();
+        let owner = deps.api.addr_make("owner");
+        let dex = deps.api.addr_make("dex");
+        let rogue = deps.api.addr_make("rogue");
+
+        // A fresh factory starts without allowed callers
+        let instantiate_msg = InstantiateMsg {
+            token_code_id: 10,
+            token_code_hash: HexBinary::default(),
+            allowed_callers: vec![],
+        };
+        let env = mock_env();
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&owner, &[]),
+            instantiate_msg,
+        )
+        .unwrap();
+
+        // The owner instantiated the DEX, someone else the rogue contract
+        let (dex_addr, owner_addr) = (dex.clone(), owner.clone());
+        deps.querier.update_wasm(move |query| match query {
+            WasmQuery::ContractInfo { contract_addr } => {
+                let creator = if *contract_addr == dex_addr.to_string() {
+                    owner_addr.clone()
+                } else {
+                    Addr::unchecked("someone_else")
+                };
+                SystemResult::Ok(ContractResult::Ok(
+                    to_json_binary(&ContractInfoResponse::new(1, creator, None, false, None))
+                        .unwrap(),
+                ))
+            }
+            _ => panic!("unexpected query"),
+        });
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&rogue, &[]),
+            ExecuteMsg::RegisterDex {},
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Generic error: Unauthorized: DEX was not instantiated by the factory owner"
+        );
+
+        // Registering is idempotent
+        for _ in 0..2 {
+            let res = execute(
+                deps.as_mut(),
+                env.clone(),
+                message_info(&dex, &[]),
+                ExecuteMsg::RegisterDex {},
+            )
+            .unwrap();
+            assert_eq!(
+                res.attributes,
+                vec![attr("action", "register_dex"), attr("dex", dex.as_str())]
+            );
+        }
+        assert_eq!(
+            query_allowed_callers(deps.as_ref())
+                .unwrap()
+                .allowed_callers,
+            vec![dex]
+        );
+    }
+
+    #[test]
+    fn test_execute_update_token_metadata() {
+        use cosmwasm_std::{
+            from_json, ContractResult, CosmosMsg, SystemResult, WasmMsg, WasmQuery,
+        };
+        use cw20::{Cw20ExecuteMsg, Cw20QueryMsg, Logo, MarketingInfoResponse};
+
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let factory = env.contract.address.clone();
+
+        // "woof1new" was created with the factory as marketing address, "woof1old" before that
+        deps.querier.update_wasm(move |query| match query {
+            WasmQuery::Smart { contract_addr, msg } => {
+                assert_eq!(
+                    from_json::<Cw20QueryMsg>(msg).unwrap(),
+                    Cw20QueryMsg::MarketingInfo {}
+                );
+                let marketing = (contract_addr == "woof1new").then(|| factory.clone());
+                SystemResult::Ok(ContractResult::Ok(
+                    to_json_binary(&MarketingInfoResponse {
+                        project: None,
+                        description: None,
+                        logo: None,
+                        marketing,
+                    })
+                    .unwrap(),
+                ))
+            }
+            _ => panic!("unexpected query"),
+        });
+        for address in ["woof1new", "woof1old"] {
+            let token_info = TokenInfo {
+                name: "Token".to_string(),
+                symbol: "TKN".to_string(),
+                decimals: 9,
+                uri: "URL".to_string(),
+                creator: Addr::unchecked("creator"),
+                address: Addr::unchecked(address),
+                creation_time: 1234567890,
+                total_supply: Uint128::new(1000),
+                metadata: TokenMetadata::default(),
+                kind: TokenKind::Cw20,
+            };
+            TOKEN_INFO
+                .save(&mut deps.storage, address, &token_info)
+                .unwrap();
+        }
+
+        let metadata = TokenMetadata {
+            description: Some("Much wow".to_string()),
+            project_url: Some("https://woof.example".to_string()),
+            logo_url: Some("https://woof.example/logo.png".to_string()),
+            socials: vec![SocialLink {
+                platform: "x".to_string(),
+                url: "https://x.com/woof".to_string(),
+            }],
+        };
+        let update = |address: &str| ExecuteMsg::UpdateTokenMetadata {
+            address: address.to_string(),
+            metadata: metadata.clone(),
+        };
+
+        // Only the creator can update
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&Addr::unchecked("other"), &[]),
+            update("woof1new"),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Generic error: Unauthorized: not the token creator"
+        );
+
+        let creator = message_info(&Addr::unchecked("creator"), &[]);
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            creator.clone(),
+            update("woof1new"),
+        )
+        .unwrap();
+        assert_eq!(res.attributes[2], attr("synced", "true"));
+        assert_eq!(
+            res.messages
+                .iter()
+                .map(|msg| msg.msg.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: "woof1new".to_string(),
+                    msg: to_json_binary(&Cw20ExecuteMsg::UpdateMarketing {
+                        project: Some("https://woof.example".to_string()),
+                        description: Some("Much wow".to_string()),
+                        marketing: None,
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                }),
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: "woof1new".to_string(),
+                    msg: to_json_binary(&Cw20ExecuteMsg::UploadLogo(Logo::Url(
+                        "https://woof.example/logo.png".to_string()
+                    )))
+                    .unwrap(),
+                    funds: vec![],
+                }),
+            ]
+        );
+
+        // Older tokens only get the factory copy updated
+        let res = execute(deps.as_mut(), env, creator, update("woof1old")).unwrap();
+        assert_eq!(res.attributes[2], attr("synced", "false"));
+        assert!(res.messages.is_empty());
+        for address in ["woof1new", "woof1old"] {
+            let token_info = TOKEN_INFO.load(&deps.storage, address).unwrap();
+            assert_eq!(token_info.metadata, metadata);
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_reply() {
+        let mut deps = mock_dependencies();
+
+        let creator = deps.api.addr_make("creator");
+        let instantiate_msg = InstantiateMsg {
+            token_code_id: 10,
+            token_code_hash: HexBinary::from_hex(
+                "528E5F16D05CDE640CDEF6D779A458CBF566AA4820E40ACFCF5066978D388CAD",
+            )
+            .unwrap(),
+            allowed_callers: vec![creator.to_string()],
+        };
+        let info = message_info(&creator, &[]);
+        let env = mock_env();
+        instantiate(deps.as_mut(), env.clone(), info.clone(), instantiate_msg).unwrap();
+
+        let create_token_msg = ExecuteMsg::CreateToken {
+            name: "Token".to_string(),
+            symbol: "TKN".to_string(),
+            decimals: 9,
+            uri: "URL".to_string(),
+            initial_balances: vec![],
+            max_price_impact: 25u128.into(),
+            curve_slope: 7u128.into(),
+            creator: None,
+            metadata: None,
+            kind: None,
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), create_token_msg).unwrap();
+        let token_address =
+            query_token_address(deps.as_ref(), "Token".to_string(), "TKN".to_string())
+                .unwrap()
+                .address
+                .to_string();
+
+        // Create a Protobuf message for the instantiate response
         let contract_address_msg = ContractAddress {
-            contract_address: "woof1abcd".to_string(),
+            contract_address: token_address.clone(),
         };
 
         let mut buf = Vec::new();
         contract_address_msg.encode(&mut buf).unwrap();
 
-        // Create a SubMsgResponse with msg_responses for newer versions and payload for the older version
+        // Older chains only fill the deprecated data field
         let msg_response = SubMsgResponse {
-            events: vec![Event::new("instantiate").add_attribute("contract_address", "woof1abcd")],
-            data: Some(Binary::from(buf.clone())), // This is deprecated but required
+            events: vec![
+                Event::new("instantiate").add_attribute("contract_address", token_address.clone())
+            ],
+            data: Some(Binary::from(buf)),
             msg_responses: vec![],
         };
 
-        // Create Reply message including the payload
         let msg = Reply {
             id: 1,
             result: SubMsgResult::Ok(msg_response),
-            payload: Binary::from(buf),
+            payload: Binary::default(),
             gas_used: 1000,
         };
 
-        let res = reply(deps.as_mut(), env.clone(), msg).unwrap();
-        assert_eq!(res.attributes, vec![attr("token_address", "woof1abcd"),]);
+        let res = reply(deps.as_mut(), env.clone(), msg.clone()).unwrap();
+        assert_eq!(res.attributes, vec![attr("token_address", &token_address)]);
+
+        // The reply hands back the parameters passed to CreateToken
+        let data: TokenCreationResponse = cosmwasm_std::from_json(res.data.unwrap()).unwrap();
+        assert_eq!(
+            data,
+            TokenCreationResponse {
+                token_address: token_address.clone(),
+                name: "Token".to_string(),
+                symbol: "TKN".to_string(),
+                decimals: 9,
+                max_price_impact: 25u128.into(),
+                curve_slope: 7u128.into(),
+                kind: TokenKind::Cw20,
+            }
+        );
+
+        // The pending parameters are consumed, so replaying the reply fails
+        assert!(!PENDING_CREATIONS.has(&deps.storage, &token_address));
+        assert_eq!(
+            reply(deps.as_mut(), env, msg).unwrap_err(),
+            StdError::generic_err(format!(
+                "Token address mismatch: no creation was predicted at {}",
+                token_address
+            ))
+        );
+    }
+
+    #[test]
+    fn test_query_predict_token_address() {
+        let mut deps = mock_dependencies();
+
+        let dex = deps.api.addr_make("dex");
+        let instantiate_msg = InstantiateMsg {
+            token_code_id: 10,
+            token_code_hash: HexBinary::from_hex(
+                "528E5F16D05CDE640CDEF6D779A458CBF566AA4820E40ACFCF5066978D388CAD",
+            )
+            .unwrap(),
+            allowed_callers: vec![dex.to_string()],
+        };
+        let env = mock_env();
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&dex, &[]),
+            instantiate_msg,
+        )
+        .unwrap();
+
+        let predict = |deps: Deps, name: &str, symbol: &str| {
+            query_predict_token_address(deps, mock_env(), name.to_string(), symbol.to_string())
+                .unwrap()
+                .address
+        };
+        let predicted = predict(deps.as_ref(), "Token", "TKN");
+        assert_ne!(predicted, predict(deps.as_ref(), "Token", "TKN2"));
+        assert_ne!(predicted, predict(deps.as_ref(), "TokenT", "KN"));
+
+        // CreateToken lands at the predicted address, however many tokens came before
+        let create_token_msg = |name: &str, symbol: &str| ExecuteMsg::CreateToken {
+            name: name.to_string(),
+            symbol: symbol.to_string(),
+            decimals: 9,
+            uri: "URL".to_string(),
+            initial_balances: vec![],
+            max_price_impact: 10u128.into(),
+            curve_slope: 500u128.into(),
+            creator: None,
+            metadata: None,
+            kind: None,
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&dex, &[]),
+            create_token_msg("Other", "OTH"),
+        )
+        .unwrap();
+        let res = execute(
+            deps.as_mut(),
+            env,
+            message_info(&dex, &[]),
+            create_token_msg("Token", "TKN"),
+        )
+        .unwrap();
+        assert!(res
+            .attributes
+            .contains(&attr("address", predicted.as_str())));
     }
 
     #[test]
@@ -639,6 +2185,8 @@ mod tests {
             address: Addr::unchecked(token_address.clone()),
             creation_time: 1234567890,
             total_supply: Uint128::new(1000),
+            metadata: TokenMetadata::default(),
+            kind: TokenKind::Cw20,
         };
 
         TOKEN_INFO
@@ -662,6 +2210,8 @@ mod tests {
             address: Addr::unchecked("woof1abcd"),
             creation_time: 1234567890,
             total_supply: Uint128::new(1000),
+            metadata: TokenMetadata::default(),
+            kind: TokenKind::Cw20,
         };
 
         let token_info2 = TokenInfo {
@@ -673,6 +2223,8 @@ mod tests {
             address: Addr::unchecked("woof1efgh"),
             creation_time: 1234567890,
             total_supply: Uint128::new(2000),
+            metadata: TokenMetadata::default(),
+            kind: TokenKind::Cw20,
         };
 
         TOKEN_INFO
@@ -690,11 +2242,46 @@ mod tests {
             )
             .unwrap();
 
+        let token_info3 = TokenInfo {
+            name: "Token3".to_string(),
+            symbol: "TK3".to_string(),
+            creator: Addr::unchecked("other"),
+            address: Addr::unchecked("woof1ijkl"),
+            ..token_info1.clone()
+        };
+        TOKEN_INFO
+            .save(
+                &mut deps.storage,
+                token_info3.address.as_str(),
+                &token_info3,
+            )
+            .unwrap();
+
         let response =
-            query::query_tokens_by_creator(deps.as_ref(), Addr::unchecked("creator")).unwrap();
-        assert_eq!(response.tokens.len(), 2);
-        assert!(response.tokens.contains(&token_info1));
-        assert!(response.tokens.contains(&token_info2));
+            query::query_tokens_by_creator(deps.as_ref(), Addr::unchecked("creator"), None, None)
+                .unwrap();
+        assert_eq!(
+            response.tokens,
+            vec![token_info1.clone(), token_info2.clone()]
+        );
+
+        // Pages continue after the last address returned
+        let response = query::query_tokens_by_creator(
+            deps.as_ref(),
+            Addr::unchecked("creator"),
+            None,
+            Some(1),
+        )
+        .unwrap();
+        assert_eq!(response.tokens, vec![token_info1.clone()]);
+        let response = query::query_tokens_by_creator(
+            deps.as_ref(),
+            Addr::unchecked("creator"),
+            Some(token_info1.address.to_string()),
+            Some(1),
+        )
+        .unwrap();
+        assert_eq!(response.tokens, vec![token_info2]);
     }
 
     #[test]
@@ -742,6 +2329,8 @@ mod tests {
             address: Addr::unchecked("woof1abcd".to_string()),
             creation_time: 1234567890,
             total_supply: Uint128::new(1000),
+            metadata: TokenMetadata::default(),
+            kind: TokenKind::Cw20,
         };
 
         let token_info2 = TokenInfo {
@@ -753,6 +2342,8 @@ mod tests {
             address: Addr::unchecked("woof1efgh".to_string()),
             creation_time: 1234567890,
             total_supply: Uint128::new(2000),
+            metadata: TokenMetadata::default(),
+            kind: TokenKind::Cw20,
         };
 
         TOKEN_INFO
@@ -775,5 +2366,256 @@ mod tests {
         assert_eq!(response.tokens.len(), 2);
         assert!(response.tokens.contains(&token_info1));
         assert!(response.tokens.contains(&token_info2));
+
+        // No start_after lists from the beginning
+        let response = query::query_list_tokens(deps.as_ref(), None, None).unwrap();
+        assert_eq!(
+            response.tokens,
+            vec![token_info1.clone(), token_info2.clone()]
+        );
+        let response =
+            query::query_list_tokens(deps.as_ref(), Some(token_info1.address.to_string()), None)
+                .unwrap();
+        assert_eq!(response.tokens, vec![token_info2]);
+    }
+
+    #[test]
+    fn test_query_tokens_by_symbol_and_age() {
+        let mut deps = mock_dependencies();
+
+        let tokens: Vec<TokenInfo> = [
+            ("woof1aaaa", "DOGE", 100),
+            ("woof1bbbb", "doggo", 300),
+            ("woof1cccc", "CAT", 200),
+            ("woof1dddd", "DOG", 400),
+        ]
+        .into_iter()
+        .map(|(address, symbol, creation_time)| TokenInfo {
+            name: symbol.to_string(),
+            symbol: symbol.to_string(),
+            decimals: 6,
+            uri: "URL".to_string(),
+            creator: Addr::unchecked("creator"),
+            address: Addr::unchecked(address),
+            creation_time,
+            total_supply: Uint128::new(1000),
+            metadata: TokenMetadata::default(),
+            kind: TokenKind::Cw20,
+        })
+        .collect();
+        for token_info in &tokens {
+            TOKEN_INFO
+                .save(&mut deps.storage, token_info.address.as_str(), token_info)
+                .unwrap();
+        }
+
+        // Prefix match ignores case and stops at the first non-matching symbol
+        let response =
+            query::query_search_tokens_by_symbol(deps.as_ref(), "dog".to_string(), None, None)
+                .unwrap();
+        let symbols: Vec<&str> = response.tokens.iter().map(|t| t.symbol.as_str()).collect();
+        assert_eq!(symbols, vec!["DOG", "DOGE", "doggo"]);
+
+        let response = query::query_search_tokens_by_symbol(
+            deps.as_ref(),
+            "DOG".to_string(),
+            Some("woof1aaaa".to_string()),
+            Some(5),
+        )
+        .unwrap();
+        assert_eq!(response.tokens, vec![tokens[1].clone()]);
+
+        let response =
+            query::query_search_tokens_by_symbol(deps.as_ref(), "FOX".to_string(), None, None)
+                .unwrap();
+        assert!(response.tokens.is_empty());
+
+        // Newest first, paging from the last token seen
+        let response = query::query_newest_tokens(deps.as_ref(), None, Some(2)).unwrap();
+        assert_eq!(response.tokens, vec![tokens[3].clone(), tokens[1].clone()]);
+        let response =
+            query::query_newest_tokens(deps.as_ref(), Some("woof1bbbb".to_string()), None).unwrap();
+        assert_eq!(response.tokens, vec![tokens[2].clone(), tokens[0].clone()]);
+    }
+
+    #[test]
+    fn test_airdrop_claim_and_clawback() {
+        use crate::msg::ReceiveMsg;
+        use cosmwasm_std::{CosmosMsg, WasmMsg};
+        use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let token = deps.api.addr_make("token");
+        let creator = deps.api.addr_make("creator");
+        let alice = deps.api.addr_make("alice");
+        let bob = deps.api.addr_make("bob");
+        TOKEN_INFO
+            .save(
+                &mut deps.storage,
+                token.as_str(),
+                &TokenInfo {
+                    name: "Token".to_string(),
+                    symbol: "TKN".to_string(),
+                    decimals: 6,
+                    uri: "URL".to_string(),
+                    creator: creator.clone(),
+                    address: token.clone(),
+                    creation_time: 1234567890,
+                    total_supply: Uint128::new(1_000),
+                    metadata: TokenMetadata::default(),
+                    kind: TokenKind::Cw20,
+                },
+            )
+            .unwrap();
+
+        // Two leaves, each one the other's proof
+        let leaf = |address: &Addr, amount: u128| {
+            HexBinary::from(Sha256::digest(format!("{}{}", address, amount)).to_vec())
+        };
+        let (alice_leaf, bob_leaf) = (leaf(&alice, 100), leaf(&bob, 200));
+        let (first, second) = if alice_leaf <= bob_leaf {
+            (&alice_leaf, &bob_leaf)
+        } else {
+            (&bob_leaf, &alice_leaf)
+        };
+        let root = HexBinary::from(
+            Sha256::new()
+                .chain_update(first.as_slice())
+                .chain_update(second.as_slice())
+                .finalize()
+                .to_vec(),
+        );
+        let expiration = Expiration::AtHeight(env.block.height + 100);
+
+        let register = |deps: DepsMut, token: &Addr, expiration: Expiration| {
+            execute(
+                deps,
+                mock_env(),
+                message_info(&creator, &[]),
+                ExecuteMsg::RegisterAirdrop {
+                    token: token.to_string(),
+                    merkle_root: root.clone(),
+                    expiration,
+                },
+            )
+        };
+        let err = register(deps.as_mut(), &alice, expiration).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Generic error: Token was not created by this factory"
+        );
+        let err = register(deps.as_mut(), &token, Expiration::Never {}).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Generic error: Airdrop expiration must be set and in the future"
+        );
+        register(deps.as_mut(), &token, expiration).unwrap();
+
+        // Funding goes through the token's Receive hook
+        let fund = |deps: DepsMut, token: &Addr, amount: u128| {
+            execute(
+                deps,
+                mock_env(),
+                message_info(token, &[]),
+                ExecuteMsg::Receive(Cw20ReceiveMsg {
+                    sender: creator.to_string(),
+                    amount: Uint128::new(amount),
+                    msg: to_json_binary(&ReceiveMsg::FundAirdrop { airdrop_id: 1 }).unwrap(),
+                }),
+            )
+        };
+        let err = fund(deps.as_mut(), &alice, 250).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Generic error: Wrong token for this airdrop"
+        );
+        fund(deps.as_mut(), &token, 250).unwrap();
+
+        let claim = |deps: DepsMut, env: &Env, claimer: &Addr, amount: u128, proof: &HexBinary| {
+            execute(
+                deps,
+                env.clone(),
+                message_info(claimer, &[]),
+                ExecuteMsg::ClaimAirdrop {
+                    airdrop_id: 1,
+                    amount: Uint128::new(amount),
+                    proof: vec![proof.clone()],
+                },
+            )
+        };
+        let res = claim(deps.as_mut(), &env, &alice, 100, &bob_leaf).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: token.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: alice.to_string(),
+                    amount: Uint128::new(100),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+        let err = claim(deps.as_mut(), &env, &alice, 100, &bob_leaf).unwrap_err();
+        assert_eq!(err.to_string(), "Generic error: Airdrop already claimed");
+        let err = claim(deps.as_mut(), &env, &bob, 300, &alice_leaf).unwrap_err();
+        assert_eq!(err.to_string(), "Generic error: Invalid Merkle proof");
+
+        // Only 150 of bob's 200 are funded
+        let err = claim(deps.as_mut(), &env, &bob, 200, &alice_leaf).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Generic error: Airdrop is not funded enough for this claim"
+        );
+
+        let clawback = |deps: DepsMut, env: &Env| {
+            execute(
+                deps,
+                env.clone(),
+                message_info(&creator, &[]),
+                ExecuteMsg::ClawbackAirdrop { airdrop_id: 1 },
+            )
+        };
+        let err = clawback(deps.as_mut(), &env).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Generic error: Airdrop has not expired yet"
+        );
+
+        // Once expired, claims close and the creator takes back the rest
+        env.block.height += 100;
+        let err = claim(deps.as_mut(), &env, &bob, 200, &alice_leaf).unwrap_err();
+        assert_eq!(err.to_string(), "Generic error: Airdrop has expired");
+        let res = clawback(deps.as_mut(), &env).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: token.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: creator.to_string(),
+                    amount: Uint128::new(150),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+        let err = clawback(deps.as_mut(), &env).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Generic error: Airdrop was already clawed back"
+        );
+
+        let airdrop = query::query_airdrop(deps.as_ref(), 1).unwrap().airdrop;
+        assert_eq!(airdrop.funded, Uint128::new(250));
+        assert_eq!(airdrop.claimed, Uint128::new(100));
+        assert!(airdrop.clawed_back);
+        let claimed = |address: &Addr| {
+            query::query_airdrop_claim(deps.as_ref(), 1, address.to_string())
+                .unwrap()
+                .claimed
+        };
+        assert_eq!(claimed(&alice), Some(Uint128::new(100)));
+        assert_eq!(claimed(&bob), None);
     }
 }
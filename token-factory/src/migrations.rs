@@ -0,0 +1,49 @@
+use cosmwasm_std::{Addr, Order, StdResult, Storage, Uint128};
+use cw_storage_plus::Map;
+use serde::{Deserialize, Serialize};
+
+use crate::state::{TokenInfo, TokenKind, TokenMetadata, TOKEN_INFO};
+
+// Token info as written by 0.1.x, before tokens carried metadata
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TokenInfoV01 {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub uri: String,
+    pub creator: Addr,
+    pub address: Addr,
+    pub creation_time: u64,
+    pub total_supply: Uint128,
+}
+
+pub const TOKEN_INFO_V01: Map<&str, TokenInfoV01> = Map::new("token_info");
+
+// Rewrites 0.1.x token info into the 0.2 layout
+pub fn migrate_from_v0_1(storage: &mut dyn Storage) -> StdResult<()> {
+    let tokens = TOKEN_INFO_V01
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (address, token) in tokens {
+        // The indexed save reads the old entry back, which no longer parses
+        TOKEN_INFO_V01.remove(storage, &address);
+        TOKEN_INFO.save(
+            storage,
+            &address,
+            &TokenInfo {
+                name: token.name,
+                symbol: token.symbol,
+                decimals: token.decimals,
+                uri: token.uri,
+                creator: token.creator,
+                address: token.address,
+                creation_time: token.creation_time,
+                total_supply: token.total_supply,
+                metadata: TokenMetadata::default(),
+                kind: TokenKind::Cw20,
+            },
+        )?;
+    }
+
+    Ok(())
+}
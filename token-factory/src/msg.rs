@@ -1,12 +1,17 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
 use cosmwasm_std::{Addr, HexBinary, Uint128};
+use cw20::Cw20ReceiveMsg;
+use cw_utils::Expiration;
 
-use crate::state::{Cw20Coin, State, TokenInfo};
+use crate::state::{Airdrop, Cw20Coin, State, TokenInfo, TokenKind, TokenMetadata};
 
 #[cw_serde]
 pub struct InstantiateMsg {
     pub token_code_id: u64,
     pub token_code_hash: HexBinary,
+    // Contracts allowed to call CreateToken. Usually left empty, since a DEX
+    // instantiated by the factory owner adds itself with RegisterDex.
+    pub allowed_callers: Vec<String>,
 }
 
 #[cw_serde]
@@ -19,14 +24,59 @@ pub enum ExecuteMsg {
         max_price_impact: Uint128, 
         curve_slope: Uint128,
         initial_balances: Vec<Cw20Coin>,
+        // Creator to record when called on someone's behalf, defaults to the sender
+        creator: Option<String>,
+        metadata: Option<TokenMetadata>,
+        // Defaults to a CW20, Native needs the chain's x/tokenfactory module
+        kind: Option<TokenKind>,
     },
     TransferOwnership {
-        new_owner: Addr,
+        new_owner: String,
+        expiry: Option<Expiration>,
     },
+    AcceptOwnership {},
     UpdateTokenCodeId {
         new_token_code_id: u64,
         new_token_code_hash: HexBinary,
     },
+    UpdateAllowedCallers {
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    // Sent by a DEX from its instantiate, accepted if the factory owner instantiated it
+    RegisterDex {},
+    UpdateTokenMetadata {
+        address: String,
+        metadata: TokenMetadata,
+    },
+    // Opens a Merkle airdrop of a factory CW20, funded afterwards with FundAirdrop
+    RegisterAirdrop {
+        token: String,
+        merkle_root: HexBinary,
+        expiration: Expiration,
+    },
+    Receive(Cw20ReceiveMsg),
+    ClaimAirdrop {
+        airdrop_id: u64,
+        amount: Uint128,
+        proof: Vec<HexBinary>,
+    },
+    // Returns what was not claimed to the airdrop creator once it expired
+    ClawbackAirdrop {
+        airdrop_id: u64,
+    },
+}
+
+// Messages sent along with CW20 tokens
+#[cw_serde]
+pub enum ReceiveMsg {
+    FundAirdrop { airdrop_id: u64 },
+}
+
+#[cw_serde]
+pub struct MigrateMsg {
+    // CreateToken callers to allow, required when migrating from 0.1.x
+    pub allowed_callers: Option<Vec<String>>,
 }
 
 #[cw_serde]
@@ -37,18 +87,46 @@ pub enum QueryMsg {
     #[returns(GetTokenInfoResponse)]
     GetTokenInfo { address: String },
     #[returns(GetTokensByCreatorResponse)]
-    GetTokensByCreator { creator: Addr },
+    GetTokensByCreator {
+        creator: Addr,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
     #[returns(GetTokenCountResponse)]
     GetTokenCount {},
     #[returns(GetOwnerResponse)]
     GetOwner {},
+    #[returns(GetOwnershipResponse)]
+    GetOwnership {},
     #[returns(GetListTokensResponse)]
     GetListTokens {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    // Case-insensitive symbol prefix match, ordered by symbol
+    #[returns(GetListTokensResponse)]
+    SearchTokensBySymbol {
+        prefix: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // Most recently created first
+    #[returns(GetListTokensResponse)]
+    GetNewestTokens {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
     #[returns(GetConfigResponse)]
     GetConfig {},
+    #[returns(GetAllowedCallersResponse)]
+    GetAllowedCallers {},
+    // Address CreateToken will instantiate the token at
+    #[returns(PredictTokenAddressResponse)]
+    PredictTokenAddress { name: String, symbol: String },
+    #[returns(GetAirdropResponse)]
+    GetAirdrop { airdrop_id: u64 },
+    #[returns(GetAirdropClaimResponse)]
+    GetAirdropClaim { airdrop_id: u64, address: String },
 }
 
 // We define a custom struct for each query response
@@ -77,6 +155,13 @@ pub struct GetOwnerResponse {
     pub owner: Addr,
 }
 
+#[cw_serde]
+pub struct GetOwnershipResponse {
+    pub owner: Addr,
+    pub pending_owner: Option<Addr>,
+    pub pending_expiry: Option<Expiration>,
+}
+
 #[cw_serde]
 pub struct GetListTokensResponse {
     pub tokens: Vec<TokenInfo>,
@@ -86,3 +171,23 @@ pub struct GetListTokensResponse {
 pub struct GetConfigResponse {
     pub config: State,
 }
+
+#[cw_serde]
+pub struct GetAllowedCallersResponse {
+    pub allowed_callers: Vec<Addr>,
+}
+
+#[cw_serde]
+pub struct PredictTokenAddressResponse {
+    pub address: Addr,
+}
+
+#[cw_serde]
+pub struct GetAirdropResponse {
+    pub airdrop: Airdrop,
+}
+
+#[cw_serde]
+pub struct GetAirdropClaimResponse {
+    pub claimed: Option<Uint128>,
+}
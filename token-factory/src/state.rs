@@ -1,8 +1,12 @@
+use std::fmt;
+
+use cosmwasm_schema::cw_serde;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cosmwasm_std::{Addr, HexBinary, Uint128};
-use cw_storage_plus::{Item, Map};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex, UniqueIndex};
+use cw_utils::Expiration;
 
 pub const DEFAULT_LIMIT: u32 = 10;
 pub const MAX_LIMIT: u32 = 30;
@@ -16,6 +20,12 @@ pub struct State {
     pub token_creation_reply_id: u64,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingOwner {
+    pub address: Addr,
+    pub expiry: Option<Expiration>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Cw20Coin {
     pub address: String,
@@ -32,6 +42,83 @@ pub struct TokenInfo {
     pub address: Addr,
     pub creation_time: u64,
     pub total_supply: Uint128,
+    pub metadata: TokenMetadata,
+    pub kind: TokenKind, // For native tokens the address field holds the denom
+}
+
+// How a token is issued: a cw20-base contract or an x/tokenfactory denom
+#[cw_serde]
+#[derive(Copy, Default, Eq)]
+pub enum TokenKind {
+    #[default]
+    Cw20,
+    Native,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TokenKind::Cw20 => write!(f, "cw20"),
+            TokenKind::Native => write!(f, "native"),
+        }
+    }
+}
+
+// Secondary indexes over TOKEN_INFO, which is keyed by token address
+pub struct TokenIndexes<'a> {
+    pub creator: MultiIndex<'a, Addr, TokenInfo, String>,
+    pub symbol: UniqueIndex<'a, String, TokenInfo, String>,
+    pub creation_time: MultiIndex<'a, u64, TokenInfo, String>,
+}
+
+impl IndexList<TokenInfo> for TokenIndexes<'_> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<TokenInfo>> + '_> {
+        let v: Vec<&dyn Index<TokenInfo>> = vec![&self.creator, &self.symbol, &self.creation_time];
+        Box::new(v.into_iter())
+    }
+}
+
+// Upper-cased symbol followed by the address, so a plain range over the index
+// walks symbols in order and a prefix search is a contiguous scan
+pub fn symbol_index_key(token: &TokenInfo) -> String {
+    format!("{}/{}", token.symbol.to_uppercase(), token.address)
+}
+
+// Project details mirrored into the CW20 marketing info for wallets and explorers
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct TokenMetadata {
+    pub description: Option<String>,
+    pub project_url: Option<String>,
+    pub logo_url: Option<String>,
+    pub socials: Vec<SocialLink>, // Kept by the factory only, CW20 has no field for them
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SocialLink {
+    pub platform: String,
+    pub url: String,
+}
+
+// CreateToken parameters held until the instantiate reply hands them back
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingCreation {
+    pub max_price_impact: Uint128,
+    pub curve_slope: Uint128,
+}
+
+// Merkle airdrop of a factory CW20, funded by its creator through a Receive hook.
+// Leaves are sha256 of the claimer's address followed by the amount, and each
+// proof step hashes the sorted pair.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Airdrop {
+    pub id: u64,
+    pub creator: Addr,
+    pub token: Addr,
+    pub merkle_root: HexBinary,
+    pub expiration: Expiration, // Claims close here and the creator can claw back the rest
+    pub funded: Uint128,
+    pub claimed: Uint128,
+    pub clawed_back: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -42,11 +129,33 @@ pub struct TokenCreationResponse {
     pub decimals: u8,
     pub max_price_impact: Uint128,
     pub curve_slope: Uint128,
+    pub kind: TokenKind,
 }
 
 pub const STATE: Item<State> = Item::new("state");
-pub const TOKEN_INFO: Map<&str, TokenInfo> = Map::new("token_info");
+pub const PENDING_OWNER: Item<PendingOwner> = Item::new("pending_owner");
+pub const ALLOWED_CALLERS: Item<Vec<Addr>> = Item::new("allowed_callers");
+pub const TOKEN_INFO: IndexedMap<&str, TokenInfo, TokenIndexes> = IndexedMap::new(
+    "token_info",
+    TokenIndexes {
+        creator: MultiIndex::new(
+            |_pk, token| token.creator.clone(),
+            "token_info",
+            "token_info__creator",
+        ),
+        symbol: UniqueIndex::new(symbol_index_key, "token_info__symbol"),
+        creation_time: MultiIndex::new(
+            |_pk, token| token.creation_time,
+            "token_info",
+            "token_info__creation_time",
+        ),
+    },
+);
 pub const TOKEN_ADDRESS: Map<(&str, &str), Addr> = Map::new("token_address");
+pub const PENDING_CREATIONS: Map<&str, PendingCreation> = Map::new("pending_creations");
+pub const NEXT_AIRDROP_ID: Item<u64> = Item::new("next_airdrop_id");
+pub const AIRDROPS: Map<u64, Airdrop> = Map::new("airdrops");
+pub const AIRDROP_CLAIMS: Map<(u64, &Addr), Uint128> = Map::new("airdrop_claims");
 
 use prost::Message;
 
@@ -55,3 +164,76 @@ pub struct ContractAddress {
     #[prost(string, tag = "1")]
     pub contract_address: String,
 }
+
+// x/tokenfactory messages, as registered by the Osmosis module and its forks
+pub const MSG_CREATE_DENOM_TYPE_URL: &str = "/osmosis.tokenfactory.v1beta1.MsgCreateDenom";
+pub const MSG_MINT_TYPE_URL: &str = "/osmosis.tokenfactory.v1beta1.MsgMint";
+pub const MSG_SET_DENOM_METADATA_TYPE_URL: &str =
+    "/osmosis.tokenfactory.v1beta1.MsgSetDenomMetadata";
+// Longest subdenom x/tokenfactory accepts
+pub const MAX_SUBDENOM_LENGTH: usize = 44;
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ProtoCoin {
+    #[prost(string, tag = "1")]
+    pub denom: String,
+    #[prost(string, tag = "2")]
+    pub amount: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgCreateDenom {
+    #[prost(string, tag = "1")]
+    pub sender: String,
+    #[prost(string, tag = "2")]
+    pub subdenom: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgMint {
+    #[prost(string, tag = "1")]
+    pub sender: String,
+    #[prost(message, optional, tag = "2")]
+    pub amount: Option<ProtoCoin>,
+    #[prost(string, tag = "3")]
+    pub mint_to_address: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct DenomUnit {
+    #[prost(string, tag = "1")]
+    pub denom: String,
+    #[prost(uint32, tag = "2")]
+    pub exponent: u32,
+    #[prost(string, repeated, tag = "3")]
+    pub aliases: Vec<String>,
+}
+
+// cosmos.bank.v1beta1.Metadata
+#[derive(Clone, PartialEq, Message)]
+pub struct DenomMetadata {
+    #[prost(string, tag = "1")]
+    pub description: String,
+    #[prost(message, repeated, tag = "2")]
+    pub denom_units: Vec<DenomUnit>,
+    #[prost(string, tag = "3")]
+    pub base: String,
+    #[prost(string, tag = "4")]
+    pub display: String,
+    #[prost(string, tag = "5")]
+    pub name: String,
+    #[prost(string, tag = "6")]
+    pub symbol: String,
+    #[prost(string, tag = "7")]
+    pub uri: String,
+    #[prost(string, tag = "8")]
+    pub uri_hash: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgSetDenomMetadata {
+    #[prost(string, tag = "1")]
+    pub sender: String,
+    #[prost(message, optional, tag = "2")]
+    pub metadata: Option<DenomMetadata>,
+}